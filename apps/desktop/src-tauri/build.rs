@@ -1,3 +1,42 @@
+use std::env;
+
 fn main() {
-    tauri_build::build()
+    // The headless shell has no Tauri app to generate the context for, but
+    // the `desktop` and `mobile` cfgs `tauri-build` sets are still checked
+    if env::var_os("CARGO_FEATURE_DESKTOP").is_some() {
+        if env::var("PROFILE").as_deref() == Ok("release") {
+            require_updater_pubkey();
+        }
+        tauri_build::build()
+    } else {
+        println!("cargo:rustc-check-cfg=cfg(desktop)");
+        println!("cargo:rustc-check-cfg=cfg(mobile)");
+    }
+}
+
+/// A release without the updater's public key could never be updated, so
+/// refuse to build one. The key comes in through `TAURI_CONFIG`, which
+/// `tauri build --config` sets, merged over `tauri.conf.json`.
+fn require_updater_pubkey() {
+    println!("cargo:rerun-if-changed=tauri.conf.json");
+    println!("cargo:rerun-if-env-changed=TAURI_CONFIG");
+    let pubkey = |config: &str| {
+        serde_json::from_str::<serde_json::Value>(config)
+            .ok()?
+            .pointer("/plugins/updater/pubkey")?
+            .as_str()
+            .map(|key| key.trim().to_string())
+    };
+    let merged = env::var("TAURI_CONFIG")
+        .ok()
+        .and_then(|config| pubkey(&config));
+    let file = std::fs::read_to_string("tauri.conf.json")
+        .ok()
+        .and_then(|config| pubkey(&config));
+    if merged.or(file).is_none_or(|key| key.is_empty()) {
+        panic!(
+            "release builds need the updater's public key in plugins.updater.pubkey, e.g. \
+             `tauri build --config '{{\"plugins\":{{\"updater\":{{\"pubkey\":\"...\"}}}}}}'`"
+        );
+    }
 }
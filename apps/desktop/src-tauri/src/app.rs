@@ -0,0 +1,339 @@
+//! The Tauri app: plugins, commands, managed state and the background
+//! tasks started from its setup hook. The tray, the updater, the quick-ask
+//! shortcut, deep links and native dialogs are desktop only.
+
+use std::time::Duration;
+
+use tauri::{Manager, RunEvent};
+#[cfg(desktop)]
+use tauri_plugin_deep_link::DeepLinkExt;
+#[cfg(desktop)]
+use tauri_plugin_global_shortcut::ShortcutState;
+use tracing::{error, warn};
+
+use crate::backend::{commands as backend_api, tasks, BackendSupervisor, HealthProbe};
+use crate::deskflow_client::{commands as api, DeskflowClient};
+use crate::encryption::DbEncryption;
+use crate::history::History;
+use crate::instance::{Instance, Launch};
+use crate::logs::LogStore;
+use crate::profile::{ProfileRoots, ProfileStore};
+use crate::secrets::SecretStore;
+use crate::settings::SettingsStore;
+use crate::state::AppState;
+#[cfg(desktop)]
+use crate::tray::{self, Tray};
+#[cfg(desktop)]
+use crate::updater;
+use crate::{backup, commands, csp, events, instance, windows};
+
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let state = AppState::from_env().expect("failed to allocate a port for the backend");
+    let endpoint = state.endpoint();
+
+    let mut context = tauri::generate_context!();
+    let security = &mut context.config_mut().app.security;
+    if let Some(policy) = security.csp.take() {
+        security.csp = Some(csp::allow_backend(policy, endpoint.port));
+    }
+
+    let builder = tauri::Builder::default().plugin(tauri_plugin_shell::init());
+    #[cfg(desktop)]
+    let builder = builder
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, _shortcut, event| {
+                    if event.state() == ShortcutState::Pressed {
+                        windows::toggle_quick_ask(app);
+                    }
+                })
+                .build(),
+        )
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .on_window_event(windows::on_window_event);
+    let app = builder
+        .invoke_handler(tauri::generate_handler![
+            backend_api::check_backend_health,
+            backend_api::get_backend_url,
+            commands::backend::backend_start,
+            commands::backend::backend_stop,
+            commands::backend::backend_restart,
+            commands::backend::backend_status,
+            commands::backend::backend_logs,
+            commands::logs::tail_logs,
+            #[cfg(desktop)]
+            commands::logs::export_logs,
+            #[cfg(desktop)]
+            commands::logs::collect_diagnostics,
+            commands::chat::chat_send,
+            commands::chat::chat_cancel,
+            commands::chat::chat_snapshot,
+            commands::chat::chat_connected,
+            commands::shell::get_shell_settings,
+            commands::shell::set_shell_settings,
+            commands::shell::open_in_main_window,
+            #[cfg(desktop)]
+            commands::shell::open_search_window,
+            commands::shell::take_launch_requests,
+            commands::shell::shell_ready,
+            commands::history::list_conversations,
+            commands::history::get_conversation,
+            commands::history::search_conversations,
+            commands::history::search_memories,
+            commands::history::search_messages,
+            #[cfg(desktop)]
+            commands::transcripts::export_conversation,
+            #[cfg(desktop)]
+            commands::transcripts::export_conversations,
+            commands::profile::list_profiles,
+            commands::profile::create_profile,
+            commands::profile::switch_profile,
+            commands::secrets::secrets_status,
+            commands::secrets::secrets_set,
+            commands::secrets::secrets_delete,
+            commands::profile::database_encryption_status,
+            commands::profile::encrypt_database,
+            commands::profile::rotate_database_key,
+            commands::profile::verify_database,
+            #[cfg(desktop)]
+            commands::profile::export_profile,
+            #[cfg(desktop)]
+            commands::profile::import_profile,
+            #[cfg(desktop)]
+            commands::updater::update_status,
+            #[cfg(desktop)]
+            commands::updater::check_for_updates,
+            #[cfg(desktop)]
+            commands::updater::download_update,
+            #[cfg(desktop)]
+            commands::updater::install_update,
+            api::api_chat,
+            api::api_conversations,
+            api::api_conversation,
+            api::api_delete_conversation,
+            api::api_save_conversation,
+            api::api_create_session,
+            api::api_session,
+            api::api_delete_session,
+            api::api_memory_stats,
+            api::api_recent_memories,
+            api::api_delete_memory,
+            api::api_skills,
+            api::api_skill,
+            api::api_toggle_skill,
+            api::api_install_skill,
+            api::api_config,
+            api::api_update_config,
+            api::api_llm_models,
+            api::api_test_llm,
+            api::api_status,
+            api::api_setup_config,
+            api::api_save_setup_config,
+            api::api_setup_start,
+            api::api_monitor_status,
+            api::api_llm_stats,
+            api::api_activity,
+            api::api_token_stats,
+            api::api_orchestration_status,
+            api::api_worker_stats,
+            api::api_submit_task,
+            api::api_cancel_task,
+            api::api_task_result
+        ])
+        .manage(endpoint)
+        .manage(HealthProbe::new(endpoint.url()))
+        .manage(DeskflowClient::new(endpoint.url()))
+        .manage(History::new())
+        .manage(state)
+        .setup(setup)
+        .build(context)
+        .expect("error while building tauri application");
+
+    app.run(|app, event| {
+        // The last window closed: take the backend down with us. A launch
+        // that bowed out in setup never started one
+        if let RunEvent::Exit = event {
+            if let Some(supervisor) = app.try_state::<BackendSupervisor>() {
+                tauri::async_runtime::block_on(supervisor.stop());
+            }
+        }
+    });
+}
+
+fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    // First, so everything below is logged
+    let logs = LogStore::init(&app.path().app_log_dir()?)?;
+    app.manage(logs.clone());
+
+    let profiles = ProfileStore::load(ProfileRoots {
+        data: app.path().app_data_dir()?,
+        config: app.path().app_config_dir()?,
+        cache: app.path().app_cache_dir()?,
+    })?;
+
+    // Hand our arguments to the shell already running this profile and bow
+    // out before starting a second backend against it; its window comes up
+    let launch = Launch::current();
+    let profile = profiles.current();
+    match instance::acquire(&profile.data, &launch) {
+        Ok(Instance::Secondary) => std::process::exit(0),
+        Ok(Instance::Unreachable(e)) => {
+            error!(
+                "Profile {} is in use by an unresponsive instance: {e}",
+                profile.name
+            );
+            windows::show_fatal_error(
+                app.handle(),
+                "DeskFlow is already running",
+                format!(
+                    "Profile \"{}\" is open in another DeskFlow window that is not responding. \
+                     Close it, or start DeskFlow with another profile.",
+                    profile.name
+                ),
+            );
+            return Ok(());
+        }
+        Ok(Instance::Primary(listener)) => {
+            let handle = app.handle().clone();
+            let running = listener.spawn(move |launch| {
+                // Linux and Windows open links by launching us with the URL
+                // as the only argument
+                #[cfg(desktop)]
+                {
+                    let exe = std::iter::once(String::new());
+                    handle
+                        .deep_link()
+                        .handle_cli_arguments(exe.chain(launch.args.iter().cloned()));
+                }
+                events::deliver_launch(&handle, launch.request());
+            });
+            app.manage(running);
+        }
+        Err(e) => warn!("Single-instance check failed, continuing: {e}"),
+    }
+    let state = app.state::<AppState>();
+    let request = launch.request();
+    if !request.is_empty() {
+        state.queue_launch(request);
+    }
+
+    // Links we were started with wait until the frontend is ready (see
+    // `shell_ready`); later ones are followed right away
+    #[cfg(desktop)]
+    {
+        let startup = app.deep_link().get_current()?;
+        state
+            .hold_startup_links(startup.map(|urls| urls.iter().map(ToString::to_string).collect()));
+        let handle = app.handle().clone();
+        app.deep_link().on_open_url(move |event| {
+            for url in event.urls() {
+                events::open_link(&handle, url.as_str());
+            }
+        });
+    }
+    let (endpoint, external) = (state.endpoint(), state.external_backend());
+
+    let settings = SettingsStore::load(&app.path().app_config_dir()?);
+    #[cfg(desktop)]
+    {
+        app.manage(Tray::new(app, &settings.get())?);
+        if let Err(e) =
+            windows::register_quick_ask_shortcut(app.handle(), &settings.get().quick_ask_shortcut)
+        {
+            warn!("{e}");
+        }
+    }
+    app.manage(settings);
+
+    let secrets = SecretStore::open(&app.path().app_data_dir()?.join("secrets"));
+    app.manage(secrets.clone());
+    app.manage(profiles.clone());
+
+    let encryption = DbEncryption::new(secrets.clone());
+    app.manage(encryption.clone());
+
+    let supervisor = tasks::spawn_supervisor(
+        app,
+        endpoint,
+        profiles.clone(),
+        secrets,
+        encryption.clone(),
+        logs,
+        !external,
+    );
+    tasks::spawn_watchdog(app, supervisor.clone(), endpoint);
+    app.manage(supervisor);
+    #[cfg(desktop)]
+    updater::init(app, HealthProbe::new(endpoint.url()));
+    spawn_integrity_check(app, encryption.clone(), profiles.clone());
+    spawn_auto_backups(app, encryption, profiles);
+    app.manage(events::spawn_chat_bridge(app, endpoint));
+    events::spawn_notifications(app, endpoint);
+    #[cfg_attr(mobile, allow(unused_variables))]
+    let health = tasks::spawn_health_prober(app, HealthProbe::new(endpoint.url()));
+    #[cfg(desktop)]
+    tray::spawn_updates(app, health);
+
+    #[cfg(debug_assertions)]
+    {
+        let window = app.get_webview_window(windows::MAIN_LABEL).unwrap();
+        window.open_devtools();
+    }
+    Ok(())
+}
+
+/// Check the profile database in the background and tell the user if it
+/// cannot be opened or is damaged.
+fn spawn_integrity_check(app: &tauri::App, encryption: DbEncryption, profiles: ProfileStore) {
+    let handle = app.handle().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let dirs = profiles.current();
+        let problems = match encryption.verify(&dirs) {
+            Ok(report) if report.ok => return,
+            Ok(report) => report.problems,
+            Err(e) => vec![e.to_string()],
+        };
+        error!(?problems, "Database integrity check failed");
+        windows::show_error(
+            &handle,
+            "Database check failed",
+            format!(
+                "The database of profile \"{}\" failed its integrity check:\n\n{}",
+                dirs.name,
+                problems.join("\n")
+            ),
+        );
+    });
+}
+
+/// Back up the active profile whenever automatic backups are on and the
+/// newest one is older than the configured interval. Backups are taken
+/// while the backend runs; databases go in as consistent snapshots.
+fn spawn_auto_backups(app: &tauri::App, encryption: DbEncryption, profiles: ProfileStore) {
+    let handle = app.handle().clone();
+    tauri::async_runtime::spawn(async move {
+        let mut ticks = tokio::time::interval(BACKUP_CHECK_INTERVAL);
+        loop {
+            ticks.tick().await;
+            let settings = handle.state::<SettingsStore>().get();
+            if settings.auto_backup_hours == 0 {
+                continue;
+            }
+            let every = Duration::from_secs(u64::from(settings.auto_backup_hours) * 3600);
+            let keep = settings.backup_retention as usize;
+            let (encryption, dirs) = (encryption.clone(), profiles.current());
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                backup::backup_if_due(&encryption, &dirs, every, keep)
+            })
+            .await;
+            if let Ok(Err(e)) = result {
+                error!("Automatic backup failed: {e}");
+            }
+        }
+    });
+}
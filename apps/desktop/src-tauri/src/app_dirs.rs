@@ -0,0 +1,58 @@
+//! The app's data, config, cache and log dirs without Tauri, so the
+//! headless shell reads and writes the same files as the app.
+
+use std::path::PathBuf;
+
+use crate::profile::ProfileRoots;
+
+/// The bundle identifier from `tauri.conf.json`; Tauri names the app dirs
+/// after it.
+pub const APP_IDENTIFIER: &str = "com.coolaw.deskflow";
+
+/// Subdirectory of the log dir for hang dumps and other diagnostics the
+/// shell collects by itself.
+pub const DIAGNOSTICS_DIR: &str = "diagnostics";
+
+/// The per-user dirs, resolved the way Tauri's path resolver does.
+#[derive(Debug, Clone)]
+pub struct AppDirs {
+    pub data: PathBuf,
+    pub config: PathBuf,
+    pub cache: PathBuf,
+    pub log: PathBuf,
+}
+
+impl AppDirs {
+    /// `None` when the platform has no home or data dir to put them in.
+    pub fn resolve() -> Option<Self> {
+        let data = dirs::data_dir()?.join(APP_IDENTIFIER);
+        let config = dirs::config_dir()?.join(APP_IDENTIFIER);
+        let cache = dirs::cache_dir()?.join(APP_IDENTIFIER);
+        let log = if cfg!(target_os = "macos") {
+            dirs::home_dir()?
+                .join("Library")
+                .join("Logs")
+                .join(APP_IDENTIFIER)
+        } else {
+            dirs::data_local_dir()?.join(APP_IDENTIFIER).join("logs")
+        };
+        Some(Self {
+            data,
+            config,
+            cache,
+            log,
+        })
+    }
+
+    pub fn profile_roots(&self) -> ProfileRoots {
+        ProfileRoots {
+            data: self.data.clone(),
+            config: self.config.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+
+    pub fn diagnostics(&self) -> PathBuf {
+        self.log.join(DIAGNOSTICS_DIR)
+    }
+}
@@ -0,0 +1,17 @@
+//! Tauri commands about the backend that need nothing but managed state,
+//! so they run on any runtime, the mock one in tests included.
+
+use tauri::State;
+
+use super::{BackendEndpoint, BackendHealth, HealthProbe};
+use crate::error::DeskflowResult;
+
+#[tauri::command]
+pub async fn check_backend_health(probe: State<'_, HealthProbe>) -> DeskflowResult<BackendHealth> {
+    Ok(probe.check(true).await)
+}
+
+#[tauri::command]
+pub fn get_backend_url(endpoint: State<'_, BackendEndpoint>) -> String {
+    endpoint.url()
+}
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::watch;
+
+use crate::deskflow_client::models::{ComponentHealth, HealthResponse};
+use crate::runtime::now_ms;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Error,
+    Unreachable,
+}
+
+impl HealthStatus {
+    fn parse(status: &str) -> Self {
+        match status {
+            "ok" => HealthStatus::Ok,
+            "error" => HealthStatus::Error,
+            _ => HealthStatus::Degraded,
+        }
+    }
+}
+
+/// Health of the backend as reported by `/api/health` and
+/// `/api/health/detailed`, plus what the shell measured itself.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendHealth {
+    pub status: HealthStatus,
+    pub version: Option<String>,
+    pub llm_configured: bool,
+    pub memory_reachable: bool,
+    pub components: BTreeMap<String, ComponentHealth>,
+    pub recommendations: Vec<String>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    pub checked_at_ms: u64,
+}
+
+impl BackendHealth {
+    pub fn unreachable(error: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Unreachable,
+            version: None,
+            llm_configured: false,
+            memory_reachable: false,
+            components: BTreeMap::new(),
+            recommendations: Vec::new(),
+            latency_ms: None,
+            error: Some(error.into()),
+            checked_at_ms: now_ms(),
+        }
+    }
+
+    pub fn is_reachable(&self) -> bool {
+        self.status != HealthStatus::Unreachable
+    }
+
+    /// Whether two reports describe the same state, ignoring latency and
+    /// timestamps.
+    pub fn same_state(&self, other: &BackendHealth) -> bool {
+        self.status == other.status
+            && self.version == other.version
+            && self.llm_configured == other.llm_configured
+            && self.memory_reachable == other.memory_reachable
+            && self
+                .components
+                .iter()
+                .map(|(name, c)| (name, &c.status))
+                .eq(other.components.iter().map(|(name, c)| (name, &c.status)))
+    }
+}
+
+#[derive(Deserialize)]
+struct DetailedHealthResponse {
+    #[serde(default)]
+    components: BTreeMap<String, ComponentHealth>,
+    #[serde(default)]
+    system: Option<Value>,
+    #[serde(default)]
+    process: Option<Value>,
+    #[serde(default)]
+    recommendations: Vec<String>,
+}
+
+/// Queries the backend health endpoints.
+#[derive(Clone)]
+pub struct HealthProbe {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl HealthProbe {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            http,
+            base_url: base_url.into(),
+        }
+    }
+
+    /// Check `/api/health`, and with `detailed` also merge in the
+    /// per-component report from `/api/health/detailed`. Never fails: an
+    /// unreachable backend is a health state of its own.
+    pub async fn check(&self, detailed: bool) -> BackendHealth {
+        let started = Instant::now();
+        let response = match self.fetch::<HealthResponse>("/api/health").await {
+            Ok(response) => response,
+            Err(err) => return BackendHealth::unreachable(err),
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let mut health = BackendHealth {
+            status: HealthStatus::parse(&response.status),
+            version: Some(response.version).filter(|v| !v.is_empty()),
+            llm_configured: false,
+            memory_reachable: false,
+            components: response.components,
+            recommendations: Vec::new(),
+            latency_ms: Some(latency_ms),
+            error: None,
+            checked_at_ms: now_ms(),
+        };
+
+        if detailed {
+            match self
+                .fetch::<DetailedHealthResponse>("/api/health/detailed")
+                .await
+            {
+                Ok(report) => {
+                    health.components.extend(report.components);
+                    for (name, details) in [("system", report.system), ("process", report.process)]
+                    {
+                        if let Some(details) = details {
+                            let status = details
+                                .get("status")
+                                .and_then(Value::as_str)
+                                .unwrap_or("ok")
+                                .to_string();
+                            health
+                                .components
+                                .insert(name.into(), ComponentHealth { status, details });
+                        }
+                    }
+                    health.recommendations = report.recommendations;
+                }
+                Err(err) => health.error = Some(err),
+            }
+        }
+
+        health.llm_configured = health
+            .components
+            .get("llm")
+            .and_then(|llm| llm.details.get("api_key_configured"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        health.memory_reachable = health
+            .components
+            .get("memory")
+            .is_some_and(|memory| memory.status == "ok");
+
+        health
+    }
+
+    async fn fetch<T: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<T, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("Backend connection failed: {e}"))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Backend returned status: {}", response.status()));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid health response from {path}: {e}"))
+    }
+}
+
+/// Probe the backend every `interval` in the background. The receiver only
+/// sees a new value when the health state changes, and that value comes
+/// from a detailed check, so `llm_configured` and the recommendations are
+/// current after every transition.
+pub fn spawn_prober(probe: HealthProbe, interval: Duration) -> watch::Receiver<BackendHealth> {
+    let (tx, rx) = watch::channel(BackendHealth::unreachable("not checked yet"));
+
+    crate::runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // Transitions are told apart by the quick check alone; the detailed
+        // one reports more components
+        let mut last = tx.borrow().clone();
+
+        loop {
+            ticker.tick().await;
+            let health = probe.check(false).await;
+            if last.same_state(&health) {
+                tx.send_if_modified(|current| {
+                    current.latency_ms = health.latency_ms;
+                    current.checked_at_ms = health.checked_at_ms;
+                    false
+                });
+            } else if health.is_reachable() {
+                tx.send_replace(probe.check(true).await);
+            } else {
+                tx.send_replace(health.clone());
+            }
+            last = health;
+            if tx.is_closed() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
@@ -0,0 +1,238 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use tracing::error;
+
+use super::{port, BackendEndpoint, STACK_DUMP_FILE_ENV};
+use crate::encryption::DbEncryption;
+use crate::profile::ProfileStore;
+use crate::secrets::SecretStore;
+
+/// Environment variable that overrides the backend command line, e.g.
+/// `DESKFLOW_BACKEND_CMD="/opt/venv/bin/deskflow serve"`. Split with POSIX
+/// shell quoting, so paths with spaces can be quoted; on Windows put paths
+/// in single quotes to keep their backslashes.
+pub const BACKEND_CMD_ENV: &str = "DESKFLOW_BACKEND_CMD";
+
+/// Fully resolved command line used to spawn the Python backend.
+#[derive(Debug, Clone)]
+pub struct BackendCommand {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+    pub cwd: Option<PathBuf>,
+    pub envs: Vec<(String, String)>,
+}
+
+impl BackendCommand {
+    pub fn new(program: impl Into<OsString>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            cwd: None,
+            envs: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn cwd(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Resolve the backend command in order of preference:
+    /// 1. `DESKFLOW_BACKEND_CMD` from the environment
+    /// 2. the interpreter bundled under `<resources>/backend`
+    /// 3. the system `python3 -m deskflow serve`
+    pub fn resolve(resource_dir: Option<&Path>) -> Self {
+        if let Some(configured) = Self::from_env() {
+            return configured;
+        }
+
+        if let Some(bundled) = resource_dir.and_then(Self::bundled) {
+            return bundled;
+        }
+
+        Self::system()
+    }
+
+    fn from_env() -> Option<Self> {
+        let raw = std::env::var(BACKEND_CMD_ENV).ok()?;
+        Self::parse(&raw)
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        let parts = match shell_words::split(raw) {
+            Ok(parts) => parts,
+            Err(e) => {
+                error!("Ignoring {BACKEND_CMD_ENV}: {e}");
+                return None;
+            }
+        };
+        let mut parts = parts.into_iter();
+        let mut command = Self::new(parts.next()?);
+        for part in parts {
+            command = command.arg(part);
+        }
+        Some(command)
+    }
+
+    fn bundled(resource_dir: &Path) -> Option<Self> {
+        let root = resource_dir.join("backend");
+        let interpreter = if cfg!(windows) {
+            root.join("python.exe")
+        } else {
+            root.join("bin").join("python3")
+        };
+
+        if !interpreter.is_file() {
+            return None;
+        }
+
+        Some(
+            Self::new(interpreter)
+                .arg("-m")
+                .arg("deskflow")
+                .arg("serve"),
+        )
+    }
+
+    fn system() -> Self {
+        let python = if cfg!(windows) { "python" } else { "python3" };
+        let command = Self::new(python).arg("-m").arg("deskflow").arg("serve");
+
+        // In a source checkout run against the repo's `src/` so `tauri dev`
+        // works without `pip install -e .`
+        #[cfg(debug_assertions)]
+        {
+            let repo_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../..");
+            if repo_root.join("src/deskflow").is_dir() {
+                if let Ok(root) = repo_root.canonicalize() {
+                    let src = root.join("src").to_string_lossy().into_owned();
+                    return command.cwd(root).env("PYTHONPATH", src);
+                }
+            }
+        }
+
+        command
+    }
+
+    pub(crate) fn to_command(&self) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(k, v)| (k, v)))
+            .env("PYTHONUNBUFFERED", "1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+
+        command
+    }
+}
+
+/// How the shell launches the backend: the resolved command, run from the
+/// active profile's data dir with the port, the profile dirs, the API keys
+/// and the database key in its environment. Called again on every
+/// (re)start, so profile switches and new keys take effect.
+pub fn profile_launcher(
+    resource_dir: Option<PathBuf>,
+    endpoint: BackendEndpoint,
+    profiles: ProfileStore,
+    secrets: SecretStore,
+    encryption: DbEncryption,
+    stack_dump_file: Option<PathBuf>,
+) -> impl Fn() -> BackendCommand + Send + Sync + 'static {
+    move || {
+        // Run from the profile's data dir so paths the backend still
+        // resolves against its working directory stay inside the profile
+        let dirs = profiles.current();
+        let mut command = BackendCommand::resolve(resource_dir.as_deref())
+            .cwd(&dirs.data)
+            .env(port::PORT_ENV, endpoint.port.to_string());
+        if let Some(file) = &stack_dump_file {
+            command = command.env(STACK_DUMP_FILE_ENV, file.to_string_lossy());
+        }
+        for (key, value) in dirs.env() {
+            command = command.env(key, value);
+        }
+        // API keys and the database key travel only through the
+        // child's environment
+        if let Err(e) = encryption.recover(&dirs) {
+            error!("Failed to recover the database after encrypting: {e}");
+        }
+        for (key, value) in secrets.env().into_iter().chain(encryption.env(&dirs)) {
+            command = command.env(key, value);
+        }
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(raw: &str) -> Option<Vec<String>> {
+        let command = BackendCommand::parse(raw)?;
+        Some(
+            std::iter::once(command.program)
+                .chain(command.args)
+                .map(|part| part.to_string_lossy().into_owned())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parse_splits_on_whitespace() {
+        assert_eq!(
+            parts("/opt/venv/bin/deskflow  serve"),
+            Some(vec!["/opt/venv/bin/deskflow".into(), "serve".into()])
+        );
+    }
+
+    #[test]
+    fn parse_keeps_quoted_paths_whole() {
+        assert_eq!(
+            parts(r#""/Users/me/My Venv/bin/python3" -m deskflow serve"#),
+            Some(vec![
+                "/Users/me/My Venv/bin/python3".into(),
+                "-m".into(),
+                "deskflow".into(),
+                "serve".into(),
+            ])
+        );
+        assert_eq!(
+            parts(r"'C:\Program Files\Deskflow\python.exe' -m deskflow"),
+            Some(vec![
+                r"C:\Program Files\Deskflow\python.exe".into(),
+                "-m".into(),
+                "deskflow".into(),
+            ])
+        );
+        assert_eq!(
+            parts(r"/opt/My\ Venv/bin/deskflow serve"),
+            Some(vec!["/opt/My Venv/bin/deskflow".into(), "serve".into()])
+        );
+    }
+
+    #[test]
+    fn parse_rejects_empty_and_unbalanced_quotes() {
+        assert_eq!(parts(""), None);
+        assert_eq!(parts("   "), None);
+        assert_eq!(parts(r#""/opt/venv/bin/deskflow serve"#), None);
+    }
+}
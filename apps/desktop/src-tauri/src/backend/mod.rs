@@ -0,0 +1,20 @@
+//! Supervision of the Python backend (`deskflow serve`) as a child process
+//! of the shell.
+
+#[cfg(feature = "desktop")]
+pub mod commands;
+pub mod health;
+mod launch;
+pub mod port;
+mod supervisor;
+#[cfg(feature = "desktop")]
+pub mod tasks;
+pub mod watchdog;
+
+pub use health::{BackendHealth, HealthProbe, HealthStatus};
+pub use launch::{profile_launcher, BackendCommand, BACKEND_CMD_ENV};
+pub use port::BackendEndpoint;
+pub use supervisor::{
+    BackendLogLine, BackendState, BackendSupervisor, LogStream, StateChange, SupervisorOptions,
+};
+pub use watchdog::{Watchdog, WatchdogEvent, WatchdogOptions, STACK_DUMP_FILE_ENV};
@@ -0,0 +1,109 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4, TcpListener};
+
+/// Port the backend has historically listened on; still preferred when free
+/// so a browser-only `npm run dev:web` keeps working.
+pub const DEFAULT_PORT: u16 = 8420;
+
+/// Environment variable carrying the port to `deskflow serve`. When set for
+/// the shell itself it pins the port instead of allocating one.
+pub const PORT_ENV: &str = "DESKFLOW_PORT";
+
+pub const HOST: Ipv4Addr = Ipv4Addr::LOCALHOST;
+
+/// Port pinned through `DESKFLOW_PORT`, if any.
+pub fn configured_port() -> Option<u16> {
+    std::env::var(PORT_ENV).ok()?.trim().parse().ok()
+}
+
+/// Pick the port for a supervised backend: the pinned one, else 8420 if it
+/// is free, else whatever the OS hands out.
+pub fn allocate_port() -> io::Result<u16> {
+    allocate(configured_port(), DEFAULT_PORT)
+}
+
+fn allocate(pinned: Option<u16>, preferred: u16) -> io::Result<u16> {
+    if let Some(port) = pinned {
+        return Ok(port);
+    }
+
+    if is_free(preferred) {
+        return Ok(preferred);
+    }
+
+    let listener = TcpListener::bind(SocketAddrV4::new(HOST, 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+pub fn is_free(port: u16) -> bool {
+    TcpListener::bind(SocketAddrV4::new(HOST, port)).is_ok()
+}
+
+pub fn backend_url(port: u16) -> String {
+    format!("http://{HOST}:{port}")
+}
+
+pub fn backend_ws_url(port: u16) -> String {
+    format!("ws://{HOST}:{port}")
+}
+
+/// Where the backend for this shell instance listens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendEndpoint {
+    pub port: u16,
+}
+
+impl BackendEndpoint {
+    pub fn new(port: u16) -> Self {
+        Self { port }
+    }
+
+    pub fn url(&self) -> String {
+        backend_url(self.port)
+    }
+
+    pub fn ws_url(&self) -> String {
+        backend_ws_url(self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A port that was free a moment ago.
+    fn free_port() -> u16 {
+        let listener = TcpListener::bind(SocketAddrV4::new(HOST, 0)).unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[test]
+    fn a_pinned_port_is_used_even_when_taken() {
+        let taken = TcpListener::bind(SocketAddrV4::new(HOST, 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+        assert_eq!(allocate(Some(port), free_port()).unwrap(), port);
+    }
+
+    #[test]
+    fn the_preferred_port_is_used_when_free() {
+        let preferred = free_port();
+        assert_eq!(allocate(None, preferred).unwrap(), preferred);
+    }
+
+    #[test]
+    fn a_taken_preferred_port_falls_back_to_a_free_one() {
+        let taken = TcpListener::bind(SocketAddrV4::new(HOST, 0)).unwrap();
+        let preferred = taken.local_addr().unwrap().port();
+
+        let port = allocate(None, preferred).unwrap();
+        assert_ne!(port, preferred);
+        assert!(is_free(port));
+    }
+
+    #[test]
+    fn urls_point_at_loopback() {
+        let endpoint = BackendEndpoint::new(51234);
+        assert_eq!(endpoint.url(), "http://127.0.0.1:51234");
+        assert_eq!(endpoint.ws_url(), "ws://127.0.0.1:51234");
+    }
+}
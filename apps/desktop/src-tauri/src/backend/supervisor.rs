@@ -0,0 +1,569 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Child;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+
+use super::launch::BackendCommand;
+use crate::runtime::now_ms;
+
+const LOG_BUFFER_LINES: usize = 500;
+const STATE_HISTORY_LEN: usize = 100;
+
+/// Lifecycle state of the supervised backend, emitted as `backend://state`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum BackendState {
+    Stopped,
+    Starting {
+        attempt: u32,
+    },
+    /// The process has been spawned and not exited yet. Whether it answers
+    /// requests is up to the health probe.
+    Running {
+        pid: u32,
+        started_at_ms: u64,
+    },
+    Stopping,
+    Restarting {
+        attempt: u32,
+        delay_ms: u64,
+        exit_code: Option<i32>,
+    },
+    Failed {
+        exit_code: Option<i32>,
+        message: String,
+    },
+}
+
+impl BackendState {
+    /// Whether the process exists, not whether it is ready to serve.
+    pub fn is_running(&self) -> bool {
+        matches!(self, BackendState::Running { .. })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A state the backend entered, kept so restarts and exit codes can be
+/// looked at after the fact.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateChange {
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub state: BackendState,
+}
+
+/// One line of output captured from the backend process.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendLogLine {
+    pub stream: LogStream,
+    pub line: String,
+    pub timestamp_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SupervisorOptions {
+    /// Consecutive crashes tolerated before giving up.
+    pub max_restarts: u32,
+    pub backoff_initial: Duration,
+    pub backoff_max: Duration,
+    /// A process that stayed up this long resets the crash counter.
+    pub stable_after: Duration,
+    /// Grace period between SIGTERM and SIGKILL.
+    pub stop_timeout: Duration,
+}
+
+impl Default for SupervisorOptions {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            backoff_initial: Duration::from_millis(500),
+            backoff_max: Duration::from_secs(30),
+            stable_after: Duration::from_secs(30),
+            stop_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl SupervisorOptions {
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.backoff_initial
+            .saturating_mul(factor)
+            .min(self.backoff_max)
+    }
+}
+
+enum Control {
+    Start,
+    Restart,
+    /// Answered with whether the backend was wanted up until then.
+    Stop(oneshot::Sender<bool>),
+}
+
+type Launcher = Box<dyn Fn() -> BackendCommand + Send + Sync>;
+
+/// Owns the backend child process and restarts it with backoff when it
+/// exits unexpectedly. All process handling happens on a single task; this
+/// handle only sends it control messages.
+#[derive(Clone)]
+pub struct BackendSupervisor {
+    control: mpsc::UnboundedSender<Control>,
+    state: watch::Receiver<BackendState>,
+    logs: broadcast::Sender<BackendLogLine>,
+    recent: Arc<Mutex<VecDeque<BackendLogLine>>>,
+    history: Arc<Mutex<VecDeque<StateChange>>>,
+}
+
+impl BackendSupervisor {
+    /// Spawn the supervisor task. The launcher is called before every
+    /// (re)start so it can pick up changed settings.
+    pub fn spawn<F>(launcher: F, options: SupervisorOptions) -> Self
+    where
+        F: Fn() -> BackendCommand + Send + Sync + 'static,
+    {
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(BackendState::Stopped);
+        let (logs_tx, _) = broadcast::channel(256);
+        let recent = Arc::new(Mutex::new(VecDeque::with_capacity(LOG_BUFFER_LINES)));
+        let history = Arc::new(Mutex::new(VecDeque::with_capacity(STATE_HISTORY_LEN)));
+
+        let worker = Worker {
+            launcher: Box::new(launcher),
+            options,
+            control: control_rx,
+            state: state_tx,
+            logs: logs_tx.clone(),
+            recent: recent.clone(),
+            history: history.clone(),
+        };
+        crate::runtime::spawn(worker.run());
+
+        Self {
+            control: control_tx,
+            state: state_rx,
+            logs: logs_tx,
+            recent,
+            history,
+        }
+    }
+
+    pub fn start(&self) {
+        let _ = self.control.send(Control::Start);
+    }
+
+    pub fn restart(&self) {
+        let _ = self.control.send(Control::Restart);
+    }
+
+    /// Stop the backend and wait until the process has exited. Returns
+    /// whether the supervisor was keeping it up until now: running, but
+    /// also starting or waiting out a restart delay.
+    pub async fn stop(&self) -> bool {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.control.send(Control::Stop(done_tx)).is_err() {
+            return false;
+        }
+        done_rx.await.unwrap_or(false)
+    }
+
+    pub fn state(&self) -> BackendState {
+        self.state.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<BackendState> {
+        self.state.clone()
+    }
+
+    pub fn subscribe_logs(&self) -> broadcast::Receiver<BackendLogLine> {
+        self.logs.subscribe()
+    }
+
+    /// The most recent output lines, oldest first.
+    pub fn recent_logs(&self) -> Vec<BackendLogLine> {
+        self.recent
+            .lock()
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The most recent state changes, oldest first.
+    pub fn state_history(&self) -> Vec<StateChange> {
+        self.history
+            .lock()
+            .map(|changes| changes.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+struct Worker {
+    launcher: Launcher,
+    options: SupervisorOptions,
+    control: mpsc::UnboundedReceiver<Control>,
+    state: watch::Sender<BackendState>,
+    logs: broadcast::Sender<BackendLogLine>,
+    recent: Arc<Mutex<VecDeque<BackendLogLine>>>,
+    history: Arc<Mutex<VecDeque<StateChange>>>,
+}
+
+enum Exit {
+    Crashed(Option<i32>, String),
+    Stopped(oneshot::Sender<bool>),
+    Restart,
+    Closed,
+}
+
+impl Worker {
+    async fn run(mut self) {
+        let mut wanted = false;
+        let mut attempt = 0u32;
+
+        loop {
+            if !wanted {
+                match self.control.recv().await {
+                    Some(Control::Start) | Some(Control::Restart) => {
+                        wanted = true;
+                        attempt = 0;
+                    }
+                    Some(Control::Stop(done)) => {
+                        let _ = done.send(false);
+                    }
+                    None => return,
+                }
+                continue;
+            }
+
+            self.set_state(BackendState::Starting { attempt });
+            let started = Instant::now();
+
+            let exit = match (self.launcher)().to_command().spawn() {
+                Ok(child) => self.supervise(child).await,
+                Err(err) => Exit::Crashed(None, format!("failed to spawn backend: {err}")),
+            };
+
+            match exit {
+                Exit::Closed => return,
+                Exit::Restart => attempt = 0,
+                Exit::Stopped(done) => {
+                    wanted = false;
+                    self.stopped(done);
+                }
+                Exit::Crashed(exit_code, message) => {
+                    if started.elapsed() >= self.options.stable_after {
+                        attempt = 0;
+                    }
+                    attempt += 1;
+
+                    if attempt > self.options.max_restarts {
+                        wanted = false;
+                        self.set_state(BackendState::Failed { exit_code, message });
+                        continue;
+                    }
+
+                    let delay = self.options.backoff(attempt);
+                    self.set_state(BackendState::Restarting {
+                        attempt,
+                        delay_ms: delay.as_millis() as u64,
+                        exit_code,
+                    });
+
+                    match self.wait_backoff(delay).await {
+                        Some(Exit::Closed) => return,
+                        Some(Exit::Stopped(done)) => {
+                            wanted = false;
+                            self.stopped(done);
+                        }
+                        Some(Exit::Restart) => attempt = 0,
+                        Some(Exit::Crashed(..)) | None => {}
+                    }
+                }
+            }
+        }
+    }
+
+    async fn supervise(&mut self, mut child: Child) -> Exit {
+        let pid = child.id().unwrap_or_default();
+        if let Some(stdout) = child.stdout.take() {
+            self.capture(stdout, LogStream::Stdout);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            self.capture(stderr, LogStream::Stderr);
+        }
+
+        self.set_state(BackendState::Running {
+            pid,
+            started_at_ms: now_ms(),
+        });
+
+        loop {
+            tokio::select! {
+                status = child.wait() => {
+                    return match status {
+                        Ok(status) => Exit::Crashed(
+                            status.code(),
+                            format!("backend exited with {status}"),
+                        ),
+                        Err(err) => Exit::Crashed(None, format!("failed to wait for backend: {err}")),
+                    };
+                }
+                control = self.control.recv() => match control {
+                    Some(Control::Start) => continue,
+                    Some(Control::Restart) => {
+                        self.terminate(&mut child).await;
+                        return Exit::Restart;
+                    }
+                    Some(Control::Stop(done)) => {
+                        self.terminate(&mut child).await;
+                        return Exit::Stopped(done);
+                    }
+                    None => {
+                        self.terminate(&mut child).await;
+                        return Exit::Closed;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Sleep out the restart delay. Returns `None` once the delay elapsed, or
+    /// the control message that interrupted it.
+    async fn wait_backoff(&mut self, delay: Duration) -> Option<Exit> {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => None,
+            control = self.control.recv() => Some(match control {
+                Some(Control::Start) | Some(Control::Restart) => Exit::Restart,
+                Some(Control::Stop(done)) => Exit::Stopped(done),
+                None => Exit::Closed,
+            }),
+        }
+    }
+
+    fn set_state(&self, state: BackendState) {
+        if let Ok(mut history) = self.history.lock() {
+            if history.len() == STATE_HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(StateChange {
+                timestamp_ms: now_ms(),
+                state: state.clone(),
+            });
+        }
+        self.state.send_replace(state);
+    }
+
+    fn stopped(&self, done: oneshot::Sender<bool>) {
+        self.set_state(BackendState::Stopped);
+        let _ = done.send(true);
+    }
+
+    /// Ask the backend to exit (SIGTERM on Unix) and kill it if it does not
+    /// within the grace period.
+    async fn terminate(&self, child: &mut Child) {
+        self.set_state(BackendState::Stopping);
+
+        #[cfg(unix)]
+        if let Some(pid) = child.id() {
+            // SAFETY: plain kill(2) on a pid we own; failure is handled below.
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGTERM);
+            }
+            if tokio::time::timeout(self.options.stop_timeout, child.wait())
+                .await
+                .is_ok()
+            {
+                return;
+            }
+        }
+
+        let _ = child.kill().await;
+    }
+
+    fn capture<R>(&self, reader: R, stream: LogStream)
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let logs = self.logs.clone();
+        let recent = self.recent.clone();
+
+        crate::runtime::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let entry = BackendLogLine {
+                    stream,
+                    line,
+                    timestamp_ms: now_ms(),
+                };
+
+                if let Ok(mut recent) = recent.lock() {
+                    if recent.len() == LOG_BUFFER_LINES {
+                        recent.pop_front();
+                    }
+                    recent.push_back(entry.clone());
+                }
+                let _ = logs.send(entry);
+            }
+        });
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const WAIT: Duration = Duration::from_secs(10);
+
+    fn shell(script: &str) -> BackendCommand {
+        BackendCommand::new("sh").arg("-c").arg(script)
+    }
+
+    fn spawn(script: &'static str, options: SupervisorOptions) -> BackendSupervisor {
+        BackendSupervisor::spawn(move || shell(script), options)
+    }
+
+    async fn wait_for(
+        supervisor: &BackendSupervisor,
+        what: impl FnMut(&BackendState) -> bool,
+    ) -> BackendState {
+        let mut states = supervisor.subscribe();
+        let state = tokio::time::timeout(WAIT, states.wait_for(what))
+            .await
+            .expect("timed out waiting for the backend state")
+            .unwrap();
+        state.clone()
+    }
+
+    #[tokio::test]
+    async fn stop_reports_whether_the_backend_was_wanted() {
+        let supervisor = spawn(
+            "exit 3",
+            SupervisorOptions {
+                backoff_initial: Duration::from_secs(60),
+                ..SupervisorOptions::default()
+            },
+        );
+        assert!(!supervisor.stop().await);
+
+        // Crashed and waiting out the delay still counts as wanted
+        supervisor.start();
+        wait_for(&supervisor, |s| {
+            matches!(s, BackendState::Restarting { .. })
+        })
+        .await;
+        assert!(supervisor.stop().await);
+        assert_eq!(supervisor.state(), BackendState::Stopped);
+        assert!(!supervisor.stop().await);
+    }
+
+    fn fast(max_restarts: u32) -> SupervisorOptions {
+        SupervisorOptions {
+            max_restarts,
+            backoff_initial: Duration::from_millis(10),
+            backoff_max: Duration::from_millis(25),
+            ..SupervisorOptions::default()
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let options = SupervisorOptions::default();
+        let delays: Vec<u64> = (1..=9)
+            .map(|attempt| options.backoff(attempt).as_millis() as u64)
+            .collect();
+        assert_eq!(
+            delays,
+            [500, 1_000, 2_000, 4_000, 8_000, 16_000, 30_000, 30_000, 30_000]
+        );
+        assert_eq!(options.backoff(u32::MAX), options.backoff_max);
+    }
+
+    #[tokio::test]
+    async fn a_crashed_backend_is_started_again() {
+        let supervisor = spawn("sleep 0.2; exit 1", fast(5));
+        supervisor.start();
+        let BackendState::Running { pid: first, .. } =
+            wait_for(&supervisor, |s| s.is_running()).await
+        else {
+            unreachable!()
+        };
+        let restarted = wait_for(
+            &supervisor,
+            |s| matches!(s, BackendState::Running { pid, .. } if *pid != first),
+        )
+        .await;
+        assert!(restarted.is_running());
+        assert!(supervisor.state_history().iter().any(|change| {
+            change.state
+                == BackendState::Restarting {
+                    attempt: 1,
+                    delay_ms: 10,
+                    exit_code: Some(1),
+                }
+        }));
+        assert!(supervisor.stop().await);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_restarts_with_growing_delays() {
+        let supervisor = spawn("exit 7", fast(3));
+        supervisor.start();
+        let failed = wait_for(&supervisor, |s| matches!(s, BackendState::Failed { .. })).await;
+        assert!(matches!(
+            failed,
+            BackendState::Failed {
+                exit_code: Some(7),
+                ..
+            }
+        ));
+
+        let delays: Vec<(u32, u64)> = supervisor
+            .state_history()
+            .into_iter()
+            .filter_map(|change| match change.state {
+                BackendState::Restarting {
+                    attempt, delay_ms, ..
+                } => Some((attempt, delay_ms)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(delays, [(1, 10), (2, 20), (3, 25)]);
+
+        // Failed is no longer wanted
+        assert!(!supervisor.stop().await);
+    }
+
+    #[tokio::test]
+    async fn stop_cancels_a_pending_restart() {
+        let supervisor = spawn(
+            "exit 1",
+            SupervisorOptions {
+                backoff_initial: Duration::from_millis(300),
+                ..SupervisorOptions::default()
+            },
+        );
+        supervisor.start();
+        wait_for(&supervisor, |s| {
+            matches!(s, BackendState::Restarting { .. })
+        })
+        .await;
+        assert!(supervisor.stop().await);
+
+        // Well past the delay, nothing was started again
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert_eq!(supervisor.state(), BackendState::Stopped);
+        let history = supervisor.state_history();
+        let stopped = history
+            .iter()
+            .position(|change| change.state == BackendState::Stopped)
+            .unwrap();
+        assert_eq!(stopped, history.len() - 1);
+    }
+}
@@ -0,0 +1,142 @@
+//! The backend as the app runs it: the supervised process, its watchdog
+//! and health prober, with lifecycle changes, output and health forwarded
+//! to the webview as `backend://*` events.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use super::{
+    health, profile_launcher, watchdog, BackendEndpoint, BackendHealth, BackendState,
+    BackendSupervisor, HealthProbe, SupervisorOptions, Watchdog, WatchdogEvent, WatchdogOptions,
+};
+use crate::app_dirs::DIAGNOSTICS_DIR;
+use crate::encryption::DbEncryption;
+use crate::logs::LogStore;
+use crate::profile::ProfileStore;
+use crate::secrets::SecretStore;
+use crate::settings::SettingsStore;
+
+const HEALTH_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn spawn_supervisor(
+    app: &tauri::App,
+    endpoint: BackendEndpoint,
+    profiles: ProfileStore,
+    secrets: SecretStore,
+    encryption: DbEncryption,
+    logs: LogStore,
+    autostart: bool,
+) -> BackendSupervisor {
+    let resource_dir = app.path().resource_dir().ok();
+    let stack_dump_file = diagnostics_dir(app.handle()).map(|dir| watchdog::stack_dump_file(&dir));
+    let supervisor = BackendSupervisor::spawn(
+        profile_launcher(
+            resource_dir,
+            endpoint,
+            profiles,
+            secrets,
+            encryption,
+            stack_dump_file,
+        ),
+        SupervisorOptions::default(),
+    );
+
+    // Forward lifecycle changes and captured output to the webview, and
+    // keep both in the log files
+    let handle = app.handle().clone();
+    let mut states = supervisor.subscribe();
+    tauri::async_runtime::spawn(async move {
+        while states.changed().await.is_ok() {
+            let state = states.borrow_and_update().clone();
+            match &state {
+                BackendState::Failed { .. } => error!(?state, "Backend state changed"),
+                _ => info!(?state, "Backend state changed"),
+            }
+            let _ = handle.emit("backend://state", state);
+        }
+    });
+
+    let handle = app.handle().clone();
+    let mut lines = supervisor.subscribe_logs();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match lines.recv().await {
+                Ok(line) => {
+                    logs.record_backend(&line);
+                    let _ = handle.emit("backend://log", line);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("Dropped {n} backend log lines");
+                }
+                Err(_) => break,
+            }
+        }
+    });
+
+    if autostart {
+        supervisor.start();
+    }
+
+    supervisor
+}
+
+/// Where hang dumps and other diagnostics the shell collects by itself go.
+pub fn diagnostics_dir(app: &AppHandle) -> Option<PathBuf> {
+    Some(app.path().app_log_dir().ok()?.join(DIAGNOSTICS_DIR))
+}
+
+/// Restart the backend when it stops answering, after saving its stacks.
+/// Steps go to the webview as `backend://watchdog`. Without a log dir to
+/// save the stacks to there is no watchdog.
+pub fn spawn_watchdog(app: &tauri::App, supervisor: BackendSupervisor, endpoint: BackendEndpoint) {
+    let handle = app.handle().clone();
+    let Some(dump_dir) = diagnostics_dir(&handle) else {
+        warn!("No log dir for hang dumps; the backend watchdog is off");
+        return;
+    };
+    let watchdog = Watchdog::spawn(supervisor, endpoint.url(), dump_dir, move || {
+        let settings = handle.state::<SettingsStore>().get();
+        (settings.watchdog_latency_ms > 0).then(|| WatchdogOptions {
+            latency_slo: Duration::from_millis(settings.watchdog_latency_ms.into()),
+            misses: settings.watchdog_misses,
+        })
+    });
+
+    let handle = app.handle().clone();
+    let mut events = watchdog.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    match &event {
+                        WatchdogEvent::Slow { .. } | WatchdogEvent::Recovered { .. } => {
+                            info!(?event, "Backend watchdog")
+                        }
+                        WatchdogEvent::DumpFailed { .. } => warn!(?event, "Backend watchdog"),
+                        _ => error!(?event, "Backend watchdog"),
+                    }
+                    let _ = handle.emit("backend://watchdog", event);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+pub fn spawn_health_prober(app: &tauri::App, probe: HealthProbe) -> watch::Receiver<BackendHealth> {
+    let handle = app.handle().clone();
+    let reports = health::spawn_prober(probe, HEALTH_PROBE_INTERVAL);
+    let mut health = reports.clone();
+    tauri::async_runtime::spawn(async move {
+        while health.changed().await.is_ok() {
+            let report = health.borrow_and_update().clone();
+            let _ = handle.emit("backend://health", report);
+        }
+    });
+    reports
+}
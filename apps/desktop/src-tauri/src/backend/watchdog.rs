@@ -0,0 +1,584 @@
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::supervisor::{BackendState, BackendSupervisor};
+use crate::runtime::now_ms;
+
+/// Environment variable naming the file the backend appends thread stacks
+/// to on `SIGUSR1`; see `observability/stack_dump.py`.
+pub const STACK_DUMP_FILE_ENV: &str = "DESKFLOW_STACK_DUMP_FILE";
+
+const STACK_DUMP_FILE: &str = "backend-stacks.txt";
+const HANG_DUMP_PREFIX: &str = "hang-";
+const MAX_HANG_DUMPS: usize = 20;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// A backend that has just come up may still be loading models and skills.
+const STARTUP_GRACE: Duration = Duration::from_secs(15);
+const PY_SPY: &str = "py-spy";
+const PY_SPY_TIMEOUT: Duration = Duration::from_secs(20);
+#[cfg(unix)]
+const SIGNAL_DUMP_WAIT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchdogOptions {
+    /// `/api/health` answering slower than this counts as a miss.
+    pub latency_slo: Duration,
+    /// Consecutive misses that make a hang.
+    pub misses: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DumpMethod {
+    PySpy,
+    Signal,
+}
+
+/// What the watchdog saw and did, emitted as `backend://watchdog`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum WatchdogEvent {
+    /// A health check answered too slowly, or not within the SLO at all.
+    Slow {
+        latency_ms: Option<u64>,
+        slo_ms: u64,
+        misses: u32,
+    },
+    HangDetected {
+        pid: u32,
+        misses: u32,
+    },
+    Dumped {
+        path: String,
+        method: DumpMethod,
+    },
+    DumpFailed {
+        error: String,
+    },
+    Restarting,
+    /// Health checks are within the SLO again after misses or a restart.
+    Recovered {
+        latency_ms: u64,
+    },
+}
+
+enum Probe {
+    Fast(Duration),
+    Slow(Option<Duration>),
+    /// Nothing listening: starting up or crashed, which is the supervisor's
+    /// business.
+    Unreachable,
+}
+
+/// Watches a supervised backend whose port is open but which has stopped
+/// answering, e.g. because a tool call blocks its event loop. After
+/// [`WatchdogOptions::misses`] slow health checks in a row it saves the
+/// backend's thread stacks to the dump directory and restarts it.
+///
+/// Stacks come from `py-spy dump` when py-spy is installed, else (on Unix)
+/// from the backend itself via `SIGUSR1`. The latter relies on the backend
+/// having installed its handler; one that has not is killed by the signal,
+/// which the restart that follows makes harmless.
+#[derive(Clone)]
+pub struct Watchdog {
+    events: broadcast::Sender<WatchdogEvent>,
+}
+
+impl Watchdog {
+    /// Start watching. `options` is called before every check so settings
+    /// changes apply right away; `None` pauses the watchdog.
+    pub fn spawn<F>(
+        supervisor: BackendSupervisor,
+        base_url: impl Into<String>,
+        dump_dir: PathBuf,
+        options: F,
+    ) -> Self
+    where
+        F: Fn() -> Option<WatchdogOptions> + Send + Sync + 'static,
+    {
+        let (events, _) = broadcast::channel(32);
+        let worker = Worker::new(supervisor, base_url.into(), dump_dir, events.clone());
+        crate::runtime::spawn(worker.run(options));
+        Self { events }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchdogEvent> {
+        self.events.subscribe()
+    }
+}
+
+/// The file a backend started with [`STACK_DUMP_FILE_ENV`] should append
+/// its stacks to.
+pub fn stack_dump_file(dump_dir: &Path) -> PathBuf {
+    dump_dir.join(STACK_DUMP_FILE)
+}
+
+/// Saved hang dumps, oldest first.
+pub fn hang_dumps(dump_dir: &Path) -> Vec<PathBuf> {
+    let mut dumps: Vec<PathBuf> = fs::read_dir(dump_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(HANG_DUMP_PREFIX))
+        })
+        .collect();
+    // Named by timestamp
+    dumps.sort();
+    dumps
+}
+
+/// Delete all but the newest [`MAX_HANG_DUMPS`] hang dumps.
+fn prune_hang_dumps(dump_dir: &Path) {
+    let dumps = hang_dumps(dump_dir);
+    for old in &dumps[..dumps.len().saturating_sub(MAX_HANG_DUMPS)] {
+        let _ = fs::remove_file(old);
+    }
+}
+
+struct Worker {
+    supervisor: BackendSupervisor,
+    http: reqwest::Client,
+    url: String,
+    dump_dir: PathBuf,
+    events: broadcast::Sender<WatchdogEvent>,
+    check_interval: Duration,
+    startup_grace: Duration,
+}
+
+impl Worker {
+    fn new(
+        supervisor: BackendSupervisor,
+        base_url: String,
+        dump_dir: PathBuf,
+        events: broadcast::Sender<WatchdogEvent>,
+    ) -> Self {
+        Self {
+            supervisor,
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            url: format!("{base_url}/api/health"),
+            dump_dir,
+            events,
+            check_interval: CHECK_INTERVAL,
+            startup_grace: STARTUP_GRACE,
+        }
+    }
+
+    async fn run<F>(self, options: F)
+    where
+        F: Fn() -> Option<WatchdogOptions>,
+    {
+        let mut ticker = tokio::time::interval(self.check_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut misses = 0u32;
+        let mut recovering = false;
+
+        loop {
+            ticker.tick().await;
+            let (Some(options), BackendState::Running { pid, started_at_ms }) =
+                (options(), self.supervisor.state())
+            else {
+                misses = 0;
+                continue;
+            };
+            if now_ms().saturating_sub(started_at_ms) < self.startup_grace.as_millis() as u64 {
+                continue;
+            }
+
+            match self.probe(options.latency_slo).await {
+                Probe::Fast(latency) => {
+                    if recovering {
+                        self.emit(WatchdogEvent::Recovered {
+                            latency_ms: latency.as_millis() as u64,
+                        });
+                    }
+                    misses = 0;
+                    recovering = false;
+                }
+                Probe::Slow(latency) => {
+                    misses += 1;
+                    recovering = true;
+                    self.emit(WatchdogEvent::Slow {
+                        latency_ms: latency.map(|l| l.as_millis() as u64),
+                        slo_ms: options.latency_slo.as_millis() as u64,
+                        misses,
+                    });
+                    if misses >= options.misses.max(1) {
+                        self.handle_hang(pid, misses).await;
+                        misses = 0;
+                    }
+                }
+                Probe::Unreachable => misses = 0,
+            }
+        }
+    }
+
+    async fn probe(&self, slo: Duration) -> Probe {
+        let started = Instant::now();
+        match tokio::time::timeout(slo, self.http.get(&self.url).send()).await {
+            Err(_) => Probe::Slow(None),
+            Ok(Err(e)) if e.is_connect() => Probe::Unreachable,
+            Ok(Err(_)) => Probe::Slow(None),
+            Ok(Ok(_)) => {
+                let latency = started.elapsed();
+                if latency > slo {
+                    Probe::Slow(Some(latency))
+                } else {
+                    Probe::Fast(latency)
+                }
+            }
+        }
+    }
+
+    async fn handle_hang(&self, pid: u32, misses: u32) {
+        self.emit(WatchdogEvent::HangDetected { pid, misses });
+        match dump_stacks(pid, &self.dump_dir).await {
+            Ok((path, method)) => self.emit(WatchdogEvent::Dumped {
+                path: path.to_string_lossy().into_owned(),
+                method,
+            }),
+            Err(error) => self.emit(WatchdogEvent::DumpFailed { error }),
+        }
+        // Only restart the process that hung, not one started meanwhile
+        if matches!(self.supervisor.state(), BackendState::Running { pid: current, .. } if current == pid)
+        {
+            self.emit(WatchdogEvent::Restarting);
+            self.supervisor.restart();
+        }
+    }
+
+    fn emit(&self, event: WatchdogEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+/// Save the stacks of every thread in the backend to a new file in
+/// `dump_dir`.
+async fn dump_stacks(pid: u32, dump_dir: &Path) -> Result<(PathBuf, DumpMethod), String> {
+    fs::create_dir_all(dump_dir).map_err(|e| e.to_string())?;
+    let dest = dump_dir.join(format!("{HANG_DUMP_PREFIX}{}-{pid}.txt", now_ms() / 1000));
+
+    let (stacks, method) = match py_spy_dump(pid).await {
+        Ok(stacks) => (stacks, DumpMethod::PySpy),
+        Err(py_spy) => match signal_dump(pid, &stack_dump_file(dump_dir)).await {
+            Ok(stacks) => (stacks, DumpMethod::Signal),
+            Err(signal) => return Err(format!("{py_spy}; {signal}")),
+        },
+    };
+    fs::write(&dest, stacks).map_err(|e| e.to_string())?;
+    prune_hang_dumps(dump_dir);
+    Ok((dest, method))
+}
+
+async fn py_spy_dump(pid: u32) -> Result<String, String> {
+    let output = tokio::process::Command::new(PY_SPY)
+        .args(["dump", "--pid", &pid.to_string()])
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(PY_SPY_TIMEOUT, output).await {
+        Err(_) => Err("py-spy timed out".to_string()),
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err("py-spy is not installed".to_string())
+        }
+        Ok(Err(e)) => Err(format!("py-spy failed: {e}")),
+        Ok(Ok(output)) if !output.status.success() => Err(format!(
+            "py-spy failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Ok(Ok(output)) => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+    }
+}
+
+/// Ask the backend to append its stacks to `file` and return what it
+/// wrote.
+#[cfg(unix)]
+async fn signal_dump(pid: u32, file: &Path) -> Result<String, String> {
+    let before = fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+    // SAFETY: plain kill(2) on the pid of our own child.
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGUSR1) } != 0 {
+        return Err(format!(
+            "SIGUSR1 failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    // faulthandler writes from the signal handler in one go; wait until
+    // the file stops growing
+    let deadline = Instant::now() + SIGNAL_DUMP_WAIT;
+    let mut size = before;
+    loop {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let current = fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+        if current > before && current == size {
+            break;
+        }
+        if Instant::now() >= deadline {
+            if current > before {
+                break;
+            }
+            return Err("the backend wrote no stacks after SIGUSR1".to_string());
+        }
+        size = current;
+    }
+
+    let mut stacks = String::new();
+    let mut dump = fs::File::open(file).map_err(|e| e.to_string())?;
+    dump.seek(SeekFrom::Start(before))
+        .and_then(|_| dump.read_to_string(&mut stacks))
+        .map_err(|e| e.to_string())?;
+    Ok(stacks)
+}
+
+#[cfg(not(unix))]
+async fn signal_dump(_pid: u32, _file: &Path) -> Result<String, String> {
+    Err("no stack dump signal on this platform".to_string())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use axum::routing::get;
+    use axum::Router;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::backend::{BackendCommand, SupervisorOptions};
+
+    const WAIT: Duration = Duration::from_secs(10);
+    /// Stands in for the backend: answers `SIGUSR1` the way its
+    /// faulthandler does, by appending its stacks to the dump file.
+    const BACKEND: &str = r#"trap 'echo "stacks of $$" >> "$DESKFLOW_STACK_DUMP_FILE"' USR1
+while :; do sleep 0.05; done"#;
+
+    fn options(misses: u32) -> Option<WatchdogOptions> {
+        Some(WatchdogOptions {
+            latency_slo: Duration::from_millis(50),
+            misses,
+        })
+    }
+
+    /// `/api/health` that answers after however many milliseconds `delay`
+    /// holds at the time.
+    async fn health_server(delay: Arc<AtomicU64>) -> String {
+        let app = Router::new().route(
+            "/api/health",
+            get(move || {
+                let delay = Duration::from_millis(delay.load(Ordering::SeqCst));
+                async move {
+                    tokio::time::sleep(delay).await;
+                    "{}"
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    fn backend(dump_dir: &Path) -> BackendCommand {
+        BackendCommand::new("sh").arg("-c").arg(BACKEND).env(
+            STACK_DUMP_FILE_ENV,
+            stack_dump_file(dump_dir).to_string_lossy(),
+        )
+    }
+
+    async fn running_backend(dump_dir: &Path) -> (BackendSupervisor, u32) {
+        let dump_dir = dump_dir.to_path_buf();
+        let supervisor =
+            BackendSupervisor::spawn(move || backend(&dump_dir), SupervisorOptions::default());
+        supervisor.start();
+        let pid = running_pid(&supervisor, None).await;
+        (supervisor, pid)
+    }
+
+    /// The pid of the running backend, once it is one other than `not`.
+    async fn running_pid(supervisor: &BackendSupervisor, not: Option<u32>) -> u32 {
+        let mut states = supervisor.subscribe();
+        let state = tokio::time::timeout(
+            WAIT,
+            states
+                .wait_for(|s| matches!(s, BackendState::Running { pid, .. } if Some(*pid) != not)),
+        )
+        .await
+        .expect("the backend did not come up")
+        .unwrap()
+        .clone();
+        match state {
+            BackendState::Running { pid, .. } => pid,
+            _ => unreachable!(),
+        }
+    }
+
+    fn worker(
+        supervisor: BackendSupervisor,
+        url: String,
+        dump_dir: &Path,
+    ) -> (Worker, broadcast::Receiver<WatchdogEvent>) {
+        let (events, received) = broadcast::channel(64);
+        let mut worker = Worker::new(supervisor, url, dump_dir.to_path_buf(), events);
+        worker.check_interval = Duration::from_millis(20);
+        worker.startup_grace = Duration::ZERO;
+        (worker, received)
+    }
+
+    async fn next(events: &mut broadcast::Receiver<WatchdogEvent>) -> WatchdogEvent {
+        tokio::time::timeout(WAIT, events.recv())
+            .await
+            .expect("no watchdog event")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn consecutive_misses_dump_the_stacks_and_restart() {
+        let dir = TempDir::new().unwrap();
+        let delay = Arc::new(AtomicU64::new(300));
+        let url = health_server(delay.clone()).await;
+        let (supervisor, pid) = running_backend(dir.path()).await;
+        let (worker, mut events) = worker(supervisor.clone(), url, dir.path());
+        tokio::spawn(worker.run(|| options(2)));
+
+        for expected in 1..=2 {
+            match next(&mut events).await {
+                WatchdogEvent::Slow {
+                    latency_ms: None,
+                    slo_ms: 50,
+                    misses,
+                } => assert_eq!(misses, expected),
+                other => panic!("expected a miss, got {other:?}"),
+            }
+        }
+        assert!(matches!(
+            next(&mut events).await,
+            WatchdogEvent::HangDetected { pid: hung, misses: 2 } if hung == pid
+        ));
+        let WatchdogEvent::Dumped {
+            path,
+            method: DumpMethod::Signal,
+        } = next(&mut events).await
+        else {
+            panic!("expected a dump");
+        };
+        let stacks = fs::read_to_string(&path).unwrap();
+        assert_eq!(stacks.trim(), format!("stacks of {pid}"));
+        assert_eq!(hang_dumps(dir.path()), [PathBuf::from(path)]);
+        assert!(matches!(next(&mut events).await, WatchdogEvent::Restarting));
+        running_pid(&supervisor, Some(pid)).await;
+    }
+
+    #[tokio::test]
+    async fn a_fast_answer_resets_the_count() {
+        let dir = TempDir::new().unwrap();
+        let delay = Arc::new(AtomicU64::new(300));
+        let url = health_server(delay.clone()).await;
+        let (supervisor, _) = running_backend(dir.path()).await;
+        let (worker, mut events) = worker(supervisor, url, dir.path());
+        tokio::spawn(worker.run(|| options(10)));
+
+        assert!(matches!(
+            next(&mut events).await,
+            WatchdogEvent::Slow { misses: 1, .. }
+        ));
+        delay.store(0, Ordering::SeqCst);
+        // A check already under way may still miss
+        loop {
+            match next(&mut events).await {
+                WatchdogEvent::Slow { .. } => continue,
+                WatchdogEvent::Recovered { latency_ms } => {
+                    assert!(latency_ms <= 50);
+                    break;
+                }
+                other => panic!("expected recovery, got {other:?}"),
+            }
+        }
+        delay.store(300, Ordering::SeqCst);
+        assert!(matches!(
+            next(&mut events).await,
+            WatchdogEvent::Slow { misses: 1, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_checks_while_starting_up_or_switched_off() {
+        let dir = TempDir::new().unwrap();
+        let url = health_server(Arc::new(AtomicU64::new(300))).await;
+        let (supervisor, _) = running_backend(dir.path()).await;
+
+        let (mut starting, mut grace_events) = worker(supervisor.clone(), url.clone(), dir.path());
+        starting.startup_grace = Duration::from_secs(3600);
+        tokio::spawn(starting.run(|| options(1)));
+        let (off, mut off_events) = worker(supervisor, url, dir.path());
+        tokio::spawn(off.run(|| None));
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(grace_events.try_recv().is_err());
+        assert!(off_events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn only_the_process_that_hung_is_restarted() {
+        let dir = TempDir::new().unwrap();
+        let (supervisor, pid) = running_backend(dir.path()).await;
+        // A backend from before a restart, still around to be dumped
+        let mut old = backend(dir.path()).to_command().spawn().unwrap();
+        let old_pid = old.id().unwrap();
+        let (worker, mut events) = worker(supervisor.clone(), String::new(), dir.path());
+
+        worker.handle_hang(old_pid, 3).await;
+        assert!(matches!(
+            next(&mut events).await,
+            WatchdogEvent::HangDetected { pid: hung, misses: 3 } if hung == old_pid
+        ));
+        assert!(matches!(
+            next(&mut events).await,
+            WatchdogEvent::Dumped { .. }
+        ));
+        assert!(events.try_recv().is_err());
+        assert!(
+            matches!(supervisor.state(), BackendState::Running { pid: current, .. } if current == pid)
+        );
+        let _ = old.kill().await;
+    }
+
+    #[test]
+    fn hang_dumps_are_listed_oldest_first_and_pruned() {
+        let dir = TempDir::new().unwrap();
+        fs::write(stack_dump_file(dir.path()), "not a hang dump").unwrap();
+        let names: Vec<String> = (0..MAX_HANG_DUMPS + 3)
+            .rev()
+            .map(|i| format!("{HANG_DUMP_PREFIX}{}-42.txt", 1_760_000_000 + i))
+            .collect();
+        for name in &names {
+            fs::write(dir.path().join(name), "stacks").unwrap();
+        }
+
+        let listed = |dir: &Path| -> Vec<String> {
+            hang_dumps(dir)
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        let mut oldest_first = names.clone();
+        oldest_first.reverse();
+        assert_eq!(listed(dir.path()), oldest_first);
+
+        prune_hang_dumps(dir.path());
+        assert_eq!(listed(dir.path()), oldest_first[3..]);
+        assert!(stack_dump_file(dir.path()).is_file());
+        assert!(hang_dumps(&dir.path().join("missing")).is_empty());
+    }
+}
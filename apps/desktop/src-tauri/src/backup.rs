@@ -0,0 +1,766 @@
+//! Profile archives: everything that makes up an agent (its databases,
+//! `identity/`, `skills/`, channel configs and other files in the profile's
+//! data and config directories) in one zip file that can be restored on
+//! another machine.
+//!
+//! An archive holds `manifest.json` plus the files under `data/` and
+//! `config/`. The manifest records the format version, the backend schema
+//! version of the database and the size and SHA-256 of every file; import
+//! refuses archives that do not match it. Databases are written as
+//! consistent snapshots (see [`DbEncryption::snapshot`]), so an export does
+//! not need the backend stopped, though the shell stops it for manual
+//! exports to catch files the backend writes outside SQLite.
+//!
+//! Encrypted databases stay encrypted with their profile's key unless the
+//! export asks for decrypted copies. Such an archive can only be imported
+//! where that key is in the keyring, i.e. on the same machine.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::encryption::{self, DbEncryption, DbState, EncryptionError};
+use crate::profile::{ProfileDirs, ProfileError, ProfileInfo, ProfileStore};
+use crate::runtime::now_secs;
+
+pub const ARCHIVE_EXTENSION: &str = "dfprofile";
+
+/// Newest backend schema this shell can restore; keep in step with
+/// `SCHEMA_VERSION` in `memory/storage.py`.
+pub const SCHEMA_VERSION: i64 = 1;
+
+const FORMAT: &str = "deskflow-profile";
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const DATA_PREFIX: &str = "data";
+const CONFIG_PREFIX: &str = "config";
+
+// Where the profile database and the chat history live relative to the
+// data dir
+const DB_RELATIVE_PATH: &str = "db/deskflow.db";
+const CONVERSATIONS_RELATIVE_PATH: &str = crate::history::CONVERSATIONS_DB;
+
+// Files SQLite and the shell keep next to a database while working on it
+// (the snapshot already contains what matters), and the running instance's
+// lock and port files
+const SKIPPED_SUFFIXES: &[&str] = &[
+    "-wal",
+    "-shm",
+    "-journal",
+    ".plaintext",
+    ".encrypting",
+    ".tmp",
+    ".lock",
+    ".port",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    /// Name of the profile the archive was made from.
+    pub profile: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    /// Backend schema version of the database, if the profile had one.
+    pub schema_version: Option<i64>,
+    /// Whether the databases are encrypted with the source profile's key.
+    pub database_encrypted: bool,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path inside the archive, e.g. `data/identity/SOUL.md`.
+    pub path: String,
+    pub size: u64,
+    /// Lowercase hex.
+    pub sha256: String,
+}
+
+#[derive(Debug)]
+pub enum BackupError {
+    /// Not an archive of ours, or one that does not match its manifest.
+    Invalid(String),
+    /// The archive comes from a newer backend than this one.
+    UnsupportedSchema(i64),
+    /// The database is encrypted with a key this machine does not have.
+    KeyUnavailable(String),
+    Encryption(EncryptionError),
+    Profile(ProfileError),
+    Sqlite(rusqlite::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupError::Invalid(reason) => write!(f, "Invalid profile archive: {reason}"),
+            BackupError::UnsupportedSchema(version) => write!(
+                f,
+                "The archive's database has schema version {version}, this version of DeskFlow supports up to {SCHEMA_VERSION}; update DeskFlow first"
+            ),
+            BackupError::KeyUnavailable(profile) => write!(
+                f,
+                "The archive's database is encrypted with the key of profile \"{profile}\", which is not in this machine's keyring; export it again with a decrypted database"
+            ),
+            BackupError::Encryption(e) => e.fmt(f),
+            BackupError::Profile(e) => e.fmt(f),
+            BackupError::Sqlite(e) => write!(f, "Database error: {e}"),
+            BackupError::Io(e) => write!(f, "Backup I/O error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
+impl From<EncryptionError> for BackupError {
+    fn from(err: EncryptionError) -> Self {
+        BackupError::Encryption(err)
+    }
+}
+
+impl From<ProfileError> for BackupError {
+    fn from(err: ProfileError) -> Self {
+        BackupError::Profile(err)
+    }
+}
+
+impl From<rusqlite::Error> for BackupError {
+    fn from(err: rusqlite::Error) -> Self {
+        BackupError::Sqlite(err)
+    }
+}
+
+impl From<io::Error> for BackupError {
+    fn from(err: io::Error) -> Self {
+        BackupError::Io(err)
+    }
+}
+
+impl From<ZipError> for BackupError {
+    fn from(err: ZipError) -> Self {
+        match err {
+            ZipError::Io(e) => BackupError::Io(e),
+            other => BackupError::Invalid(other.to_string()),
+        }
+    }
+}
+
+pub type BackupResult<T> = Result<T, BackupError>;
+
+/// Write an archive of the profile `dirs` to `dest`, replacing it. With
+/// `decrypt` an encrypted database goes into the archive in plaintext.
+pub fn export(
+    encryption: &DbEncryption,
+    dirs: &ProfileDirs,
+    dest: &Path,
+    decrypt: bool,
+) -> BackupResult<Manifest> {
+    // Database snapshots are made in the cache dir, which is not archived
+    let scratch = dirs.cache.join(".backup");
+    remove_dir_if_exists(&scratch)?;
+    fs::create_dir_all(&scratch)?;
+    let tmp = sibling(dest, "tmp");
+    let result = write_archive(encryption, dirs, &scratch, &tmp, decrypt)
+        .and_then(|manifest| Ok(fs::rename(&tmp, dest).map(|_| manifest)?));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+/// Restore an archive as a new profile called `name`, or the name it was
+/// exported under. Never touches existing profiles.
+pub fn import(
+    encryption: &DbEncryption,
+    profiles: &ProfileStore,
+    archive: &Path,
+    name: Option<&str>,
+) -> BackupResult<ProfileInfo> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    let manifest = read_manifest(&mut zip)?;
+    let name = name.unwrap_or(&manifest.profile).trim().to_string();
+    if profiles.list().iter().any(|p| p.info.name == name) {
+        return Err(ProfileError::AlreadyExists(name).into());
+    }
+
+    // Unpack next to the profiles so moving them in is a rename; names
+    // starting with a dot are never valid profile names
+    let staging = profiles
+        .current()
+        .data
+        .with_file_name(format!(".import-{}", std::process::id()));
+    remove_dir_if_exists(&staging)?;
+    let result = extract(&mut zip, &manifest, &staging)
+        .and_then(|_| check_database(encryption, &manifest, &staging))
+        .and_then(|_| restore(encryption, profiles, &manifest, &staging, &name));
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// Archives of `profile` in `dir`, newest first, with their creation time
+/// in seconds since the Unix epoch.
+pub fn list_backups(dir: &Path, profile: &str) -> io::Result<Vec<(u64, PathBuf)>> {
+    let prefix = format!("{profile}-");
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let created = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_prefix(&prefix))
+            .and_then(|n| n.strip_suffix(&format!(".{ARCHIVE_EXTENSION}")))
+            .and_then(|secs| secs.parse::<u64>().ok());
+        if let Some(created) = created {
+            backups.push((created, path));
+        }
+    }
+    backups.sort_by_key(|(created, _)| std::cmp::Reverse(*created));
+    Ok(backups)
+}
+
+/// Make a backup of `dirs` in `dir` and delete all but the newest `keep`.
+pub fn backup(
+    encryption: &DbEncryption,
+    dirs: &ProfileDirs,
+    dir: &Path,
+    keep: usize,
+) -> BackupResult<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let dest = dir.join(format!("{}-{}.{ARCHIVE_EXTENSION}", dirs.name, now_secs()));
+    export(encryption, dirs, &dest, false)?;
+    for (_, old) in list_backups(dir, &dirs.name)?.into_iter().skip(keep.max(1)) {
+        fs::remove_file(old)?;
+    }
+    Ok(dest)
+}
+
+/// Back up `dirs` into [`ProfileDirs::backups`] unless its newest backup
+/// there is younger than `every`, keeping the newest `keep`.
+pub fn backup_if_due(
+    encryption: &DbEncryption,
+    dirs: &ProfileDirs,
+    every: Duration,
+    keep: usize,
+) -> BackupResult<Option<PathBuf>> {
+    let dir = dirs.backups();
+    let newest = list_backups(&dir, &dirs.name)?
+        .first()
+        .map(|(created, _)| *created);
+    if newest.is_some_and(|created| now_secs() < created + every.as_secs()) {
+        return Ok(None);
+    }
+    backup(encryption, dirs, &dir, keep).map(Some)
+}
+
+fn write_archive(
+    encryption: &DbEncryption,
+    dirs: &ProfileDirs,
+    scratch: &Path,
+    dest: &Path,
+    decrypt: bool,
+) -> BackupResult<Manifest> {
+    let mut manifest = Manifest {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        profile: dirs.name.clone(),
+        created_at: now_secs(),
+        schema_version: None,
+        database_encrypted: false,
+        files: Vec::new(),
+    };
+
+    let mut sources = Vec::new();
+    for (prefix, root) in [(DATA_PREFIX, &dirs.data), (CONFIG_PREFIX, &dirs.config)] {
+        for relative in walk(root)? {
+            let path = root.join(&relative);
+            let entry = archive_path(prefix, &relative);
+            let state = encryption::state(&path)?;
+            let source = if state == DbState::Missing {
+                path
+            } else if path == dirs.db_path() {
+                let copy = scratch.join(sources.len().to_string());
+                let encrypted = encryption.snapshot(dirs, &path, &copy, decrypt)?;
+                manifest.database_encrypted |= encrypted;
+                manifest.schema_version = schema_version(&if encrypted {
+                    encryption.open_copy(&copy, &dirs.name)?
+                } else {
+                    Connection::open(&copy)?
+                })?;
+                copy
+            } else if path == dirs.conversations_db_path() {
+                let copy = scratch.join(sources.len().to_string());
+                manifest.database_encrypted |= encryption.snapshot(dirs, &path, &copy, decrypt)?;
+                copy
+            } else if state == DbState::Plaintext {
+                let copy = scratch.join(sources.len().to_string());
+                encryption::snapshot_plaintext(&path, &copy)?;
+                copy
+            } else {
+                path
+            };
+            sources.push((entry, source));
+        }
+    }
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(File::create(dest)?);
+    for (entry, source) in sources {
+        let size = fs::metadata(&source)?.len();
+        zip.start_file(entry.as_str(), options.large_file(size >= u32::MAX as u64))?;
+        let (size, sha256) = copy_hashed(&mut File::open(&source)?, &mut zip)?;
+        manifest.files.push(ManifestFile {
+            path: entry,
+            size,
+            sha256,
+        });
+    }
+    zip.start_file(MANIFEST, options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest).map_err(io::Error::from)?;
+    zip.finish()?.sync_all()?;
+    Ok(manifest)
+}
+
+fn read_manifest(zip: &mut ZipArchive<File>) -> BackupResult<Manifest> {
+    let file = match zip.by_name(MANIFEST) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => {
+            return Err(BackupError::Invalid(format!("no {MANIFEST}")));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let manifest: Manifest = serde_json::from_reader(file)
+        .map_err(|e| BackupError::Invalid(format!("unreadable {MANIFEST}: {e}")))?;
+    if manifest.format != FORMAT {
+        return Err(BackupError::Invalid(format!(
+            "unknown format \"{}\"",
+            manifest.format
+        )));
+    }
+    if manifest.version > FORMAT_VERSION {
+        return Err(BackupError::Invalid(format!(
+            "format version {} is newer than this version of DeskFlow supports",
+            manifest.version
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Unpack every file the manifest lists into `staging`, checking sizes and
+/// checksums on the way.
+fn extract(zip: &mut ZipArchive<File>, manifest: &Manifest, staging: &Path) -> BackupResult<()> {
+    let mut expected: HashMap<&str, &ManifestFile> = manifest
+        .files
+        .iter()
+        .map(|file| (file.path.as_str(), file))
+        .collect();
+
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() || file.name() == MANIFEST {
+            continue;
+        }
+        let listed = expected
+            .remove(file.name())
+            .ok_or_else(|| BackupError::Invalid(format!("unlisted file {}", file.name())))?;
+        let relative = file
+            .enclosed_name()
+            .filter(|path| {
+                matches!(path.components().next(), Some(Component::Normal(first))
+                    if first == DATA_PREFIX || first == CONFIG_PREFIX)
+            })
+            .ok_or_else(|| BackupError::Invalid(format!("unsafe path {}", file.name())))?;
+
+        let dest = staging.join(relative);
+        if let Some(dir) = dest.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let (size, sha256) = copy_hashed(&mut file, &mut File::create(&dest)?)?;
+        if size != listed.size || sha256 != listed.sha256 {
+            return Err(BackupError::Invalid(format!(
+                "checksum mismatch for {}",
+                listed.path
+            )));
+        }
+    }
+
+    match expected.into_keys().next() {
+        Some(missing) => Err(BackupError::Invalid(format!("missing file {missing}"))),
+        None => Ok(()),
+    }
+}
+
+/// Make sure the backend here can run the unpacked database before it
+/// becomes a profile.
+fn check_database(
+    encryption: &DbEncryption,
+    manifest: &Manifest,
+    staging: &Path,
+) -> BackupResult<()> {
+    let db = staging.join(DATA_PREFIX).join(DB_RELATIVE_PATH);
+    if encryption::state(&db)? == DbState::Missing {
+        return Ok(());
+    }
+    let conn = match encryption.open_copy(&db, &manifest.profile) {
+        Err(EncryptionError::NoKey) => {
+            return Err(BackupError::KeyUnavailable(manifest.profile.clone()));
+        }
+        conn => conn?,
+    };
+    let version = schema_version(&conn)?;
+    // The chat history has no schema version, but must open with the key too
+    let history = staging.join(DATA_PREFIX).join(CONVERSATIONS_RELATIVE_PATH);
+    if encryption::state(&history)? == DbState::Encrypted {
+        match encryption.open_copy(&history, &manifest.profile) {
+            Err(EncryptionError::NoKey) => {
+                return Err(BackupError::KeyUnavailable(manifest.profile.clone()));
+            }
+            conn => conn?,
+        };
+    }
+    if version != manifest.schema_version {
+        return Err(BackupError::Invalid(
+            "the database does not match the manifest's schema version".to_string(),
+        ));
+    }
+    match version {
+        Some(version) if version > SCHEMA_VERSION => Err(BackupError::UnsupportedSchema(version)),
+        _ => Ok(()),
+    }
+}
+
+fn restore(
+    encryption: &DbEncryption,
+    profiles: &ProfileStore,
+    manifest: &Manifest,
+    staging: &Path,
+    name: &str,
+) -> BackupResult<ProfileInfo> {
+    let info = profiles.create(name)?;
+    let dirs = profiles.dirs(&info.name)?;
+    for (prefix, target) in [(DATA_PREFIX, &dirs.data), (CONFIG_PREFIX, &dirs.config)] {
+        let source = staging.join(prefix);
+        if source.exists() {
+            // Only the empty directories the new profile was created with
+            fs::remove_dir_all(target)?;
+            move_dir(&source, target)?;
+        }
+    }
+    fs::create_dir_all(&dirs.data)?;
+    fs::create_dir_all(&dirs.config)?;
+    if manifest.database_encrypted {
+        encryption.adopt_key(&manifest.profile, &dirs)?;
+    }
+    Ok(info)
+}
+
+/// Backend schema version recorded in a database, if it has been set up.
+pub(crate) fn schema_version(conn: &Connection) -> rusqlite::Result<Option<i64>> {
+    let has_table: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_table {
+        return Ok(None);
+    }
+    Ok(conn
+        .query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+            row.get(0)
+        })
+        .optional()?
+        .flatten())
+}
+
+/// Regular files below `root`, relative to it. Symlinks are not followed
+/// and scratch files next to databases are left out.
+pub(crate) fn walk(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(root.join(&dir)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let relative = dir.join(entry.file_name());
+            let kind = entry.file_type()?;
+            if kind.is_dir() {
+                pending.push(relative);
+            } else if kind.is_file() && !is_scratch(&relative) {
+                files.push(relative);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn is_scratch(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    SKIPPED_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// `identity/SOUL.md` under `data` → `data/identity/SOUL.md`, with forward
+/// slashes on every platform.
+fn archive_path(prefix: &str, relative: &Path) -> String {
+    let mut path = prefix.to_string();
+    for part in relative.components() {
+        path.push('/');
+        path.push_str(&part.as_os_str().to_string_lossy());
+    }
+    path
+}
+
+/// Copy `reader` to `writer`, returning the byte count and SHA-256.
+fn copy_hashed(reader: &mut impl Read, writer: &mut impl Write) -> io::Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+        size += n as u64;
+    }
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok((size, sha256))
+}
+
+/// Rename a directory, copying it when `to` is on another file system.
+fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    for relative in walk(from)? {
+        let dest = to.join(&relative);
+        if let Some(dir) = dest.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::copy(from.join(&relative), dest)?;
+    }
+    fs::create_dir_all(to)?;
+    fs::remove_dir_all(from)
+}
+
+fn sibling(path: &Path, tag: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{tag}"));
+    PathBuf::from(name)
+}
+
+fn remove_dir_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::ProfileRoots;
+    use crate::secrets::SecretStore;
+    use tempfile::TempDir;
+
+    /// A profile store with a default profile holding `identity/SOUL.md`
+    /// and a plaintext database at backend schema `schema`.
+    fn profiles(root: &TempDir, schema: i64) -> (DbEncryption, ProfileStore) {
+        let profiles = ProfileStore::load(ProfileRoots {
+            data: root.path().join("data"),
+            config: root.path().join("config"),
+            cache: root.path().join("cache"),
+        })
+        .unwrap();
+        let dirs = profiles.current();
+        fs::create_dir_all(dirs.data.join("identity")).unwrap();
+        fs::write(dirs.data.join("identity/SOUL.md"), "# Soul\n").unwrap();
+        fs::create_dir_all(dirs.db_path().parent().unwrap()).unwrap();
+        Connection::open(dirs.db_path())
+            .unwrap()
+            .execute_batch(&format!(
+                "CREATE TABLE schema_version (version INTEGER);
+                 INSERT INTO schema_version VALUES ({schema});"
+            ))
+            .unwrap();
+        let encryption = DbEncryption::new(SecretStore::vault(&root.path().join("vault")));
+        (encryption, profiles)
+    }
+
+    fn exported(root: &TempDir, encryption: &DbEncryption, profiles: &ProfileStore) -> PathBuf {
+        let archive = root.path().join(format!("default.{ARCHIVE_EXTENSION}"));
+        export(encryption, &profiles.current(), &archive, false).unwrap();
+        archive
+    }
+
+    fn unpack(archive: &Path) -> (Manifest, Vec<(String, Vec<u8>)>) {
+        let mut zip = ZipArchive::new(File::open(archive).unwrap()).unwrap();
+        let manifest = read_manifest(&mut zip).unwrap();
+        let mut entries = Vec::new();
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i).unwrap();
+            if file.name() != MANIFEST {
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes).unwrap();
+                entries.push((file.name().to_string(), bytes));
+            }
+        }
+        (manifest, entries)
+    }
+
+    fn repack(archive: &Path, manifest: &Manifest, entries: &[(String, Vec<u8>)]) {
+        let mut zip = ZipWriter::new(File::create(archive).unwrap());
+        for (name, bytes) in entries {
+            zip.start_file(name.as_str(), SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.start_file(MANIFEST, SimpleFileOptions::default())
+            .unwrap();
+        serde_json::to_writer(&mut zip, manifest).unwrap();
+        zip.finish().unwrap();
+    }
+
+    fn listed(path: &str, bytes: &[u8]) -> ManifestFile {
+        let (size, sha256) = copy_hashed(&mut &bytes[..], &mut io::sink()).unwrap();
+        ManifestFile {
+            path: path.to_string(),
+            size,
+            sha256,
+        }
+    }
+
+    fn imported(
+        encryption: &DbEncryption,
+        profiles: &ProfileStore,
+        archive: &Path,
+    ) -> BackupResult<ProfileInfo> {
+        let result = import(encryption, profiles, archive, Some("copy"));
+        if result.is_err() {
+            assert!(
+                profiles.dirs("copy").is_err(),
+                "a failed import left a profile"
+            );
+        }
+        result
+    }
+
+    #[test]
+    fn import_restores_an_export() {
+        let root = TempDir::new().unwrap();
+        let (encryption, profiles) = profiles(&root, SCHEMA_VERSION);
+        let archive = exported(&root, &encryption, &profiles);
+
+        let info = imported(&encryption, &profiles, &archive).unwrap();
+
+        let dirs = profiles.dirs(&info.name).unwrap();
+        assert_eq!(
+            fs::read_to_string(dirs.data.join("identity/SOUL.md")).unwrap(),
+            "# Soul\n"
+        );
+        let conn = Connection::open(dirs.db_path()).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), Some(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn import_refuses_tampered_bytes() {
+        let root = TempDir::new().unwrap();
+        let (encryption, profiles) = profiles(&root, SCHEMA_VERSION);
+        let archive = exported(&root, &encryption, &profiles);
+        let (manifest, mut entries) = unpack(&archive);
+        // Same size, different content
+        let soul = entries
+            .iter_mut()
+            .find(|(name, _)| name == "data/identity/SOUL.md")
+            .unwrap();
+        soul.1 = b"# Evil\n".to_vec();
+        repack(&archive, &manifest, &entries);
+
+        let err = imported(&encryption, &profiles, &archive).unwrap_err();
+        assert!(
+            matches!(&err, BackupError::Invalid(reason) if reason.contains("checksum mismatch")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn import_refuses_paths_leaving_the_profile() {
+        let root = TempDir::new().unwrap();
+        let (encryption, profiles) = profiles(&root, SCHEMA_VERSION);
+        let archive = exported(&root, &encryption, &profiles);
+
+        for path in ["../escaped.txt", "data/../../escaped.txt"] {
+            let (mut manifest, mut entries) = unpack(&archive);
+            manifest.files.push(listed(path, b"outside"));
+            entries.push((path.to_string(), b"outside".to_vec()));
+            let tampered = root.path().join("tampered.zip");
+            repack(&tampered, &manifest, &entries);
+
+            let err = imported(&encryption, &profiles, &tampered).unwrap_err();
+            assert!(
+                matches!(&err, BackupError::Invalid(reason) if reason.contains("unsafe path")),
+                "{path}: {err}"
+            );
+        }
+        assert!(!root.path().join("escaped.txt").exists());
+        assert!(!root.path().join("data/escaped.txt").exists());
+    }
+
+    #[test]
+    fn import_refuses_unlisted_files() {
+        let root = TempDir::new().unwrap();
+        let (encryption, profiles) = profiles(&root, SCHEMA_VERSION);
+        let archive = exported(&root, &encryption, &profiles);
+        let (manifest, mut entries) = unpack(&archive);
+        entries.push(("data/skills/extra.py".to_string(), b"print()".to_vec()));
+        repack(&archive, &manifest, &entries);
+
+        let err = imported(&encryption, &profiles, &archive).unwrap_err();
+        assert!(
+            matches!(&err, BackupError::Invalid(reason) if reason.contains("unlisted file")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn import_refuses_a_newer_schema() {
+        let root = TempDir::new().unwrap();
+        let (encryption, profiles) = profiles(&root, SCHEMA_VERSION + 1);
+        let archive = exported(&root, &encryption, &profiles);
+        assert_eq!(unpack(&archive).0.schema_version, Some(SCHEMA_VERSION + 1));
+
+        let err = imported(&encryption, &profiles, &archive).unwrap_err();
+        assert!(
+            matches!(err, BackupError::UnsupportedSchema(v) if v == SCHEMA_VERSION + 1),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,489 @@
+//! `deskflow-shell`: the backend without the desktop app, for servers and
+//! CI. It supervises `deskflow serve` the same way the app does, against
+//! the same profiles, secrets and logs, and talks to it over HTTP and the
+//! chat stream. Builds without Tauri:
+//!
+//! ```text
+//! cargo build --no-default-features --bin deskflow-shell
+//! ```
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use coolaw_deskflow_lib::app_dirs::AppDirs;
+use coolaw_deskflow_lib::backend::{
+    port, profile_launcher, watchdog, BackendEndpoint, BackendLogLine, BackendState,
+    BackendSupervisor, HealthProbe, HealthStatus, LogStream, SupervisorOptions, Watchdog,
+    WatchdogOptions,
+};
+use coolaw_deskflow_lib::chat::ChatBridge;
+use coolaw_deskflow_lib::deskflow_client::models::StreamChunkType;
+use coolaw_deskflow_lib::encryption::DbEncryption;
+use coolaw_deskflow_lib::instance::{self, Instance, Launch};
+use coolaw_deskflow_lib::logs::{LogFilter, LogSource, LogStore};
+use coolaw_deskflow_lib::profile::{ProfileStore, PROFILE_ENV};
+use coolaw_deskflow_lib::secrets::SecretStore;
+use coolaw_deskflow_lib::settings::SettingsStore;
+use tokio::sync::Notify;
+
+const USAGE: &str = "\
+Usage: deskflow-shell [--profile NAME] <command>
+
+Commands:
+  start                 Run the backend in the foreground until Ctrl-C or `stop`
+  stop                  Stop the backend a running `start` supervises
+  tail [-n N] [-f]      Print the backend's last N log lines, then follow them
+  health [--watch]      Check the backend's health, once or every few seconds
+  ask [-c ID] PROMPT    Send PROMPT to the agent and print the reply as it streams";
+
+/// Written by `start` into the profile's data dir so the other commands
+/// find the backend's port.
+const PORT_FILE: &str = "backend.port";
+/// Forwarded to the running `start` to make it shut down.
+const STOP_ARG: &str = "--stop";
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const DEFAULT_TAIL_LINES: usize = 50;
+
+enum Command {
+    Start,
+    Stop,
+    Tail {
+        lines: usize,
+        follow: bool,
+    },
+    Health {
+        watch: bool,
+    },
+    Ask {
+        conversation: Option<String>,
+        prompt: String,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let command = match parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let Some(dirs) = AppDirs::resolve() else {
+        eprintln!("Cannot find the user's data directory");
+        return ExitCode::FAILURE;
+    };
+
+    let result = match command {
+        Command::Start => start(&dirs).await,
+        Command::Stop => stop(&dirs).await,
+        Command::Tail { lines, follow } => tail(&dirs, lines, follow).await,
+        Command::Health { watch } => health(&dirs, watch).await,
+        Command::Ask {
+            conversation,
+            prompt,
+        } => ask(&dirs, conversation, prompt).await,
+    };
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut name = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => {
+                let profile = args.next().ok_or("--profile needs a name")?;
+                // Picked up by `ProfileStore::load`, also in `start`
+                std::env::set_var(PROFILE_ENV, profile);
+            }
+            "-h" | "--help" => return Err("DeskFlow without the desktop app".into()),
+            _ => {
+                name = Some(arg);
+                break;
+            }
+        }
+    }
+    let rest: Vec<String> = args.collect();
+    let Some(name) = name else {
+        return Err("Missing command".into());
+    };
+
+    match name.as_str() {
+        "start" | "stop" if !rest.is_empty() => Err(format!("`{name}` takes no arguments")),
+        "start" => Ok(Command::Start),
+        "stop" => Ok(Command::Stop),
+        "tail" => {
+            let (mut lines, mut follow) = (DEFAULT_TAIL_LINES, false);
+            let mut rest = rest.into_iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "-f" | "--follow" => follow = true,
+                    "-n" | "--lines" => {
+                        lines = rest
+                            .next()
+                            .and_then(|n| n.parse().ok())
+                            .ok_or("-n needs a number")?;
+                    }
+                    other => return Err(format!("Unknown option for `tail`: {other}")),
+                }
+            }
+            Ok(Command::Tail { lines, follow })
+        }
+        "health" => match rest.as_slice() {
+            [] => Ok(Command::Health { watch: false }),
+            [flag] if flag == "--watch" || flag == "-w" => Ok(Command::Health { watch: true }),
+            _ => Err("`health` only takes --watch".into()),
+        },
+        "ask" => {
+            let mut conversation = None;
+            let mut rest = rest.into_iter();
+            let mut words = Vec::new();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "-c" | "--conversation" if words.is_empty() => {
+                        conversation = Some(rest.next().ok_or("-c needs a conversation id")?);
+                    }
+                    _ => words.push(arg),
+                }
+            }
+            let prompt = words.join(" ");
+            if prompt.trim().is_empty() {
+                return Err("`ask` needs a prompt".into());
+            }
+            Ok(Command::Ask {
+                conversation,
+                prompt,
+            })
+        }
+        other => Err(format!("Unknown command: {other}")),
+    }
+}
+
+/// Supervise the backend in the foreground, restarting it when it crashes
+/// or hangs, until interrupted or told to stop.
+async fn start(dirs: &AppDirs) -> io::Result<ExitCode> {
+    let logs = LogStore::init(&dirs.log)?;
+
+    // One backend per profile, whether the app or we run it. Forward
+    // nothing: a flagless launch only brings the app's window up.
+    let profiles = ProfileStore::load(dirs.profile_roots()).map_err(io::Error::other)?;
+    let profile = profiles.current();
+    let listener = match instance::acquire(&profile.data, &Launch::default())? {
+        Instance::Primary(listener) => listener,
+        Instance::Secondary | Instance::Unreachable(_) => {
+            eprintln!("DeskFlow is already running profile {}", profile.name);
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    let stop = Arc::new(Notify::new());
+    let notify = stop.clone();
+    let _running = listener.spawn(move |launch| {
+        if launch.args.iter().any(|arg| arg == STOP_ARG) {
+            notify.notify_one();
+        }
+    });
+
+    let endpoint = BackendEndpoint::new(port::allocate_port()?);
+    let port_file = profile.data.join(PORT_FILE);
+    fs::write(&port_file, endpoint.port.to_string())?;
+
+    let settings = SettingsStore::load(&dirs.config);
+    let secrets = SecretStore::open(&dirs.data.join("secrets"));
+    let encryption = DbEncryption::new(secrets.clone());
+    let diagnostics = dirs.diagnostics();
+
+    let supervisor = BackendSupervisor::spawn(
+        profile_launcher(
+            None,
+            endpoint,
+            profiles.clone(),
+            secrets,
+            encryption,
+            Some(watchdog::stack_dump_file(&diagnostics)),
+        ),
+        SupervisorOptions::default(),
+    );
+    let _watchdog = Watchdog::spawn(supervisor.clone(), endpoint.url(), diagnostics, move || {
+        let settings = settings.get();
+        (settings.watchdog_latency_ms > 0).then(|| WatchdogOptions {
+            latency_slo: Duration::from_millis(settings.watchdog_latency_ms.into()),
+            misses: settings.watchdog_misses,
+        })
+    });
+
+    let mut lines = supervisor.subscribe_logs();
+    tokio::spawn(async move {
+        loop {
+            match lines.recv().await {
+                Ok(line) => {
+                    logs.record_backend(&line);
+                    print_line(&line);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    });
+    let mut states = supervisor.subscribe();
+    tokio::spawn(async move {
+        while states.changed().await.is_ok() {
+            let state = states.borrow_and_update().clone();
+            match &state {
+                BackendState::Failed { .. } => tracing::error!(?state, "Backend state changed"),
+                _ => tracing::info!(?state, "Backend state changed"),
+            }
+        }
+    });
+
+    tracing::info!(
+        profile = %profile.name,
+        url = %endpoint.url(),
+        "Starting the backend"
+    );
+    supervisor.start();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminated() => {}
+        _ = stop.notified() => {}
+    }
+    tracing::info!("Stopping the backend");
+    supervisor.stop().await;
+    let _ = fs::remove_file(&port_file);
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(unix)]
+async fn terminated() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut signals) => {
+            signals.recv().await;
+        }
+        Err(_) => std::future::pending().await,
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminated() {
+    std::future::pending().await
+}
+
+fn print_line(line: &BackendLogLine) {
+    match line.stream {
+        LogStream::Stdout => println!("{}", line.line),
+        LogStream::Stderr => eprintln!("{}", line.line),
+    }
+}
+
+async fn stop(dirs: &AppDirs) -> io::Result<ExitCode> {
+    let data = profile_data(dirs)?;
+    let port_file = data.join(PORT_FILE);
+    let launch = Launch {
+        args: vec![STOP_ARG.to_string()],
+        cwd: None,
+    };
+    match instance::acquire(&data, &launch)? {
+        Instance::Primary(_) => {
+            // Whatever wrote it is gone
+            let _ = fs::remove_file(&port_file);
+            eprintln!("DeskFlow is not running");
+            return Ok(ExitCode::FAILURE);
+        }
+        Instance::Secondary if !port_file.exists() => {
+            eprintln!("DeskFlow runs as the desktop app; quit it from its tray menu");
+            return Ok(ExitCode::FAILURE);
+        }
+        Instance::Secondary => {}
+        Instance::Unreachable(e) => {
+            eprintln!("The running instance does not respond: {e}");
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
+    let deadline = tokio::time::Instant::now() + STOP_TIMEOUT;
+    while port_file.exists() {
+        if tokio::time::Instant::now() >= deadline {
+            eprintln!(
+                "The backend did not stop within {}s",
+                STOP_TIMEOUT.as_secs()
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    println!("Stopped");
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Print the newest backend log entries, then keep printing new ones.
+async fn tail(dirs: &AppDirs, lines: usize, follow: bool) -> io::Result<ExitCode> {
+    let store = LogStore::open(&dirs.log)?;
+    let mut filter = LogFilter {
+        source: Some(LogSource::Backend),
+        ..LogFilter::default()
+    };
+    let entries = store.tail(&filter, Some(lines))?;
+    // Entries sharing the last millisecond come back on the next poll too
+    let (mut since, mut seen) = (0, 0);
+    for entry in &entries {
+        println!("{entry}");
+        if entry.timestamp_ms == since {
+            seen += 1;
+        } else {
+            (since, seen) = (entry.timestamp_ms, 1);
+        }
+    }
+    if !follow {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    loop {
+        tokio::time::sleep(TAIL_POLL_INTERVAL).await;
+        filter.since_ms = Some(since);
+        let entries = store.tail(&filter, None)?;
+        let mut skip = seen;
+        for entry in entries {
+            if entry.timestamp_ms == since && skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            println!("{entry}");
+            if entry.timestamp_ms == since {
+                seen += 1;
+            } else {
+                (since, seen) = (entry.timestamp_ms, 1);
+            }
+        }
+        io::stdout().flush()?;
+    }
+}
+
+async fn health(dirs: &AppDirs, watch: bool) -> io::Result<ExitCode> {
+    let probe = HealthProbe::new(endpoint(&profile_data(dirs)?).url());
+    loop {
+        let report = probe.check(true).await;
+        match &report.error {
+            Some(error) => println!("{:?}: {error}", report.status),
+            None => println!(
+                "{:?} version={} llm={} memory={} latency={}ms",
+                report.status,
+                report.version.as_deref().unwrap_or("?"),
+                report.llm_configured,
+                report.memory_reachable,
+                report.latency_ms.unwrap_or_default(),
+            ),
+        }
+        for (name, component) in &report.components {
+            println!("  {name}: {component:?}");
+        }
+        for recommendation in &report.recommendations {
+            println!("  ! {recommendation}");
+        }
+        if !watch {
+            return Ok(match report.status {
+                HealthStatus::Ok => ExitCode::SUCCESS,
+                _ => ExitCode::FAILURE,
+            });
+        }
+        tokio::time::sleep(HEALTH_INTERVAL).await;
+    }
+}
+
+/// Send one prompt and stream the reply: text to stdout, tool activity
+/// and errors to stderr.
+async fn ask(dirs: &AppDirs, conversation: Option<String>, prompt: String) -> io::Result<ExitCode> {
+    let endpoint = endpoint(&profile_data(dirs)?);
+    let chat = ChatBridge::spawn(format!("{}/api/chat/stream", endpoint.ws_url()));
+    let mut connected = chat.subscribe_connection();
+    if tokio::time::timeout(CONNECT_TIMEOUT, connected.wait_for(|up| *up))
+        .await
+        .is_err()
+    {
+        eprintln!("Cannot reach the backend at {}", endpoint.url());
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let mut events = chat.subscribe();
+    let request_id = match chat.send(prompt, conversation, None) {
+        Ok(request_id) => request_id,
+        Err(e) => {
+            eprintln!("{e}");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    let (mut failed, mut conversation) = (false, None);
+    let mut stdout = io::stdout();
+    while let Ok(event) = events.recv().await {
+        if event.request_id != request_id {
+            continue;
+        }
+        conversation = event.conversation_id.or(conversation);
+        let chunk = event.chunk;
+        match chunk.kind {
+            StreamChunkType::Text => {
+                write!(stdout, "{}", chunk.content)?;
+                stdout.flush()?;
+            }
+            StreamChunkType::ToolStart => {
+                if let Some(call) = &chunk.tool_call {
+                    eprintln!("[tool] {}", call.name);
+                }
+            }
+            StreamChunkType::ToolResult => {
+                if let Some(result) = &chunk.tool_result {
+                    let outcome = if result.success { "ok" } else { "failed" };
+                    eprintln!("[tool] {} {outcome}", result.tool_name);
+                }
+            }
+            StreamChunkType::Error => {
+                failed = true;
+                eprintln!("Error: {}", chunk.content);
+            }
+            StreamChunkType::Done => break,
+            _ => {}
+        }
+    }
+    println!();
+    if let Some(id) = conversation {
+        eprintln!("conversation: {id}");
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// Data dir of the profile the command is for, where `start` keeps its
+/// lock and port file.
+fn profile_data(dirs: &AppDirs) -> io::Result<PathBuf> {
+    let profiles = ProfileStore::load(dirs.profile_roots()).map_err(io::Error::other)?;
+    Ok(profiles.current().data)
+}
+
+/// Where the backend listens: the port a running `start` wrote, else the
+/// pinned or default one.
+fn endpoint(data: &Path) -> BackendEndpoint {
+    let written = fs::read_to_string(data.join(PORT_FILE))
+        .ok()
+        .and_then(|port| port.trim().parse().ok());
+    BackendEndpoint::new(
+        written
+            .or_else(port::configured_port)
+            .unwrap_or(port::DEFAULT_PORT),
+    )
+}
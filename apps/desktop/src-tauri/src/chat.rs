@@ -0,0 +1,561 @@
+//! Chat streaming over the backend's `/api/chat/stream` WebSocket.
+//!
+//! The shell owns one connection for the whole app, so a stream outlives
+//! webview reloads, and a backend restart only costs a reconnect. The
+//! backend answers one message at a time per socket, so outgoing messages
+//! wait in per-conversation queues and are sent one by one.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::deskflow_client::models::{StreamChunk, StreamChunkType};
+use crate::error::{DeskflowError, DeskflowResult};
+use crate::runtime::now_ms;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const EVENT_CAPACITY: usize = 1024;
+const RECONNECT_INITIAL: Duration = Duration::from_millis(500);
+const RECONNECT_MAX: Duration = Duration::from_secs(10);
+/// A connection that stayed up this long resets the reconnect backoff.
+const STABLE_AFTER: Duration = Duration::from_secs(5);
+/// How many started conversations are remembered by the request id of their
+/// first message.
+const STARTED_MEMORY: usize = 64;
+
+/// A chunk for one request, emitted as `chat://chunk`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatEvent {
+    pub request_id: String,
+    /// Known once the request named a conversation or the backend sent a
+    /// `conversation_id` chunk.
+    pub conversation_id: Option<String>,
+    /// Whoever sent the message, e.g. a window label, so chunks can be
+    /// routed back to it.
+    pub origin: Option<String>,
+    pub chunk: StreamChunk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingState {
+    Queued,
+    Streaming,
+}
+
+/// A message that has not finished streaming yet, with the chunks received
+/// so far so a reloaded webview can rebuild it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingMessage {
+    pub request_id: String,
+    pub conversation_id: Option<String>,
+    pub message: String,
+    pub origin: Option<String>,
+    pub state: PendingState,
+    pub chunks: Vec<StreamChunk>,
+    /// For a follow-up to a message that starts a new conversation, that
+    /// message's request id until the conversation id is known.
+    #[serde(skip)]
+    follows: Option<String>,
+}
+
+impl PendingMessage {
+    /// A conversation's messages share a queue, and so do a new
+    /// conversation's first message and its follow-ups.
+    fn queue_key(&self) -> String {
+        self.conversation_id
+            .clone()
+            .or_else(|| self.follows.clone())
+            .unwrap_or_else(|| self.request_id.clone())
+    }
+}
+
+enum Command {
+    Send(PendingMessage),
+    Cancel(String),
+    Snapshot(oneshot::Sender<Vec<PendingMessage>>),
+}
+
+/// Handle to the chat connection. Cheap to clone.
+#[derive(Clone)]
+pub struct ChatBridge {
+    commands: mpsc::UnboundedSender<Command>,
+    events: broadcast::Sender<ChatEvent>,
+    connected: watch::Receiver<bool>,
+}
+
+impl ChatBridge {
+    /// Start the connection task for `url` (the `ws://` stream endpoint).
+    /// It connects right away and keeps reconnecting until the last handle
+    /// is dropped.
+    pub fn spawn(url: impl Into<String>) -> Self {
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let (connected_tx, connected_rx) = watch::channel(false);
+
+        let worker = Worker {
+            url: url.into(),
+            commands: commands_rx,
+            events: events.clone(),
+            connected: connected_tx,
+            outbox: Outbox::default(),
+            in_flight: None,
+        };
+        crate::runtime::spawn(worker.run());
+
+        Self {
+            commands: commands_tx,
+            events,
+            connected: connected_rx,
+        }
+    }
+
+    /// Queue a message and return the request id its chunks will carry.
+    /// Blank messages are refused; the backend would answer them with a
+    /// lone error.
+    ///
+    /// `conversation_id` may also be the request id of a message that
+    /// starts a new conversation: the follow-up is held until that
+    /// conversation's id arrives and then sent into it.
+    pub fn send(
+        &self,
+        message: String,
+        conversation_id: Option<String>,
+        origin: Option<String>,
+    ) -> DeskflowResult<String> {
+        if message.trim().is_empty() {
+            return Err(DeskflowError::invalid_input("Message is empty"));
+        }
+        let request_id = next_request_id();
+        let _ = self.commands.send(Command::Send(PendingMessage {
+            request_id: request_id.clone(),
+            conversation_id: conversation_id.filter(|id| !id.is_empty()),
+            message,
+            origin,
+            state: PendingState::Queued,
+            chunks: Vec::new(),
+            follows: None,
+        }));
+        Ok(request_id)
+    }
+
+    /// Drop a queued message, or abort it if it is streaming. Either way a
+    /// final `done` chunk is emitted for it.
+    pub fn cancel(&self, request_id: impl Into<String>) {
+        let _ = self.commands.send(Command::Cancel(request_id.into()));
+    }
+
+    /// Messages still queued or streaming, in the order they will finish.
+    pub async fn snapshot(&self) -> Vec<PendingMessage> {
+        let (reply, rx) = oneshot::channel();
+        if self.commands.send(Command::Snapshot(reply)).is_err() {
+            return Vec::new();
+        }
+        rx.await.unwrap_or_default()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ChatEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn subscribe_connection(&self) -> watch::Receiver<bool> {
+        self.connected.clone()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        *self.connected.borrow()
+    }
+}
+
+/// Outgoing messages, one FIFO per conversation. Conversations take turns
+/// so a long queue in one does not starve the others.
+#[derive(Default)]
+struct Outbox {
+    queues: HashMap<String, VecDeque<PendingMessage>>,
+    order: VecDeque<String>,
+    /// Request ids of recent new-conversation messages and the conversation
+    /// each started.
+    started: VecDeque<(String, String)>,
+}
+
+impl Outbox {
+    fn push_back(&mut self, message: PendingMessage) {
+        let key = message.queue_key();
+        let queue = self.queues.entry(key.clone()).or_default();
+        if queue.is_empty() {
+            self.order.push_back(key);
+        }
+        queue.push_back(message);
+    }
+
+    /// Put a message back at the head of the line, e.g. after a send that
+    /// never reached the backend.
+    fn push_front(&mut self, mut message: PendingMessage) {
+        message.state = PendingState::Queued;
+        let key = message.queue_key();
+        let queue = self.queues.entry(key.clone()).or_default();
+        if queue.is_empty() {
+            self.order.push_front(key);
+        } else if let Some(pos) = self.order.iter().position(|k| *k == key) {
+            let key = self.order.remove(pos).unwrap_or(key);
+            self.order.push_front(key);
+        }
+        queue.push_front(message);
+    }
+
+    fn pop(&mut self) -> Option<PendingMessage> {
+        // Follow-ups wait until the conversation they continue has its id
+        let pos = self.order.iter().position(|key| {
+            self.queues[key]
+                .front()
+                .is_some_and(|message| message.follows.is_none())
+        })?;
+        let key = self.order.remove(pos)?;
+        let queue = self.queues.get_mut(&key)?;
+        let message = queue.pop_front();
+        if queue.is_empty() {
+            self.queues.remove(&key);
+        } else {
+            self.order.push_back(key);
+        }
+        message
+    }
+
+    fn remove(&mut self, request_id: &str) -> Option<PendingMessage> {
+        let (key, queue) = self
+            .queues
+            .iter_mut()
+            .find(|(_, queue)| queue.iter().any(|m| m.request_id == request_id))?;
+        let key = key.clone();
+        let pos = queue.iter().position(|m| m.request_id == request_id)?;
+        let message = queue.remove(pos);
+        if queue.is_empty() {
+            self.queues.remove(&key);
+            self.order.retain(|k| *k != key);
+        }
+        message
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &PendingMessage> {
+        self.order.iter().flat_map(|key| self.queues[key].iter())
+    }
+
+    /// The conversation a recent new-conversation message started.
+    fn started(&self, request_id: &str) -> Option<&str> {
+        self.started
+            .iter()
+            .find(|(id, _)| id == request_id)
+            .map(|(_, conversation_id)| conversation_id.as_str())
+    }
+
+    /// The message `request_id` started `conversation_id`: release its
+    /// follow-ups into that conversation's queue, ahead of anything sent to
+    /// the conversation by id since.
+    fn resolve(&mut self, request_id: &str, conversation_id: &str) {
+        if self.started.len() == STARTED_MEMORY {
+            self.started.pop_front();
+        }
+        self.started
+            .push_back((request_id.to_string(), conversation_id.to_string()));
+
+        let Some(held) = self.queues.remove(request_id) else {
+            return;
+        };
+        let held = held.into_iter().map(|mut message| {
+            message.conversation_id = Some(conversation_id.to_string());
+            message.follows = None;
+            message
+        });
+        let pos = self.order.iter().position(|key| key == request_id);
+        match self.queues.get_mut(conversation_id) {
+            Some(queue) => {
+                for message in held.rev() {
+                    queue.push_front(message);
+                }
+                if let Some(pos) = pos {
+                    self.order.remove(pos);
+                }
+            }
+            None => {
+                self.queues
+                    .insert(conversation_id.to_string(), held.collect());
+                if let Some(pos) = pos {
+                    self.order[pos] = conversation_id.to_string();
+                }
+            }
+        }
+    }
+
+    /// Take the follow-ups of a message that ended without starting a
+    /// conversation.
+    fn take_followers(&mut self, request_id: &str) -> VecDeque<PendingMessage> {
+        self.order.retain(|key| key != request_id);
+        self.queues.remove(request_id).unwrap_or_default()
+    }
+}
+
+struct Worker {
+    url: String,
+    commands: mpsc::UnboundedReceiver<Command>,
+    events: broadcast::Sender<ChatEvent>,
+    connected: watch::Sender<bool>,
+    outbox: Outbox,
+    in_flight: Option<PendingMessage>,
+}
+
+impl Worker {
+    async fn run(mut self) {
+        let mut backoff = RECONNECT_INITIAL;
+
+        loop {
+            if let Ok((socket, _)) = tokio_tungstenite::connect_async(self.url.as_str()).await {
+                let connected_at = Instant::now();
+                self.connected.send_replace(true);
+                let open = self.serve(socket).await;
+                self.connected.send_replace(false);
+                self.connection_lost();
+
+                if !open {
+                    return;
+                }
+                if connected_at.elapsed() >= STABLE_AFTER {
+                    backoff = RECONNECT_INITIAL;
+                }
+            }
+
+            // Keep accepting messages while we wait to reconnect
+            let retry = tokio::time::sleep(backoff);
+            tokio::pin!(retry);
+            loop {
+                tokio::select! {
+                    _ = &mut retry => break,
+                    command = self.commands.recv() => match command {
+                        Some(command) => {
+                            self.handle(command);
+                        }
+                        None => return,
+                    },
+                }
+            }
+            backoff = (backoff * 2).min(RECONNECT_MAX);
+        }
+    }
+
+    /// Pump one connection until it drops. Returns `false` once every
+    /// handle is gone and the worker should exit.
+    async fn serve(&mut self, socket: Socket) -> bool {
+        let (mut sink, mut stream) = socket.split();
+
+        loop {
+            if self.in_flight.is_none() {
+                if let Some(mut next) = self.outbox.pop() {
+                    let payload = serde_json::json!({
+                        "message": next.message,
+                        "conversation_id": next.conversation_id,
+                    });
+                    if sink.send(Message::Text(payload.to_string())).await.is_err() {
+                        self.outbox.push_front(next);
+                        return true;
+                    }
+                    next.state = PendingState::Streaming;
+                    self.in_flight = Some(next);
+                }
+            }
+
+            tokio::select! {
+                command = self.commands.recv() => match command {
+                    Some(command) => {
+                        if self.handle(command) {
+                            // The backend only stops generating once the
+                            // socket goes away
+                            let _ = sink.close().await;
+                            return true;
+                        }
+                    }
+                    None => {
+                        let _ = sink.close().await;
+                        return false;
+                    }
+                },
+                frame = stream.next() => match frame {
+                    Some(Ok(Message::Text(text))) => self.receive(&text),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return true,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+
+    /// Apply a command. Returns `true` when the in-flight message was
+    /// cancelled and the connection has to be dropped.
+    fn handle(&mut self, command: Command) -> bool {
+        match command {
+            Command::Send(mut message) => {
+                self.link(&mut message);
+                self.outbox.push_back(message);
+                false
+            }
+            Command::Cancel(request_id) => {
+                if let Some(message) = self.outbox.remove(&request_id) {
+                    self.finish(message, None);
+                    return false;
+                }
+                match self.in_flight.take() {
+                    Some(message) if message.request_id == request_id => {
+                        self.finish(message, None);
+                        true
+                    }
+                    other => {
+                        self.in_flight = other;
+                        false
+                    }
+                }
+            }
+            Command::Snapshot(reply) => {
+                let pending = self
+                    .in_flight
+                    .iter()
+                    .chain(self.outbox.iter())
+                    .cloned()
+                    .collect();
+                let _ = reply.send(pending);
+                false
+            }
+        }
+    }
+
+    /// Resolve a conversation named by the request id of the message that
+    /// starts it: to its id if known by now, otherwise the follow-up is
+    /// held behind that message.
+    fn link(&self, message: &mut PendingMessage) {
+        let Some(id) = message.conversation_id.clone() else {
+            return;
+        };
+        if let Some(conversation_id) = self.outbox.started(&id) {
+            message.conversation_id = Some(conversation_id.to_string());
+            return;
+        }
+        let Some(first) = self
+            .in_flight
+            .iter()
+            .chain(self.outbox.iter())
+            .find(|pending| pending.request_id == id)
+        else {
+            return;
+        };
+        message.conversation_id = first.conversation_id.clone();
+        if first.conversation_id.is_none() {
+            message.follows = Some(first.follows.clone().unwrap_or(id));
+        }
+    }
+
+    fn receive(&mut self, text: &str) {
+        let chunk = match serde_json::from_str::<StreamChunk>(text) {
+            Ok(chunk) => chunk,
+            Err(e) => StreamChunk::new(
+                StreamChunkType::Error,
+                format!("Invalid chunk from backend: {e}"),
+            ),
+        };
+
+        // Nothing is in flight for errors the backend sends on connect,
+        // e.g. when no LLM is configured; the next message will get them too
+        let Some(message) = self.in_flight.as_mut() else {
+            return;
+        };
+
+        if chunk.kind == StreamChunkType::ConversationId && !chunk.content.is_empty() {
+            if message.conversation_id.is_none() {
+                self.outbox.resolve(&message.request_id, &chunk.content);
+            }
+            message.conversation_id = Some(chunk.content.clone());
+        }
+        // Replies open with the conversation id; an error before it means
+        // the backend refused the message and will not send `done`
+        let refused = chunk.kind == StreamChunkType::Error && message.chunks.is_empty();
+        message.chunks.push(chunk.clone());
+
+        let _ = self.events.send(ChatEvent {
+            request_id: message.request_id.clone(),
+            conversation_id: message.conversation_id.clone(),
+            origin: message.origin.clone(),
+            chunk: chunk.clone(),
+        });
+
+        if chunk.kind == StreamChunkType::Done {
+            if let Some(message) = self.in_flight.take() {
+                self.fail_followers(&message);
+            }
+        } else if refused {
+            if let Some(message) = self.in_flight.take() {
+                self.finish(message, None);
+            }
+        }
+    }
+
+    /// A message that never got an answer is sent again on the next
+    /// connection; one that was partially answered is closed with an error
+    /// rather than replayed into a duplicate reply.
+    fn connection_lost(&mut self) {
+        let Some(message) = self.in_flight.take() else {
+            return;
+        };
+
+        if message.chunks.is_empty() {
+            self.outbox.push_front(message);
+        } else {
+            self.finish(message, Some("Connection to the backend was lost"));
+        }
+    }
+
+    /// Emit the closing chunks for a message that will not get a `done`
+    /// from the backend.
+    fn finish(&mut self, message: PendingMessage, error: Option<&str>) {
+        self.close(&message, error);
+        self.fail_followers(&message);
+    }
+
+    /// Follow-ups of a message that ended without starting a conversation
+    /// have nothing to continue.
+    fn fail_followers(&mut self, message: &PendingMessage) {
+        if message.conversation_id.is_some() {
+            return;
+        }
+        for follower in self.outbox.take_followers(&message.request_id) {
+            self.close(
+                &follower,
+                Some("The message this follows up on did not start a conversation"),
+            );
+        }
+    }
+
+    fn close(&self, message: &PendingMessage, error: Option<&str>) {
+        let error = error.map(|e| StreamChunk::new(StreamChunkType::Error, e));
+        let done = StreamChunk::new(StreamChunkType::Done, "");
+        for chunk in error.into_iter().chain([done]) {
+            let _ = self.events.send(ChatEvent {
+                request_id: message.request_id.clone(),
+                conversation_id: message.conversation_id.clone(),
+                origin: message.origin.clone(),
+                chunk,
+            });
+        }
+    }
+}
+
+fn next_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(1);
+    format!(
+        "chat-{}-{}",
+        now_ms(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
@@ -0,0 +1,112 @@
+//! Starting and stopping the supervised backend, and helpers for commands
+//! that need it out of the way or restarted.
+
+use std::time::Duration;
+
+use tauri::State;
+use tokio::sync::watch;
+
+use crate::backend::{BackendLogLine, BackendState, BackendSupervisor, HealthProbe};
+use crate::error::{DeskflowError, DeskflowResult};
+use crate::state::AppState;
+
+const BACKEND_RESTART_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tauri::command]
+pub fn backend_start(supervisor: State<'_, BackendSupervisor>) {
+    supervisor.start();
+}
+
+#[tauri::command]
+pub async fn backend_stop(supervisor: State<'_, BackendSupervisor>) -> DeskflowResult<()> {
+    supervisor.stop().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn backend_restart(supervisor: State<'_, BackendSupervisor>) {
+    supervisor.restart();
+}
+
+#[tauri::command]
+pub fn backend_status(supervisor: State<'_, BackendSupervisor>) -> BackendState {
+    supervisor.state()
+}
+
+#[tauri::command]
+pub fn backend_logs(supervisor: State<'_, BackendSupervisor>) -> Vec<BackendLogLine> {
+    supervisor.recent_logs()
+}
+
+/// Refuse work that needs the backend stopped when it is external: the
+/// shell cannot stop it, and it keeps the databases open.
+pub(crate) fn ensure_supervised(state: &AppState) -> DeskflowResult<()> {
+    if state.external_backend() {
+        return Err(DeskflowError::Busy {
+            message: "The backend is run externally; stop it and let DeskFlow start its own first"
+                .to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Run `work` on a blocking thread with the backend stopped, so it has the
+/// database to itself, then start the backend again if it was up or on its
+/// way up (starting, or between crash restarts).
+pub(crate) async fn with_backend_stopped<T: Send + 'static>(
+    supervisor: &BackendSupervisor,
+    probe: &HealthProbe,
+    work: impl FnOnce() -> T + Send + 'static,
+) -> DeskflowResult<T> {
+    let was_wanted = supervisor.stop().await;
+    let result = tauri::async_runtime::spawn_blocking(work).await;
+    if was_wanted {
+        let states = supervisor.subscribe();
+        supervisor.start();
+        wait_until_answering(states, probe).await;
+    }
+    Ok(result?)
+}
+
+/// Secrets and profile directories only reach the backend at spawn time,
+/// so restart it if it is running and wait until the new process answers.
+/// `false` when it did not answer in time; a stopped backend is left alone
+/// and counts as fine.
+pub(crate) async fn restart_backend_and_wait(
+    supervisor: &BackendSupervisor,
+    probe: &HealthProbe,
+) -> bool {
+    if !supervisor.state().is_running() {
+        return true;
+    }
+
+    let mut states = supervisor.subscribe();
+    states.borrow_and_update();
+    supervisor.restart();
+    wait_until_answering(states, probe).await
+}
+
+/// Wait for a (re)started backend: until the previous process is gone and
+/// the new one answers health checks, or [`BACKEND_RESTART_TIMEOUT`].
+/// `false` on timeout.
+async fn wait_until_answering(
+    mut states: watch::Receiver<BackendState>,
+    probe: &HealthProbe,
+) -> bool {
+    tokio::time::timeout(BACKEND_RESTART_TIMEOUT, async {
+        // Let the old process go away first so we don't probe it
+        while states.borrow_and_update().is_running() {
+            if states.changed().await.is_err() {
+                return;
+            }
+        }
+        loop {
+            if states.borrow().is_running() && probe.check(false).await.is_reachable() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    })
+    .await
+    .is_ok()
+}
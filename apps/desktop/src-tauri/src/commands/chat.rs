@@ -0,0 +1,37 @@
+//! Streamed chat through the [`ChatBridge`].
+
+use tauri::{State, WebviewWindow};
+
+use crate::chat::{ChatBridge, PendingMessage};
+use crate::error::DeskflowResult;
+
+#[tauri::command]
+pub fn chat_send(
+    window: WebviewWindow,
+    chat: State<'_, ChatBridge>,
+    message: String,
+    conversation_id: Option<String>,
+) -> DeskflowResult<String> {
+    // Chunks go back to the window that asked
+    chat.send(message, conversation_id, Some(window.label().to_string()))
+}
+
+#[tauri::command]
+pub fn chat_cancel(chat: State<'_, ChatBridge>, request_id: String) {
+    chat.cancel(request_id);
+}
+
+#[tauri::command]
+pub async fn chat_snapshot(
+    window: WebviewWindow,
+    chat: State<'_, ChatBridge>,
+) -> DeskflowResult<Vec<PendingMessage>> {
+    let mut pending = chat.snapshot().await;
+    pending.retain(|p| p.origin.as_deref() == Some(window.label()));
+    Ok(pending)
+}
+
+#[tauri::command]
+pub fn chat_connected(chat: State<'_, ChatBridge>) -> bool {
+    chat.is_connected()
+}
@@ -0,0 +1,131 @@
+//! History straight from the profile's database, available whether or not
+//! the backend is up, and search over it. Exports are in `transcripts`.
+
+use rusqlite::Connection;
+use tauri::State;
+
+use crate::deskflow_client::models::{Conversation, ConversationList};
+use crate::encryption::DbEncryption;
+use crate::error::{DeskflowError, DeskflowResult};
+use crate::history::{History, HistoryResult, SearchHit};
+use crate::profile::{ProfileDirs, ProfileStore};
+use crate::search::{self, DateRange, MemoryFilters, MemoryHit, Page};
+
+#[tauri::command]
+pub async fn list_conversations(
+    history: State<'_, History>,
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> DeskflowResult<ConversationList> {
+    let (history, encryption) = (history.inner().clone(), encryption.inner().clone());
+    let dirs = profiles.current();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = open_history(&encryption, &dirs)?;
+        history.conversations(&conn, limit.unwrap_or(50), offset.unwrap_or(0))
+    })
+    .await?
+    .map_err(DeskflowError::from)
+}
+
+#[tauri::command]
+pub async fn get_conversation(
+    history: State<'_, History>,
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    id: String,
+) -> DeskflowResult<Conversation> {
+    let (history, encryption) = (history.inner().clone(), encryption.inner().clone());
+    let dirs = profiles.current();
+    tauri::async_runtime::spawn_blocking(move || {
+        history.conversation(&open_history(&encryption, &dirs)?, &id)
+    })
+    .await?
+    .map_err(DeskflowError::from)
+}
+
+#[tauri::command]
+pub async fn search_conversations(
+    history: State<'_, History>,
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    query: String,
+    limit: Option<u32>,
+) -> DeskflowResult<Vec<SearchHit>> {
+    let (history, encryption) = (history.inner().clone(), encryption.inner().clone());
+    let dirs = profiles.current();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = open_history(&encryption, &dirs)?;
+        let db = dirs.conversations_db_path();
+        history.search(
+            &conn,
+            &db,
+            &query,
+            &DateRange::default(),
+            limit.unwrap_or(50),
+            0,
+        )
+    })
+    .await?
+    .map_err(DeskflowError::from)
+}
+
+#[tauri::command]
+pub async fn search_memories(
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    query: String,
+    filters: Option<MemoryFilters>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> DeskflowResult<Page<MemoryHit>> {
+    let (encryption, dirs) = (encryption.inner().clone(), profiles.current());
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = encryption.open_read_only(&dirs, &dirs.db_path())?;
+        Ok(search::memories(
+            &conn,
+            &query,
+            &filters.unwrap_or_default(),
+            limit.unwrap_or(20),
+            offset.unwrap_or(0),
+        )?)
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn search_messages(
+    history: State<'_, History>,
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    query: String,
+    range: Option<DateRange>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> DeskflowResult<Page<SearchHit>> {
+    let (history, encryption) = (history.inner().clone(), encryption.inner().clone());
+    let dirs = profiles.current();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = open_history(&encryption, &dirs)?;
+        search::messages(
+            &history,
+            &conn,
+            &dirs.conversations_db_path(),
+            &query,
+            &range.unwrap_or_default(),
+            limit.unwrap_or(20),
+            offset.unwrap_or(0),
+        )
+    })
+    .await?
+    .map_err(DeskflowError::from)
+}
+
+/// The profile's chat history, with its key when it is encrypted.
+pub(super) fn open_history(
+    encryption: &DbEncryption,
+    dirs: &ProfileDirs,
+) -> HistoryResult<Connection> {
+    Ok(encryption.open_read_only(dirs, &dirs.conversations_db_path())?)
+}
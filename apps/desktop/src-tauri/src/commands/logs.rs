@@ -0,0 +1,171 @@
+//! The stored shell and backend logs, and diagnostics bundles. Saving
+//! either to a file needs the native file dialogs, so only reading the
+//! logs works on mobile.
+
+#[cfg(desktop)]
+use std::future::Future;
+#[cfg(desktop)]
+use std::time::Duration;
+
+#[cfg(desktop)]
+use serde::Serialize;
+use tauri::State;
+#[cfg(desktop)]
+use tauri::{AppHandle, Manager};
+#[cfg(desktop)]
+use tauri_plugin_dialog::DialogExt;
+#[cfg(desktop)]
+use tracing::info;
+
+#[cfg(desktop)]
+use crate::backend::{tasks::diagnostics_dir, BackendSupervisor};
+#[cfg(desktop)]
+use crate::deskflow_client::{ClientResult, DeskflowClient};
+#[cfg(desktop)]
+use crate::diagnostics::{Bundle, Redactor, SystemInfo};
+#[cfg(desktop)]
+use crate::encryption::DbEncryption;
+use crate::error::{DeskflowError, DeskflowResult};
+use crate::logs::{LogEntry, LogFilter, LogStore};
+#[cfg(desktop)]
+use crate::profile::ProfileStore;
+#[cfg(desktop)]
+use crate::settings::SettingsStore;
+
+#[cfg(desktop)]
+const DIAGNOSTICS_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+#[cfg(desktop)]
+const DIAGNOSTICS_ACTIVITY_LIMIT: u32 = 100;
+#[cfg(desktop)]
+const DIAGNOSTICS_LOG_DAYS: u64 = 3;
+
+/// The newest `limit` stored shell and backend log entries matching
+/// `filter`, oldest first. Served from disk, so it works with the backend
+/// down.
+#[tauri::command]
+pub async fn tail_logs(
+    logs: State<'_, LogStore>,
+    filter: Option<LogFilter>,
+    limit: Option<usize>,
+) -> DeskflowResult<Vec<LogEntry>> {
+    let logs = logs.inner().clone();
+    let filter = filter.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || logs.tail(&filter, limit))
+        .await?
+        .map_err(DeskflowError::from)
+}
+
+/// Save the stored log entries matching `filter` as text to a file the
+/// user picks. Returns the path, or `None` if the dialog was cancelled.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn export_logs(
+    app: AppHandle,
+    logs: State<'_, LogStore>,
+    filter: Option<LogFilter>,
+) -> DeskflowResult<Option<String>> {
+    let dialog = app
+        .dialog()
+        .file()
+        .add_filter("Log", &["log", "txt"])
+        .set_file_name("deskflow.log");
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file()).await?;
+    let Some(dest) = picked else {
+        return Ok(None);
+    };
+    let dest = dest.into_path().map_err(DeskflowError::internal)?;
+
+    let logs = logs.inner().clone();
+    let filter = filter.unwrap_or_default();
+    let target = dest.clone();
+    tauri::async_runtime::spawn_blocking(move || logs.export(&filter, &target)).await??;
+    Ok(Some(dest.to_string_lossy().into_owned()))
+}
+
+/// Save a diagnostics bundle (see `diagnostics`) to a file the user picks.
+/// Returns the path, or `None` if the dialog was cancelled.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn collect_diagnostics(
+    app: AppHandle,
+    supervisor: State<'_, BackendSupervisor>,
+    client: State<'_, DeskflowClient>,
+) -> DeskflowResult<Option<String>> {
+    let dialog = app
+        .dialog()
+        .file()
+        .add_filter("Zip", &["zip"])
+        .set_file_name(format!(
+            "deskflow-diagnostics-{}.zip",
+            crate::runtime::now_secs()
+        ));
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file()).await?;
+    let Some(dest) = picked else {
+        return Ok(None);
+    };
+    let dest = dest.into_path().map_err(DeskflowError::internal)?;
+
+    let mut bundle = Bundle::new(Redactor::default());
+    bundle.add_json(
+        "system.json",
+        SystemInfo::current(tauri::webview_version().ok()),
+    );
+    bundle.add_json(
+        "backend/state.json",
+        serde_json::json!({
+            "current": supervisor.state(),
+            "history": supervisor.state_history(),
+        }),
+    );
+
+    // A hung backend shows up as timeouts in the manifest
+    let (health, activity, config) = tokio::join!(
+        within_diagnostics_timeout(client.health_detailed()),
+        within_diagnostics_timeout(client.activity(DIAGNOSTICS_ACTIVITY_LIMIT)),
+        within_diagnostics_timeout(client.config()),
+    );
+    bundle.add_result("backend/health.json", health);
+    bundle.add_result("backend/activity.json", activity);
+    bundle.add_result("config/backend.json", config);
+    bundle.add_json(
+        "config/shell-settings.json",
+        app.state::<SettingsStore>().get(),
+    );
+    bundle.add_json(
+        "config/environment.json",
+        std::env::vars()
+            .filter(|(key, _)| key.starts_with("DESKFLOW_") || key == "RUST_LOG")
+            .collect::<std::collections::BTreeMap<_, _>>(),
+    );
+
+    let logs = app.state::<LogStore>().inner().clone();
+    let encryption = app.state::<DbEncryption>().inner().clone();
+    let dirs = app.state::<ProfileStore>().current();
+    let dumps = diagnostics_dir(&app);
+    let target = dest.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        bundle.add_profile(&encryption, &dirs);
+        bundle.add_logs(&logs, DIAGNOSTICS_LOG_DAYS);
+        if let Some(dir) = dumps {
+            bundle.add_hang_dumps(&dir);
+        }
+        bundle.write(&target, &dirs.name)
+    })
+    .await??;
+    info!(path = %dest.display(), "Wrote diagnostics bundle");
+    Ok(Some(dest.to_string_lossy().into_owned()))
+}
+
+#[cfg(desktop)]
+async fn within_diagnostics_timeout<T: Serialize>(
+    request: impl Future<Output = ClientResult<T>>,
+) -> Result<serde_json::Value, String> {
+    match tokio::time::timeout(DIAGNOSTICS_REQUEST_TIMEOUT, request).await {
+        Ok(Ok(value)) => serde_json::to_value(value).map_err(|e| e.to_string()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!(
+            "No answer within {}s",
+            DIAGNOSTICS_REQUEST_TIMEOUT.as_secs()
+        )),
+    }
+}
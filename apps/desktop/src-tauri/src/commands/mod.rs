@@ -0,0 +1,17 @@
+//! Tauri commands the shell answers on its own, grouped by what they touch.
+//! Commands that only wrap the backend's REST API live in
+//! `deskflow_client::commands`; the health and URL ones in
+//! `backend::commands`. Ones that need native dialogs or the updater are
+//! desktop only.
+
+pub mod backend;
+pub mod chat;
+pub mod history;
+pub mod logs;
+pub mod profile;
+pub mod secrets;
+pub mod shell;
+#[cfg(desktop)]
+pub mod transcripts;
+#[cfg(desktop)]
+pub mod updater;
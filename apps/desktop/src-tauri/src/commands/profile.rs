@@ -0,0 +1,232 @@
+//! Profiles, the encryption of their databases, and moving them in and out
+//! as archives. The archive commands need the native file dialogs, so they
+//! are desktop only.
+
+use tauri::{AppHandle, Emitter, Manager, State};
+#[cfg(desktop)]
+use tauri_plugin_dialog::DialogExt;
+
+use super::backend::{ensure_supervised, restart_backend_and_wait, with_backend_stopped};
+use crate::backend::{BackendSupervisor, HealthProbe};
+#[cfg(desktop)]
+use crate::backup::{self, ARCHIVE_EXTENSION};
+use crate::encryption::{DbEncryption, EncryptionStatus, IntegrityReport};
+use crate::error::{DeskflowError, DeskflowResult};
+use crate::instance::RunningInstance;
+use crate::profile::{ProfileDirs, ProfileEntry, ProfileInfo, ProfileStore};
+use crate::state::AppState;
+
+#[tauri::command]
+pub fn list_profiles(profiles: State<'_, ProfileStore>) -> Vec<ProfileEntry> {
+    profiles.list()
+}
+
+#[tauri::command]
+pub fn create_profile(
+    profiles: State<'_, ProfileStore>,
+    name: String,
+) -> DeskflowResult<ProfileInfo> {
+    Ok(profiles.create(&name)?)
+}
+
+/// Point the backend at another profile. The frontend gets
+/// `profile://changed` once the backend has come back on the new dirs.
+/// Refused while another instance runs that profile, and with an external
+/// backend, which would keep serving the old one. If the backend does not
+/// come back on the new profile, the switch is undone.
+#[tauri::command]
+pub async fn switch_profile(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    profiles: State<'_, ProfileStore>,
+    supervisor: State<'_, BackendSupervisor>,
+    probe: State<'_, HealthProbe>,
+    name: String,
+) -> DeskflowResult<ProfileDirs> {
+    ensure_supervised(&state)?;
+    let current = profiles.current();
+    if current.name == name {
+        return Ok(current);
+    }
+    let dirs = move_to_profile(&app, &profiles, &name)?;
+    if !restart_backend_and_wait(&supervisor, &probe).await {
+        move_to_profile(&app, &profiles, &current.name)?;
+        restart_backend_and_wait(&supervisor, &probe).await;
+        return Err(DeskflowError::Timeout {
+            message: format!(
+                "The backend did not come up on profile \"{name}\"; staying on \"{}\"",
+                current.name
+            ),
+        });
+    }
+    let _ = app.emit("profile://changed", &dirs);
+    Ok(dirs)
+}
+
+/// Make `name` the active profile, taking this instance's lock along.
+fn move_to_profile(
+    app: &AppHandle,
+    profiles: &ProfileStore,
+    name: &str,
+) -> DeskflowResult<ProfileDirs> {
+    // Without a single-instance lock (its check failed at startup) there
+    // is nothing to move
+    let running = app.try_state::<RunningInstance>();
+    let previous = profiles.current();
+    if let Some(running) = &running {
+        if !running.move_to(&profiles.dirs(name)?.data)? {
+            return Err(DeskflowError::Busy {
+                message: format!("Profile \"{name}\" is open in another DeskFlow window"),
+            });
+        }
+    }
+    profiles.switch(name).map_err(|e| {
+        if let Some(running) = &running {
+            let _ = running.move_to(&previous.data);
+        }
+        e.into()
+    })
+}
+
+#[tauri::command]
+pub async fn database_encryption_status(
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+) -> DeskflowResult<EncryptionStatus> {
+    let (encryption, dirs) = (encryption.inner().clone(), profiles.current());
+    tauri::async_runtime::spawn_blocking(move || encryption.status(&dirs))
+        .await?
+        .map_err(DeskflowError::from)
+}
+
+/// Encrypt the current profile's databases in place. The backend is
+/// stopped meanwhile and comes back with the key, so it must be able to
+/// open encrypted databases.
+#[tauri::command]
+pub async fn encrypt_database(
+    state: State<'_, AppState>,
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    supervisor: State<'_, BackendSupervisor>,
+    probe: State<'_, HealthProbe>,
+) -> DeskflowResult<EncryptionStatus> {
+    ensure_supervised(&state)?;
+    let health = probe.check(true).await;
+    if !health.is_reachable() {
+        return Err(DeskflowError::invalid_input(
+            "Start the backend first: it has to confirm it can open encrypted databases",
+        ));
+    }
+    // Reported by /api/health/detailed once sqlcipher3 imports
+    if health
+        .components
+        .get("encryption")
+        .is_none_or(|component| component.status != "ok")
+    {
+        return Err(DeskflowError::invalid_input(
+            "The backend cannot open encrypted databases; install its encryption extra \
+             (pip install 'coolaw-deskflow[encryption]')",
+        ));
+    }
+
+    let (encryption, dirs) = (encryption.inner().clone(), profiles.current());
+    with_backend_stopped(&supervisor, &probe, move || {
+        encryption.encrypt(&dirs)?;
+        encryption.status(&dirs)
+    })
+    .await?
+    .map_err(DeskflowError::from)
+}
+
+#[tauri::command]
+pub async fn rotate_database_key(
+    state: State<'_, AppState>,
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    supervisor: State<'_, BackendSupervisor>,
+    probe: State<'_, HealthProbe>,
+) -> DeskflowResult<()> {
+    ensure_supervised(&state)?;
+    let (encryption, dirs) = (encryption.inner().clone(), profiles.current());
+    with_backend_stopped(&supervisor, &probe, move || encryption.rotate(&dirs))
+        .await?
+        .map_err(DeskflowError::from)
+}
+
+#[tauri::command]
+pub async fn verify_database(
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+) -> DeskflowResult<IntegrityReport> {
+    let (encryption, dirs) = (encryption.inner().clone(), profiles.current());
+    tauri::async_runtime::spawn_blocking(move || encryption.verify(&dirs))
+        .await?
+        .map_err(DeskflowError::from)
+}
+
+/// Save the current profile to an archive the user picks. Returns where it
+/// went, or nothing if the user cancelled.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn export_profile(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    supervisor: State<'_, BackendSupervisor>,
+    probe: State<'_, HealthProbe>,
+    decrypt: bool,
+) -> DeskflowResult<Option<String>> {
+    ensure_supervised(&state)?;
+    let dirs = profiles.current();
+    let dialog = app
+        .dialog()
+        .file()
+        .add_filter("DeskFlow profile", &[ARCHIVE_EXTENSION])
+        .set_file_name(format!("{}.{ARCHIVE_EXTENSION}", dirs.name));
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file()).await?;
+    let Some(dest) = picked else {
+        return Ok(None);
+    };
+    let dest = dest.into_path().map_err(DeskflowError::internal)?;
+
+    // Stopped so that files the backend writes outside its database are
+    // settled too
+    let encryption = encryption.inner().clone();
+    let target = dest.clone();
+    with_backend_stopped(&supervisor, &probe, move || {
+        backup::export(&encryption, &dirs, &target, decrypt)
+    })
+    .await??;
+    Ok(Some(dest.to_string_lossy().into_owned()))
+}
+
+/// Restore an archive the user picks as a new profile, named `name` or as
+/// it was exported. Emits `profile://imported`.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn import_profile(
+    app: AppHandle,
+    encryption: State<'_, DbEncryption>,
+    profiles: State<'_, ProfileStore>,
+    name: Option<String>,
+) -> DeskflowResult<Option<ProfileInfo>> {
+    let dialog = app
+        .dialog()
+        .file()
+        .add_filter("DeskFlow profile", &[ARCHIVE_EXTENSION]);
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_pick_file()).await?;
+    let Some(archive) = picked else {
+        return Ok(None);
+    };
+    let archive = archive.into_path().map_err(DeskflowError::internal)?;
+
+    let (encryption, profiles) = (encryption.inner().clone(), profiles.inner().clone());
+    let name = name.filter(|n| !n.trim().is_empty());
+    let info = tauri::async_runtime::spawn_blocking(move || {
+        backup::import(&encryption, &profiles, &archive, name.as_deref())
+    })
+    .await??;
+    let _ = app.emit("profile://imported", &info);
+    Ok(Some(info))
+}
@@ -0,0 +1,45 @@
+//! Provider API keys in the [`SecretStore`].
+
+use tauri::State;
+
+use super::backend::restart_backend_and_wait;
+use crate::backend::{BackendSupervisor, HealthProbe};
+use crate::error::{DeskflowError, DeskflowResult};
+use crate::secrets::{SecretProvider, SecretStore, SecretsStatus};
+
+#[tauri::command]
+pub async fn secrets_status(secrets: State<'_, SecretStore>) -> DeskflowResult<SecretsStatus> {
+    let secrets = secrets.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || secrets.status())
+        .await?
+        .map_err(DeskflowError::from)
+}
+
+/// Store a provider key. A running backend is restarted so it picks the
+/// key up; the command returns once it answers again.
+#[tauri::command]
+pub async fn secrets_set(
+    secrets: State<'_, SecretStore>,
+    supervisor: State<'_, BackendSupervisor>,
+    probe: State<'_, HealthProbe>,
+    provider: SecretProvider,
+    value: String,
+) -> DeskflowResult<()> {
+    let store = secrets.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || store.set(provider, value.trim())).await??;
+    restart_backend_and_wait(&supervisor, &probe).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn secrets_delete(
+    secrets: State<'_, SecretStore>,
+    supervisor: State<'_, BackendSupervisor>,
+    probe: State<'_, HealthProbe>,
+    provider: SecretProvider,
+) -> DeskflowResult<()> {
+    let store = secrets.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || store.delete(provider)).await??;
+    restart_backend_and_wait(&supervisor, &probe).await;
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! The shell's own settings, windows and launch handoff.
+
+use tauri::{AppHandle, State};
+
+#[cfg(desktop)]
+use crate::error::DeskflowError;
+use crate::error::DeskflowResult;
+use crate::events::{navigate, open_link, settings_changed, Navigate};
+use crate::instance::LaunchRequest;
+use crate::settings::{SettingsStore, ShellSettings};
+use crate::state::AppState;
+#[cfg(desktop)]
+use crate::windows::{register_quick_ask_shortcut, show_search};
+
+#[tauri::command]
+pub fn get_shell_settings(settings: State<'_, SettingsStore>) -> ShellSettings {
+    settings.get()
+}
+
+#[tauri::command]
+pub fn set_shell_settings(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    value: ShellSettings,
+) -> DeskflowResult<ShellSettings> {
+    // Mobile has no global shortcuts; there the setting is only stored
+    #[cfg(desktop)]
+    {
+        let previous = settings.get();
+        if value.quick_ask_shortcut != previous.quick_ask_shortcut {
+            if let Err(e) = register_quick_ask_shortcut(&app, &value.quick_ask_shortcut) {
+                // Put the old one back so the user is not left without a shortcut
+                let _ = register_quick_ask_shortcut(&app, &previous.quick_ask_shortcut);
+                return Err(DeskflowError::invalid_input(e));
+            }
+        }
+    }
+    settings.set(value.clone())?;
+    settings_changed(&app, &value);
+    Ok(value)
+}
+
+/// Show a conversation (or a fresh chat) in the main window, e.g. from the
+/// quick-ask window.
+#[tauri::command]
+pub fn open_in_main_window(app: AppHandle, conversation_id: Option<String>) {
+    navigate(&app, Navigate::chat(conversation_id));
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn open_search_window(app: AppHandle) {
+    show_search(&app);
+}
+
+/// Launch requests (this process's own, then ones forwarded by later
+/// launches) the frontend has not picked up yet.
+#[tauri::command]
+pub fn take_launch_requests(state: State<'_, AppState>) -> Vec<LaunchRequest> {
+    state.take_launches()
+}
+
+/// Called once the main window listens for shell events.
+#[tauri::command]
+pub fn shell_ready(app: AppHandle, state: State<'_, AppState>) {
+    for link in state.take_startup_links() {
+        open_link(&app, &link);
+    }
+}
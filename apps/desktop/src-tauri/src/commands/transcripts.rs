@@ -0,0 +1,130 @@
+//! Conversation transcripts saved to files the user picks. Desktop only,
+//! since it needs the native file dialogs.
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+use tracing::warn;
+
+use super::history::open_history;
+use crate::deskflow_client::DeskflowClient;
+use crate::encryption::DbEncryption;
+use crate::error::{DeskflowError, DeskflowResult};
+use crate::history::{History, HistoryError};
+use crate::profile::ProfileStore;
+use crate::search::DateRange;
+use crate::transcript::{self, Transcript, TranscriptFormat};
+
+/// Save a conversation as Markdown, HTML or JSON Lines where the user
+/// picks. Returns the path, or nothing if the user cancelled.
+#[tauri::command]
+pub async fn export_conversation(
+    app: AppHandle,
+    client: State<'_, DeskflowClient>,
+    id: String,
+    format: TranscriptFormat,
+) -> DeskflowResult<Option<String>> {
+    let (history, profiles, encryption) = transcript_sources(&app);
+    let loaded = tauri::async_runtime::spawn_blocking({
+        let id = id.clone();
+        move || local_transcript(&history, &profiles, &encryption, &id)
+    })
+    .await?;
+    let transcript = match loaded {
+        Ok(transcript) => transcript,
+        // Not on disk (yet); the backend may still have it
+        Err(HistoryError::Missing(_) | HistoryError::NotFound(_)) => {
+            Transcript::from(client.conversation(&id).await?)
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let dialog = app
+        .dialog()
+        .file()
+        .add_filter(format.label(), &[format.extension()])
+        .set_file_name(transcript::file_name(&transcript, format, false));
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file()).await?;
+    let Some(dest) = picked else {
+        return Ok(None);
+    };
+    let dest = dest.into_path().map_err(DeskflowError::internal)?;
+    tokio::fs::write(&dest, transcript::render(&transcript, format))
+        .await
+        .map_err(|e| DeskflowError::from(e).context(format!("Cannot write {}", dest.display())))?;
+    Ok(Some(dest.to_string_lossy().into_owned()))
+}
+
+#[derive(Serialize)]
+pub struct TranscriptExport {
+    dir: String,
+    count: usize,
+}
+
+/// Save every conversation active within `range` (all of them without
+/// one) into a directory the user picks, one file each.
+#[tauri::command]
+pub async fn export_conversations(
+    app: AppHandle,
+    range: Option<DateRange>,
+    format: TranscriptFormat,
+) -> DeskflowResult<Option<TranscriptExport>> {
+    let range = range.unwrap_or_default();
+    range.validate()?;
+
+    let dialog = app.dialog().file();
+    let picked =
+        tauri::async_runtime::spawn_blocking(move || dialog.blocking_pick_folder()).await?;
+    let Some(dir) = picked else {
+        return Ok(None);
+    };
+    let dir = dir.into_path().map_err(DeskflowError::internal)?;
+
+    let (history, profiles, encryption) = transcript_sources(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = open_history(&encryption, &profiles.current())?;
+        let conversations = history.conversations_between(&conn, &range)?;
+        for summary in &conversations {
+            let transcript = local_transcript(&history, &profiles, &encryption, &summary.id)?;
+            let dest = dir.join(transcript::file_name(&transcript, format, true));
+            std::fs::write(&dest, transcript::render(&transcript, format)).map_err(|e| {
+                DeskflowError::from(e).context(format!("Cannot write {}", dest.display()))
+            })?;
+        }
+        Ok::<_, DeskflowError>(Some(TranscriptExport {
+            dir: dir.to_string_lossy().into_owned(),
+            count: conversations.len(),
+        }))
+    })
+    .await?
+}
+
+fn transcript_sources(app: &AppHandle) -> (History, ProfileStore, DbEncryption) {
+    (
+        app.state::<History>().inner().clone(),
+        app.state::<ProfileStore>().inner().clone(),
+        app.state::<DbEncryption>().inner().clone(),
+    )
+}
+
+/// A conversation as the agent recorded it in the profile database, tool
+/// calls included, or from the history database when the agent has no
+/// record of it.
+fn local_transcript(
+    history: &History,
+    profiles: &ProfileStore,
+    encryption: &DbEncryption,
+    id: &str,
+) -> Result<Transcript, HistoryError> {
+    let dirs = profiles.current();
+    if let Ok(conn) = encryption.open_read_only(&dirs, &dirs.db_path()) {
+        match transcript::from_profile_db(&conn, id) {
+            Ok(Some(transcript)) => return Ok(transcript),
+            Ok(None) => {}
+            Err(e) => warn!(conversation = id, "Cannot read the agent's copy: {e}"),
+        }
+    }
+    Ok(Transcript::from(
+        history.conversation(&open_history(encryption, &dirs)?, id)?,
+    ))
+}
@@ -0,0 +1,30 @@
+//! Updates of the app: the updater's status, checking, downloading and
+//! installing. Progress also arrives as `updater://status` events.
+
+use tauri::{AppHandle, State};
+
+use crate::error::DeskflowResult;
+use crate::updater::{self, UpdateStatus, Updates};
+
+#[tauri::command]
+pub fn update_status(updates: State<'_, Updates>) -> UpdateStatus {
+    updates.status()
+}
+
+/// Check the configured channel now, whatever the schedule says.
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> DeskflowResult<UpdateStatus> {
+    updater::check(&app).await
+}
+
+#[tauri::command]
+pub async fn download_update(app: AppHandle) -> DeskflowResult<UpdateStatus> {
+    updater::download(&app).await
+}
+
+/// Install the downloaded update and restart into it. Only returns on
+/// failure.
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> DeskflowResult<()> {
+    updater::install(&app).await
+}
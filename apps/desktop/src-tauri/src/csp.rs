@@ -0,0 +1,77 @@
+//! Runtime adjustments to the webview content security policy.
+
+use std::collections::HashMap;
+
+use tauri::utils::config::{Csp, CspDirectiveSources};
+
+use crate::backend::port::{backend_url, backend_ws_url};
+
+/// Replace `connect-src` so the webview may only talk to the backend on the
+/// port it actually got, instead of whatever was baked into
+/// `tauri.conf.json`.
+pub fn allow_backend(csp: Csp, port: u16) -> Csp {
+    let mut directives: HashMap<String, CspDirectiveSources> = csp.into();
+    directives.insert(
+        "connect-src".into(),
+        CspDirectiveSources::List(vec![
+            "'self'".into(),
+            "ipc:".into(),
+            "http://ipc.localhost".into(),
+            backend_url(port),
+            backend_ws_url(port),
+        ]),
+    );
+    Csp::DirectiveMap(directives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(csp: &Csp, directive: &str) -> Vec<String> {
+        let directives: HashMap<String, CspDirectiveSources> = csp.clone().into();
+        match directives.get(directive) {
+            Some(CspDirectiveSources::List(list)) => list.clone(),
+            Some(CspDirectiveSources::Inline(inline)) => {
+                inline.split_whitespace().map(String::from).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn connect_src_allows_only_the_allocated_port() {
+        let policy = Csp::Policy(
+            "default-src 'self'; connect-src 'self' http://127.0.0.1:8420 ws://127.0.0.1:8420; img-src 'self' data:"
+                .to_string(),
+        );
+        let csp = allow_backend(policy, 51234);
+
+        assert_eq!(
+            sources(&csp, "connect-src"),
+            [
+                "'self'",
+                "ipc:",
+                "http://ipc.localhost",
+                "http://127.0.0.1:51234",
+                "ws://127.0.0.1:51234",
+            ]
+        );
+        assert_eq!(sources(&csp, "default-src"), ["'self'"]);
+        assert_eq!(sources(&csp, "img-src"), ["'self'", "data:"]);
+        assert!(!csp.to_string().contains("8420"));
+    }
+
+    #[test]
+    fn connect_src_is_added_when_missing() {
+        let mut directives = HashMap::new();
+        directives.insert(
+            "default-src".to_string(),
+            CspDirectiveSources::Inline("'self'".to_string()),
+        );
+        let csp = allow_backend(Csp::DirectiveMap(directives), 8420);
+
+        assert!(sources(&csp, "connect-src").contains(&"http://127.0.0.1:8420".to_string()));
+        assert_eq!(sources(&csp, "default-src"), ["'self'"]);
+    }
+}
@@ -0,0 +1,416 @@
+//! `deskflow://` URLs, parsed into routes the shell knows how to open.
+//!
+//! Supported forms:
+//!
+//! - `deskflow://chat` and `deskflow://chat?prompt=...` (prefills, never sends)
+//! - `deskflow://conversation/<id>`
+//! - `deskflow://skills`, `deskflow://monitor`, `deskflow://settings`,
+//!   `deskflow://imchannels`
+//! - `deskflow://skills/install?url=...` with a GitHub repository URL or a
+//!   `file://` zip archive, or `?template=<name>`
+//!
+//! Links come from anywhere (browsers, scripts, other apps), so everything is
+//! validated here and routes that change state must be confirmed by the user
+//! before they run.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use url::Url;
+
+pub const SCHEME: &str = "deskflow";
+
+const MAX_PROMPT_CHARS: usize = 10_000;
+const MAX_ID_CHARS: usize = 128;
+
+/// Where a deep link leads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    /// A new chat, optionally with the input prefilled.
+    Chat {
+        prompt: Option<String>,
+    },
+    Conversation {
+        id: String,
+    },
+    View(View),
+    InstallSkill(SkillSource),
+}
+
+/// Views reachable without further arguments. Names match the frontend's
+/// `ViewName`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Skills,
+    Monitor,
+    Settings,
+    ImChannels,
+}
+
+impl View {
+    pub fn name(self) -> &'static str {
+        match self {
+            View::Skills => "skills",
+            View::Monitor => "monitor",
+            View::Settings => "settings",
+            View::ImChannels => "imchannels",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkillSource {
+    /// One of the backend's built-in templates.
+    Template(String),
+    /// A GitHub repository, downloaded by the backend.
+    GitHub(Url),
+    /// A local zip archive containing a `skill.json`.
+    Archive(PathBuf),
+}
+
+impl fmt::Display for SkillSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkillSource::Template(name) => write!(f, "built-in template \"{name}\""),
+            SkillSource::GitHub(url) => write!(f, "{url}"),
+            SkillSource::Archive(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    Malformed(String),
+    WrongScheme(String),
+    UnknownRoute(String),
+    MissingParameter(&'static str),
+    InvalidParameter { name: &'static str, reason: String },
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::Malformed(e) => write!(f, "Malformed link: {e}"),
+            RouteError::WrongScheme(s) => write!(f, "Not a {SCHEME}:// link: {s}"),
+            RouteError::UnknownRoute(r) => write!(f, "Unknown link target: {r}"),
+            RouteError::MissingParameter(p) => write!(f, "Link is missing \"{p}\""),
+            RouteError::InvalidParameter { name, reason } => {
+                write!(f, "Invalid \"{name}\" in link: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+/// Whether `arg` looks like a deep link rather than a prompt or a file.
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.get(..SCHEME.len())
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case(SCHEME))
+        && arg[SCHEME.len()..].starts_with("://")
+}
+
+impl Route {
+    pub fn parse(link: &str) -> Result<Route, RouteError> {
+        let url = Url::parse(link).map_err(|e| RouteError::Malformed(e.to_string()))?;
+        if url.scheme() != SCHEME {
+            return Err(RouteError::WrongScheme(url.scheme().to_string()));
+        }
+
+        // `deskflow://conversation/abc` puts "conversation" in the host
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let query = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+
+        match (host.as_str(), segments.as_slice()) {
+            ("chat", []) => {
+                let prompt = query("prompt").filter(|p| !p.trim().is_empty());
+                if let Some(prompt) = &prompt {
+                    if prompt.chars().count() > MAX_PROMPT_CHARS {
+                        return Err(RouteError::InvalidParameter {
+                            name: "prompt",
+                            reason: format!("longer than {MAX_PROMPT_CHARS} characters"),
+                        });
+                    }
+                }
+                Ok(Route::Chat { prompt })
+            }
+            ("conversation", [id]) => Ok(Route::Conversation {
+                id: validate_id("id", id)?,
+            }),
+            ("conversation", []) => Err(RouteError::MissingParameter("id")),
+            ("skills", []) => Ok(Route::View(View::Skills)),
+            ("skills", ["install"]) => {
+                if let Some(template) = query("template") {
+                    return Ok(Route::InstallSkill(SkillSource::Template(validate_id(
+                        "template", &template,
+                    )?)));
+                }
+                let source = query("url").ok_or(RouteError::MissingParameter("url"))?;
+                Ok(Route::InstallSkill(skill_source(&source)?))
+            }
+            ("monitor", []) => Ok(Route::View(View::Monitor)),
+            ("settings", []) => Ok(Route::View(View::Settings)),
+            ("imchannels", []) => Ok(Route::View(View::ImChannels)),
+            _ => Err(RouteError::UnknownRoute(
+                std::iter::once(host.as_str())
+                    .chain(segments.iter().copied())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            )),
+        }
+    }
+
+    /// Whether the route does more than show something, and so needs the
+    /// user's confirmation first.
+    pub fn changes_state(&self) -> bool {
+        matches!(self, Route::InstallSkill(_))
+    }
+
+    /// What following the link will do, phrased for a confirmation prompt.
+    pub fn summary(&self) -> String {
+        match self {
+            Route::Chat { prompt: None } => "start a new chat".into(),
+            Route::Chat { prompt: Some(_) } => "start a new chat with a prepared prompt".into(),
+            Route::Conversation { id } => format!("open conversation {id}"),
+            Route::View(view) => format!("open the {} view", view.name()),
+            Route::InstallSkill(source) => format!("install a skill from {source}"),
+        }
+    }
+}
+
+fn validate_id(name: &'static str, value: &str) -> Result<String, RouteError> {
+    let valid = !value.is_empty()
+        && value.len() <= MAX_ID_CHARS
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(RouteError::InvalidParameter {
+            name,
+            reason: "expected letters, digits, '-' or '_'".into(),
+        })
+    }
+}
+
+fn skill_source(source: &str) -> Result<SkillSource, RouteError> {
+    let invalid = |reason: &str| RouteError::InvalidParameter {
+        name: "url",
+        reason: reason.to_string(),
+    };
+    let url = Url::parse(source).map_err(|e| invalid(&e.to_string()))?;
+
+    match url.scheme() {
+        "file" => {
+            let path = url
+                .to_file_path()
+                .map_err(|_| invalid("not a local file path"))?;
+            let is_zip = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+            if !is_zip {
+                return Err(invalid("skill archives must be .zip files"));
+            }
+            Ok(SkillSource::Archive(path))
+        }
+        "https" if url.host_str() == Some("github.com") => {
+            let parts = url.path_segments().map(|s| s.count()).unwrap_or(0);
+            if parts < 2 {
+                return Err(invalid("expected https://github.com/<owner>/<repo>"));
+            }
+            Ok(SkillSource::GitHub(url))
+        }
+        _ => Err(invalid(
+            "only https://github.com/... and file:// are supported",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(link: &str, name: &str) -> bool {
+        matches!(Route::parse(link), Err(RouteError::InvalidParameter { name: n, .. }) if n == name)
+    }
+
+    #[test]
+    fn chat_prompts_are_decoded() {
+        assert_eq!(
+            Route::parse("deskflow://chat?prompt=hello%20world%20%26%20%E4%BD%A0%E5%A5%BD"),
+            Ok(Route::Chat {
+                prompt: Some("hello world & 你好".into())
+            })
+        );
+        assert_eq!(
+            Route::parse("deskflow://chat?prompt=a+b"),
+            Ok(Route::Chat {
+                prompt: Some("a b".into())
+            })
+        );
+        // Blank prompts are dropped, not prefilled
+        assert_eq!(
+            Route::parse("deskflow://chat?prompt=%20%20"),
+            Ok(Route::Chat { prompt: None })
+        );
+        assert_eq!(
+            Route::parse("deskflow://chat"),
+            Ok(Route::Chat { prompt: None })
+        );
+        let long = format!(
+            "deskflow://chat?prompt={}",
+            "a".repeat(MAX_PROMPT_CHARS + 1)
+        );
+        assert!(invalid(&long, "prompt"));
+    }
+
+    #[test]
+    fn conversation_ids_are_validated() {
+        assert_eq!(
+            Route::parse("deskflow://conversation/abc-123_x"),
+            Ok(Route::Conversation {
+                id: "abc-123_x".into()
+            })
+        );
+        for link in ["deskflow://conversation", "deskflow://conversation/"] {
+            assert_eq!(
+                Route::parse(link),
+                Err(RouteError::MissingParameter("id")),
+                "{link}"
+            );
+        }
+        // URL parsing resolves dot segments before the id is taken, and
+        // they cannot climb out of the host
+        for link in [
+            "deskflow://conversation/..",
+            "deskflow://conversation/%2e%2e",
+        ] {
+            assert_eq!(
+                Route::parse(link),
+                Err(RouteError::MissingParameter("id")),
+                "{link}"
+            );
+        }
+        assert_eq!(
+            Route::parse("deskflow://conversation/../settings"),
+            Ok(Route::Conversation {
+                id: "settings".into()
+            })
+        );
+        assert!(invalid("deskflow://conversation/a%2Fb", "id"));
+        assert!(invalid("deskflow://conversation/a%2F..%2Fb", "id"));
+        assert!(invalid(
+            &format!("deskflow://conversation/{}", "a".repeat(MAX_ID_CHARS + 1)),
+            "id"
+        ));
+        assert!(matches!(
+            Route::parse("deskflow://conversation/a/b"),
+            Err(RouteError::UnknownRoute(_))
+        ));
+    }
+
+    #[test]
+    fn skills_install_only_from_github_or_zip_files() {
+        assert_eq!(
+            Route::parse("deskflow://skills/install?url=https://github.com/owner/repo"),
+            Ok(Route::InstallSkill(SkillSource::GitHub(
+                Url::parse("https://github.com/owner/repo").unwrap()
+            )))
+        );
+        assert!(matches!(
+            Route::parse("deskflow://skills/install?url=file:///tmp/skill.zip"),
+            Ok(Route::InstallSkill(SkillSource::Archive(_)))
+        ));
+        assert_eq!(
+            Route::parse("deskflow://skills/install?template=web-search"),
+            Ok(Route::InstallSkill(SkillSource::Template(
+                "web-search".into()
+            )))
+        );
+
+        for url in [
+            "http://github.com/owner/repo",
+            "https://example.com/owner/repo",
+            "https://github.com/owner",
+            "ftp://github.com/owner/repo",
+            "javascript:alert(1)",
+            "data:application/zip;base64,UEs=",
+            "file:///tmp/skill.exe",
+            "not a url",
+        ] {
+            let link = format!(
+                "deskflow://skills/install?url={}",
+                url::form_urlencoded::byte_serialize(url.as_bytes()).collect::<String>()
+            );
+            assert!(invalid(&link, "url"), "{url}");
+        }
+        assert!(invalid(
+            "deskflow://skills/install?template=../etc",
+            "template"
+        ));
+        assert_eq!(
+            Route::parse("deskflow://skills/install"),
+            Err(RouteError::MissingParameter("url"))
+        );
+    }
+
+    #[test]
+    fn views_and_unknown_hosts() {
+        assert_eq!(
+            Route::parse("deskflow://Settings"),
+            Ok(Route::View(View::Settings))
+        );
+        assert_eq!(
+            Route::parse("deskflow://imchannels/"),
+            Ok(Route::View(View::ImChannels))
+        );
+        assert_eq!(
+            Route::parse("deskflow://admin/reset"),
+            Err(RouteError::UnknownRoute("admin/reset".into()))
+        );
+        assert_eq!(
+            Route::parse("deskflow://"),
+            Err(RouteError::UnknownRoute(String::new()))
+        );
+    }
+
+    #[test]
+    fn other_schemes_are_refused() {
+        assert_eq!(
+            Route::parse("https://chat?prompt=hi"),
+            Err(RouteError::WrongScheme("https".into()))
+        );
+        assert_eq!(
+            Route::parse("deskflowx://chat"),
+            Err(RouteError::WrongScheme("deskflowx".into()))
+        );
+        assert!(matches!(
+            Route::parse("chat?prompt=hi"),
+            Err(RouteError::Malformed(_))
+        ));
+
+        assert!(is_deep_link("DESKFLOW://chat"));
+        assert!(!is_deep_link("deskflow:chat"));
+        assert!(!is_deep_link("deskflowx://chat"));
+        assert!(!is_deep_link("dësk"));
+    }
+
+    #[test]
+    fn only_installs_need_confirmation() {
+        assert!(Route::parse("deskflow://skills/install?template=x")
+            .unwrap()
+            .changes_state());
+        assert!(!Route::parse("deskflow://chat?prompt=x")
+            .unwrap()
+            .changes_state());
+    }
+}
@@ -0,0 +1,254 @@
+//! Tauri commands wrapping [`DeskflowClient`]. They are prefixed `api_` to
+//! keep them apart from commands the shell answers on its own.
+
+use serde_json::Value;
+use tauri::State;
+
+use super::models::*;
+use super::DeskflowClient;
+use crate::error::DeskflowResult as CommandResult;
+
+// --- Chat ------------------------------------------------------------------
+
+#[tauri::command]
+pub async fn api_chat(
+    client: State<'_, DeskflowClient>,
+    request: ChatRequest,
+) -> CommandResult<ChatResponse> {
+    client.chat(&request).await
+}
+
+#[tauri::command]
+pub async fn api_conversations(
+    client: State<'_, DeskflowClient>,
+    limit: Option<u32>,
+) -> CommandResult<ConversationList> {
+    client.conversations(limit.unwrap_or(50)).await
+}
+
+#[tauri::command]
+pub async fn api_conversation(
+    client: State<'_, DeskflowClient>,
+    id: String,
+) -> CommandResult<Conversation> {
+    client.conversation(&id).await
+}
+
+#[tauri::command]
+pub async fn api_delete_conversation(
+    client: State<'_, DeskflowClient>,
+    id: String,
+) -> CommandResult<SuccessResponse> {
+    client.delete_conversation(&id).await
+}
+
+#[tauri::command]
+pub async fn api_save_conversation(
+    client: State<'_, DeskflowClient>,
+    request: SaveConversationRequest,
+) -> CommandResult<SaveConversationResponse> {
+    client.save_conversation(&request).await
+}
+
+// --- Sessions --------------------------------------------------------------
+
+#[tauri::command]
+pub async fn api_create_session(
+    client: State<'_, DeskflowClient>,
+    request: CreateSessionRequest,
+) -> CommandResult<CreateSessionResponse> {
+    client.create_session(&request).await
+}
+
+#[tauri::command]
+pub async fn api_session(client: State<'_, DeskflowClient>, id: String) -> CommandResult<Value> {
+    client.session(&id).await
+}
+
+#[tauri::command]
+pub async fn api_delete_session(
+    client: State<'_, DeskflowClient>,
+    id: String,
+) -> CommandResult<SuccessResponse> {
+    client.delete_session(&id).await
+}
+
+// --- Memory ----------------------------------------------------------------
+
+#[tauri::command]
+pub async fn api_memory_stats(client: State<'_, DeskflowClient>) -> CommandResult<Value> {
+    client.memory_stats().await
+}
+
+#[tauri::command]
+pub async fn api_recent_memories(
+    client: State<'_, DeskflowClient>,
+    limit: Option<u32>,
+) -> CommandResult<RecentMemories> {
+    client.recent_memories(limit.unwrap_or(20)).await
+}
+
+#[tauri::command]
+pub async fn api_delete_memory(
+    client: State<'_, DeskflowClient>,
+    id: String,
+) -> CommandResult<SuccessResponse> {
+    client.delete_memory(&id).await
+}
+
+// --- Skills ----------------------------------------------------------------
+
+#[tauri::command]
+pub async fn api_skills(client: State<'_, DeskflowClient>) -> CommandResult<SkillList> {
+    client.skills().await
+}
+
+#[tauri::command]
+pub async fn api_skill(client: State<'_, DeskflowClient>, name: String) -> CommandResult<Value> {
+    client.skill(&name).await
+}
+
+#[tauri::command]
+pub async fn api_toggle_skill(
+    client: State<'_, DeskflowClient>,
+    name: String,
+    action: Option<String>,
+) -> CommandResult<SkillToggleResponse> {
+    client
+        .toggle_skill(&name, action.as_deref().unwrap_or("toggle"))
+        .await
+}
+
+#[tauri::command]
+pub async fn api_install_skill(
+    client: State<'_, DeskflowClient>,
+    request: SkillInstallRequest,
+) -> CommandResult<SkillInstallResponse> {
+    client.install_skill(&request).await
+}
+
+// --- Config & LLM ----------------------------------------------------------
+
+#[tauri::command]
+pub async fn api_config(client: State<'_, DeskflowClient>) -> CommandResult<ConfigResponse> {
+    client.config().await
+}
+
+#[tauri::command]
+pub async fn api_update_config(
+    client: State<'_, DeskflowClient>,
+    request: ConfigUpdateRequest,
+) -> CommandResult<ConfigResponse> {
+    client.update_config(&request).await
+}
+
+#[tauri::command]
+pub async fn api_llm_models(
+    client: State<'_, DeskflowClient>,
+    provider: String,
+    base_url: Option<String>,
+    api_key: Option<String>,
+) -> CommandResult<LlmModelsResponse> {
+    client
+        .llm_models(&provider, base_url.as_deref(), api_key.as_deref())
+        .await
+}
+
+#[tauri::command]
+pub async fn api_test_llm(
+    client: State<'_, DeskflowClient>,
+    request: LlmTestRequest,
+) -> CommandResult<LlmTestResponse> {
+    client.test_llm(&request).await
+}
+
+#[tauri::command]
+pub async fn api_status(client: State<'_, DeskflowClient>) -> CommandResult<StatusResponse> {
+    client.status().await
+}
+
+// --- Setup -----------------------------------------------------------------
+
+#[tauri::command]
+pub async fn api_setup_config(client: State<'_, DeskflowClient>) -> CommandResult<Value> {
+    client.setup_config().await
+}
+
+#[tauri::command]
+pub async fn api_save_setup_config(
+    client: State<'_, DeskflowClient>,
+    request: SetupConfigRequest,
+) -> CommandResult<SetupResponse> {
+    client.save_setup_config(&request).await
+}
+
+#[tauri::command]
+pub async fn api_setup_start(client: State<'_, DeskflowClient>) -> CommandResult<SetupResponse> {
+    client.setup_start().await
+}
+
+// --- Monitor ---------------------------------------------------------------
+
+#[tauri::command]
+pub async fn api_monitor_status(client: State<'_, DeskflowClient>) -> CommandResult<SystemStatus> {
+    client.monitor_status().await
+}
+
+#[tauri::command]
+pub async fn api_llm_stats(client: State<'_, DeskflowClient>) -> CommandResult<LlmStats> {
+    client.llm_stats().await
+}
+
+#[tauri::command]
+pub async fn api_activity(
+    client: State<'_, DeskflowClient>,
+    limit: Option<u32>,
+) -> CommandResult<ActivityList> {
+    client.activity(limit.unwrap_or(50)).await
+}
+
+#[tauri::command]
+pub async fn api_token_stats(
+    client: State<'_, DeskflowClient>,
+    days: Option<u32>,
+) -> CommandResult<Value> {
+    client.token_stats(days.unwrap_or(7)).await
+}
+
+// --- Orchestration ---------------------------------------------------------
+
+#[tauri::command]
+pub async fn api_orchestration_status(
+    client: State<'_, DeskflowClient>,
+) -> CommandResult<MasterAgentStatus> {
+    client.orchestration_status().await
+}
+
+#[tauri::command]
+pub async fn api_worker_stats(client: State<'_, DeskflowClient>) -> CommandResult<WorkerPoolStats> {
+    client.worker_stats().await
+}
+
+#[tauri::command]
+pub async fn api_submit_task(
+    client: State<'_, DeskflowClient>,
+    request: TaskSubmitRequest,
+) -> CommandResult<TaskSubmitResponse> {
+    client.submit_task(&request).await
+}
+
+#[tauri::command]
+pub async fn api_cancel_task(
+    client: State<'_, DeskflowClient>,
+    task_id: String,
+) -> CommandResult<TaskCancelResponse> {
+    client.cancel_task(&task_id).await
+}
+
+#[tauri::command]
+pub async fn api_task_result(
+    client: State<'_, DeskflowClient>,
+    task_id: String,
+) -> CommandResult<TaskResult> {
+    client.task_result(&task_id).await
+}
@@ -0,0 +1,441 @@
+//! Typed client for the DeskFlow REST API served by the Python backend.
+//!
+//! Timeouts, retries and the mapping of backend failures to
+//! [`DeskflowError`] all live here so the commands and the rest of the shell
+//! don't each hand-roll them.
+
+#[cfg(feature = "desktop")]
+pub mod commands;
+pub mod models;
+
+use std::time::Duration;
+
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::error::DeskflowError;
+use models::*;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+/// `metadata.warning` of a chat answered without an LLM.
+const LLM_NOT_CONFIGURED: &str = "LLM not configured";
+
+pub type ClientResult<T> = Result<T, DeskflowError>;
+
+/// FastAPI reports errors as `{"detail": ...}`; our own handlers sometimes
+/// use the `ErrorResponse` schema instead.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorBody {
+    Detail { detail: Value },
+    Error(ErrorResponse),
+}
+
+#[derive(Clone)]
+pub struct DeskflowClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl DeskflowClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            // A fresh connection per request, so a backend that went away
+            // fails to connect (and is retried) rather than failing a send
+            // on a kept-alive socket it closed. The backend is normally local,
+            // so connecting is cheap
+            .pool_max_idle_per_host(0)
+            .build()
+            .unwrap_or_default();
+        Self {
+            http,
+            base_url: base_url.into(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // --- Chat --------------------------------------------------------------
+
+    /// Without an LLM the backend still answers 200, with a canned message
+    /// and a warning in the metadata; that comes back as
+    /// [`DeskflowError::LlmNotConfigured`].
+    pub async fn chat(&self, request: &ChatRequest) -> ClientResult<ChatResponse> {
+        let response: ChatResponse = self.post("/api/chat", request).await?;
+        if response.metadata.get("warning").and_then(Value::as_str) == Some(LLM_NOT_CONFIGURED) {
+            return Err(DeskflowError::LlmNotConfigured {
+                status: None,
+                message: response.message,
+            });
+        }
+        Ok(response)
+    }
+
+    pub async fn conversations(&self, limit: u32) -> ClientResult<ConversationList> {
+        self.get_query("/api/chat/history", &[("limit", limit.to_string())])
+            .await
+    }
+
+    pub async fn conversation(&self, id: &str) -> ClientResult<Conversation> {
+        self.get_at("/api/chat", &[id]).await
+    }
+
+    pub async fn delete_conversation(&self, id: &str) -> ClientResult<SuccessResponse> {
+        self.delete("/api/chat", &[id]).await
+    }
+
+    pub async fn save_conversation(
+        &self,
+        request: &SaveConversationRequest,
+    ) -> ClientResult<SaveConversationResponse> {
+        self.post("/api/chat/history/save", request).await
+    }
+
+    // --- Sessions ----------------------------------------------------------
+
+    pub async fn create_session(
+        &self,
+        request: &CreateSessionRequest,
+    ) -> ClientResult<CreateSessionResponse> {
+        self.post("/api/sessions/create", request).await
+    }
+
+    pub async fn session(&self, id: &str) -> ClientResult<Value> {
+        self.get_at("/api/sessions", &[id]).await
+    }
+
+    pub async fn delete_session(&self, id: &str) -> ClientResult<SuccessResponse> {
+        self.delete("/api/sessions", &[id]).await
+    }
+
+    // --- Memory ------------------------------------------------------------
+
+    pub async fn memory_stats(&self) -> ClientResult<Value> {
+        self.get("/api/memory/stats").await
+    }
+
+    pub async fn recent_memories(&self, limit: u32) -> ClientResult<RecentMemories> {
+        self.get_query("/api/memory/recent", &[("limit", limit.to_string())])
+            .await
+    }
+
+    pub async fn delete_memory(&self, id: &str) -> ClientResult<SuccessResponse> {
+        self.delete("/api/memory", &[id]).await
+    }
+
+    // --- Skills ------------------------------------------------------------
+
+    pub async fn skills(&self) -> ClientResult<SkillList> {
+        self.get("/api/skills").await
+    }
+
+    pub async fn skill(&self, name: &str) -> ClientResult<Value> {
+        self.get_at("/api/skills", &[name]).await
+    }
+
+    /// `action` is one of `enable`, `disable` or `toggle`.
+    pub async fn toggle_skill(
+        &self,
+        name: &str,
+        action: &str,
+    ) -> ClientResult<SkillToggleResponse> {
+        let request = self
+            .request_at(Method::POST, "/api/skills", &[name, "toggle"])?
+            .query(&[("action", action)]);
+        self.send(request, false).await
+    }
+
+    pub async fn install_skill(
+        &self,
+        request: &SkillInstallRequest,
+    ) -> ClientResult<SkillInstallResponse> {
+        self.post("/api/skills/install", request).await
+    }
+
+    // --- Config & LLM ------------------------------------------------------
+
+    pub async fn config(&self) -> ClientResult<ConfigResponse> {
+        self.get("/api/config").await
+    }
+
+    pub async fn update_config(
+        &self,
+        request: &ConfigUpdateRequest,
+    ) -> ClientResult<ConfigResponse> {
+        self.post("/api/config", request).await
+    }
+
+    pub async fn llm_models(
+        &self,
+        provider: &str,
+        base_url: Option<&str>,
+        api_key: Option<&str>,
+    ) -> ClientResult<LlmModelsResponse> {
+        let mut query = vec![("provider", provider.to_string())];
+        if let Some(base_url) = base_url {
+            query.push(("base_url", base_url.to_string()));
+        }
+        if let Some(api_key) = api_key {
+            query.push(("api_key", api_key.to_string()));
+        }
+        self.get_query("/api/llm/models", &query).await
+    }
+
+    pub async fn test_llm(&self, request: &LlmTestRequest) -> ClientResult<LlmTestResponse> {
+        self.post("/api/llm/test", request).await
+    }
+
+    pub async fn status(&self) -> ClientResult<StatusResponse> {
+        self.get("/api/status").await
+    }
+
+    // --- Setup -------------------------------------------------------------
+
+    pub async fn setup_config(&self) -> ClientResult<Value> {
+        self.get("/api/setup/config").await
+    }
+
+    pub async fn save_setup_config(
+        &self,
+        request: &SetupConfigRequest,
+    ) -> ClientResult<SetupResponse> {
+        self.post("/api/setup/config", request).await
+    }
+
+    pub async fn setup_start(&self) -> ClientResult<SetupResponse> {
+        self.send(self.request(Method::POST, "/api/setup/start"), false)
+            .await
+    }
+
+    // --- Monitor -----------------------------------------------------------
+
+    pub async fn monitor_status(&self) -> ClientResult<SystemStatus> {
+        self.get("/api/monitor/status").await
+    }
+
+    pub async fn llm_stats(&self) -> ClientResult<LlmStats> {
+        self.get("/api/monitor/llm-stats").await
+    }
+
+    pub async fn activity(&self, limit: u32) -> ClientResult<ActivityList> {
+        self.get_query("/api/monitor/activity", &[("limit", limit.to_string())])
+            .await
+    }
+
+    /// Component, system and process health with recommendations; slow,
+    /// as it probes the LLM provider.
+    pub async fn health_detailed(&self) -> ClientResult<Value> {
+        self.get("/api/health/detailed").await
+    }
+
+    pub async fn token_stats(&self, days: u32) -> ClientResult<Value> {
+        self.get_query("/api/monitor/token-stats", &[("days", days.to_string())])
+            .await
+    }
+
+    // --- Orchestration -----------------------------------------------------
+
+    pub async fn orchestration_status(&self) -> ClientResult<MasterAgentStatus> {
+        self.get("/api/orchestration/status").await
+    }
+
+    pub async fn worker_stats(&self) -> ClientResult<WorkerPoolStats> {
+        self.get("/api/orchestration/workers/stats").await
+    }
+
+    pub async fn submit_task(
+        &self,
+        request: &TaskSubmitRequest,
+    ) -> ClientResult<TaskSubmitResponse> {
+        self.post("/api/orchestration/tasks/submit", request).await
+    }
+
+    pub async fn cancel_task(&self, task_id: &str) -> ClientResult<TaskCancelResponse> {
+        self.post(
+            "/api/orchestration/tasks/cancel",
+            &serde_json::json!({ "task_id": task_id }),
+        )
+        .await
+    }
+
+    pub async fn task_result(&self, task_id: &str) -> ClientResult<TaskResult> {
+        self.get_at("/api/orchestration/tasks/result", &[task_id])
+            .await
+    }
+
+    // --- Plumbing ----------------------------------------------------------
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", self.base_url, path))
+    }
+
+    /// `path` followed by `segments`, each percent-encoded as a single
+    /// path segment. Empty, `.` and `..` segments are refused, as they
+    /// would address a different resource.
+    fn url(&self, path: &str, segments: &[&str]) -> ClientResult<Url> {
+        if let Some(bad) = segments
+            .iter()
+            .find(|s| s.is_empty() || **s == "." || **s == "..")
+        {
+            return Err(DeskflowError::invalid_input(format!(
+                "\"{bad}\" is not a valid name or id"
+            )));
+        }
+        let mut url = Url::parse(&format!("{}{}", self.base_url, path))
+            .map_err(|e| DeskflowError::internal(format!("Bad backend URL: {e}")))?;
+        url.path_segments_mut()
+            .map_err(|_| DeskflowError::internal("Backend URL cannot have a path"))?
+            .extend(segments);
+        Ok(url)
+    }
+
+    fn request_at(
+        &self,
+        method: Method,
+        path: &str,
+        segments: &[&str],
+    ) -> ClientResult<RequestBuilder> {
+        Ok(self.http.request(method, self.url(path, segments)?))
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> ClientResult<T> {
+        self.send(self.request(Method::GET, path), true).await
+    }
+
+    async fn get_at<T: DeserializeOwned>(&self, path: &str, segments: &[&str]) -> ClientResult<T> {
+        self.send(self.request_at(Method::GET, path, segments)?, true)
+            .await
+    }
+
+    async fn get_query<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> ClientResult<T> {
+        self.send(self.request(Method::GET, path).query(query), true)
+            .await
+    }
+
+    pub async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> ClientResult<T> {
+        self.send(self.request(Method::POST, path).json(body), false)
+            .await
+    }
+
+    async fn delete<T: DeserializeOwned>(&self, path: &str, segments: &[&str]) -> ClientResult<T> {
+        self.send(self.request_at(Method::DELETE, path, segments)?, true)
+            .await
+    }
+
+    /// Send with retries. Idempotent requests are retried on any transient
+    /// failure; others only when the connection was never established, so
+    /// a request is never applied twice.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        idempotent: bool,
+    ) -> ClientResult<T> {
+        let mut attempt = 1;
+        loop {
+            let Some(this_try) = request.try_clone() else {
+                return self.send_once(request).await;
+            };
+
+            match self.send_once(this_try).await {
+                Err(err)
+                    if attempt < MAX_ATTEMPTS
+                        && (err.is_retryable()
+                            && (idempotent || matches!(err, DeskflowError::Connect { .. }))) =>
+                {
+                    tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send_once<T: DeserializeOwned>(&self, request: RequestBuilder) -> ClientResult<T> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+
+        if !status.is_success() {
+            return Err(DeskflowError::from_status(
+                status.as_u16(),
+                error_message(status, &body),
+            ));
+        }
+
+        serde_json::from_slice(&body).map_err(|e| DeskflowError::Decode {
+            message: e.to_string(),
+        })
+    }
+}
+
+fn error_message(status: StatusCode, body: &[u8]) -> String {
+    match serde_json::from_slice::<ErrorBody>(body) {
+        Ok(ErrorBody::Detail {
+            detail: Value::String(detail),
+        }) => detail,
+        Ok(ErrorBody::Detail { detail }) => detail.to_string(),
+        Ok(ErrorBody::Error(error)) => error.error,
+        Err(_) => status
+            .canonical_reason()
+            .unwrap_or("Unknown error")
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> DeskflowClient {
+        DeskflowClient::new("http://127.0.0.1:8420")
+    }
+
+    #[test]
+    fn url_encodes_each_segment() {
+        let url = client()
+            .url("/api/chat", &["a b/c?d#e%f", "toggle"])
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "http://127.0.0.1:8420/api/chat/a%20b%2Fc%3Fd%23e%25f/toggle"
+        );
+        assert_eq!(
+            client().url("/api/skills", &["数据"]).unwrap().path(),
+            "/api/skills/%E6%95%B0%E6%8D%AE"
+        );
+        assert_eq!(
+            client().url("/api/memory", &["..a", "a.b"]).unwrap().path(),
+            "/api/memory/..a/a.b"
+        );
+    }
+
+    #[test]
+    fn url_refuses_segments_that_move_around() {
+        for segment in ["", ".", ".."] {
+            let err = client().url("/api/chat", &[segment]).unwrap_err();
+            assert!(
+                matches!(err, DeskflowError::InvalidInput { .. }),
+                "{segment:?}: {err:?}"
+            );
+        }
+    }
+}
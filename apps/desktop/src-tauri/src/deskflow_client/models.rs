@@ -0,0 +1,628 @@
+//! Request and response models for the DeskFlow REST API.
+//!
+//! The first block mirrors `api/schemas/models.py`; the rest mirrors the
+//! ad-hoc dictionaries and route-local pydantic models returned by
+//! `api/routes/*.py`. Fields the backend fills with defaults are
+//! `#[serde(default)]` so older backends keep deserializing.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// --- api/schemas/models.py -------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatRequest {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatResponse {
+    pub message: String,
+    pub conversation_id: String,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCallInfo>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallInfo {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: BTreeMap<String, Value>,
+    #[serde(default = "default_true")]
+    pub success: bool,
+    #[serde(default)]
+    pub output: String,
+    #[serde(default)]
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthResponse {
+    #[serde(default = "default_ok")]
+    pub status: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub components: BTreeMap<String, ComponentHealth>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentHealth {
+    #[serde(default = "default_ok")]
+    pub status: String,
+    #[serde(default)]
+    pub details: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusResponse {
+    pub is_online: bool,
+    pub is_busy: bool,
+    pub current_task: Option<String>,
+    pub uptime_seconds: f64,
+    pub total_conversations: u64,
+    pub total_tool_calls: u64,
+    pub total_tokens_used: u64,
+    pub memory_count: u64,
+    pub active_tools: u32,
+    pub available_tools: u32,
+    pub llm_provider: String,
+    pub llm_model: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigResponse {
+    pub llm_provider: String,
+    pub llm_model: String,
+    pub llm_temperature: f64,
+    pub llm_max_tokens: u32,
+    pub has_api_key: bool,
+    pub openai_base_url: String,
+    pub server_host: String,
+    pub server_port: u16,
+    pub memory_cache_size: u32,
+    pub tool_timeout: f64,
+    pub log_level: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigUpdateRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_cache_size: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_timeout: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmTestRequest {
+    pub provider: String,
+    pub model: String,
+    pub api_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default = "default_temperature")]
+    pub temperature: f64,
+    #[serde(default = "default_test_max_tokens")]
+    pub max_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmTestResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmModelsResponse {
+    pub models: Vec<String>,
+    pub provider: String,
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    #[serde(default = "default_error_code")]
+    pub code: String,
+    #[serde(default)]
+    pub details: BTreeMap<String, Value>,
+}
+
+// --- core/models.py: chat streaming ----------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamChunkType {
+    ConversationId,
+    #[default]
+    Text,
+    ToolStart,
+    ToolEnd,
+    ToolResult,
+    Error,
+    Done,
+    #[serde(other)]
+    Unknown,
+}
+
+/// One frame sent by `/api/chat/stream`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamChunk {
+    #[serde(rename = "type")]
+    pub kind: StreamChunkType,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub tool_call: Option<ToolCall>,
+    #[serde(default)]
+    pub tool_result: Option<ToolResult>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, Value>,
+}
+
+impl StreamChunk {
+    pub fn new(kind: StreamChunkType, content: impl Into<String>) -> Self {
+        Self {
+            kind,
+            content: content.into(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub arguments: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub tool_call_id: String,
+    pub tool_name: String,
+    pub success: bool,
+    #[serde(default)]
+    pub output: String,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub duration_ms: f64,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, Value>,
+}
+
+// --- Shared ----------------------------------------------------------------
+
+/// The `{"success": ..., "message": ...}` shape most mutating routes return.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SuccessResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub message: String,
+}
+
+// --- api/routes/chat.py ----------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub message_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationList {
+    pub conversations: Vec<ConversationSummary>,
+    #[serde(default)]
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    #[serde(default)]
+    pub id: String,
+    pub role: String,
+    pub content: String,
+    #[serde(default)]
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub message_count: u32,
+    #[serde(default)]
+    pub messages: Vec<ConversationMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveConversationRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    pub title: String,
+    pub messages: Vec<ConversationMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveConversationResponse {
+    pub success: bool,
+    pub conversation_id: String,
+}
+
+// --- api/routes/sessions.py ------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSessionRequest {
+    pub user_id: String,
+    pub channel_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<BTreeMap<String, Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSessionResponse {
+    pub success: bool,
+    pub session_id: String,
+}
+
+// --- api/routes/memory.py --------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub id: String,
+    pub content: String,
+    pub memory_type: String,
+    #[serde(default)]
+    pub importance: f64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub created_at: f64,
+    #[serde(default)]
+    pub last_accessed: f64,
+    #[serde(default)]
+    pub access_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentMemories {
+    pub memories: Vec<MemoryEntry>,
+    #[serde(default)]
+    pub total: u32,
+}
+
+// --- api/routes/skills.py --------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillInfo {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub is_active: bool,
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub color: String,
+    #[serde(default)]
+    pub installed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillList {
+    pub skills: Vec<SkillInfo>,
+    #[serde(default)]
+    pub total: u32,
+}
+
+/// Exactly one of the fields should be set; see `install_skill` in
+/// `api/routes/skills.py`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkillInstallRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// Base64-encoded zip archive containing a `skill.json`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill_data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillInstallResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub skill_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillToggleResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub is_active: bool,
+}
+
+// --- api/routes/setup.py ---------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupLlmConfig {
+    pub provider: String,
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+    #[serde(default = "default_temperature")]
+    pub temperature: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupImConfig {
+    pub channel_type: String,
+    pub token: String,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupWorkspaceConfig {
+    pub path: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupConfigRequest {
+    pub llm: SetupLlmConfig,
+    #[serde(default)]
+    pub im: Option<SetupImConfig>,
+    #[serde(default)]
+    pub workspace: Option<SetupWorkspaceConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(default)]
+    pub config_path: Option<String>,
+}
+
+// --- api/routes/monitor.py -------------------------------------------------
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CpuUsage {
+    pub percent: f64,
+    pub cores: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryUsage {
+    pub used_mb: f64,
+    pub total_mb: f64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiskUsage {
+    pub used_gb: f64,
+    pub total_gb: f64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SystemStatus {
+    pub cpu: CpuUsage,
+    pub memory: MemoryUsage,
+    pub disk: DiskUsage,
+    pub data_disk: DiskUsage,
+    pub uptime_seconds: f64,
+    pub platform: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmStats {
+    pub provider: String,
+    pub model: String,
+    pub memory_count: u64,
+    pub active_tools: u32,
+    pub total_tokens: u64,
+    pub today_tokens: u64,
+    pub total_cost_usd: f64,
+    pub today_cost_usd: f64,
+    pub request_count: u64,
+    pub today_request_count: u64,
+}
+
+/// `ActivityRecord.to_dict()` from `observability/activity_logger.py`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityRecord {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub status: String,
+    pub timestamp: String,
+    #[serde(default)]
+    pub duration_ms: f64,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub details: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityList {
+    pub activities: Vec<ActivityRecord>,
+    #[serde(default)]
+    pub total: u32,
+    #[serde(default)]
+    pub statistics: Value,
+}
+
+// --- api/routes/orchestration.py -------------------------------------------
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MasterAgentStatus {
+    pub workers: BTreeMap<String, Value>,
+    pub pending_tasks: u32,
+    pub active_tasks: u32,
+    pub completed_tasks: u32,
+    pub running: bool,
+    pub load_balancer_strategy: String,
+    pub max_concurrent_tasks: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkerPoolStats {
+    pub total_workers: u32,
+    pub idle_workers: u32,
+    pub busy_workers: u32,
+    pub offline_workers: u32,
+    pub total_tasks_completed: u64,
+    pub total_tasks_failed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSubmitRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub payload: BTreeMap<String, Value>,
+    #[serde(default = "default_priority")]
+    pub priority: u8,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default = "default_task_timeout")]
+    pub timeout: f64,
+    #[serde(default = "default_retry_count")]
+    pub retry_count: u32,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSubmitResponse {
+    pub success: bool,
+    pub task_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskCancelResponse {
+    pub success: bool,
+    pub task_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskResult {
+    pub task_id: String,
+    pub worker_id: String,
+    pub success: bool,
+    pub result: Value,
+    pub error: Option<String>,
+    pub duration: f64,
+    pub retries_used: u32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_ok() -> String {
+    "ok".into()
+}
+
+fn default_error_code() -> String {
+    "UNKNOWN_ERROR".into()
+}
+
+fn default_temperature() -> f64 {
+    0.7
+}
+
+fn default_max_tokens() -> u32 {
+    4096
+}
+
+fn default_test_max_tokens() -> u32 {
+    100
+}
+
+fn default_priority() -> u8 {
+    5
+}
+
+fn default_task_timeout() -> f64 {
+    300.0
+}
+
+fn default_retry_count() -> u32 {
+    3
+}
@@ -0,0 +1,337 @@
+//! Diagnostics bundles: one zip with what is needed to look into a crash or
+//! a hang without access to the user's machine.
+//!
+//! A bundle holds the shell and backend logs of the last few days, the
+//! backend's state history, health and recent activity, stacks saved from
+//! hangs by the [watchdog](crate::backend::Watchdog), the shell and
+//! backend configuration, system and database details, and a
+//! `manifest.json` listing every file with its SHA-256 plus what could not
+//! be collected and why (a dead backend is itself worth knowing about).
+//! Everything passes through a [`Redactor`] on the way in.
+
+pub mod redact;
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::backend::watchdog;
+use crate::backup::{self, ManifestFile};
+use crate::encryption::{DbEncryption, DbState};
+use crate::logs::LogStore;
+use crate::profile::ProfileDirs;
+use crate::runtime::now_secs;
+
+pub use redact::Redactor;
+
+const FORMAT: &str = "deskflow-diagnostics";
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+
+/// Larger files are cut to their last this many bytes; for logs the end is
+/// what matters.
+const MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+/// Config files bigger than this are not configuration.
+const MAX_CONFIG_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    pub profile: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    pub files: Vec<ManifestFile>,
+    pub missing: Vec<MissingItem>,
+}
+
+/// Something that should have been in the bundle but could not be
+/// collected.
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingItem {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SystemInfo {
+    pub os: String,
+    pub os_version: String,
+    pub os_edition: Option<String>,
+    pub arch: String,
+    pub webview_version: Option<String>,
+    pub tauri_version: String,
+    pub app_version: String,
+}
+
+impl SystemInfo {
+    pub fn current(webview_version: Option<String>) -> Self {
+        let info = os_info::get();
+        Self {
+            os: info.os_type().to_string(),
+            os_version: info.version().to_string(),
+            os_edition: info.edition().map(str::to_string),
+            arch: std::env::consts::ARCH.to_string(),
+            webview_version,
+            tauri_version: tauri::VERSION.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DatabaseInfo {
+    exists: bool,
+    size_bytes: Option<u64>,
+    state: Option<DbState>,
+    key_stored: Option<bool>,
+    schema_version: Option<i64>,
+    supported_schema_version: i64,
+}
+
+#[derive(Debug)]
+pub enum DiagnosticsError {
+    Archive(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for DiagnosticsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticsError::Archive(reason) => {
+                write!(f, "Failed to write the diagnostics bundle: {reason}")
+            }
+            DiagnosticsError::Io(e) => write!(f, "Diagnostics I/O error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DiagnosticsError {}
+
+impl From<io::Error> for DiagnosticsError {
+    fn from(err: io::Error) -> Self {
+        DiagnosticsError::Io(err)
+    }
+}
+
+impl From<ZipError> for DiagnosticsError {
+    fn from(err: ZipError) -> Self {
+        match err {
+            ZipError::Io(e) => DiagnosticsError::Io(e),
+            other => DiagnosticsError::Archive(other.to_string()),
+        }
+    }
+}
+
+pub type DiagnosticsResult<T> = Result<T, DiagnosticsError>;
+
+/// The contents of a bundle, redacted as they are added.
+pub struct Bundle {
+    redactor: Redactor,
+    files: Vec<(String, Vec<u8>)>,
+    missing: Vec<MissingItem>,
+}
+
+impl Bundle {
+    pub fn new(redactor: Redactor) -> Self {
+        Self {
+            redactor,
+            files: Vec::new(),
+            missing: Vec::new(),
+        }
+    }
+
+    pub fn add_json(&mut self, path: &str, value: impl Serialize) {
+        match serde_json::to_value(value) {
+            Ok(mut value) => {
+                self.redactor.redact_json(&mut value);
+                let json = serde_json::to_vec_pretty(&value).unwrap_or_default();
+                self.files.push((path.to_string(), json));
+            }
+            Err(e) => self.add_missing(path, e),
+        }
+    }
+
+    pub fn add_text(&mut self, path: &str, text: &str) {
+        let text = self.redactor.redact_text(text);
+        self.files.push((path.to_string(), text.into_bytes()));
+    }
+
+    /// Add `value` as JSON, or record why it is missing.
+    pub fn add_result<T: Serialize, E: fmt::Display>(&mut self, path: &str, result: Result<T, E>) {
+        match result {
+            Ok(value) => self.add_json(path, value),
+            Err(e) => self.add_missing(path, e),
+        }
+    }
+
+    pub fn add_missing(&mut self, path: &str, reason: impl fmt::Display) {
+        self.missing.push(MissingItem {
+            path: path.to_string(),
+            reason: self.redactor.redact_text(&reason.to_string()),
+        });
+    }
+
+    /// Add a text file, keeping its last [`MAX_FILE_BYTES`]. JSON files are
+    /// redacted as JSON.
+    pub fn add_file(&mut self, path: &str, source: &Path) {
+        match read_tail(source, MAX_FILE_BYTES) {
+            Ok(text) if path.ends_with(".json") => match serde_json::from_str::<Value>(&text) {
+                Ok(value) => self.add_json(path, value),
+                // Cut, or not JSON after all
+                Err(_) => self.add_text(path, &text),
+            },
+            Ok(text) => self.add_text(path, &text),
+            Err(e) => self.add_missing(path, e),
+        }
+    }
+
+    /// The shell and backend log files of the last `days` days.
+    pub fn add_logs(&mut self, logs: &LogStore, days: u64) {
+        match logs.recent_files(days) {
+            Ok(files) => {
+                for file in files {
+                    if let Some(name) = file.file_name().and_then(|n| n.to_str()) {
+                        self.add_file(&format!("logs/{name}"), &file);
+                    }
+                }
+            }
+            Err(e) => self.add_missing("logs", e),
+        }
+    }
+
+    /// Thread dumps the watchdog saved from a hung backend.
+    pub fn add_hang_dumps(&mut self, dir: &Path) {
+        for dump in watchdog::hang_dumps(dir) {
+            if let Some(name) = dump.file_name().and_then(|n| n.to_str()) {
+                self.add_file(&format!("hangs/{name}"), &dump);
+            }
+        }
+    }
+
+    /// The profile's configuration files and `.env`, and what state its
+    /// database is in. Databases and other data are never included.
+    pub fn add_profile(&mut self, encryption: &DbEncryption, dirs: &ProfileDirs) {
+        // The backend reads `.env` from its working directory, the data dir
+        let env = dirs.data.join(".env");
+        if env.is_file() {
+            self.add_file("config/.env", &env);
+        }
+        match backup::walk(&dirs.config) {
+            Ok(files) => {
+                for relative in files {
+                    let source = dirs.config.join(&relative);
+                    let path = format!(
+                        "config/profile/{}",
+                        relative.to_string_lossy().replace('\\', "/")
+                    );
+                    match fs::metadata(&source) {
+                        Ok(meta) if meta.len() > MAX_CONFIG_BYTES => {
+                            self.add_missing(&path, "too large for a config file")
+                        }
+                        Ok(_) => self.add_file(&path, &source),
+                        Err(e) => self.add_missing(&path, e),
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => self.add_missing("config/profile", e),
+        }
+
+        let db = dirs.db_path();
+        let mut info = DatabaseInfo {
+            exists: db.exists(),
+            size_bytes: fs::metadata(&db).ok().map(|meta| meta.len()),
+            state: None,
+            key_stored: None,
+            schema_version: None,
+            supported_schema_version: backup::SCHEMA_VERSION,
+        };
+        match encryption.status(dirs) {
+            Ok(status) => {
+                info.state = Some(status.state);
+                info.key_stored = Some(status.key_stored);
+            }
+            Err(e) => self.add_missing("database.json#encryption", e),
+        }
+        if info.state.is_some_and(|state| state != DbState::Missing) {
+            match encryption
+                .open_read_only(dirs, &dirs.db_path())
+                .map_err(|e| e.to_string())
+                .and_then(|conn| backup::schema_version(&conn).map_err(|e| e.to_string()))
+            {
+                Ok(version) => info.schema_version = version,
+                Err(e) => self.add_missing("database.json#schema_version", e),
+            }
+        }
+        self.add_json("database.json", info);
+    }
+
+    /// Write the bundle and its manifest to `dest`, replacing it.
+    pub fn write(self, dest: &Path, profile: &str) -> DiagnosticsResult<Manifest> {
+        let tmp = dest.with_extension("tmp");
+        let result = self
+            .write_zip(&tmp, profile)
+            .and_then(|manifest| Ok(fs::rename(&tmp, dest).map(|_| manifest)?));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result
+    }
+
+    fn write_zip(self, dest: &Path, profile: &str) -> DiagnosticsResult<Manifest> {
+        let mut manifest = Manifest {
+            format: FORMAT.to_string(),
+            version: FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            profile: profile.to_string(),
+            created_at: now_secs(),
+            files: Vec::new(),
+            missing: self.missing,
+        };
+
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(File::create(dest)?);
+        for (path, contents) in self.files {
+            zip.start_file(path.as_str(), options)?;
+            zip.write_all(&contents)?;
+            manifest.files.push(ManifestFile {
+                path,
+                size: contents.len() as u64,
+                sha256: format!("{:x}", Sha256::digest(&contents)),
+            });
+        }
+        zip.start_file(MANIFEST, options)?;
+        serde_json::to_writer_pretty(&mut zip, &manifest).map_err(io::Error::from)?;
+        zip.finish()?.sync_all()?;
+        Ok(manifest)
+    }
+}
+
+/// The last `limit` bytes of a file as text, starting at a line boundary
+/// when cut.
+fn read_tail(path: &Path, limit: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let cut = len > limit;
+    if cut {
+        file.seek(SeekFrom::Start(len - limit))?;
+    }
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    if !cut {
+        return Ok(text.into_owned());
+    }
+    let start = text.find('\n').map_or(0, |i| i + 1);
+    Ok(text[start..].to_string())
+}
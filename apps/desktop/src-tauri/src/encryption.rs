@@ -0,0 +1,763 @@
+//! Encryption at rest for a profile's databases: `deskflow.db`, which holds
+//! every conversation, extracted memory and the user profile, and
+//! `conversations.db`, the chat history the UI saves.
+//!
+//! Both are encrypted page by page with SQLCipher under the same key. The
+//! shell owns the key: a random 256-bit value kept with the other secrets
+//! (see [`SecretStore`]), one per profile, handed to the backend only
+//! through [`DB_KEY_ENV`] of the child process.
+//!
+//! `deskflow.db` decides whether a profile is encrypted; the chat history
+//! follows it. Encrypting and rotating never leave a database unreadable:
+//! the new key is stored as "pending" first and only promoted once
+//! `deskflow.db` opens with it, and the old key is kept as "previous" until
+//! the chat history has been re-keyed too. [`DbEncryption::key`] finishes a
+//! promotion that a crash interrupted, [`DbEncryption::recover`] the rest.
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+
+use crate::profile::ProfileDirs;
+use crate::secrets::{SecretError, SecretStore};
+
+/// Variable the backend reads the database key from.
+pub const DB_KEY_ENV: &str = "DESKFLOW_DB_KEY";
+
+const KEY_BYTES: usize = 32;
+const BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+const PLAINTEXT_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// A raw SQLCipher key, as hex. Raw keys skip SQLCipher's key derivation,
+/// which only matters for passphrases.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DbKey(String);
+
+impl DbKey {
+    fn generate() -> Self {
+        let mut bytes = [0u8; KEY_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes.iter().map(|b| format!("{b:02x}")).collect())
+    }
+
+    fn parse(stored: String) -> Option<Self> {
+        let valid = stored.len() == KEY_BYTES * 2 && stored.bytes().all(|b| b.is_ascii_hexdigit());
+        valid.then_some(Self(stored))
+    }
+
+    /// Value for `PRAGMA key` and `PRAGMA rekey`.
+    fn pragma(&self) -> String {
+        format!("x'{}'", self.0)
+    }
+}
+
+// Never print the key itself
+impl fmt::Debug for DbKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DbKey(..)")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DbState {
+    /// The backend has not created the database yet.
+    Missing,
+    Plaintext,
+    Encrypted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EncryptionStatus {
+    pub state: DbState,
+    pub key_stored: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    pub state: DbState,
+    pub ok: bool,
+    /// What SQLite and SQLCipher found wrong, empty when `ok`.
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum EncryptionError {
+    Missing(PathBuf),
+    AlreadyEncrypted,
+    NotEncrypted,
+    /// Another process still has the database open.
+    InUse,
+    Integrity(String),
+    /// The database is encrypted but no stored key opens it.
+    NoKey,
+    Secret(SecretError),
+    Sqlite(rusqlite::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncryptionError::Missing(path) => {
+                write!(f, "No database at {}", path.display())
+            }
+            EncryptionError::AlreadyEncrypted => write!(f, "The database is already encrypted"),
+            EncryptionError::NotEncrypted => write!(f, "The database is not encrypted"),
+            EncryptionError::InUse => write!(f, "The database is still in use"),
+            EncryptionError::Integrity(problem) => {
+                write!(f, "Database integrity check failed: {problem}")
+            }
+            EncryptionError::NoKey => write!(f, "No stored key opens the encrypted database"),
+            EncryptionError::Secret(e) => e.fmt(f),
+            EncryptionError::Sqlite(e) => write!(f, "Database error: {e}"),
+            EncryptionError::Io(e) => write!(f, "Database file error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+impl From<SecretError> for EncryptionError {
+    fn from(err: SecretError) -> Self {
+        EncryptionError::Secret(err)
+    }
+}
+
+impl From<rusqlite::Error> for EncryptionError {
+    fn from(err: rusqlite::Error) -> Self {
+        EncryptionError::Sqlite(err)
+    }
+}
+
+impl From<io::Error> for EncryptionError {
+    fn from(err: io::Error) -> Self {
+        EncryptionError::Io(err)
+    }
+}
+
+pub type EncryptionResult<T> = Result<T, EncryptionError>;
+
+/// Manages the database keys of all profiles. Cheap to clone.
+///
+/// Calls block on the keyring and disk; keep them off the main thread.
+/// Encrypting and rotating rewrite the database, so the backend must be
+/// stopped while they run.
+#[derive(Clone)]
+pub struct DbEncryption {
+    secrets: SecretStore,
+}
+
+impl DbEncryption {
+    pub fn new(secrets: SecretStore) -> Self {
+        Self { secrets }
+    }
+
+    pub fn status(&self, dirs: &ProfileDirs) -> EncryptionResult<EncryptionStatus> {
+        Ok(EncryptionStatus {
+            state: state(&dirs.db_path())?,
+            key_stored: self.key(dirs)?.is_some(),
+        })
+    }
+
+    /// The profile's key, after settling a rotation or encryption that was
+    /// interrupted between rewriting the database and storing the key.
+    pub fn key(&self, dirs: &ProfileDirs) -> EncryptionResult<Option<DbKey>> {
+        let (account, pending_account) = accounts(dirs);
+        let current = self.load(&account)?;
+        let Some(pending) = self.load(&pending_account)? else {
+            return Ok(current);
+        };
+
+        let db = dirs.db_path();
+        if state(&db)? == DbState::Encrypted && opens(&db, &pending) {
+            self.secrets.set_account(&account, &pending.0)?;
+            self.secrets.delete_account(&pending_account)?;
+            return Ok(Some(pending));
+        }
+        self.secrets.delete_account(&pending_account)?;
+        Ok(current)
+    }
+
+    /// Environment that lets the backend open the profile's database. A
+    /// plaintext database gets no key, so storing one before the database
+    /// is encrypted cannot lock the backend out.
+    pub fn env(&self, dirs: &ProfileDirs) -> Vec<(String, String)> {
+        let key = match state(&dirs.db_path()) {
+            Ok(DbState::Plaintext) | Err(_) => None,
+            Ok(DbState::Missing | DbState::Encrypted) => self.key(dirs).ok().flatten(),
+        };
+        key.map(|key| vec![(DB_KEY_ENV.to_string(), key.0)])
+            .unwrap_or_default()
+    }
+
+    /// Encrypt the profile's databases in place with a new key. Without a
+    /// `deskflow.db` yet, only the key is created and the backend starts an
+    /// encrypted one.
+    pub fn encrypt(&self, dirs: &ProfileDirs) -> EncryptionResult<()> {
+        let db = dirs.db_path();
+        let (account, pending_account) = accounts(dirs);
+        match state(&db)? {
+            DbState::Encrypted => {
+                // The chat history may still be in plaintext
+                let mut plaintext = false;
+                for db in databases(dirs).into_iter().skip(1) {
+                    plaintext |= state(&db)? == DbState::Plaintext;
+                }
+                if !plaintext {
+                    return Err(EncryptionError::AlreadyEncrypted);
+                }
+                let key = self
+                    .key(dirs)?
+                    .filter(|key| opens(&db, key))
+                    .ok_or(EncryptionError::NoKey)?;
+                return self.finish(dirs, &key);
+            }
+            DbState::Missing => {
+                let key = match self.key(dirs)? {
+                    Some(key) => key,
+                    None => {
+                        let key = DbKey::generate();
+                        self.secrets.set_account(&account, &key.0)?;
+                        key
+                    }
+                };
+                return self.finish(dirs, &key);
+            }
+            DbState::Plaintext => {}
+        }
+
+        let key = DbKey::generate();
+        self.secrets.set_account(&pending_account, &key.0)?;
+        let plain = match encrypt_file(&db, &key) {
+            Ok(plain) => plain,
+            Err(e) => {
+                self.secrets.delete_account(&pending_account)?;
+                return Err(e);
+            }
+        };
+        self.secrets.set_account(&account, &key.0)?;
+        self.secrets.delete_account(&pending_account)?;
+        scrub(&plain)?;
+        self.finish(dirs, &key)
+    }
+
+    /// Bring the chat history under `key`, the key `deskflow.db` opens
+    /// with: encrypt it if it is still in plaintext, re-key it if a
+    /// rotation stopped before reaching it.
+    fn finish(&self, dirs: &ProfileDirs, key: &DbKey) -> EncryptionResult<()> {
+        let previous_account = previous_account(dirs);
+        for db in databases(dirs).into_iter().skip(1) {
+            match state(&db)? {
+                DbState::Missing => {}
+                DbState::Plaintext => scrub(&encrypt_file(&db, key)?)?,
+                DbState::Encrypted if opens(&db, key) => {}
+                DbState::Encrypted => {
+                    let previous = self
+                        .load(&previous_account)?
+                        .filter(|previous| opens(&db, previous))
+                        .ok_or(EncryptionError::NoKey)?;
+                    rekey(&db, &previous, key)?;
+                }
+            }
+        }
+        self.secrets.delete_account(&previous_account)?;
+        Ok(())
+    }
+
+    /// Re-encrypt the profile's databases under a new key.
+    pub fn rotate(&self, dirs: &ProfileDirs) -> EncryptionResult<()> {
+        let db = dirs.db_path();
+        if state(&db)? != DbState::Encrypted {
+            return Err(EncryptionError::NotEncrypted);
+        }
+        let old = self
+            .key(dirs)?
+            .filter(|key| opens(&db, key))
+            .ok_or(EncryptionError::NoKey)?;
+
+        let (account, pending_account) = accounts(dirs);
+        let new = DbKey::generate();
+        // The chat history is re-keyed after deskflow.db; keep the old key
+        // until it is
+        self.secrets.set_account(&previous_account(dirs), &old.0)?;
+        self.secrets.set_account(&pending_account, &new.0)?;
+        rekey(&db, &old, &new)?;
+        self.secrets.set_account(&account, &new.0)?;
+        self.secrets.delete_account(&pending_account)?;
+        self.finish(dirs, &new)
+    }
+
+    /// Clear up after an [`encrypt`](Self::encrypt) or
+    /// [`rotate`](Self::rotate) that was interrupted, and bring a chat
+    /// history left in plaintext by an older version under the key. Cheap
+    /// when there is nothing to do; run it before every backend start.
+    pub fn recover(&self, dirs: &ProfileDirs) -> EncryptionResult<()> {
+        for db in databases(dirs) {
+            let plain = sibling(&db, "plaintext");
+            remove_if_exists(&sibling(&db, "encrypting"))?;
+            if state(&db)? == DbState::Missing && plain.exists() {
+                // Stopped between moving the plaintext aside and swapping
+                // in the encrypted copy
+                fs::rename(&plain, &db)?;
+            }
+        }
+        let Some(key) = self.key(dirs)? else {
+            return Ok(());
+        };
+        let db = dirs.db_path();
+        match state(&db)? {
+            DbState::Plaintext => Ok(()),
+            DbState::Encrypted if !opens(&db, &key) => Ok(()),
+            DbState::Missing | DbState::Encrypted => self.finish(dirs, &key),
+        }
+    }
+
+    /// Check that the databases open and are intact: SQLCipher's per-page
+    /// HMACs for an encrypted database, then SQLite's own quick check.
+    /// The report's state is that of `deskflow.db`; problems with the chat
+    /// history name the file.
+    pub fn verify(&self, dirs: &ProfileDirs) -> EncryptionResult<IntegrityReport> {
+        let key = self.key(dirs)?;
+        let mut report = IntegrityReport {
+            state: DbState::Missing,
+            ok: true,
+            problems: Vec::new(),
+        };
+        for (i, db) in databases(dirs).iter().enumerate() {
+            let state = state(db)?;
+            let problems = match state {
+                DbState::Missing => Vec::new(),
+                DbState::Plaintext => check(db, None)?,
+                DbState::Encrypted => match &key {
+                    Some(key) if opens(db, key) => check(db, Some(key))?,
+                    _ => vec![EncryptionError::NoKey.to_string()],
+                },
+            };
+
+            let plain = sibling(db, "plaintext");
+            if problems.is_empty() && state == DbState::Encrypted && plain.exists() {
+                // Left behind when encrypting stopped right after the swap
+                scrub(&plain)?;
+            }
+            if i == 0 {
+                report.state = state;
+                report.problems = problems;
+            } else {
+                let name = db.file_name().unwrap_or_default().to_string_lossy();
+                report
+                    .problems
+                    .extend(problems.into_iter().map(|p| format!("{name}: {p}")));
+            }
+        }
+        report.ok = report.problems.is_empty();
+        Ok(report)
+    }
+
+    /// Write a consistent copy of one of the profile's databases to `dest`
+    /// while the backend may keep using it. The copy keeps the key unless
+    /// `decrypt` is set; returns whether it is encrypted.
+    pub fn snapshot(
+        &self,
+        dirs: &ProfileDirs,
+        db: &Path,
+        dest: &Path,
+        decrypt: bool,
+    ) -> EncryptionResult<bool> {
+        match state(db)? {
+            DbState::Missing => Err(EncryptionError::Missing(db.to_path_buf())),
+            DbState::Plaintext => {
+                snapshot_plaintext(db, dest)?;
+                Ok(false)
+            }
+            DbState::Encrypted => {
+                let key = self.key(dirs)?.ok_or(EncryptionError::NoKey)?;
+                // The snapshot is created through ATTACH, which needs the
+                // connection's create flag
+                let conn = open_keyed(db, &key, OpenFlags::default())?;
+                conn.busy_timeout(BUSY_TIMEOUT)?;
+                export(&conn, dest, (!decrypt).then_some(&key))?;
+                Ok(!decrypt)
+            }
+        }
+    }
+
+    /// Open a copy of a database (e.g. from a backup) that was encrypted
+    /// with the key of profile `profile`.
+    pub fn open_copy(&self, db: &Path, profile: &str) -> EncryptionResult<Connection> {
+        if state(db)? != DbState::Encrypted {
+            return Ok(Connection::open_with_flags(
+                db,
+                OpenFlags::SQLITE_OPEN_READ_ONLY,
+            )?);
+        }
+        let key = self
+            .load(&account(profile))?
+            .filter(|key| opens(db, key))
+            .ok_or(EncryptionError::NoKey)?;
+        open_keyed(db, &key, OpenFlags::SQLITE_OPEN_READ_ONLY)
+    }
+
+    /// Give `dirs` the key of profile `from`, once its databases have been
+    /// restored from copies encrypted under that key.
+    pub fn adopt_key(&self, from: &str, dirs: &ProfileDirs) -> EncryptionResult<()> {
+        let mut encrypted = Vec::new();
+        for db in databases(dirs) {
+            if state(&db)? == DbState::Encrypted {
+                encrypted.push(db);
+            }
+        }
+        let key = self
+            .load(&account(from))?
+            .filter(|key| encrypted.iter().all(|db| opens(db, key)))
+            .ok_or(EncryptionError::NoKey)?;
+        self.secrets.set_account(&accounts(dirs).0, &key.0)?;
+        Ok(())
+    }
+
+    /// Open one of the profile's databases read-only, with its key if it
+    /// is encrypted.
+    pub fn open_read_only(&self, dirs: &ProfileDirs, db: &Path) -> EncryptionResult<Connection> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = match state(db)? {
+            DbState::Missing => return Err(EncryptionError::Missing(db.to_path_buf())),
+            DbState::Plaintext => Connection::open_with_flags(db, flags)?,
+            DbState::Encrypted => {
+                let key = self.key(dirs)?.ok_or(EncryptionError::NoKey)?;
+                open_keyed(db, &key, flags)?
+            }
+        };
+        // The backend may be writing at the same time
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(conn)
+    }
+
+    fn load(&self, account: &str) -> EncryptionResult<Option<DbKey>> {
+        Ok(self.secrets.get_account(account)?.and_then(DbKey::parse))
+    }
+}
+
+/// Keyring accounts of a profile's key and of one waiting to be promoted.
+fn accounts(dirs: &ProfileDirs) -> (String, String) {
+    let account = account(&dirs.name);
+    let pending = format!("{account}.pending");
+    (account, pending)
+}
+
+/// Keyring account of the key a rotation replaced, kept until every
+/// database has been re-keyed.
+fn previous_account(dirs: &ProfileDirs) -> String {
+    format!("{}.previous", account(&dirs.name))
+}
+
+fn account(profile: &str) -> String {
+    format!("database_key.{profile}")
+}
+
+/// The profile's databases, `deskflow.db` first.
+pub fn databases(dirs: &ProfileDirs) -> [PathBuf; 2] {
+    [dirs.db_path(), dirs.conversations_db_path()]
+}
+
+/// Encrypt a plaintext database with `key` in place. Returns where the
+/// plaintext was moved; scrub it once the key is stored.
+fn encrypt_file(db: &Path, key: &DbKey) -> EncryptionResult<PathBuf> {
+    // Export into a fresh file, check it, then swap it in
+    let encrypted = sibling(db, "encrypting");
+    let plain = sibling(db, "plaintext");
+    remove_if_exists(&encrypted)?;
+    export(&Connection::open(db)?, &encrypted, Some(key))?;
+    let problem = check(&encrypted, Some(key))?.into_iter().next();
+    // The last connection to close folds the WAL back in; one left over
+    // means someone else has the database open, and it would be replayed
+    // onto the encrypted file
+    let in_use = path_with_suffix(db, "-wal").exists();
+    if problem.is_some() || in_use {
+        remove_if_exists(&encrypted)?;
+        return Err(problem.map_or(EncryptionError::InUse, EncryptionError::Integrity));
+    }
+
+    fs::rename(db, &plain)?;
+    fs::rename(&encrypted, db)?;
+    // Best effort from here: on SSDs and copy-on-write filesystems the old
+    // blocks may survive the scrub anyway
+    Ok(plain)
+}
+
+fn rekey(db: &Path, old: &DbKey, new: &DbKey) -> EncryptionResult<()> {
+    let conn = open_keyed(db, old, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    conn.pragma_update(None, "rekey", new.pragma())?;
+    Ok(())
+}
+
+/// Write a consistent copy of a plaintext SQLite database to `dest`.
+pub fn snapshot_plaintext(db: &Path, dest: &Path) -> EncryptionResult<()> {
+    let conn = Connection::open(db)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    export(&conn, dest, None)
+}
+
+/// Copy everything in `conn` into a new database at `dest`, encrypted with
+/// `key` or in plaintext. Runs in one read transaction, so the copy is
+/// consistent even while others write.
+fn export(conn: &Connection, dest: &Path, key: Option<&DbKey>) -> EncryptionResult<()> {
+    let key = key.map(DbKey::pragma).unwrap_or_default();
+    conn.execute(
+        "ATTACH DATABASE ?1 AS export KEY ?2",
+        (dest.to_string_lossy(), key),
+    )?;
+    let exported = conn.execute_batch(
+        "BEGIN;
+         SELECT sqlcipher_export('export');
+         COMMIT;",
+    );
+    if exported.is_err() && !conn.is_autocommit() {
+        let _ = conn.execute_batch("ROLLBACK");
+    }
+    conn.execute("DETACH DATABASE export", [])?;
+    Ok(exported?)
+}
+
+/// Tell the states apart by the header: a plaintext database always starts
+/// with the SQLite magic, an encrypted one with random salt.
+pub fn state(db: &Path) -> EncryptionResult<DbState> {
+    let mut header = [0u8; PLAINTEXT_HEADER.len()];
+    let read = match fs::File::open(db) {
+        Ok(mut file) => file.read(&mut header)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(DbState::Missing),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(match read {
+        // SQLite creates an empty file before writing the first page
+        0 => DbState::Missing,
+        _ if &header == PLAINTEXT_HEADER => DbState::Plaintext,
+        _ => DbState::Encrypted,
+    })
+}
+
+fn open_keyed(db: &Path, key: &DbKey, flags: OpenFlags) -> EncryptionResult<Connection> {
+    let conn = Connection::open_with_flags(db, flags)?;
+    conn.pragma_update(None, "key", key.pragma())?;
+    Ok(conn)
+}
+
+/// Whether `key` decrypts `db`. SQLCipher only notices a wrong key on the
+/// first read.
+fn opens(db: &Path, key: &DbKey) -> bool {
+    open_keyed(db, key, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| {
+            conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+                .map_err(Into::into)
+        })
+        .is_ok()
+}
+
+fn check(db: &Path, key: Option<&DbKey>) -> EncryptionResult<Vec<String>> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY;
+    let conn = match key {
+        Some(key) => open_keyed(db, key, flags)?,
+        None => Connection::open_with_flags(db, flags)?,
+    };
+
+    let mut problems = Vec::new();
+    if key.is_some() {
+        // One row per bad page; none when every HMAC matches
+        problems.extend(pragma_rows(&conn, "cipher_integrity_check"));
+    }
+    problems.extend(
+        pragma_rows(&conn, "quick_check")
+            .into_iter()
+            .filter(|row| row != "ok"),
+    );
+    Ok(problems)
+}
+
+/// Rows of a checking pragma. A page too damaged to read aborts the check;
+/// that error is reported as a row too.
+fn pragma_rows(conn: &Connection, pragma: &str) -> Vec<String> {
+    let rows = conn
+        .prepare(&format!("PRAGMA {pragma}"))
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()
+        });
+    rows.unwrap_or_else(|e| vec![format!("{pragma}: {e}")])
+}
+
+/// `deskflow.db` → `deskflow.db.<tag>`
+fn sibling(db: &Path, tag: &str) -> PathBuf {
+    path_with_suffix(db, &format!(".{tag}"))
+}
+
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Overwrite a file with zeros before deleting it.
+fn scrub(path: &Path) -> io::Result<()> {
+    let len = fs::metadata(path)?.len();
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let zeros = vec![0u8; 64 * 1024];
+    let mut left = len;
+    while left > 0 {
+        let n = left.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..n])?;
+        left -= n as u64;
+    }
+    file.sync_all()?;
+    drop(file);
+    fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn profile(root: &TempDir) -> (DbEncryption, ProfileDirs) {
+        let dirs = ProfileDirs {
+            name: "default".to_string(),
+            data: root.path().join("data"),
+            config: root.path().join("config"),
+            cache: root.path().join("cache"),
+        };
+        fs::create_dir_all(dirs.db_path().parent().unwrap()).unwrap();
+        let encryption = DbEncryption::new(SecretStore::vault(&root.path().join("vault")));
+        (encryption, dirs)
+    }
+
+    fn write_plaintext(db: &Path, text: &str) {
+        let conn = Connection::open(db).unwrap();
+        conn.execute_batch("CREATE TABLE t (x TEXT)").unwrap();
+        conn.execute("INSERT INTO t VALUES (?1)", [text]).unwrap();
+    }
+
+    fn read(encryption: &DbEncryption, dirs: &ProfileDirs, db: &Path) -> String {
+        let conn = encryption.open_read_only(dirs, db).unwrap();
+        conn.query_row("SELECT x FROM t", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn encrypt_covers_both_databases() {
+        let root = TempDir::new().unwrap();
+        let (encryption, dirs) = profile(&root);
+        write_plaintext(&dirs.db_path(), "memories");
+        write_plaintext(&dirs.conversations_db_path(), "chats");
+
+        encryption.encrypt(&dirs).unwrap();
+
+        for db in databases(&dirs) {
+            assert_eq!(state(&db).unwrap(), DbState::Encrypted);
+            assert!(!sibling(&db, "plaintext").exists());
+        }
+        assert_eq!(
+            read(&encryption, &dirs, &dirs.conversations_db_path()),
+            "chats"
+        );
+        assert!(encryption.verify(&dirs).unwrap().ok);
+        assert!(matches!(
+            encryption.encrypt(&dirs),
+            Err(EncryptionError::AlreadyEncrypted)
+        ));
+    }
+
+    #[test]
+    fn rotate_rekeys_both_databases() {
+        let root = TempDir::new().unwrap();
+        let (encryption, dirs) = profile(&root);
+        write_plaintext(&dirs.db_path(), "memories");
+        write_plaintext(&dirs.conversations_db_path(), "chats");
+        encryption.encrypt(&dirs).unwrap();
+        let old = encryption.key(&dirs).unwrap().unwrap();
+
+        encryption.rotate(&dirs).unwrap();
+
+        let new = encryption.key(&dirs).unwrap().unwrap();
+        assert_ne!(old, new);
+        for db in databases(&dirs) {
+            assert!(opens(&db, &new));
+        }
+        assert_eq!(encryption.load(&previous_account(&dirs)).unwrap(), None);
+    }
+
+    #[test]
+    fn recover_finishes_an_interrupted_rotation() {
+        let root = TempDir::new().unwrap();
+        let (encryption, dirs) = profile(&root);
+        write_plaintext(&dirs.db_path(), "memories");
+        write_plaintext(&dirs.conversations_db_path(), "chats");
+        encryption.encrypt(&dirs).unwrap();
+
+        // Stopped after re-keying deskflow.db, before the chat history
+        let (account, _) = accounts(&dirs);
+        let old = encryption.key(&dirs).unwrap().unwrap();
+        let new = DbKey::generate();
+        encryption
+            .secrets
+            .set_account(&previous_account(&dirs), &old.0)
+            .unwrap();
+        rekey(&dirs.db_path(), &old, &new).unwrap();
+        encryption.secrets.set_account(&account, &new.0).unwrap();
+
+        encryption.recover(&dirs).unwrap();
+
+        assert!(opens(&dirs.conversations_db_path(), &new));
+        assert_eq!(
+            read(&encryption, &dirs, &dirs.conversations_db_path()),
+            "chats"
+        );
+    }
+
+    #[test]
+    fn recover_encrypts_a_plaintext_chat_history() {
+        let root = TempDir::new().unwrap();
+        let (encryption, dirs) = profile(&root);
+        write_plaintext(&dirs.db_path(), "memories");
+        encryption.encrypt(&dirs).unwrap();
+        // Written before conversations.db was encrypted too
+        write_plaintext(&dirs.conversations_db_path(), "chats");
+
+        encryption.recover(&dirs).unwrap();
+
+        assert_eq!(
+            state(&dirs.conversations_db_path()).unwrap(),
+            DbState::Encrypted
+        );
+        assert_eq!(
+            read(&encryption, &dirs, &dirs.conversations_db_path()),
+            "chats"
+        );
+    }
+
+    #[test]
+    fn recover_leaves_a_plaintext_profile_alone() {
+        let root = TempDir::new().unwrap();
+        let (encryption, dirs) = profile(&root);
+        write_plaintext(&dirs.db_path(), "memories");
+        write_plaintext(&dirs.conversations_db_path(), "chats");
+
+        encryption.recover(&dirs).unwrap();
+
+        for db in databases(&dirs) {
+            assert_eq!(state(&db).unwrap(), DbState::Plaintext);
+        }
+        assert!(encryption.env(&dirs).is_empty());
+    }
+}
@@ -0,0 +1,599 @@
+//! [`DeskflowError`], the error every command hands back to the webview.
+//!
+//! It serializes as `{"kind", "message", "retryable", "status"}` so the
+//! frontend can tell a backend that is down from one that answered with an
+//! error or one without an LLM, and offer the matching fix. `status` is the
+//! backend's HTTP status where there was one and `null` otherwise.
+//!
+//! The shell's own error types convert into it, so commands can use `?`.
+
+use std::{fmt, io};
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::backup::BackupError;
+#[cfg(feature = "desktop")]
+use crate::diagnostics::DiagnosticsError;
+use crate::encryption::EncryptionError;
+use crate::history::HistoryError;
+use crate::profile::ProfileError;
+use crate::search::SearchError;
+use crate::secrets::SecretError;
+
+#[derive(Debug, Clone)]
+pub enum DeskflowError {
+    /// The backend could not be reached at all.
+    Connect {
+        message: String,
+    },
+    Timeout {
+        message: String,
+    },
+    /// The backend answered with a non-success status.
+    Status {
+        status: u16,
+        message: String,
+    },
+    /// No LLM provider or API key is set up, so the agent cannot answer.
+    LlmNotConfigured {
+        status: Option<u16>,
+        message: String,
+    },
+    /// Something we could not parse, from the backend or on disk.
+    Decode {
+        message: String,
+    },
+    NotFound {
+        message: String,
+    },
+    /// The request itself was wrong, e.g. a bad filter or profile name.
+    InvalidInput {
+        message: String,
+    },
+    /// Another process holds the database.
+    Busy {
+        message: String,
+    },
+    Database {
+        message: String,
+    },
+    Io {
+        message: String,
+    },
+    /// The OS keyring or the secret vault failed.
+    Keyring {
+        message: String,
+    },
+    Internal {
+        message: String,
+    },
+}
+
+pub type DeskflowResult<T> = Result<T, DeskflowError>;
+
+impl DeskflowError {
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        DeskflowError::InvalidInput {
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(err: impl fmt::Display) -> Self {
+        DeskflowError::Internal {
+            message: err.to_string(),
+        }
+    }
+
+    /// A non-success answer from the backend. The backend reports a missing
+    /// provider key as an ordinary error naming the key, so that is how it
+    /// is recognised.
+    pub fn from_status(status: u16, message: String) -> Self {
+        if names_missing_llm_key(&message) {
+            DeskflowError::LlmNotConfigured {
+                status: Some(status),
+                message,
+            }
+        } else {
+            DeskflowError::Status { status, message }
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeskflowError::Connect { .. } => "connect",
+            DeskflowError::Timeout { .. } => "timeout",
+            DeskflowError::Status { .. } => "status",
+            DeskflowError::LlmNotConfigured { .. } => "llm_not_configured",
+            DeskflowError::Decode { .. } => "decode",
+            DeskflowError::NotFound { .. } => "not_found",
+            DeskflowError::InvalidInput { .. } => "invalid_input",
+            DeskflowError::Busy { .. } => "busy",
+            DeskflowError::Database { .. } => "database",
+            DeskflowError::Io { .. } => "io",
+            DeskflowError::Keyring { .. } => "keyring",
+            DeskflowError::Internal { .. } => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            DeskflowError::Connect { message }
+            | DeskflowError::Timeout { message }
+            | DeskflowError::Status { message, .. }
+            | DeskflowError::LlmNotConfigured { message, .. }
+            | DeskflowError::Decode { message }
+            | DeskflowError::NotFound { message }
+            | DeskflowError::InvalidInput { message }
+            | DeskflowError::Busy { message }
+            | DeskflowError::Database { message }
+            | DeskflowError::Io { message }
+            | DeskflowError::Keyring { message }
+            | DeskflowError::Internal { message } => message,
+        }
+    }
+
+    /// The backend's HTTP status, if it answered.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            DeskflowError::Status { status, .. } => Some(*status),
+            DeskflowError::LlmNotConfigured { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    /// Whether trying again unchanged may work.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DeskflowError::Connect { .. }
+            | DeskflowError::Timeout { .. }
+            | DeskflowError::Busy { .. } => true,
+            DeskflowError::Status { status, .. } => matches!(status, 502..=504),
+            _ => false,
+        }
+    }
+
+    /// Say what was being done, e.g. `Cannot write notes.md: <message>`.
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        let message = format!("{context}: {}", self.message());
+        self.with_message(message)
+    }
+
+    /// The same kind with the wording of the error it was converted from.
+    fn with_message(mut self, text: String) -> Self {
+        match &mut self {
+            DeskflowError::Connect { message }
+            | DeskflowError::Timeout { message }
+            | DeskflowError::Status { message, .. }
+            | DeskflowError::LlmNotConfigured { message, .. }
+            | DeskflowError::Decode { message }
+            | DeskflowError::NotFound { message }
+            | DeskflowError::InvalidInput { message }
+            | DeskflowError::Busy { message }
+            | DeskflowError::Database { message }
+            | DeskflowError::Io { message }
+            | DeskflowError::Keyring { message }
+            | DeskflowError::Internal { message } => *message = text,
+        }
+        self
+    }
+}
+
+fn names_missing_llm_key(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    let names_key = message.contains("api_key") || message.contains("api key");
+    message.contains("llm not configured")
+        || (names_key
+            && ["required", "missing", "not configured", "not set"]
+                .iter()
+                .any(|reason| message.contains(reason)))
+}
+
+impl fmt::Display for DeskflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeskflowError::Connect { message } => {
+                write!(f, "Backend connection failed: {message}")
+            }
+            DeskflowError::Timeout { message } => write!(f, "Backend request timed out: {message}"),
+            DeskflowError::Status { status, message } => {
+                write!(f, "Backend returned status {status}: {message}")
+            }
+            DeskflowError::Decode { message } => write!(f, "Invalid response: {message}"),
+            other => f.write_str(other.message()),
+        }
+    }
+}
+
+impl std::error::Error for DeskflowError {}
+
+impl Serialize for DeskflowError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("DeskflowError", 4)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", self.message())?;
+        error.serialize_field("retryable", &self.is_retryable())?;
+        error.serialize_field("status", &self.status())?;
+        error.end()
+    }
+}
+
+/// Only a request that never reached the backend or got no answer in time
+/// is worth retrying; the rest would fail the same way again.
+impl From<reqwest::Error> for DeskflowError {
+    fn from(err: reqwest::Error) -> Self {
+        let message = err.to_string();
+        if err.is_timeout() {
+            DeskflowError::Timeout { message }
+        } else if err.is_connect() {
+            DeskflowError::Connect { message }
+        } else if err.is_decode() || err.is_body() {
+            DeskflowError::Decode { message }
+        } else if let Some(status) = err.status() {
+            DeskflowError::from_status(status.as_u16(), message)
+        } else if err.is_builder() {
+            DeskflowError::InvalidInput { message }
+        } else {
+            DeskflowError::Internal { message }
+        }
+    }
+}
+
+impl From<rusqlite::Error> for DeskflowError {
+    fn from(err: rusqlite::Error) -> Self {
+        let message = err.to_string();
+        if matches!(err, rusqlite::Error::QueryReturnedNoRows) {
+            return DeskflowError::NotFound { message };
+        }
+        match err.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) => {
+                DeskflowError::Busy { message }
+            }
+            _ => DeskflowError::Database { message },
+        }
+    }
+}
+
+impl From<io::Error> for DeskflowError {
+    fn from(err: io::Error) -> Self {
+        let message = err.to_string();
+        match err.kind() {
+            io::ErrorKind::NotFound => DeskflowError::NotFound { message },
+            io::ErrorKind::TimedOut => DeskflowError::Timeout { message },
+            _ => DeskflowError::Io { message },
+        }
+    }
+}
+
+impl From<serde_json::Error> for DeskflowError {
+    fn from(err: serde_json::Error) -> Self {
+        match err.classify() {
+            serde_json::error::Category::Io => DeskflowError::Io {
+                message: err.to_string(),
+            },
+            _ => DeskflowError::Decode {
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "desktop")]
+impl From<tauri::Error> for DeskflowError {
+    fn from(err: tauri::Error) -> Self {
+        DeskflowError::internal(err)
+    }
+}
+
+impl From<tokio::task::JoinError> for DeskflowError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        DeskflowError::internal(err)
+    }
+}
+
+// The shell's own errors keep their wording, which is already meant for
+// the user; only the kind is picked here.
+
+impl From<HistoryError> for DeskflowError {
+    fn from(err: HistoryError) -> Self {
+        let message = err.to_string();
+        match err {
+            HistoryError::Missing(_) | HistoryError::NotFound(_) => {
+                DeskflowError::NotFound { message }
+            }
+            HistoryError::Encryption(e) => DeskflowError::from(e).with_message(message),
+            HistoryError::Sqlite(e) => DeskflowError::from(e).with_message(message),
+        }
+    }
+}
+
+impl From<SearchError> for DeskflowError {
+    fn from(err: SearchError) -> Self {
+        match err {
+            SearchError::InvalidFilter(_) => DeskflowError::invalid_input(err.to_string()),
+            SearchError::History(e) => e.into(),
+        }
+    }
+}
+
+impl From<SecretError> for DeskflowError {
+    fn from(err: SecretError) -> Self {
+        DeskflowError::Keyring {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<EncryptionError> for DeskflowError {
+    fn from(err: EncryptionError) -> Self {
+        let message = err.to_string();
+        match err {
+            EncryptionError::Missing(_) => DeskflowError::NotFound { message },
+            EncryptionError::AlreadyEncrypted | EncryptionError::NotEncrypted => {
+                DeskflowError::InvalidInput { message }
+            }
+            EncryptionError::InUse => DeskflowError::Busy { message },
+            EncryptionError::Integrity(_) => DeskflowError::Database { message },
+            EncryptionError::NoKey => DeskflowError::Keyring { message },
+            EncryptionError::Secret(e) => e.into(),
+            EncryptionError::Sqlite(e) => DeskflowError::from(e).with_message(message),
+            EncryptionError::Io(e) => DeskflowError::from(e).with_message(message),
+        }
+    }
+}
+
+impl From<ProfileError> for DeskflowError {
+    fn from(err: ProfileError) -> Self {
+        let message = err.to_string();
+        match err {
+            ProfileError::InvalidName(_) | ProfileError::AlreadyExists(_) => {
+                DeskflowError::InvalidInput { message }
+            }
+            ProfileError::NotFound(_) => DeskflowError::NotFound { message },
+            ProfileError::Io(e) => DeskflowError::from(e).with_message(message),
+        }
+    }
+}
+
+impl From<BackupError> for DeskflowError {
+    fn from(err: BackupError) -> Self {
+        let message = err.to_string();
+        match err {
+            BackupError::Invalid(_) | BackupError::UnsupportedSchema(_) => {
+                DeskflowError::InvalidInput { message }
+            }
+            BackupError::KeyUnavailable(_) => DeskflowError::Keyring { message },
+            BackupError::Encryption(e) => e.into(),
+            BackupError::Profile(e) => e.into(),
+            BackupError::Sqlite(e) => DeskflowError::from(e).with_message(message),
+            BackupError::Io(e) => DeskflowError::from(e).with_message(message),
+        }
+    }
+}
+
+#[cfg(feature = "desktop")]
+impl From<DiagnosticsError> for DeskflowError {
+    fn from(err: DiagnosticsError) -> Self {
+        let message = err.to_string();
+        match err {
+            DiagnosticsError::Archive(_) => DeskflowError::Io { message },
+            DiagnosticsError::Io(e) => DeskflowError::from(e).with_message(message),
+        }
+    }
+}
+
+#[cfg(all(feature = "desktop", desktop))]
+impl From<tauri_plugin_updater::Error> for DeskflowError {
+    fn from(err: tauri_plugin_updater::Error) -> Self {
+        use tauri_plugin_updater::Error;
+        let message = err.to_string();
+        match err {
+            // The updater's reqwest is another major than ours
+            Error::Reqwest(e) if e.is_timeout() => DeskflowError::Timeout { message },
+            Error::Reqwest(e) if e.is_connect() => DeskflowError::Connect { message },
+            Error::Network(_) => DeskflowError::Connect { message },
+            Error::Reqwest(e) => match e.status() {
+                Some(status) => DeskflowError::from_status(status.as_u16(), message),
+                None => DeskflowError::Internal { message },
+            },
+            Error::ReleaseNotFound | Error::TargetNotFound(_) | Error::TargetsNotFound(_) => {
+                DeskflowError::NotFound { message }
+            }
+            // A release that does not verify against our key
+            Error::Minisign(_)
+            | Error::Base64(_)
+            | Error::SignatureUtf8(_)
+            | Error::SignedVersionMismatch { .. }
+            | Error::MissingSignedVersion
+            | Error::Serialization(_) => DeskflowError::Decode { message },
+            Error::UrlParse(_) | Error::InsecureTransportProtocol => {
+                DeskflowError::InvalidInput { message }
+            }
+            Error::Io(e) => DeskflowError::from(e).with_message(message),
+            _ => DeskflowError::Internal { message },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn every_kind() -> Vec<DeskflowError> {
+        let message = || "oops".to_string();
+        vec![
+            DeskflowError::Connect { message: message() },
+            DeskflowError::Timeout { message: message() },
+            DeskflowError::Status {
+                status: 500,
+                message: message(),
+            },
+            DeskflowError::LlmNotConfigured {
+                status: None,
+                message: message(),
+            },
+            DeskflowError::Decode { message: message() },
+            DeskflowError::NotFound { message: message() },
+            DeskflowError::InvalidInput { message: message() },
+            DeskflowError::Busy { message: message() },
+            DeskflowError::Database { message: message() },
+            DeskflowError::Io { message: message() },
+            DeskflowError::Keyring { message: message() },
+            DeskflowError::Internal { message: message() },
+        ]
+    }
+
+    #[test]
+    fn kinds_serialize_as_the_tags_the_frontend_matches() {
+        let kinds: Vec<Value> = every_kind()
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap()["kind"].clone())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "connect",
+                "timeout",
+                "status",
+                "llm_not_configured",
+                "decode",
+                "not_found",
+                "invalid_input",
+                "busy",
+                "database",
+                "io",
+                "keyring",
+                "internal",
+            ]
+        );
+    }
+
+    #[test]
+    fn serializes_message_retryable_and_status() {
+        let error = DeskflowError::Status {
+            status: 503,
+            message: "Service Unavailable".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "kind": "status",
+                "message": "Service Unavailable",
+                "retryable": true,
+                "status": 503,
+            })
+        );
+        let error = DeskflowError::invalid_input("bad name");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "kind": "invalid_input",
+                "message": "bad name",
+                "retryable": false,
+                "status": null,
+            })
+        );
+    }
+
+    #[test]
+    fn only_transient_failures_are_retryable() {
+        let retryable: Vec<&str> = every_kind()
+            .iter()
+            .filter(|e| e.is_retryable())
+            .map(|e| e.kind())
+            .collect();
+        assert_eq!(retryable, ["connect", "timeout", "busy"]);
+
+        for (status, retryable) in [
+            (400, false),
+            (404, false),
+            (500, false),
+            (502, true),
+            (503, true),
+            (504, true),
+        ] {
+            let error = DeskflowError::from_status(status, "failed".to_string());
+            assert_eq!(error.is_retryable(), retryable, "{status}");
+        }
+    }
+
+    #[test]
+    fn missing_llm_keys_are_recognised() {
+        for message in [
+            "LLM not configured",
+            "OPENAI_API_KEY is required",
+            "api_key missing for provider anthropic",
+            "No API key set: API key not set",
+            "The API key is not configured",
+        ] {
+            assert!(names_missing_llm_key(message), "{message}");
+        }
+        for message in [
+            "Internal Server Error",
+            "Invalid API key",
+            "api_key rejected by provider",
+            "Field required: conversation_id",
+        ] {
+            assert!(!names_missing_llm_key(message), "{message}");
+        }
+    }
+
+    #[test]
+    fn from_status_picks_llm_not_configured_by_message() {
+        assert!(matches!(
+            DeskflowError::from_status(400, "OPENAI_API_KEY is required".to_string()),
+            DeskflowError::LlmNotConfigured {
+                status: Some(400),
+                ..
+            }
+        ));
+        let error = DeskflowError::from_status(422, "Field required".to_string());
+        assert!(matches!(error, DeskflowError::Status { status: 422, .. }));
+        assert_eq!(error.status(), Some(422));
+        assert_eq!(
+            error.to_string(),
+            "Backend returned status 422: Field required"
+        );
+    }
+
+    #[tokio::test]
+    async fn reqwest_errors_are_retryable_only_when_transient() {
+        let http = reqwest::Client::new();
+
+        let builder = http.get("not a url").send().await.unwrap_err();
+        let error = DeskflowError::from(builder);
+        assert_eq!(error.kind(), "invalid_input");
+        assert!(!error.is_retryable());
+
+        // Nothing listens on a port we just let go of
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let refused = http
+            .get(format!("http://127.0.0.1:{port}/"))
+            .send()
+            .await
+            .unwrap_err();
+        let error = DeskflowError::from(refused);
+        assert_eq!(error.kind(), "connect");
+        assert!(error.is_retryable());
+
+        let response = reqwest::Response::from(axum::http::Response::new("<html>"));
+        let decode = response.json::<Value>().await.unwrap_err();
+        let error = DeskflowError::from(decode);
+        assert_eq!(error.kind(), "decode");
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn context_keeps_the_kind() {
+        let error = DeskflowError::from(io::Error::from(io::ErrorKind::NotFound))
+            .context("Cannot read notes.md");
+        assert_eq!(error.kind(), "not_found");
+        assert!(error.message().starts_with("Cannot read notes.md: "));
+    }
+}
@@ -0,0 +1,273 @@
+//! Events between the shell and the webview: navigation, settings changes,
+//! launches and `deskflow://` links coming in from outside, and the chat
+//! stream and agent notices forwarded from the backend.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+#[cfg(desktop)]
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tracing::warn;
+
+use crate::backend::BackendEndpoint;
+use crate::chat::ChatBridge;
+use crate::deep_link::{Route, SkillSource};
+use crate::deskflow_client::models::SkillInstallRequest;
+use crate::deskflow_client::DeskflowClient;
+use crate::instance::LaunchRequest;
+use crate::notifications::{self, ActivityFeed, AgentNotice, NoticeAction};
+use crate::settings::{SettingsStore, ShellSettings};
+use crate::state::AppState;
+#[cfg(desktop)]
+use crate::tray::Tray;
+use crate::windows::{show_error, show_main_window, MAIN_LABEL};
+
+/// Where the main window should go, emitted as `shell://navigate`.
+#[derive(Clone, Serialize)]
+pub struct Navigate {
+    pub view: &'static str,
+    pub new_chat: bool,
+    pub conversation_id: Option<String>,
+}
+
+impl Navigate {
+    pub fn chat(conversation_id: Option<String>) -> Self {
+        Self {
+            view: "chat",
+            new_chat: conversation_id.is_none(),
+            conversation_id,
+        }
+    }
+
+    pub fn view(view: &'static str) -> Self {
+        Self {
+            view,
+            new_chat: false,
+            conversation_id: None,
+        }
+    }
+}
+
+pub fn navigate(app: &AppHandle, to: Navigate) {
+    show_main_window(app);
+    let _ = app.emit_to(MAIN_LABEL, "shell://navigate", to);
+}
+
+pub fn settings_changed(app: &AppHandle, settings: &ShellSettings) {
+    #[cfg(desktop)]
+    if let Some(tray) = app.try_state::<Tray>() {
+        tray.show_settings(settings);
+    }
+    let _ = app.emit("shell://settings", settings);
+}
+
+/// Queue a launch for the frontend and bring the main window forward. The
+/// event only announces it; the frontend drains the queue, so a request
+/// made before the webview listens is not lost.
+pub fn deliver_launch(app: &AppHandle, request: LaunchRequest) {
+    show_main_window(app);
+    if request.is_empty() {
+        return;
+    }
+    app.state::<AppState>().queue_launch(request);
+    let _ = app.emit_to(MAIN_LABEL, "instance://launch", ());
+}
+
+/// Follow a `deskflow://` link, asking first if it would change anything.
+pub fn open_link(app: &AppHandle, link: &str) {
+    let route = match Route::parse(link) {
+        Ok(route) => route,
+        Err(e) => {
+            show_error(app, "Cannot open link", e.to_string());
+            return;
+        }
+    };
+    if !route.changes_state() {
+        open_route(app, route);
+        return;
+    }
+    confirm_route(app, route);
+}
+
+#[cfg(desktop)]
+fn confirm_route(app: &AppHandle, route: Route) {
+    show_main_window(app);
+    let handle = app.clone();
+    app.dialog()
+        .message(format!(
+            "A link wants to {}.\n\nOnly continue if you trust where it came from.",
+            route.summary()
+        ))
+        .title("Open DeskFlow link")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Continue".into(),
+            "Cancel".into(),
+        ))
+        .show(move |confirmed| {
+            if confirmed {
+                open_route(&handle, route);
+            }
+        });
+}
+
+/// Without native dialogs there is no one to ask.
+#[cfg(mobile)]
+fn confirm_route(app: &AppHandle, route: Route) {
+    show_error(
+        app,
+        "Cannot open link",
+        format!(
+            "Only the desktop app can follow links that {}.",
+            route.summary()
+        ),
+    );
+}
+
+fn open_route(app: &AppHandle, route: Route) {
+    match route {
+        Route::Chat { prompt } => {
+            navigate(app, Navigate::chat(None));
+            if let Some(prompt) = prompt {
+                deliver_launch(
+                    app,
+                    LaunchRequest {
+                        prompt: Some(prompt),
+                        files: Vec::new(),
+                    },
+                );
+            }
+        }
+        Route::Conversation { id } => navigate(app, Navigate::chat(Some(id))),
+        Route::View(view) => navigate(app, Navigate::view(view.name())),
+        Route::InstallSkill(source) => {
+            let handle = app.clone();
+            tauri::async_runtime::spawn(async move {
+                match install_skill(&handle, source).await {
+                    Ok(()) => {
+                        navigate(&handle, Navigate::view("skills"));
+                        let _ = handle.emit("skills://changed", ());
+                    }
+                    Err(e) => show_error(&handle, "Skill installation failed", e),
+                }
+            });
+        }
+    }
+}
+
+async fn install_skill(app: &AppHandle, source: SkillSource) -> Result<(), String> {
+    let request = match source {
+        SkillSource::Template(name) => SkillInstallRequest {
+            template_name: Some(name),
+            ..Default::default()
+        },
+        SkillSource::GitHub(url) => SkillInstallRequest {
+            source_url: Some(url.to_string()),
+            ..Default::default()
+        },
+        SkillSource::Archive(path) => {
+            let archive = tokio::fs::read(&path)
+                .await
+                .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+            SkillInstallRequest {
+                skill_data: Some(BASE64.encode(archive)),
+                ..Default::default()
+            }
+        }
+    };
+
+    let client = app.state::<DeskflowClient>();
+    let response = client
+        .install_skill(&request)
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.success {
+        Ok(())
+    } else {
+        Err(response.message)
+    }
+}
+
+/// Stream chat through the backend, with chunks going to the window that
+/// asked as `chat://chunk` and the connection as `chat://connection`.
+pub fn spawn_chat_bridge(app: &tauri::App, endpoint: BackendEndpoint) -> ChatBridge {
+    let chat = ChatBridge::spawn(format!("{}/api/chat/stream", endpoint.ws_url()));
+
+    let handle = app.handle().clone();
+    let mut events = chat.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let _ = match event.origin.clone() {
+                        Some(label) => handle.emit_to(label.as_str(), "chat://chunk", event),
+                        None => handle.emit("chat://chunk", event),
+                    };
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    });
+
+    let handle = app.handle().clone();
+    let mut connected = chat.subscribe_connection();
+    tauri::async_runtime::spawn(async move {
+        while connected.changed().await.is_ok() {
+            let connected = *connected.borrow_and_update();
+            let _ = handle.emit("chat://connection", connected);
+        }
+    });
+
+    chat
+}
+
+/// Turn agent events from the backend into native notifications.
+pub fn spawn_notifications(app: &tauri::App, endpoint: BackendEndpoint) {
+    let feed = ActivityFeed::spawn(format!("{}/api/monitor/ws/activity", endpoint.ws_url()));
+
+    let handle = app.handle().clone();
+    let mut notices = feed.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match notices.recv().await {
+                Ok(notice) => notify(&handle, notice),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+fn notify(app: &AppHandle, notice: AgentNotice) {
+    if !notifications::should_notify(&app.state::<SettingsStore>().get(), notice.category) {
+        return;
+    }
+    // Whoever is looking at DeskFlow sees it happen
+    let focused = app
+        .webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false));
+    if focused {
+        return;
+    }
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || match notice.show(&handle.config().identifier) {
+        Ok(Some(NoticeAction::Open)) => open_link(&handle, &notice.link()),
+        Ok(Some(NoticeAction::Mute)) => {
+            let settings = handle.state::<SettingsStore>();
+            match settings.update(|s| {
+                if !s.muted_notifications.contains(&notice.category) {
+                    s.muted_notifications.push(notice.category);
+                }
+            }) {
+                Ok(updated) => settings_changed(&handle, &updated),
+                Err(e) => warn!("Cannot mute {:?} notifications: {e}", notice.category),
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Cannot show a notification: {e}"),
+    });
+}
@@ -0,0 +1,662 @@
+//! Read-only access to the chat history in `conversations.db`, so
+//! conversations stay browsable while the backend is starting, crashed or
+//! misconfigured.
+//!
+//! The database belongs to the backend (`api/routes/chat.py`) and is only
+//! ever read here, through a connection the caller opens with the
+//! profile's key when it is encrypted (see
+//! [`DbEncryption::open_read_only`](crate::encryption::DbEncryption::open_read_only)).
+//! It has no full-text index of its own, so searching builds one in memory
+//! and rebuilds it whenever the history changes.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::deskflow_client::models::{
+    Conversation, ConversationList, ConversationMessage, ConversationSummary,
+};
+use crate::encryption::EncryptionError;
+use crate::search::DateRange;
+
+pub const CONVERSATIONS_DB: &str = "conversations.db";
+
+/// Marks the start of a match in [`SearchHit::snippet`].
+pub const MATCH_START: &str = "\u{2}";
+/// Marks the end of a match in [`SearchHit::snippet`].
+pub const MATCH_END: &str = "\u{3}";
+
+const SNIPPET_TOKENS: i32 = 24;
+const EXCERPT_CHARS: usize = 60;
+
+/// The trigram tokenizer needs at least this many characters per term;
+/// shorter queries fall back to a plain substring scan.
+const MIN_FTS_CHARS: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub conversation_id: String,
+    pub conversation_title: String,
+    pub message_id: String,
+    pub role: String,
+    /// Excerpt of the message with matches wrapped in [`MATCH_START`] and
+    /// [`MATCH_END`].
+    pub snippet: String,
+    pub created_at: String,
+}
+
+#[derive(Debug)]
+pub enum HistoryError {
+    /// The backend has not created the database yet.
+    Missing(PathBuf),
+    NotFound(String),
+    /// The database is encrypted and cannot be opened.
+    Encryption(EncryptionError),
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Missing(path) => {
+                write!(f, "No chat history yet ({} does not exist)", path.display())
+            }
+            HistoryError::NotFound(id) => write!(f, "Conversation {id} not found"),
+            HistoryError::Encryption(e) => write!(f, "Cannot read chat history: {e}"),
+            HistoryError::Sqlite(e) => write!(f, "Cannot read chat history: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+impl From<rusqlite::Error> for HistoryError {
+    fn from(err: rusqlite::Error) -> Self {
+        HistoryError::Sqlite(err)
+    }
+}
+
+impl From<EncryptionError> for HistoryError {
+    fn from(err: EncryptionError) -> Self {
+        match err {
+            EncryptionError::Missing(path) => HistoryError::Missing(path),
+            EncryptionError::Sqlite(e) => HistoryError::Sqlite(e),
+            other => HistoryError::Encryption(other),
+        }
+    }
+}
+
+pub type HistoryResult<T> = Result<T, HistoryError>;
+
+/// Reader for a profile's `conversations.db`. Every call is handed a
+/// connection to the database, so the reader follows profile switches.
+/// Cheap to clone.
+///
+/// Calls block on disk; keep them off the main thread.
+#[derive(Clone, Default)]
+pub struct History {
+    index: Arc<Mutex<Option<SearchIndex>>>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Conversations, most recently updated first.
+    pub fn conversations(
+        &self,
+        conn: &Connection,
+        limit: u32,
+        offset: u32,
+    ) -> HistoryResult<ConversationList> {
+        let total: u32 =
+            conn.query_row("SELECT COUNT(*) FROM conversations", [], |row| row.get(0))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, title, created_at, updated_at, message_count
+             FROM conversations
+             ORDER BY updated_at DESC
+             LIMIT ?1 OFFSET ?2",
+        )?;
+        let conversations = stmt
+            .query_map(params![limit, offset], |row| {
+                Ok(ConversationSummary {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                    message_count: row.get::<_, Option<u32>>(4)?.unwrap_or_default(),
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(ConversationList {
+            conversations,
+            total,
+        })
+    }
+
+    pub fn conversation(&self, conn: &Connection, id: &str) -> HistoryResult<Conversation> {
+        let mut conversation = conn
+            .query_row(
+                "SELECT id, title, created_at, updated_at, message_count
+                 FROM conversations
+                 WHERE id = ?1",
+                [id],
+                |row| {
+                    Ok(Conversation {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        created_at: row.get(2)?,
+                        updated_at: row.get(3)?,
+                        message_count: row.get::<_, Option<u32>>(4)?.unwrap_or_default(),
+                        messages: Vec::new(),
+                    })
+                },
+            )
+            .optional()?
+            .ok_or_else(|| HistoryError::NotFound(id.to_string()))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, role, content, created_at
+             FROM messages
+             WHERE conversation_id = ?1
+             ORDER BY created_at ASC",
+        )?;
+        conversation.messages = stmt
+            .query_map([id], |row| {
+                Ok(ConversationMessage {
+                    id: row.get(0)?,
+                    role: row.get(1)?,
+                    content: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(conversation)
+    }
+
+    /// Every conversation with activity within `range`, oldest first.
+    pub fn conversations_between(
+        &self,
+        conn: &Connection,
+        range: &DateRange,
+    ) -> HistoryResult<Vec<ConversationSummary>> {
+        // Timestamps are local ISO strings, which sort like the plain dates
+        // in `range`
+        let mut stmt = conn.prepare(
+            "SELECT id, title, created_at, updated_at, message_count
+             FROM conversations
+             WHERE (?1 IS NULL OR updated_at >= ?1)
+               AND (?2 IS NULL OR created_at < date(?2, '+1 day'))
+             ORDER BY created_at ASC",
+        )?;
+        let conversations = stmt
+            .query_map(params![range.since, range.until], |row| {
+                Ok(ConversationSummary {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                    message_count: row.get::<_, Option<u32>>(4)?.unwrap_or_default(),
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(conversations)
+    }
+
+    /// Messages matching every term of `query` and sent within `range`,
+    /// best matches first. `db` is where `source` was opened, to tell
+    /// profiles apart.
+    pub fn search(
+        &self,
+        source: &Connection,
+        db: &Path,
+        query: &str,
+        range: &DateRange,
+        limit: u32,
+        offset: u32,
+    ) -> HistoryResult<Vec<SearchHit>> {
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if terms.iter().any(|t| t.chars().count() < MIN_FTS_CHARS) {
+            return scan(source, &terms, range, limit, offset);
+        }
+
+        let mut index = self.index.lock().unwrap();
+        let stamp = Stamp::read(source, db)?;
+        if index.as_ref().is_none_or(|index| index.stamp != stamp) {
+            *index = Some(SearchIndex::build(source, stamp)?);
+        }
+        index.as_ref().unwrap().search(&terms, range, limit, offset)
+    }
+}
+
+/// Identifies a state of the history; any write the backend makes changes
+/// at least one of these.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Stamp {
+    db: PathBuf,
+    messages: i64,
+    last_message: Option<String>,
+    last_update: Option<String>,
+}
+
+impl Stamp {
+    fn read(conn: &Connection, db: &Path) -> HistoryResult<Self> {
+        let (messages, last_message) = conn.query_row(
+            "SELECT COUNT(*), MAX(created_at) FROM messages",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let last_update =
+            conn.query_row("SELECT MAX(updated_at) FROM conversations", [], |row| {
+                row.get(0)
+            })?;
+        Ok(Self {
+            db: db.to_path_buf(),
+            messages,
+            last_message,
+            last_update,
+        })
+    }
+}
+
+/// In-memory FTS5 copy of the messages. The trigram tokenizer matches
+/// substrings, which also works for Chinese text without word breaks.
+struct SearchIndex {
+    stamp: Stamp,
+    conn: Connection,
+}
+
+impl SearchIndex {
+    fn build(source: &Connection, stamp: Stamp) -> HistoryResult<Self> {
+        let mut conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE messages_fts USING fts5(
+                 content,
+                 message_id UNINDEXED,
+                 conversation_id UNINDEXED,
+                 conversation_title UNINDEXED,
+                 role UNINDEXED,
+                 created_at UNINDEXED,
+                 tokenize = 'trigram'
+             );",
+        )?;
+
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO messages_fts
+                 (content, message_id, conversation_id, conversation_title, role, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            let mut select = source.prepare(
+                "SELECT m.content, m.id, m.conversation_id, COALESCE(c.title, ''), m.role, m.created_at
+                 FROM messages m
+                 LEFT JOIN conversations c ON c.id = m.conversation_id",
+            )?;
+            let mut rows = select.query([])?;
+            while let Some(row) = rows.next()? {
+                insert.execute(params![
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(Self { stamp, conn })
+    }
+
+    fn search(
+        &self,
+        terms: &[&str],
+        range: &DateRange,
+        limit: u32,
+        offset: u32,
+    ) -> HistoryResult<Vec<SearchHit>> {
+        // `created_at` is the backend's local ISO timestamp, which sorts
+        // like the plain dates in `range`
+        let mut stmt = self.conn.prepare(
+            "SELECT conversation_id, conversation_title, message_id, role,
+                    snippet(messages_fts, 0, ?2, ?3, '…', ?4), created_at
+             FROM messages_fts
+             WHERE messages_fts MATCH ?1
+               AND (?5 IS NULL OR created_at >= ?5)
+               AND (?6 IS NULL OR created_at < date(?6, '+1 day'))
+             ORDER BY bm25(messages_fts)
+             LIMIT ?7 OFFSET ?8",
+        )?;
+        let hits = stmt
+            .query_map(
+                params![
+                    fts_query(terms),
+                    MATCH_START,
+                    MATCH_END,
+                    SNIPPET_TOKENS,
+                    range.since,
+                    range.until,
+                    limit,
+                    offset
+                ],
+                |row| {
+                    Ok(SearchHit {
+                        conversation_id: row.get(0)?,
+                        conversation_title: row.get(1)?,
+                        message_id: row.get(2)?,
+                        role: row.get(3)?,
+                        snippet: row.get(4)?,
+                        created_at: row.get(5)?,
+                    })
+                },
+            )?
+            .collect::<Result<_, _>>()?;
+        Ok(hits)
+    }
+}
+
+/// Quote every term so user input is never read as FTS5 query syntax.
+pub(crate) fn fts_query(terms: &[&str]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Substring search straight on the database, newest first, for terms too
+/// short for the trigram index.
+fn scan(
+    conn: &Connection,
+    terms: &[&str],
+    range: &DateRange,
+    limit: u32,
+    offset: u32,
+) -> HistoryResult<Vec<SearchHit>> {
+    let mut sql = String::from(
+        "SELECT m.conversation_id, COALESCE(c.title, ''), m.id, m.role, m.content, m.created_at
+         FROM messages m
+         LEFT JOIN conversations c ON c.id = m.conversation_id
+         WHERE (?1 IS NULL OR m.created_at >= ?1)
+           AND (?2 IS NULL OR m.created_at < date(?2, '+1 day'))",
+    );
+    let mut patterns = Vec::with_capacity(terms.len());
+    for (i, term) in terms.iter().enumerate() {
+        sql.push_str(&format!(" AND m.content LIKE ?{} ESCAPE '\\'", i + 3));
+        patterns.push(format!("%{}%", escape_like(term)));
+    }
+    sql.push_str(&format!(
+        " ORDER BY m.created_at DESC LIMIT ?{} OFFSET ?{}",
+        terms.len() + 3,
+        terms.len() + 4
+    ));
+
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&range.since, &range.until];
+    values.extend(patterns.iter().map(|p| p as &dyn rusqlite::ToSql));
+    values.push(&limit);
+    values.push(&offset);
+
+    let mut stmt = conn.prepare(&sql)?;
+    let hits = stmt
+        .query_map(values.as_slice(), |row| {
+            let content: String = row.get(4)?;
+            Ok(SearchHit {
+                conversation_id: row.get(0)?,
+                conversation_title: row.get(1)?,
+                message_id: row.get(2)?,
+                role: row.get(3)?,
+                snippet: excerpt(&content, terms[0]),
+                created_at: row.get(5)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(hits)
+}
+
+pub(crate) fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// A window of `content` around the first case-insensitive occurrence of
+/// `needle`, with the match marked.
+pub(crate) fn excerpt(content: &str, needle: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    // One char per char, so positions in `lower` are positions in `chars`
+    let fold = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let needle: Vec<char> = needle.chars().map(fold).collect();
+    let lower: Vec<char> = chars.iter().copied().map(fold).collect();
+
+    let Some(start) = lower
+        .windows(needle.len().max(1))
+        .position(|window| window == needle.as_slice())
+    else {
+        return chars.iter().take(EXCERPT_CHARS * 2).collect();
+    };
+    let end = start + needle.len();
+    let from = start.saturating_sub(EXCERPT_CHARS);
+    let to = (end + EXCERPT_CHARS).min(chars.len());
+
+    let mut out = String::new();
+    if from > 0 {
+        out.push('…');
+    }
+    out.extend(&chars[from..start]);
+    out.push_str(MATCH_START);
+    out.extend(&chars[start..end]);
+    out.push_str(MATCH_END);
+    out.extend(&chars[end..to]);
+    if to < chars.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The history as `api/routes/chat.py` creates it.
+    fn history(messages: &[(&str, &str, &str)]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE conversations (
+                 id TEXT PRIMARY KEY,
+                 title TEXT NOT NULL,
+                 created_at TEXT NOT NULL,
+                 updated_at TEXT NOT NULL,
+                 message_count INTEGER DEFAULT 0
+             );
+             CREATE TABLE messages (
+                 id TEXT PRIMARY KEY,
+                 conversation_id TEXT NOT NULL,
+                 role TEXT NOT NULL,
+                 content TEXT NOT NULL,
+                 created_at TEXT NOT NULL,
+                 FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+             );
+             INSERT INTO conversations VALUES
+                 ('c1', 'Notes', '2026-03-01T09:00:00', '2026-03-02T09:00:00', 0);",
+        )
+        .unwrap();
+        for (id, content, created_at) in messages {
+            conn.execute(
+                "INSERT INTO messages VALUES (?1, 'c1', 'user', ?2, ?3)",
+                [id, content, created_at],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn search(conn: &Connection, query: &str) -> Vec<String> {
+        History::new()
+            .search(
+                conn,
+                Path::new(":memory:"),
+                query,
+                &DateRange::default(),
+                10,
+                0,
+            )
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.message_id)
+            .collect()
+    }
+
+    #[test]
+    fn fts_query_quotes_every_term() {
+        assert_eq!(fts_query(&["backup", "OR"]), r#""backup" "OR""#);
+        assert_eq!(fts_query(&[r#"say"hi""#]), r#""say""hi""""#);
+    }
+
+    #[test]
+    fn search_reads_fts_syntax_literally() {
+        let conn = history(&[
+            (
+                "m1",
+                "restore NEAR(disk) after the crash",
+                "2026-03-01T09:00:00",
+            ),
+            (
+                "m2",
+                "keep \"quoted\" words and col:on",
+                "2026-03-01T09:01:00",
+            ),
+            ("m3", "nothing to see here", "2026-03-01T09:02:00"),
+        ]);
+
+        assert_eq!(search(&conn, "NEAR(disk)"), ["m1"]);
+        assert_eq!(search(&conn, "\"quoted\""), ["m2"]);
+        assert_eq!(search(&conn, "col:on"), ["m2"]);
+        assert!(search(&conn, "crash AND see").is_empty());
+        assert!(search(&conn, "AND* NOT ^restore").is_empty());
+    }
+
+    #[test]
+    fn escape_like_escapes_wildcards() {
+        assert_eq!(escape_like("50%_off"), r"50\%\_off");
+        assert_eq!(escape_like(r"C:\tmp"), r"C:\\tmp");
+    }
+
+    #[test]
+    fn short_terms_are_scanned_newest_first() {
+        let conn = history(&[
+            ("m1", "hi there", "2026-03-01T09:00:00"),
+            ("m2", "oh, Hi again", "2026-03-01T10:00:00"),
+            ("m3", "hello", "2026-03-01T11:00:00"),
+        ]);
+
+        let hits = History::new()
+            .search(
+                &conn,
+                Path::new(":memory:"),
+                "hi",
+                &DateRange::default(),
+                10,
+                0,
+            )
+            .unwrap();
+
+        let ids: Vec<_> = hits.iter().map(|hit| hit.message_id.as_str()).collect();
+        assert_eq!(ids, ["m2", "m1"]);
+        assert_eq!(
+            hits[0].snippet,
+            format!("oh, {MATCH_START}Hi{MATCH_END} again")
+        );
+        assert_eq!(hits[0].conversation_title, "Notes");
+    }
+
+    #[test]
+    fn scanned_wildcards_match_literally() {
+        let conn = history(&[
+            ("m1", "save 5% now", "2026-03-01T09:00:00"),
+            ("m2", "save 50 now", "2026-03-01T09:01:00"),
+            ("m3", "a_b", "2026-03-01T09:02:00"),
+            ("m4", "axb", "2026-03-01T09:03:00"),
+        ]);
+
+        assert_eq!(search(&conn, "5%"), ["m1"]);
+        assert_eq!(search(&conn, "_"), ["m3"]);
+    }
+
+    #[test]
+    fn cjk_substrings_match() {
+        let conn = history(&[
+            ("m1", "我们的数据库备份好了", "2026-03-01T09:00:00"),
+            ("m2", "今天天气不错", "2026-03-01T09:01:00"),
+        ]);
+
+        // Three characters go through the trigram index, two are scanned
+        assert_eq!(search(&conn, "数据库"), ["m1"]);
+        assert_eq!(search(&conn, "备份"), ["m1"]);
+        assert_eq!(search(&conn, "天气"), ["m2"]);
+    }
+
+    #[test]
+    fn search_respects_the_date_range() {
+        let conn = history(&[
+            ("m1", "deploy the service", "2026-03-01T23:59:00"),
+            ("m2", "deploy it again", "2026-03-02T00:00:00"),
+        ]);
+        let range = DateRange {
+            since: None,
+            until: Some("2026-03-01".to_string()),
+        };
+
+        for query in ["deploy", "de"] {
+            let hits = History::new()
+                .search(&conn, Path::new(":memory:"), query, &range, 10, 0)
+                .unwrap();
+            let ids: Vec<_> = hits.iter().map(|hit| hit.message_id.as_str()).collect();
+            assert_eq!(ids, ["m1"], "{query}");
+        }
+    }
+
+    #[test]
+    fn excerpt_marks_the_first_match() {
+        assert_eq!(
+            excerpt("Backup the DB", "db"),
+            format!("Backup the {MATCH_START}DB{MATCH_END}")
+        );
+        assert_eq!(
+            excerpt("数据库备份", "备份"),
+            format!("数据库{MATCH_START}备份{MATCH_END}")
+        );
+    }
+
+    #[test]
+    fn excerpt_trims_long_content() {
+        let content = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let snippet = excerpt(&content, "needle");
+        assert_eq!(
+            snippet,
+            format!(
+                "…{}{MATCH_START}needle{MATCH_END}{}…",
+                "a".repeat(EXCERPT_CHARS),
+                "b".repeat(EXCERPT_CHARS)
+            )
+        );
+    }
+
+    #[test]
+    fn excerpt_without_a_match_is_the_start() {
+        let content = "x".repeat(EXCERPT_CHARS * 3);
+        assert_eq!(excerpt(&content, "y"), "x".repeat(EXCERPT_CHARS * 2));
+    }
+}
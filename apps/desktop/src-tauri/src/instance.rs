@@ -0,0 +1,445 @@
+//! One running shell per profile, so two profiles can run side by side but
+//! never two backends against the same one.
+//!
+//! The first launch on a profile takes an exclusive lock on `instance.lock`
+//! in the profile's data dir and listens on a socket next to it (a Unix
+//! socket, or a loopback port recorded in `instance.port` on Windows).
+//! Later launches on that profile find the lock taken, hand their
+//! arguments to the running instance over that socket and exit. When the
+//! running app switches profiles in place, the lock moves with it (see
+//! [`RunningInstance::move_to`]).
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::oneshot;
+
+use crate::deep_link::is_deep_link;
+
+const LOCK_FILE: &str = "instance.lock";
+#[cfg(unix)]
+const SOCKET_FILE: &str = "instance.sock";
+#[cfg(not(unix))]
+const PORT_FILE: &str = "instance.port";
+
+/// How long a second launch waits for the first one to start listening.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+const ACCEPT_RETRY: Duration = Duration::from_millis(500);
+const MAX_MESSAGE_BYTES: u64 = 64 * 1024;
+
+/// Command line of a launch, as handed to the running instance.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Launch {
+    pub args: Vec<String>,
+    /// Working directory of the launch, to resolve relative paths against.
+    pub cwd: Option<PathBuf>,
+}
+
+impl Launch {
+    /// The arguments this process was started with, minus the executable.
+    pub fn current() -> Self {
+        Self {
+            args: std::env::args().skip(1).collect(),
+            cwd: std::env::current_dir().ok(),
+        }
+    }
+
+    /// What the launch asks the app to do. Arguments naming an existing
+    /// file become attachments, the rest make up the prompt. Flags are left
+    /// to the platform (e.g. macOS `-psn_…`) and deep links to
+    /// [`crate::deep_link`].
+    pub fn request(&self) -> LaunchRequest {
+        let mut words = Vec::new();
+        let mut files = Vec::new();
+        for arg in &self.args {
+            if arg.starts_with('-') || is_deep_link(arg) {
+                continue;
+            }
+            let path = match &self.cwd {
+                Some(cwd) => cwd.join(arg),
+                None => PathBuf::from(arg),
+            };
+            if path.is_file() {
+                files.push(path.to_string_lossy().into_owned());
+            } else {
+                words.push(arg.as_str());
+            }
+        }
+
+        let prompt = words.join(" ");
+        LaunchRequest {
+            prompt: (!prompt.trim().is_empty()).then_some(prompt),
+            files,
+        }
+    }
+}
+
+/// A launch as the frontend sees it, delivered as `instance://launch`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LaunchRequest {
+    pub prompt: Option<String>,
+    pub files: Vec<String>,
+}
+
+impl LaunchRequest {
+    pub fn is_empty(&self) -> bool {
+        self.prompt.is_none() && self.files.is_empty()
+    }
+}
+
+pub enum Instance {
+    /// We are the running instance; launches forwarded by others arrive on
+    /// the listener.
+    Primary(InstanceListener),
+    /// Another instance is running; our launch was forwarded to it.
+    Secondary,
+    /// Another instance holds the lock but did not take our launch, e.g.
+    /// because it is stuck starting up.
+    Unreachable(io::Error),
+}
+
+/// Become the running instance for `dir`, or forward `launch` to the one
+/// that already is.
+pub fn acquire(dir: &Path, launch: &Launch) -> io::Result<Instance> {
+    if let Some(listener) = claim(dir)? {
+        return Ok(Instance::Primary(listener));
+    }
+    // The profile is taken either way; losing the arguments beats running
+    // a second backend against it
+    Ok(match forward(dir, launch) {
+        Ok(()) => Instance::Secondary,
+        Err(e) => Instance::Unreachable(e),
+    })
+}
+
+/// Take the lock on `dir` and start listening there, or `None` when
+/// another instance holds it.
+fn claim(dir: &Path) -> io::Result<Option<InstanceListener>> {
+    fs::create_dir_all(dir)?;
+
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+
+    match lock.try_lock() {
+        Ok(()) => {
+            let listener = transport::bind(dir)?;
+            Ok(Some(InstanceListener {
+                _lock: lock,
+                listener,
+            }))
+        }
+        Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+        Err(std::fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Send `launch` to the running instance, retrying while it is still
+/// starting up.
+fn forward(dir: &Path, launch: &Launch) -> io::Result<()> {
+    let mut payload = serde_json::to_vec(launch)?;
+    payload.push(b'\n');
+
+    let deadline = Instant::now() + CONNECT_TIMEOUT;
+    loop {
+        match transport::send(dir, &payload) {
+            Ok(()) => return Ok(()),
+            Err(e) if Instant::now() >= deadline => return Err(e),
+            Err(_) => std::thread::sleep(Duration::from_millis(100)),
+        }
+    }
+}
+
+/// Listening side of the running instance. Holds the instance lock for as
+/// long as it lives.
+pub struct InstanceListener {
+    _lock: File,
+    listener: transport::StdListener,
+}
+
+type OnLaunch = Arc<dyn Fn(Launch) + Send + Sync>;
+
+impl InstanceListener {
+    /// Accept forwarded launches in the background and pass each one to
+    /// `on_launch`, for as long as the returned handle lives.
+    pub fn spawn<F>(self, on_launch: F) -> RunningInstance
+    where
+        F: Fn(Launch) + Send + Sync + 'static,
+    {
+        let on_launch: OnLaunch = Arc::new(on_launch);
+        let stop = self.serve(on_launch.clone());
+        RunningInstance {
+            on_launch,
+            stop: Mutex::new(stop),
+        }
+    }
+
+    /// Serve until the returned sender is dropped, then release the lock.
+    fn serve(self, on_launch: OnLaunch) -> oneshot::Sender<()> {
+        let (stop, mut stopped) = oneshot::channel::<()>();
+        crate::runtime::spawn(async move {
+            let listener = match transport::into_tokio(self.listener) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::error!("Cannot accept launches from other instances: {e}");
+                    // Hold the lock anyway, so no second backend starts
+                    let _ = stopped.await;
+                    return;
+                }
+            };
+            // Keep the lock alive with the task
+            let _lock = self._lock;
+
+            loop {
+                let stream = tokio::select! {
+                    _ = &mut stopped => return,
+                    accepted = transport::accept(&listener) => accepted,
+                };
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        // E.g. out of file descriptors; wait rather than spin
+                        tracing::warn!("Failed to accept a launch forward: {e}");
+                        tokio::time::sleep(ACCEPT_RETRY).await;
+                        continue;
+                    }
+                };
+                // A client that never finishes its line must not stall
+                // later launches
+                match tokio::time::timeout(RECEIVE_TIMEOUT, receive(stream)).await {
+                    Ok(Ok(launch)) => on_launch(launch),
+                    Ok(Err(e)) => tracing::warn!("Ignoring malformed launch forward: {e}"),
+                    Err(_) => tracing::warn!("Ignoring launch forward that timed out"),
+                }
+            }
+        });
+        stop
+    }
+}
+
+/// The running instance's hold on its profile. Dropping it releases the
+/// lock and stops accepting launches.
+pub struct RunningInstance {
+    on_launch: OnLaunch,
+    stop: Mutex<oneshot::Sender<()>>,
+}
+
+impl RunningInstance {
+    /// Move the lock to `dir`, e.g. when the app switches profiles in
+    /// place, and keep passing launches to the same handler. Returns
+    /// `false`, holding on to the current dir, when another instance runs
+    /// `dir`.
+    pub fn move_to(&self, dir: &Path) -> io::Result<bool> {
+        let Some(listener) = claim(dir)? else {
+            return Ok(false);
+        };
+        let stop = listener.serve(self.on_launch.clone());
+        // Dropping the old sender ends the old task and frees its lock
+        *self.stop.lock().unwrap_or_else(PoisonError::into_inner) = stop;
+        Ok(true)
+    }
+}
+
+async fn receive<S>(stream: S) -> io::Result<Launch>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (read, mut write) = tokio::io::split(stream);
+    let mut line = String::new();
+    BufReader::new(read.take(MAX_MESSAGE_BYTES))
+        .read_line(&mut line)
+        .await?;
+    let launch = serde_json::from_str(&line)?;
+    write.write_all(b"ok\n").await?;
+    Ok(launch)
+}
+
+#[cfg(unix)]
+mod transport {
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::SOCKET_FILE;
+
+    pub type StdListener = UnixListener;
+    pub type Listener = tokio::net::UnixListener;
+
+    pub fn bind(dir: &Path) -> io::Result<UnixListener> {
+        let path = dir.join(SOCKET_FILE);
+        // We hold the lock, so any socket file left here is stale
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        UnixListener::bind(path)
+    }
+
+    pub fn send(dir: &Path, payload: &[u8]) -> io::Result<()> {
+        let mut stream = UnixStream::connect(dir.join(SOCKET_FILE))?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        stream.write_all(payload)?;
+        let mut ack = String::new();
+        BufReader::new(stream).read_line(&mut ack)?;
+        if ack.trim() == "ok" {
+            Ok(())
+        } else {
+            Err(io::Error::other("running instance did not acknowledge"))
+        }
+    }
+
+    pub fn into_tokio(listener: UnixListener) -> io::Result<Listener> {
+        listener.set_nonblocking(true)?;
+        tokio::net::UnixListener::from_std(listener)
+    }
+
+    pub async fn accept(listener: &Listener) -> io::Result<tokio::net::UnixStream> {
+        listener.accept().await.map(|(stream, _)| stream)
+    }
+}
+
+#[cfg(not(unix))]
+mod transport {
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::PORT_FILE;
+
+    pub type StdListener = TcpListener;
+    pub type Listener = tokio::net::TcpListener;
+
+    pub fn bind(dir: &Path) -> io::Result<TcpListener> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        std::fs::write(
+            dir.join(PORT_FILE),
+            listener.local_addr()?.port().to_string(),
+        )?;
+        Ok(listener)
+    }
+
+    pub fn send(dir: &Path, payload: &[u8]) -> io::Result<()> {
+        let port: u16 = std::fs::read_to_string(dir.join(PORT_FILE))?
+            .trim()
+            .parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad instance port"))?;
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        stream.write_all(payload)?;
+        let mut ack = String::new();
+        BufReader::new(stream).read_line(&mut ack)?;
+        if ack.trim() == "ok" {
+            Ok(())
+        } else {
+            Err(io::Error::other("running instance did not acknowledge"))
+        }
+    }
+
+    pub fn into_tokio(listener: TcpListener) -> io::Result<Listener> {
+        listener.set_nonblocking(true)?;
+        tokio::net::TcpListener::from_std(listener)
+    }
+
+    pub async fn accept(listener: &Listener) -> io::Result<tokio::net::TcpStream> {
+        listener.accept().await.map(|(stream, _)| stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn launch(arg: &str) -> Launch {
+        Launch {
+            args: vec![arg.to_string()],
+            cwd: None,
+        }
+    }
+
+    /// Acquire on a blocking thread, since forwarding waits for the answer.
+    async fn acquire_from_elsewhere(dir: &Path, launch: Launch) -> Instance {
+        let dir = dir.to_path_buf();
+        tokio::task::spawn_blocking(move || acquire(&dir, &launch).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn primary(dir: &Path) -> InstanceListener {
+        match acquire(dir, &Launch::default()).unwrap() {
+            Instance::Primary(listener) => listener,
+            _ => panic!("{} is taken", dir.display()),
+        }
+    }
+
+    #[tokio::test]
+    async fn each_profile_dir_has_its_own_instance() {
+        let root = TempDir::new().unwrap();
+        let _first = primary(&root.path().join("default")).spawn(|_| {});
+        let _second = primary(&root.path().join("work")).spawn(|_| {});
+    }
+
+    #[tokio::test]
+    async fn a_second_launch_is_forwarded_to_the_running_one() {
+        let dir = TempDir::new().unwrap();
+        let (sent, received) = mpsc::channel();
+        let _running = primary(dir.path()).spawn(move |launch| {
+            let _ = sent.send(launch);
+        });
+
+        let second = acquire_from_elsewhere(dir.path(), launch("hello")).await;
+        assert!(matches!(second, Instance::Secondary));
+        let forwarded = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(forwarded.args, ["hello"]);
+    }
+
+    #[tokio::test]
+    async fn moving_frees_the_old_dir_and_takes_the_new_one() {
+        let root = TempDir::new().unwrap();
+        let (old, new) = (root.path().join("default"), root.path().join("work"));
+        let (sent, received) = mpsc::channel();
+        let running = primary(&old).spawn(move |launch| {
+            let _ = sent.send(launch);
+        });
+
+        assert!(running.move_to(&new).unwrap());
+        let second = acquire_from_elsewhere(&new, launch("moved")).await;
+        assert!(matches!(second, Instance::Secondary));
+        assert_eq!(
+            received.recv_timeout(Duration::from_secs(5)).unwrap().args,
+            ["moved"]
+        );
+
+        // The old task lets go of its lock once it sees the stop
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while claim(&old).unwrap().is_none() {
+            assert!(Instant::now() < deadline, "old dir still locked");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn moving_onto_a_running_profile_is_refused() {
+        let root = TempDir::new().unwrap();
+        let (old, taken) = (root.path().join("default"), root.path().join("work"));
+        let running = primary(&old).spawn(|_| {});
+        let _other = primary(&taken).spawn(|_| {});
+
+        assert!(!running.move_to(&taken).unwrap());
+        assert!(claim(&old).unwrap().is_none());
+    }
+}
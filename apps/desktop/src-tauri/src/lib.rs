@@ -1,5 +1,44 @@
-// Tauri library module
-// This allows the app to be used as a library if needed
+//! The Coolaw DeskFlow desktop shell. `run` builds and runs the Tauri app;
+//! the binary and the mobile entry point only call it. Without the
+//! `desktop` feature the crate has no Tauri and only serves the headless
+//! `deskflow-shell`. The tray, updater, global shortcut, deep links and
+//! native dialogs are desktop only (`cfg(desktop)` from `tauri-build`).
 
-#[cfg(mobile)]
-pub use tauri::mobile_entry_point;
+#[cfg(feature = "desktop")]
+mod app;
+pub mod app_dirs;
+pub mod backend;
+pub mod backup;
+pub mod chat;
+#[cfg(feature = "desktop")]
+pub mod commands;
+#[cfg(feature = "desktop")]
+pub mod csp;
+pub mod deep_link;
+pub mod deskflow_client;
+#[cfg(feature = "desktop")]
+pub mod diagnostics;
+pub mod encryption;
+pub mod error;
+#[cfg(feature = "desktop")]
+mod events;
+pub mod history;
+pub mod instance;
+pub mod logs;
+pub mod notifications;
+pub mod profile;
+mod runtime;
+pub mod search;
+pub mod secrets;
+pub mod settings;
+pub mod state;
+pub mod transcript;
+#[cfg(all(feature = "desktop", desktop))]
+mod tray;
+#[cfg(all(feature = "desktop", desktop))]
+pub mod updater;
+#[cfg(feature = "desktop")]
+mod windows;
+
+#[cfg(feature = "desktop")]
+pub use app::run;
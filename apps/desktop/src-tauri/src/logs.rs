@@ -0,0 +1,731 @@
+//! Logs of the shell and of the backend it supervises, kept in one place
+//! that stays readable while the backend is down.
+//!
+//! The shell logs through `tracing`: events go to stderr and, as JSON
+//! lines, to daily files `shell.<date>.log` in the app log dir. Lines the
+//! backend prints are written next to them to `backend.<date>.log` in the
+//! same shape, with the level and logger taken from the backend's
+//! structured output when it has them. Both are rotated daily and kept for
+//! [`MAX_LOG_FILES`] days. [`LogStore`] reads them back, filtered.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::time::UtcTime;
+use tracing_subscriber::fmt::writer::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::backend::{BackendLogLine, LogStream};
+
+/// Overrides which shell events are logged, in `RUST_LOG` syntax, e.g.
+/// `debug` or `coolaw_deskflow_lib::backup=trace`.
+pub const LOG_FILTER_ENV: &str = "DESKFLOW_SHELL_LOG";
+
+/// Days of logs kept per source.
+pub const MAX_LOG_FILES: usize = 14;
+
+const DEFAULT_FILTER: &str = "info";
+const LOG_SUFFIX: &str = "log";
+const DEFAULT_TAIL: usize = 500;
+const MAX_TAIL: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    Shell,
+    Backend,
+}
+
+impl LogSource {
+    const ALL: [LogSource; 2] = [LogSource::Shell, LogSource::Backend];
+
+    fn prefix(self) -> &'static str {
+        match self {
+            LogSource::Shell => "shell",
+            LogSource::Backend => "backend",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Parse the level names of `tracing` and Python's `logging`.
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "TRACE" => LogLevel::Trace,
+            "DEBUG" => LogLevel::Debug,
+            "INFO" => LogLevel::Info,
+            "WARN" | "WARNING" => LogLevel::Warn,
+            "ERROR" | "CRITICAL" | "FATAL" | "EXCEPTION" => LogLevel::Error,
+            _ => return None,
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// RFC 3339, UTC.
+    pub timestamp: String,
+    pub timestamp_ms: u64,
+    pub level: LogLevel,
+    pub source: LogSource,
+    /// Rust module path for the shell, logger name for the backend.
+    pub target: String,
+    pub message: String,
+    /// Structured fields besides the above.
+    pub fields: Map<String, Value>,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:5} {:7} {}: {}",
+            self.timestamp,
+            self.level.as_str(),
+            self.source.prefix(),
+            self.target,
+            self.message
+        )?;
+        for (key, value) in &self.fields {
+            match value {
+                Value::String(text) => write!(f, " {key}={text}")?,
+                other => write!(f, " {key}={other}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogFilter {
+    /// Least severe level to include.
+    pub level: Option<LogLevel>,
+    pub source: Option<LogSource>,
+    /// Only entries whose target contains this, e.g. `backup` or
+    /// `deskflow.api`.
+    pub module: Option<String>,
+    /// Case-insensitive text to look for in the message and fields.
+    pub text: Option<String>,
+    /// Unix milliseconds, inclusive.
+    pub since_ms: Option<u64>,
+    /// Unix milliseconds, exclusive.
+    pub until_ms: Option<u64>,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        self.level.is_none_or(|level| entry.level >= level)
+            && self.source.is_none_or(|source| entry.source == source)
+            && self
+                .since_ms
+                .is_none_or(|since| entry.timestamp_ms >= since)
+            && self.until_ms.is_none_or(|until| entry.timestamp_ms < until)
+            && self
+                .module
+                .as_deref()
+                .is_none_or(|module| entry.target.contains(module))
+            && self.text.as_deref().is_none_or(|text| {
+                let text = text.to_lowercase();
+                entry.message.to_lowercase().contains(&text)
+                    || Value::Object(entry.fields.clone())
+                        .to_string()
+                        .to_lowercase()
+                        .contains(&text)
+            })
+    }
+
+    /// Whether a file with entries from `date` (`YYYY-MM-DD`, UTC) can hold
+    /// anything in range.
+    fn covers_day(&self, date: &str) -> bool {
+        let Some(start) = day_start_ms(date) else {
+            return true;
+        };
+        let end = start + 24 * 60 * 60 * 1000;
+        self.since_ms.is_none_or(|since| since < end)
+            && self.until_ms.is_none_or(|until| until > start)
+    }
+}
+
+/// A day (`YYYY-MM-DD`) and its files from each source.
+type DayFiles = (String, Vec<(LogSource, PathBuf)>);
+
+/// The log files in the app log dir. Cheap to clone.
+#[derive(Clone)]
+pub struct LogStore {
+    dir: PathBuf,
+    backend: Arc<RollingFileAppender>,
+}
+
+impl LogStore {
+    /// Start logging the shell's `tracing` events to `dir` and stderr.
+    /// Call once, before anything worth logging happens.
+    pub fn init(dir: &Path) -> io::Result<Self> {
+        let store = Self::open(dir)?;
+        let shell = appender(dir, LogSource::Shell)?;
+
+        let filter = EnvFilter::try_from_env(LOG_FILTER_ENV)
+            .unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+        let file = tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(false)
+            .with_timer(UtcTime::rfc_3339())
+            .with_writer(shell);
+        let stderr = tracing_subscriber::fmt::layer()
+            .with_timer(UtcTime::rfc_3339())
+            .with_writer(io::stderr);
+        // Fails only if another subscriber is already set, e.g. in tests
+        let _ = tracing_subscriber::registry()
+            .with(filter)
+            .with(file)
+            .with(stderr)
+            .try_init();
+
+        Ok(store)
+    }
+
+    /// The logs in `dir`, for reading them back or recording backend lines
+    /// without taking over the process's `tracing` output.
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let backend = appender(dir, LogSource::Backend)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            backend: Arc::new(backend),
+        })
+    }
+
+    /// Store a line the backend printed.
+    pub fn record_backend(&self, line: &BackendLogLine) {
+        let entry = backend_entry(line);
+        let mut record = json!({
+            "timestamp": entry.timestamp,
+            "level": entry.level,
+            "target": entry.target,
+            "message": entry.message,
+        });
+        if let Value::Object(record) = &mut record {
+            record.extend(entry.fields);
+        }
+        let _ = writeln!(self.backend.make_writer(), "{record}");
+    }
+
+    /// The newest `limit` entries matching `filter`, oldest first.
+    pub fn tail(&self, filter: &LogFilter, limit: Option<usize>) -> io::Result<Vec<LogEntry>> {
+        let limit = limit.unwrap_or(DEFAULT_TAIL).clamp(1, MAX_TAIL);
+        let mut days = self.days(filter)?;
+        days.reverse();
+
+        let mut tail = Vec::new();
+        for (_, files) in days {
+            let mut day = self.read_day(&files, filter)?;
+            day.append(&mut tail);
+            tail = day;
+            if tail.len() >= limit {
+                break;
+            }
+        }
+        let skip = tail.len().saturating_sub(limit);
+        Ok(tail.split_off(skip))
+    }
+
+    /// Write every entry matching `filter` to `dest` as text, oldest first.
+    /// Returns how many there were.
+    pub fn export(&self, filter: &LogFilter, dest: &Path) -> io::Result<usize> {
+        let mut out = io::BufWriter::new(fs::File::create(dest)?);
+        let mut count = 0;
+        for (_, files) in self.days(filter)? {
+            for entry in self.read_day(&files, filter)? {
+                writeln!(out, "{entry}")?;
+                count += 1;
+            }
+        }
+        out.flush()?;
+        Ok(count)
+    }
+
+    /// The files of both sources from the last `days` days, oldest first.
+    pub fn recent_files(&self, days: u64) -> io::Result<Vec<PathBuf>> {
+        let now = OffsetDateTime::now_utc().unix_timestamp().max(0) as u64 * 1000;
+        let filter = LogFilter {
+            since_ms: Some(now.saturating_sub(days * 24 * 60 * 60 * 1000)),
+            ..LogFilter::default()
+        };
+        Ok(self
+            .days(&filter)?
+            .into_iter()
+            .flat_map(|(_, files)| files.into_iter().map(|(_, path)| path))
+            .collect())
+    }
+
+    /// Log files that may hold entries for `filter`, grouped by day, oldest
+    /// day first.
+    fn days(&self, filter: &LogFilter) -> io::Result<Vec<DayFiles>> {
+        let mut days: Vec<DayFiles> = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some((source, date)) = LogSource::ALL.into_iter().find_map(|source| {
+                let date = name
+                    .strip_prefix(source.prefix())?
+                    .strip_prefix('.')?
+                    .strip_suffix(LOG_SUFFIX)?
+                    .strip_suffix('.')?;
+                Some((source, date.to_string()))
+            }) else {
+                continue;
+            };
+            if filter.source.is_some_and(|s| s != source) || !filter.covers_day(&date) {
+                continue;
+            }
+            match days.iter_mut().find(|(day, _)| *day == date) {
+                Some((_, files)) => files.push((source, path)),
+                None => days.push((date, vec![(source, path)])),
+            }
+        }
+        days.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(days)
+    }
+
+    fn read_day(
+        &self,
+        files: &[(LogSource, PathBuf)],
+        filter: &LogFilter,
+    ) -> io::Result<Vec<LogEntry>> {
+        let mut entries = Vec::new();
+        for (source, path) in files {
+            let file = match fs::File::open(path) {
+                Ok(file) => file,
+                // Rotated away meanwhile
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for line in BufReader::new(file).lines() {
+                if let Some(entry) = parse_entry(*source, &line?) {
+                    if filter.matches(&entry) {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+        // Stable, so lines logged in the same instant keep their order
+        entries.sort_by_key(|entry| entry.timestamp_ms);
+        Ok(entries)
+    }
+}
+
+fn appender(dir: &Path, source: LogSource) -> io::Result<RollingFileAppender> {
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(source.prefix())
+        .filename_suffix(LOG_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)
+        .map_err(io::Error::other)
+}
+
+/// Read one stored line back.
+fn parse_entry(source: LogSource, line: &str) -> Option<LogEntry> {
+    let Value::Object(mut record) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+    let mut take = |key: &str| match record.remove(key) {
+        Some(Value::String(text)) => Some(text),
+        _ => None,
+    };
+    let timestamp = take("timestamp")?;
+    let level = take("level").and_then(|l| LogLevel::parse(&l))?;
+    let target = take("target").unwrap_or_default();
+    let message = take("message").unwrap_or_default();
+    Some(LogEntry {
+        timestamp_ms: parse_ms(&timestamp)?,
+        timestamp,
+        level,
+        source,
+        target,
+        message,
+        fields: record,
+    })
+}
+
+/// Make an entry from a backend line. The backend logs JSON through
+/// structlog (`event`, `level`, `logger`); anything else, like uvicorn's
+/// startup lines or tracebacks, is kept as plain text.
+fn backend_entry(line: &BackendLogLine) -> LogEntry {
+    let timestamp = format_ms(line.timestamp_ms);
+    let fallback = |message: String| LogEntry {
+        timestamp: timestamp.clone(),
+        timestamp_ms: line.timestamp_ms,
+        level: guess_level(&line.line, line.stream),
+        source: LogSource::Backend,
+        target: match line.stream {
+            LogStream::Stdout => "stdout".to_string(),
+            LogStream::Stderr => "stderr".to_string(),
+        },
+        message,
+        fields: Map::new(),
+    };
+
+    let Ok(Value::Object(mut record)) = serde_json::from_str::<Value>(&line.line) else {
+        return fallback(line.line.clone());
+    };
+    let mut take = |key: &str| match record.remove(key) {
+        Some(Value::String(text)) => Some(text),
+        _ => None,
+    };
+    let Some(message) = take("event") else {
+        return fallback(line.line.clone());
+    };
+    let level = take("level").and_then(|l| LogLevel::parse(&l));
+    let logger = take("logger");
+    // The capture time is used throughout so both sources sort together
+    record.remove("timestamp");
+    let entry = fallback(message);
+    LogEntry {
+        level: level.unwrap_or_else(|| guess_level(&entry.message, line.stream)),
+        target: logger.unwrap_or(entry.target.clone()),
+        fields: record,
+        ..entry
+    }
+}
+
+fn guess_level(text: &str, stream: LogStream) -> LogLevel {
+    let head: String = text
+        .chars()
+        .take(40)
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if head.starts_with("TRACEBACK") {
+        return LogLevel::Error;
+    }
+    for word in head.split(|c: char| !c.is_ascii_alphabetic()) {
+        if let Some(level) = LogLevel::parse(word) {
+            return level;
+        }
+    }
+    match stream {
+        LogStream::Stdout => LogLevel::Info,
+        LogStream::Stderr => LogLevel::Warn,
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(ms) * 1_000_000)
+        .ok()
+        .and_then(|t| t.format(&Rfc3339).ok())
+        .unwrap_or_default()
+}
+
+fn parse_ms(timestamp: &str) -> Option<u64> {
+    let time = OffsetDateTime::parse(timestamp, &Rfc3339).ok()?;
+    u64::try_from(time.unix_timestamp_nanos() / 1_000_000).ok()
+}
+
+fn day_start_ms(date: &str) -> Option<u64> {
+    parse_ms(&format!("{date}T00:00:00Z"))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn ms(timestamp: &str) -> u64 {
+        parse_ms(timestamp).unwrap()
+    }
+
+    fn entry(level: LogLevel, target: &str, timestamp: &str) -> LogEntry {
+        LogEntry {
+            timestamp: timestamp.to_string(),
+            timestamp_ms: ms(timestamp),
+            level,
+            source: LogSource::Shell,
+            target: target.to_string(),
+            message: "something happened".to_string(),
+            fields: Map::new(),
+        }
+    }
+
+    fn backend_line(stream: LogStream, line: &str) -> BackendLogLine {
+        BackendLogLine {
+            stream,
+            line: line.to_string(),
+            timestamp_ms: ms("2026-03-02T10:00:00Z"),
+        }
+    }
+
+    /// A day file as the appenders write it, one entry per `(time, message)`.
+    fn write_day(dir: &Path, source: LogSource, date: &str, entries: &[(&str, &str)]) {
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|(time, message)| {
+                json!({
+                    "timestamp": format!("{date}T{time}Z"),
+                    "level": "INFO",
+                    "target": "test",
+                    "message": message,
+                })
+                .to_string()
+            })
+            .collect();
+        fs::write(
+            dir.join(format!("{}.{date}.{LOG_SUFFIX}", source.prefix())),
+            lines.join("\n"),
+        )
+        .unwrap();
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.message.as_str()).collect()
+    }
+
+    #[test]
+    fn level_is_a_threshold() {
+        let filter = LogFilter {
+            level: Some(LogLevel::Warn),
+            ..LogFilter::default()
+        };
+        let at = "2026-03-02T10:00:00Z";
+        assert!(filter.matches(&entry(LogLevel::Warn, "a", at)));
+        assert!(filter.matches(&entry(LogLevel::Error, "a", at)));
+        assert!(!filter.matches(&entry(LogLevel::Info, "a", at)));
+        assert!(!filter.matches(&entry(LogLevel::Trace, "a", at)));
+        assert!(LogFilter::default().matches(&entry(LogLevel::Trace, "a", at)));
+    }
+
+    #[test]
+    fn module_matches_part_of_the_target() {
+        let filter = |module: &str| LogFilter {
+            module: Some(module.to_string()),
+            ..LogFilter::default()
+        };
+        let at = "2026-03-02T10:00:00Z";
+        let shell = entry(LogLevel::Info, "coolaw_deskflow_lib::backup", at);
+        let backend = entry(LogLevel::Info, "deskflow.api.chat", at);
+
+        assert!(filter("coolaw_deskflow_lib").matches(&shell));
+        assert!(filter("backup").matches(&shell));
+        assert!(filter("deskflow.api").matches(&backend));
+        assert!(!filter("deskflow.api").matches(&shell));
+        assert!(!filter("deskflow.memory").matches(&backend));
+    }
+
+    #[test]
+    fn since_is_inclusive_and_until_exclusive() {
+        let filter = LogFilter {
+            since_ms: Some(ms("2026-03-02T10:00:00Z")),
+            until_ms: Some(ms("2026-03-02T11:00:00Z")),
+            ..LogFilter::default()
+        };
+        let at = |timestamp| entry(LogLevel::Info, "a", timestamp);
+        assert!(filter.matches(&at("2026-03-02T10:00:00Z")));
+        assert!(filter.matches(&at("2026-03-02T10:59:59.999Z")));
+        assert!(!filter.matches(&at("2026-03-02T09:59:59.999Z")));
+        assert!(!filter.matches(&at("2026-03-02T11:00:00Z")));
+    }
+
+    #[test]
+    fn only_days_that_overlap_the_range_are_read() {
+        let filter = LogFilter {
+            since_ms: Some(ms("2026-03-02T23:00:00Z")),
+            until_ms: Some(ms("2026-03-04T00:00:00Z")),
+            ..LogFilter::default()
+        };
+        assert!(!filter.covers_day("2026-03-01"));
+        assert!(filter.covers_day("2026-03-02"));
+        assert!(filter.covers_day("2026-03-03"));
+        assert!(!filter.covers_day("2026-03-04"));
+        // A file name we cannot date is read to be safe
+        assert!(filter.covers_day("yesterday"));
+        assert!(LogFilter::default().covers_day("2020-01-01"));
+    }
+
+    #[test]
+    fn stored_lines_parse_back_with_their_extra_fields() {
+        let line = json!({
+            "timestamp": "2026-03-02T10:00:00.250Z",
+            "level": "WARN",
+            "target": "coolaw_deskflow_lib::backup",
+            "message": "Backup slow",
+            "seconds": 12,
+        })
+        .to_string();
+        let entry = parse_entry(LogSource::Shell, &line).unwrap();
+        assert_eq!(entry.timestamp_ms, ms("2026-03-02T10:00:00.250Z"));
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.target, "coolaw_deskflow_lib::backup");
+        assert_eq!(entry.message, "Backup slow");
+        assert_eq!(entry.fields.get("seconds"), Some(&json!(12)));
+
+        assert!(parse_entry(LogSource::Shell, "not json").is_none());
+        assert!(parse_entry(LogSource::Shell, r#"{"level": "INFO"}"#).is_none());
+        assert!(parse_entry(
+            LogSource::Shell,
+            r#"{"timestamp": "2026-03-02T10:00:00Z", "level": "LOUD"}"#
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn uvicorn_prefixes_set_the_level_of_plain_lines() {
+        let entry = backend_entry(&backend_line(
+            LogStream::Stderr,
+            "INFO:     Uvicorn running on http://127.0.0.1:8420",
+        ));
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.target, "stderr");
+        assert_eq!(
+            entry.message,
+            "INFO:     Uvicorn running on http://127.0.0.1:8420"
+        );
+        assert_eq!(entry.timestamp_ms, ms("2026-03-02T10:00:00Z"));
+
+        let level = |stream, line| backend_entry(&backend_line(stream, line)).level;
+        assert_eq!(
+            level(LogStream::Stderr, "WARNING:  StatReload detected changes"),
+            LogLevel::Warn
+        );
+        assert_eq!(
+            level(LogStream::Stderr, "ERROR:    Exception in ASGI application"),
+            LogLevel::Error
+        );
+        assert_eq!(
+            level(LogStream::Stderr, "Traceback (most recent call last):"),
+            LogLevel::Error
+        );
+        // Without a level, stderr is a warning and stdout information
+        assert_eq!(
+            level(LogStream::Stderr, "  File \"app.py\", line 3"),
+            LogLevel::Warn
+        );
+        assert_eq!(level(LogStream::Stdout, "Loading skills"), LogLevel::Info);
+    }
+
+    #[test]
+    fn structlog_lines_keep_their_logger_level_and_fields() {
+        let entry = backend_entry(&backend_line(
+            LogStream::Stdout,
+            r#"{"event": "chat started", "level": "warning", "logger": "deskflow.api.chat", "timestamp": "2020-01-01T00:00:00Z", "request_id": "r1"}"#,
+        ));
+        assert_eq!(entry.message, "chat started");
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.target, "deskflow.api.chat");
+        // The capture time wins over the backend's own clock
+        assert_eq!(entry.timestamp_ms, ms("2026-03-02T10:00:00Z"));
+        assert_eq!(entry.fields.get("request_id"), Some(&json!("r1")));
+        assert!(!entry.fields.contains_key("timestamp"));
+
+        // JSON that is not a structlog event stays a plain line
+        let entry = backend_entry(&backend_line(LogStream::Stdout, r#"{"status": "ok"}"#));
+        assert_eq!(entry.message, r#"{"status": "ok"}"#);
+        assert_eq!(entry.target, "stdout");
+    }
+
+    #[test]
+    fn tail_takes_the_newest_entries_across_days() {
+        let dir = TempDir::new().unwrap();
+        write_day(
+            dir.path(),
+            LogSource::Shell,
+            "2026-03-01",
+            &[("09:00:00", "one"), ("10:00:00", "two")],
+        );
+        write_day(
+            dir.path(),
+            LogSource::Backend,
+            "2026-03-02",
+            &[("08:00:00", "four")],
+        );
+        write_day(
+            dir.path(),
+            LogSource::Shell,
+            "2026-03-02",
+            &[("07:00:00", "three"), ("09:00:00", "five")],
+        );
+        write_day(
+            dir.path(),
+            LogSource::Shell,
+            "2026-03-03",
+            &[("01:00:00", "six")],
+        );
+        let store = LogStore::open(dir.path()).unwrap();
+        let all = LogFilter::default();
+
+        assert_eq!(
+            messages(&store.tail(&all, Some(4)).unwrap()),
+            ["three", "four", "five", "six"]
+        );
+        assert_eq!(messages(&store.tail(&all, Some(1)).unwrap()), ["six"]);
+        assert_eq!(
+            messages(&store.tail(&all, None).unwrap()),
+            ["one", "two", "three", "four", "five", "six"]
+        );
+
+        let shell_before_the_3rd = LogFilter {
+            source: Some(LogSource::Shell),
+            until_ms: Some(ms("2026-03-03T00:00:00Z")),
+            ..LogFilter::default()
+        };
+        assert_eq!(
+            messages(&store.tail(&shell_before_the_3rd, Some(3)).unwrap()),
+            ["two", "three", "five"]
+        );
+    }
+
+    #[test]
+    fn recorded_backend_lines_read_back() {
+        let dir = TempDir::new().unwrap();
+        let store = LogStore::open(dir.path()).unwrap();
+        let now = OffsetDateTime::now_utc().unix_timestamp() as u64 * 1000;
+        store.record_backend(&BackendLogLine {
+            stream: LogStream::Stdout,
+            line: r#"{"event": "ready", "level": "info", "logger": "deskflow", "port": 8420}"#
+                .to_string(),
+            timestamp_ms: now,
+        });
+
+        let filter = LogFilter {
+            source: Some(LogSource::Backend),
+            ..LogFilter::default()
+        };
+        let tail = store.tail(&filter, None).unwrap();
+        assert_eq!(messages(&tail), ["ready"]);
+        assert_eq!(tail[0].timestamp_ms, now);
+        assert_eq!(tail[0].target, "deskflow");
+        assert_eq!(tail[0].fields.get("port"), Some(&json!(8420)));
+    }
+}
@@ -0,0 +1,409 @@
+//! Native notifications for agent events, so long runs, IM messages and
+//! proactive suggestions are not missed while the window is in the
+//! background.
+//!
+//! The shell follows the backend's `/api/monitor/ws/activity` feed and picks
+//! out the activities the backend marks as agent events (`details.event`,
+//! see `observability/agent_events.py`). Whether one is shown is up to the
+//! shell settings: notifications can be turned off, muted per
+//! [`NotificationCategory`], or held back during quiet hours. Showing them
+//! needs the `desktop` feature; the headless shell only reads the feed.
+
+#[cfg(feature = "desktop")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "desktop")]
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use chrono::Timelike;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::deep_link::SCHEME;
+use crate::settings::ShellSettings;
+
+const EVENT_CAPACITY: usize = 64;
+const RECONNECT_INITIAL: Duration = Duration::from_millis(500);
+const RECONNECT_MAX: Duration = Duration::from_secs(10);
+/// A connection that stayed up this long resets the reconnect backoff.
+const STABLE_AFTER: Duration = Duration::from_secs(5);
+
+/// How long a notice stays up and its buttons are listened to.
+#[cfg(feature = "desktop")]
+const NOTICE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Notices listened to at once. Past this, new ones are shown without
+/// waiting for an answer.
+#[cfg(feature = "desktop")]
+const MAX_LISTENING: usize = 4;
+
+#[cfg(feature = "desktop")]
+const APP_NAME: &str = "DeskFlow";
+#[cfg(feature = "desktop")]
+const OPEN_ACTION: &str = "open";
+#[cfg(feature = "desktop")]
+const MUTE_ACTION: &str = "mute";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    TaskCompleted,
+    Error,
+    ChannelMessage,
+    Suggestion,
+}
+
+impl NotificationCategory {
+    fn from_event(event: &str) -> Option<Self> {
+        match event {
+            "task_completed" => Some(Self::TaskCompleted),
+            "task_failed" => Some(Self::Error),
+            "channel_message" => Some(Self::ChannelMessage),
+            "proactive_suggestion" => Some(Self::Suggestion),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "desktop")]
+    fn mute_label(self) -> &'static str {
+        match self {
+            Self::TaskCompleted => "Mute finished tasks",
+            Self::Error => "Mute errors",
+            Self::ChannelMessage => "Mute IM messages",
+            Self::Suggestion => "Mute suggestions",
+        }
+    }
+}
+
+/// An agent event worth a notification.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentNotice {
+    pub category: NotificationCategory,
+    pub summary: String,
+    pub conversation_id: Option<String>,
+    /// The IM channel a message came in on.
+    pub channel_id: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// What the user did with a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoticeAction {
+    Open,
+    Mute,
+}
+
+impl AgentNotice {
+    /// Parse one message of the activity feed. Anything that is not an agent
+    /// event gives `None`.
+    pub fn from_feed(text: &str) -> Option<Self> {
+        let message: Value = serde_json::from_str(text).ok()?;
+        if message["type"] != "new_activity" {
+            return None;
+        }
+        let activity = &message["activity"];
+        let details = &activity["details"];
+        let text = |value: &Value| value.as_str().filter(|s| !s.is_empty()).map(str::to_string);
+
+        Some(Self {
+            category: NotificationCategory::from_event(details["event"].as_str()?)?,
+            summary: activity["summary"].as_str().unwrap_or_default().to_string(),
+            conversation_id: text(&activity["conversation_id"]),
+            channel_id: text(&details["channel_id"]),
+            duration_ms: activity["duration_ms"].as_f64().unwrap_or_default() as u64,
+        })
+    }
+
+    pub fn title(&self) -> String {
+        match self.category {
+            NotificationCategory::TaskCompleted => "Task finished".to_string(),
+            NotificationCategory::Error => "Task failed".to_string(),
+            NotificationCategory::ChannelMessage => match &self.channel_id {
+                Some(channel) => format!("New message on {channel}"),
+                None => "New IM message".to_string(),
+            },
+            NotificationCategory::Suggestion => "DeskFlow has a suggestion".to_string(),
+        }
+    }
+
+    /// The `deskflow://` link that opens what the notice is about.
+    pub fn link(&self) -> String {
+        match (&self.conversation_id, self.category) {
+            (Some(id), _) => format!("{SCHEME}://conversation/{id}"),
+            (None, NotificationCategory::ChannelMessage) => format!("{SCHEME}://imchannels"),
+            (None, _) => format!("{SCHEME}://chat"),
+        }
+    }
+
+    #[cfg(feature = "desktop")]
+    fn open_label(&self) -> &'static str {
+        match (&self.conversation_id, self.category) {
+            (Some(_), _) => "Open conversation",
+            (None, NotificationCategory::ChannelMessage) => "Open IM channels",
+            (None, _) => "Open chat",
+        }
+    }
+
+    /// Show the notice and wait until one of its buttons is used, it goes
+    /// away or [`NOTICE_TIMEOUT`] passes. Blocks, so call it from a
+    /// blocking task.
+    ///
+    /// The platform is listened to on a thread of its own, which lives
+    /// until the notice is closed; at most [`MAX_LISTENING`] do at once.
+    /// `app_id` is the bundle identifier, which Windows needs to attribute
+    /// the toast.
+    #[cfg(feature = "desktop")]
+    pub fn show(&self, app_id: &str) -> Result<Option<NoticeAction>, String> {
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .appname(APP_NAME)
+            .summary(&self.title())
+            .body(&self.summary)
+            .timeout(NOTICE_TIMEOUT)
+            .action(OPEN_ACTION, self.open_label())
+            .action(MUTE_ACTION, self.category.mute_label());
+        #[cfg(target_os = "windows")]
+        notification.app_id(app_id);
+        #[cfg(not(target_os = "windows"))]
+        let _ = app_id;
+
+        let Some(listening) = Listening::start() else {
+            notification.show().map_err(|e| e.to_string())?;
+            return Ok(None);
+        };
+        let (open, mute) = (self.open_label(), self.category.mute_label());
+        let (answer, answered) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _listening = listening;
+            let handle = match notification.show() {
+                Ok(handle) => handle,
+                Err(e) => {
+                    let _ = answer.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            // macOS reports buttons by label, the others by identifier
+            handle.wait_for_action(|action| {
+                let chosen = if action == OPEN_ACTION || action == open {
+                    Some(NoticeAction::Open)
+                } else if action == MUTE_ACTION || action == mute {
+                    Some(NoticeAction::Mute)
+                } else {
+                    None
+                };
+                let _ = answer.send(Ok(chosen));
+            });
+        });
+        // Past the timeout the notice may still be on screen, but nobody
+        // is waiting for it any more
+        answered.recv_timeout(NOTICE_TIMEOUT).unwrap_or(Ok(None))
+    }
+}
+
+/// A thread listening to a shown notice, counted against [`MAX_LISTENING`].
+#[cfg(feature = "desktop")]
+struct Listening;
+
+#[cfg(feature = "desktop")]
+static LISTENING: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "desktop")]
+impl Listening {
+    fn start() -> Option<Self> {
+        LISTENING
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < MAX_LISTENING).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self)
+    }
+}
+
+#[cfg(feature = "desktop")]
+impl Drop for Listening {
+    fn drop(&mut self) {
+        LISTENING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Whether a notice of `category` may be shown now.
+pub fn should_notify(settings: &ShellSettings, category: NotificationCategory) -> bool {
+    let now = chrono::Local::now();
+    settings.notifications_enabled
+        && !settings.muted_notifications.contains(&category)
+        && !in_quiet_hours(
+            &settings.quiet_hours_start,
+            &settings.quiet_hours_end,
+            now.hour() * 60 + now.minute(),
+        )
+}
+
+/// Whether `minute` (of the day) falls between `start` and `end`, both
+/// `HH:MM`. The range may wrap past midnight; an empty or unreadable bound
+/// means no quiet hours.
+pub fn in_quiet_hours(start: &str, end: &str, minute: u32) -> bool {
+    let (Some(start), Some(end)) = (parse_time(start), parse_time(end)) else {
+        return false;
+    };
+    if start <= end {
+        (start..end).contains(&minute)
+    } else {
+        minute >= start || minute < end
+    }
+}
+
+fn parse_time(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Agent events from the backend's activity feed, reconnecting whenever
+/// the backend goes away.
+#[derive(Clone)]
+pub struct ActivityFeed {
+    notices: broadcast::Sender<AgentNotice>,
+}
+
+impl ActivityFeed {
+    pub fn spawn(url: impl Into<String>) -> Self {
+        let (notices, _) = broadcast::channel(EVENT_CAPACITY);
+        crate::runtime::spawn(follow(url.into(), notices.clone()));
+        Self { notices }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentNotice> {
+        self.notices.subscribe()
+    }
+}
+
+async fn follow(url: String, notices: broadcast::Sender<AgentNotice>) {
+    let mut backoff = RECONNECT_INITIAL;
+
+    loop {
+        if let Ok((socket, _)) = tokio_tungstenite::connect_async(url.as_str()).await {
+            let connected_at = Instant::now();
+            // The feed is one-way; the backend pings to keep it open
+            let (_sink, mut stream) = socket.split();
+            while let Some(Ok(frame)) = stream.next().await {
+                match frame {
+                    Message::Text(text) => {
+                        if let Some(notice) = AgentNotice::from_feed(&text) {
+                            let _ = notices.send(notice);
+                        }
+                    }
+                    Message::Close(_) => break,
+                    _ => {}
+                }
+            }
+            if connected_at.elapsed() >= STABLE_AFTER {
+                backoff = RECONNECT_INITIAL;
+            }
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(RECONNECT_MAX);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(event: &str, conversation_id: Option<&str>) -> String {
+        serde_json::json!({
+            "type": "new_activity",
+            "activity": {
+                "id": "a1",
+                "type": "system",
+                "status": "success",
+                "duration_ms": 1234.5,
+                "summary": "Report written",
+                "details": { "event": event, "channel_id": "telegram" },
+                "conversation_id": conversation_id,
+            },
+            "timestamp": "2026-01-01T12:00:00",
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn parse_time_reads_hours_and_minutes() {
+        assert_eq!(parse_time("00:00"), Some(0));
+        assert_eq!(parse_time(" 7:05 "), Some(7 * 60 + 5));
+        assert_eq!(parse_time("23:59"), Some(23 * 60 + 59));
+    }
+
+    #[test]
+    fn parse_time_rejects_out_of_range_and_garbage() {
+        for time in ["", "24:00", "12:60", "12", "ab:cd", "-1:00", "12:30:00"] {
+            assert_eq!(parse_time(time), None, "{time:?}");
+        }
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        assert!(!in_quiet_hours("09:00", "17:00", 8 * 60 + 59));
+        assert!(in_quiet_hours("09:00", "17:00", 9 * 60));
+        assert!(in_quiet_hours("09:00", "17:00", 16 * 60 + 59));
+        // The end is exclusive
+        assert!(!in_quiet_hours("09:00", "17:00", 17 * 60));
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        assert!(in_quiet_hours("22:00", "07:00", 22 * 60));
+        assert!(in_quiet_hours("22:00", "07:00", 23 * 60 + 59));
+        assert!(in_quiet_hours("22:00", "07:00", 0));
+        assert!(in_quiet_hours("22:00", "07:00", 6 * 60 + 59));
+        assert!(!in_quiet_hours("22:00", "07:00", 7 * 60));
+        assert!(!in_quiet_hours("22:00", "07:00", 12 * 60));
+    }
+
+    #[test]
+    fn quiet_hours_need_both_bounds() {
+        assert!(!in_quiet_hours("", "07:00", 3 * 60));
+        assert!(!in_quiet_hours("22:00", "", 23 * 60));
+        assert!(!in_quiet_hours("late", "early", 0));
+        // Equal bounds make an empty range
+        assert!(!in_quiet_hours("08:00", "08:00", 8 * 60));
+    }
+
+    #[test]
+    fn from_feed_reads_agent_events() {
+        let notice = AgentNotice::from_feed(&feed("task_completed", Some("c1"))).unwrap();
+        assert_eq!(notice.category, NotificationCategory::TaskCompleted);
+        assert_eq!(notice.summary, "Report written");
+        assert_eq!(notice.conversation_id.as_deref(), Some("c1"));
+        assert_eq!(notice.channel_id.as_deref(), Some("telegram"));
+        assert_eq!(notice.duration_ms, 1234);
+        assert_eq!(notice.link(), format!("{SCHEME}://conversation/c1"));
+
+        let notice = AgentNotice::from_feed(&feed("task_failed", None)).unwrap();
+        assert_eq!(notice.category, NotificationCategory::Error);
+        assert_eq!(notice.link(), format!("{SCHEME}://chat"));
+
+        let notice = AgentNotice::from_feed(&feed("channel_message", Some(""))).unwrap();
+        assert_eq!(notice.category, NotificationCategory::ChannelMessage);
+        assert_eq!(notice.conversation_id, None);
+        assert_eq!(notice.title(), "New message on telegram");
+        assert_eq!(notice.link(), format!("{SCHEME}://imchannels"));
+
+        let notice = AgentNotice::from_feed(&feed("proactive_suggestion", None)).unwrap();
+        assert_eq!(notice.category, NotificationCategory::Suggestion);
+    }
+
+    #[test]
+    fn from_feed_skips_everything_else() {
+        assert!(AgentNotice::from_feed(&feed("llm_call", None)).is_none());
+        assert!(AgentNotice::from_feed("not json").is_none());
+        assert!(AgentNotice::from_feed(r#"{"type": "pong"}"#).is_none());
+        let without_event = serde_json::json!({
+            "type": "new_activity",
+            "activity": { "summary": "Chat", "details": {} },
+        });
+        assert!(AgentNotice::from_feed(&without_event.to_string()).is_none());
+    }
+}
@@ -0,0 +1,468 @@
+//! Profiles: separate agents, each with its own database, identity, skills
+//! and backend configuration.
+//!
+//! A profile's files live in per-user directories the shell resolves for the
+//! platform, e.g. on Linux:
+//!
+//! - data: `~/.local/share/com.coolaw.deskflow/<profile>`
+//! - config: `~/.config/com.coolaw.deskflow/<profile>`
+//! - cache: `~/.cache/com.coolaw.deskflow/<profile>`
+//!
+//! The list of profiles and the active one are kept in `profiles.json` in
+//! the config root. The backend learns about its directories through the
+//! environment (see [`ProfileDirs::env`]).
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_dirs::DIAGNOSTICS_DIR;
+use crate::history::CONVERSATIONS_DB;
+use crate::runtime::now_secs;
+
+pub const PROFILES_FILE: &str = "profiles.json";
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Overrides the active profile for one launch without persisting it.
+pub const PROFILE_ENV: &str = "DESKFLOW_PROFILE";
+
+pub const DATA_DIR_ENV: &str = "DESKFLOW_DATA_DIR";
+pub const CONFIG_DIR_ENV: &str = "DESKFLOW_CONFIG_DIR";
+pub const CACHE_DIR_ENV: &str = "DESKFLOW_CACHE_DIR";
+pub const DB_PATH_ENV: &str = "DESKFLOW_DB_PATH";
+
+const MAX_NAME_CHARS: usize = 64;
+
+// Directories the shell itself keeps next to the profiles in the data root.
+// On Linux the log dir is `logs` there too, with the diagnostics below it
+const RESERVED_NAMES: &[&str] = &["secrets", "backups", "logs", DIAGNOSTICS_DIR];
+
+/// Per-user roots that hold one subdirectory per profile.
+#[derive(Debug, Clone)]
+pub struct ProfileRoots {
+    pub data: PathBuf,
+    pub config: PathBuf,
+    pub cache: PathBuf,
+}
+
+/// Directories of one profile.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileDirs {
+    pub name: String,
+    pub data: PathBuf,
+    pub config: PathBuf,
+    pub cache: PathBuf,
+}
+
+impl ProfileDirs {
+    fn new(roots: &ProfileRoots, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            data: roots.data.join(name),
+            config: roots.config.join(name),
+            cache: roots.cache.join(name),
+        }
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        self.data.join("db").join("deskflow.db")
+    }
+
+    /// The chat history the UI saves, next to the backend's `data/` dir.
+    pub fn conversations_db_path(&self) -> PathBuf {
+        self.data.join(CONVERSATIONS_DB)
+    }
+
+    /// Where automatic backups of this profile go, outside its own
+    /// directories so they are not archived again.
+    pub fn backups(&self) -> PathBuf {
+        self.data.with_file_name("backups").join(&self.name)
+    }
+
+    pub fn create_all(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.data)?;
+        fs::create_dir_all(&self.config)?;
+        fs::create_dir_all(&self.cache)?;
+        if let Some(db_dir) = self.db_path().parent() {
+            fs::create_dir_all(db_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Environment variables that point the backend at this profile.
+    pub fn env(&self) -> Vec<(String, String)> {
+        let path = |p: &Path| p.to_string_lossy().into_owned();
+        vec![
+            (DATA_DIR_ENV.to_string(), path(&self.data)),
+            (CONFIG_DIR_ENV.to_string(), path(&self.config)),
+            (CACHE_DIR_ENV.to_string(), path(&self.cache)),
+            (DB_PATH_ENV.to_string(), path(&self.db_path())),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    /// Seconds since the Unix epoch.
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+/// A profile as listed to the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileEntry {
+    #[serde(flatten)]
+    pub info: ProfileInfo,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProfileIndex {
+    active: String,
+    profiles: Vec<ProfileInfo>,
+}
+
+impl Default for ProfileIndex {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            profiles: vec![ProfileInfo {
+                name: DEFAULT_PROFILE.to_string(),
+                created_at: now_secs(),
+            }],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    InvalidName(String),
+    AlreadyExists(String),
+    NotFound(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::InvalidName(name) => write!(
+                f,
+                "Invalid profile name \"{name}\": use up to {MAX_NAME_CHARS} lowercase letters, digits, '-' or '_'"
+            ),
+            ProfileError::AlreadyExists(name) => write!(f, "Profile \"{name}\" already exists"),
+            ProfileError::NotFound(name) => write!(f, "No profile named \"{name}\""),
+            ProfileError::Io(e) => write!(f, "Profile storage error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<io::Error> for ProfileError {
+    fn from(err: io::Error) -> Self {
+        ProfileError::Io(err)
+    }
+}
+
+struct Inner {
+    roots: ProfileRoots,
+    index: ProfileIndex,
+    /// Profile in use by this process; differs from `index.active` only
+    /// when overridden through [`PROFILE_ENV`].
+    current: String,
+}
+
+/// Known profiles and the one the backend runs against. Cheap to clone.
+#[derive(Clone)]
+pub struct ProfileStore {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ProfileStore {
+    /// Read `profiles.json` from the config root, creating the default
+    /// profile on first run. [`PROFILE_ENV`] picks a different profile for
+    /// this launch; it is created if needed but not made the default.
+    pub fn load(roots: ProfileRoots) -> Result<Self, ProfileError> {
+        let mut index: ProfileIndex = fs::read(roots.config.join(PROFILES_FILE))
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .unwrap_or_default();
+
+        let mut dirty = false;
+        if !index.profiles.iter().any(|p| p.name == index.active) {
+            index.active = DEFAULT_PROFILE.to_string();
+            if !index.profiles.iter().any(|p| p.name == DEFAULT_PROFILE) {
+                index.profiles.insert(
+                    0,
+                    ProfileInfo {
+                        name: DEFAULT_PROFILE.to_string(),
+                        created_at: now_secs(),
+                    },
+                );
+            }
+            dirty = true;
+        }
+
+        let mut current = index.active.clone();
+        if let Ok(name) = std::env::var(PROFILE_ENV) {
+            let name = validate_name(&name)?;
+            if !index.profiles.iter().any(|p| p.name == name) {
+                index.profiles.push(ProfileInfo {
+                    name: name.clone(),
+                    created_at: now_secs(),
+                });
+                dirty = true;
+            }
+            current = name;
+        }
+
+        let inner = Inner {
+            roots,
+            index,
+            current,
+        };
+        if dirty {
+            inner.save()?;
+        }
+        inner.dirs(&inner.current).create_all()?;
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    pub fn current(&self) -> ProfileDirs {
+        let inner = self.inner.lock().unwrap();
+        inner.dirs(&inner.current)
+    }
+
+    pub fn list(&self) -> Vec<ProfileEntry> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .index
+            .profiles
+            .iter()
+            .map(|info| ProfileEntry {
+                info: info.clone(),
+                active: info.name == inner.current,
+            })
+            .collect()
+    }
+
+    pub fn create(&self, name: &str) -> Result<ProfileInfo, ProfileError> {
+        let name = validate_name(name)?;
+        let mut inner = self.inner.lock().unwrap();
+        if inner.index.profiles.iter().any(|p| p.name == name) {
+            return Err(ProfileError::AlreadyExists(name));
+        }
+
+        inner.dirs(&name).create_all()?;
+        let info = ProfileInfo {
+            name,
+            created_at: now_secs(),
+        };
+        inner.index.profiles.push(info.clone());
+        inner.save()?;
+        Ok(info)
+    }
+
+    pub fn dirs(&self, name: &str) -> Result<ProfileDirs, ProfileError> {
+        let inner = self.inner.lock().unwrap();
+        if !inner.index.profiles.iter().any(|p| p.name == name) {
+            return Err(ProfileError::NotFound(name.to_string()));
+        }
+        Ok(inner.dirs(name))
+    }
+
+    /// Make `name` the active profile, for this process and future launches.
+    pub fn switch(&self, name: &str) -> Result<ProfileDirs, ProfileError> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.index.profiles.iter().any(|p| p.name == name) {
+            return Err(ProfileError::NotFound(name.to_string()));
+        }
+
+        let dirs = inner.dirs(name);
+        dirs.create_all()?;
+        inner.index.active = name.to_string();
+        inner.current = name.to_string();
+        inner.save()?;
+        Ok(dirs)
+    }
+}
+
+impl Inner {
+    fn dirs(&self, name: &str) -> ProfileDirs {
+        ProfileDirs::new(&self.roots, name)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.roots.config)?;
+        let raw = serde_json::to_vec_pretty(&self.index)?;
+        let path = self.roots.config.join(PROFILES_FILE);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, raw)?;
+        fs::rename(tmp, path)
+    }
+}
+
+fn validate_name(name: &str) -> Result<String, ProfileError> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_CHARS
+        && !RESERVED_NAMES.contains(&name)
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(ProfileError::InvalidName(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn roots(root: &Path) -> ProfileRoots {
+        ProfileRoots {
+            data: root.join("data"),
+            config: root.join("config"),
+            cache: root.join("cache"),
+        }
+    }
+
+    fn names(profiles: &ProfileStore) -> Vec<String> {
+        profiles.list().into_iter().map(|p| p.info.name).collect()
+    }
+
+    #[test]
+    fn names_are_short_lowercase_words() {
+        for name in [
+            "work",
+            "side-project",
+            "agent_2",
+            &"a".repeat(MAX_NAME_CHARS),
+        ] {
+            assert_eq!(validate_name(name).unwrap(), name);
+        }
+        assert_eq!(validate_name("  work ").unwrap(), "work");
+        for name in [
+            "",
+            "   ",
+            "Work",
+            "my profile",
+            "../work",
+            "a/b",
+            ".hidden",
+            "数据",
+            &"a".repeat(MAX_NAME_CHARS + 1),
+        ] {
+            assert!(
+                matches!(validate_name(name), Err(ProfileError::InvalidName(_))),
+                "{name:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn names_of_the_shells_own_dirs_are_reserved() {
+        for name in ["secrets", "backups", "logs", "diagnostics"] {
+            assert!(
+                matches!(validate_name(name), Err(ProfileError::InvalidName(_))),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn first_load_creates_the_default_profile() {
+        let root = TempDir::new().unwrap();
+        let profiles = ProfileStore::load(roots(root.path())).unwrap();
+
+        let current = profiles.current();
+        assert_eq!(current.name, DEFAULT_PROFILE);
+        assert!(current.data.is_dir() && current.config.is_dir() && current.cache.is_dir());
+        assert!(current.db_path().parent().unwrap().is_dir());
+        assert_eq!(names(&profiles), [DEFAULT_PROFILE]);
+    }
+
+    #[test]
+    fn create_adds_a_profile_without_switching() {
+        let root = TempDir::new().unwrap();
+        let profiles = ProfileStore::load(roots(root.path())).unwrap();
+
+        let info = profiles.create(" work ").unwrap();
+        assert_eq!(info.name, "work");
+        assert!(info.created_at > 0);
+        assert!(profiles.dirs("work").unwrap().data.is_dir());
+        assert_eq!(profiles.current().name, DEFAULT_PROFILE);
+        assert_eq!(names(&profiles), [DEFAULT_PROFILE, "work"]);
+
+        assert!(matches!(
+            profiles.create("work"),
+            Err(ProfileError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            profiles.create("logs"),
+            Err(ProfileError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn switch_changes_the_active_profile_for_later_loads() {
+        let root = TempDir::new().unwrap();
+        let profiles = ProfileStore::load(roots(root.path())).unwrap();
+        profiles.create("work").unwrap();
+
+        let dirs = profiles.switch("work").unwrap();
+        assert_eq!(dirs.data, root.path().join("data").join("work"));
+        assert_eq!(profiles.current().name, "work");
+        let active: Vec<_> = profiles
+            .list()
+            .into_iter()
+            .filter(|p| p.active)
+            .map(|p| p.info.name)
+            .collect();
+        assert_eq!(active, ["work"]);
+
+        assert!(matches!(
+            profiles.switch("missing"),
+            Err(ProfileError::NotFound(_))
+        ));
+        assert_eq!(profiles.current().name, "work");
+
+        let reloaded = ProfileStore::load(roots(root.path())).unwrap();
+        assert_eq!(reloaded.current().name, "work");
+        assert_eq!(names(&reloaded), [DEFAULT_PROFILE, "work"]);
+    }
+
+    #[test]
+    fn load_falls_back_to_default_when_the_index_is_unusable() {
+        let root = TempDir::new().unwrap();
+        let config = root.path().join("config");
+        fs::create_dir_all(&config).unwrap();
+
+        // The active profile is not in the list
+        fs::write(
+            config.join(PROFILES_FILE),
+            r#"{"active": "gone", "profiles": [{"name": "work", "created_at": 1}]}"#,
+        )
+        .unwrap();
+        let profiles = ProfileStore::load(roots(root.path())).unwrap();
+        assert_eq!(profiles.current().name, DEFAULT_PROFILE);
+        assert_eq!(names(&profiles), [DEFAULT_PROFILE, "work"]);
+
+        fs::write(config.join(PROFILES_FILE), "not json").unwrap();
+        let profiles = ProfileStore::load(roots(root.path())).unwrap();
+        assert_eq!(profiles.current().name, DEFAULT_PROFILE);
+        assert_eq!(names(&profiles), [DEFAULT_PROFILE]);
+    }
+}
@@ -0,0 +1,41 @@
+//! Where the shell's background tasks run.
+//!
+//! The app starts most of them from its setup hook, outside any async
+//! context, so they go to Tauri's runtime there. The headless shell runs
+//! everything on its own tokio runtime and has no Tauri at all.
+//!
+//! Also the wall clock that states, logs and files are stamped with.
+
+use std::future::Future;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Run `task` in the background on the current tokio runtime, or on
+/// Tauri's when called from outside one.
+pub(crate) fn spawn<F>(task: F)
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(feature = "desktop")]
+    if tokio::runtime::Handle::try_current().is_err() {
+        tauri::async_runtime::spawn(task);
+        return;
+    }
+    tokio::spawn(task);
+}
+
+/// Milliseconds since the Unix epoch, or 0 with a clock set before it.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Seconds since the Unix epoch, or 0 with a clock set before it.
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
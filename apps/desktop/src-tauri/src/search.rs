@@ -0,0 +1,613 @@
+//! Full-text search over a profile's memories and chat history, backing the
+//! command-palette search window.
+//!
+//! Memories are matched through the `memories_fts` index the backend keeps
+//! in its database (`memory/storage.py`), messages through the history
+//! reader (see [`History::search`]). Both databases are only ever read;
+//! callers open them, as they may be encrypted (see
+//! [`DbEncryption::open_read_only`](crate::encryption::DbEncryption::open_read_only)).
+//! Results are ranked by bm25 and come with a highlighted snippet using the
+//! history's [`MATCH_START`](crate::history::MATCH_START) and
+//! [`MATCH_END`](crate::history::MATCH_END) markers.
+
+use std::fmt;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::history::{
+    escape_like, excerpt, fts_query, History, HistoryError, SearchHit, MATCH_END, MATCH_START,
+};
+
+const SNIPPET_TOKENS: i32 = 24;
+const MAX_PAGE: u32 = 100;
+
+/// Inclusive range of calendar days, as `YYYY-MM-DD` in local time.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DateRange {
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+impl DateRange {
+    pub fn validate(&self) -> SearchResult<()> {
+        for date in [&self.since, &self.until].into_iter().flatten() {
+            if !is_date(date) {
+                return Err(SearchError::InvalidFilter(format!(
+                    "\"{date}\" is not a YYYY-MM-DD date"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MemoryFilters {
+    /// Only these memory types; all of them when empty.
+    #[serde(default)]
+    pub memory_types: Vec<String>,
+    pub min_importance: Option<f64>,
+    #[serde(flatten)]
+    pub range: DateRange,
+}
+
+impl MemoryFilters {
+    fn validate(&self) -> SearchResult<()> {
+        if let Some(importance) = self.min_importance {
+            if !(0.0..=1.0).contains(&importance) {
+                return Err(SearchError::InvalidFilter(format!(
+                    "Importance must be between 0 and 1, got {importance}"
+                )));
+            }
+        }
+        self.range.validate()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryHit {
+    pub id: String,
+    pub memory_type: String,
+    pub importance: f64,
+    pub tags: Vec<String>,
+    pub source_conversation_id: Option<String>,
+    /// Excerpt of the memory with matches marked.
+    pub snippet: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: f64,
+}
+
+/// One page of results; ask again with `offset + hits.len()` for the next.
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub hits: Vec<T>,
+    pub offset: u32,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Build a page from up to `limit + 1` rows.
+    fn new(mut hits: Vec<T>, limit: u32, offset: u32) -> Self {
+        let has_more = hits.len() > limit as usize;
+        hits.truncate(limit as usize);
+        Self {
+            hits,
+            offset,
+            has_more,
+        }
+    }
+
+    fn empty(offset: u32) -> Self {
+        Self {
+            hits: Vec::new(),
+            offset,
+            has_more: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SearchError {
+    InvalidFilter(String),
+    History(HistoryError),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::InvalidFilter(reason) => write!(f, "Invalid search filter: {reason}"),
+            SearchError::History(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<HistoryError> for SearchError {
+    fn from(err: HistoryError) -> Self {
+        SearchError::History(err)
+    }
+}
+
+impl From<rusqlite::Error> for SearchError {
+    fn from(err: rusqlite::Error) -> Self {
+        SearchError::History(HistoryError::Sqlite(err))
+    }
+}
+
+pub type SearchResult<T> = Result<T, SearchError>;
+
+/// Memories in the backend database `conn` matching every term of `query`.
+///
+/// The index uses SQLite's default tokenizer, which does not split Chinese
+/// text into words. When it finds nothing the memories are scanned for
+/// substrings instead, most important first, like the backend's own
+/// retriever does.
+pub fn memories(
+    conn: &Connection,
+    query: &str,
+    filters: &MemoryFilters,
+    limit: u32,
+    offset: u32,
+) -> SearchResult<Page<MemoryHit>> {
+    filters.validate()?;
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.is_empty() {
+        return Ok(Page::empty(offset));
+    }
+    let limit = limit.clamp(1, MAX_PAGE);
+
+    let types = serde_json::to_string(&filters.memory_types).unwrap_or_default();
+    let hits = if has_indexed_match(conn, &terms, filters, &types)? {
+        indexed(conn, &terms, filters, &types, limit + 1, offset)?
+    } else {
+        scan(conn, &terms, filters, &types, limit + 1, offset)?
+    };
+    Ok(Page::new(hits, limit, offset))
+}
+
+/// Chat messages in the history database `db`, opened as `conn`, matching
+/// every term of `query`.
+pub fn messages(
+    history: &History,
+    conn: &Connection,
+    db: &Path,
+    query: &str,
+    range: &DateRange,
+    limit: u32,
+    offset: u32,
+) -> SearchResult<Page<SearchHit>> {
+    range.validate()?;
+    let limit = limit.clamp(1, MAX_PAGE);
+    let hits = history.search(conn, db, query, range, limit + 1, offset)?;
+    Ok(Page::new(hits, limit, offset))
+}
+
+// Filters shared by the indexed and the scanning query, with `m` the
+// memories table and ?1 to ?4 the memory types (as a JSON array),
+// minimum importance and date range. `created_at` is in Unix seconds.
+const MEMORY_FILTERS: &str = "
+    (?1 = '[]' OR m.memory_type IN (SELECT value FROM json_each(?1)))
+    AND (?2 IS NULL OR m.importance >= ?2)
+    AND (?3 IS NULL OR m.created_at >= unixepoch(?3, 'utc'))
+    AND (?4 IS NULL OR m.created_at < unixepoch(?4, '+1 day', 'utc'))";
+
+fn has_indexed_match(
+    conn: &Connection,
+    terms: &[&str],
+    filters: &MemoryFilters,
+    types: &str,
+) -> SearchResult<bool> {
+    let sql = format!(
+        "SELECT EXISTS(
+             SELECT 1 FROM memories_fts
+             JOIN memories m ON m.rowid = memories_fts.rowid
+             WHERE memories_fts MATCH ?5 AND {MEMORY_FILTERS})"
+    );
+    let found = conn.query_row(
+        &sql,
+        params![
+            types,
+            filters.min_importance,
+            filters.range.since,
+            filters.range.until,
+            fts_query(terms)
+        ],
+        |row| row.get(0),
+    )?;
+    Ok(found)
+}
+
+fn indexed(
+    conn: &Connection,
+    terms: &[&str],
+    filters: &MemoryFilters,
+    types: &str,
+    limit: u32,
+    offset: u32,
+) -> SearchResult<Vec<MemoryHit>> {
+    let sql = format!(
+        "SELECT m.id, m.memory_type, m.importance, m.tags, m.source_conversation_id,
+                snippet(memories_fts, 0, ?6, ?7, '…', ?8), m.created_at
+         FROM memories_fts
+         JOIN memories m ON m.rowid = memories_fts.rowid
+         WHERE memories_fts MATCH ?5 AND {MEMORY_FILTERS}
+         ORDER BY bm25(memories_fts)
+         LIMIT ?9 OFFSET ?10"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let hits = stmt
+        .query_map(
+            params![
+                types,
+                filters.min_importance,
+                filters.range.since,
+                filters.range.until,
+                fts_query(terms),
+                MATCH_START,
+                MATCH_END,
+                SNIPPET_TOKENS,
+                limit,
+                offset
+            ],
+            |row| {
+                Ok(MemoryHit {
+                    id: row.get(0)?,
+                    memory_type: row.get(1)?,
+                    importance: row.get(2)?,
+                    tags: parse_tags(&row.get::<_, String>(3)?),
+                    source_conversation_id: row.get(4)?,
+                    snippet: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            },
+        )?
+        .collect::<Result<_, _>>()?;
+    Ok(hits)
+}
+
+fn scan(
+    conn: &Connection,
+    terms: &[&str],
+    filters: &MemoryFilters,
+    types: &str,
+    limit: u32,
+    offset: u32,
+) -> SearchResult<Vec<MemoryHit>> {
+    let mut sql = format!(
+        "SELECT m.id, m.memory_type, m.importance, m.tags, m.source_conversation_id,
+                m.content, m.created_at
+         FROM memories m
+         WHERE {MEMORY_FILTERS}"
+    );
+    let mut patterns = Vec::with_capacity(terms.len());
+    for (i, term) in terms.iter().enumerate() {
+        sql.push_str(&format!(" AND m.content LIKE ?{} ESCAPE '\\'", i + 5));
+        patterns.push(format!("%{}%", escape_like(term)));
+    }
+    sql.push_str(&format!(
+        " ORDER BY m.importance DESC, m.created_at DESC LIMIT ?{} OFFSET ?{}",
+        terms.len() + 5,
+        terms.len() + 6
+    ));
+
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![
+        &types,
+        &filters.min_importance,
+        &filters.range.since,
+        &filters.range.until,
+    ];
+    values.extend(patterns.iter().map(|p| p as &dyn rusqlite::ToSql));
+    values.push(&limit);
+    values.push(&offset);
+
+    let mut stmt = conn.prepare(&sql)?;
+    let hits = stmt
+        .query_map(values.as_slice(), |row| {
+            let content: String = row.get(5)?;
+            Ok(MemoryHit {
+                id: row.get(0)?,
+                memory_type: row.get(1)?,
+                importance: row.get(2)?,
+                tags: parse_tags(&row.get::<_, String>(3)?),
+                source_conversation_id: row.get(4)?,
+                snippet: excerpt(&content, terms[0]),
+                created_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(hits)
+}
+
+/// Tags are stored as a JSON array of strings.
+fn parse_tags(raw: &str) -> Vec<String> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+fn is_date(value: &str) -> bool {
+    let field = |part: Option<&str>, len: usize, max: u32| {
+        part.is_some_and(|p| {
+            p.len() == len
+                && p.bytes().all(|b| b.is_ascii_digit())
+                && (1..=max).contains(&p.parse().unwrap_or(0))
+        })
+    };
+    let mut parts = value.split('-');
+    field(parts.next(), 4, 9999)
+        && field(parts.next(), 2, 12)
+        && field(parts.next(), 2, 31)
+        && parts.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The memory tables as `memory/storage.py` creates them.
+    fn memory_db(memories: &[(&str, &str, &str, f64)]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE memories (
+                 id TEXT PRIMARY KEY,
+                 content TEXT NOT NULL,
+                 memory_type TEXT NOT NULL DEFAULT 'episodic',
+                 importance REAL NOT NULL DEFAULT 0.5,
+                 embedding TEXT,
+                 tags TEXT NOT NULL DEFAULT '[]',
+                 source_conversation_id TEXT,
+                 created_at REAL NOT NULL,
+                 last_accessed REAL NOT NULL,
+                 access_count INTEGER NOT NULL DEFAULT 0,
+                 metadata TEXT NOT NULL DEFAULT '{}'
+             );
+             CREATE VIRTUAL TABLE memories_fts USING fts5(
+                 content,
+                 tags,
+                 content='memories',
+                 content_rowid='rowid'
+             );
+             CREATE TRIGGER memories_ai AFTER INSERT ON memories BEGIN
+                 INSERT INTO memories_fts(rowid, content, tags)
+                 VALUES (new.rowid, new.content, new.tags);
+             END;",
+        )
+        .unwrap();
+        for (id, content, memory_type, importance) in memories {
+            conn.execute(
+                "INSERT INTO memories (id, content, memory_type, importance, tags, created_at, last_accessed)
+                 VALUES (?1, ?2, ?3, ?4, '[\"work\"]', unixepoch('2026-03-01'), unixepoch('2026-03-01'))",
+                params![id, content, memory_type, importance],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    /// The history as `api/routes/chat.py` creates it, with `count`
+    /// messages mentioning a deploy.
+    fn history_db(count: usize) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE conversations (
+                 id TEXT PRIMARY KEY,
+                 title TEXT NOT NULL,
+                 created_at TEXT NOT NULL,
+                 updated_at TEXT NOT NULL,
+                 message_count INTEGER DEFAULT 0
+             );
+             CREATE TABLE messages (
+                 id TEXT PRIMARY KEY,
+                 conversation_id TEXT NOT NULL,
+                 role TEXT NOT NULL,
+                 content TEXT NOT NULL,
+                 created_at TEXT NOT NULL
+             );",
+        )
+        .unwrap();
+        for i in 0..count {
+            conn.execute(
+                "INSERT INTO messages VALUES (?1, 'c1', 'user', 'deploy number ' || ?1, ?2)",
+                params![format!("m{i}"), format!("2026-03-01T09:00:{i:02}")],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn ids(page: &Page<MemoryHit>) -> Vec<&str> {
+        page.hits.iter().map(|hit| hit.id.as_str()).collect()
+    }
+
+    #[test]
+    fn is_date_takes_only_yyyy_mm_dd() {
+        for date in ["2026-03-01", "0001-12-31"] {
+            assert!(is_date(date), "{date}");
+        }
+        for date in [
+            "",
+            "2026-3-01",
+            "2026-03-1",
+            "2026-13-01",
+            "2026-00-10",
+            "2026-03-32",
+            "2026-03-01T00:00",
+            "2026-03-01-",
+            "+026-03-01",
+            "２０２６-03-01",
+        ] {
+            assert!(!is_date(date), "{date}");
+        }
+    }
+
+    #[test]
+    fn date_range_validates_both_bounds() {
+        let range = |since: Option<&str>, until: Option<&str>| DateRange {
+            since: since.map(str::to_string),
+            until: until.map(str::to_string),
+        };
+        assert!(range(None, None).validate().is_ok());
+        assert!(range(Some("2026-03-01"), Some("2026-03-31"))
+            .validate()
+            .is_ok());
+        assert!(matches!(
+            range(Some("2026-03-01"), Some("yesterday")).validate(),
+            Err(SearchError::InvalidFilter(_))
+        ));
+        assert!(matches!(
+            range(Some("2026-3-1"), None).validate(),
+            Err(SearchError::InvalidFilter(_))
+        ));
+    }
+
+    #[test]
+    fn memories_come_from_the_index() {
+        let conn = memory_db(&[
+            ("a", "The user prefers dark mode", "semantic", 0.9),
+            ("b", "Deploys happen on Fridays", "episodic", 0.5),
+        ]);
+
+        let page = memories(&conn, "DARK mode", &MemoryFilters::default(), 10, 0).unwrap();
+
+        assert_eq!(ids(&page), ["a"]);
+        let hit = &page.hits[0];
+        assert_eq!(
+            hit.snippet,
+            format!("The user prefers {MATCH_START}dark{MATCH_END} {MATCH_START}mode{MATCH_END}")
+        );
+        assert_eq!(hit.tags, ["work"]);
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn memory_queries_are_not_fts_syntax() {
+        let conn = memory_db(&[(
+            "a",
+            "Use NEAR for proximity; col:value filters",
+            "semantic",
+            0.5,
+        )]);
+
+        for query in ["NEAR(", "col:value", "\"unbalanced", "a OR", "*"] {
+            assert!(
+                memories(&conn, query, &MemoryFilters::default(), 10, 0).is_ok(),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn cjk_memories_fall_back_to_a_scan() {
+        let conn = memory_db(&[
+            ("a", "用户喜欢深色模式", "semantic", 0.4),
+            ("b", "周五部署，深色主题上线", "episodic", 0.8),
+            ("c", "100%_done", "episodic", 0.1),
+        ]);
+
+        let page = memories(&conn, "深色", &MemoryFilters::default(), 10, 0).unwrap();
+        // Most important first
+        assert_eq!(ids(&page), ["b", "a"]);
+        assert_eq!(
+            page.hits[1].snippet,
+            format!("用户喜欢{MATCH_START}深色{MATCH_END}模式")
+        );
+
+        let page = memories(&conn, "0%_", &MemoryFilters::default(), 10, 0).unwrap();
+        assert_eq!(ids(&page), ["c"]);
+        let page = memories(&conn, "%", &MemoryFilters::default(), 10, 0).unwrap();
+        assert_eq!(ids(&page), ["c"]);
+    }
+
+    #[test]
+    fn memory_filters_apply() {
+        let conn = memory_db(&[
+            ("a", "deploy checklist", "semantic", 0.9),
+            ("b", "deploy went fine", "episodic", 0.3),
+        ]);
+        let filters = MemoryFilters {
+            memory_types: vec!["episodic".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&memories(&conn, "deploy", &filters, 10, 0).unwrap()),
+            ["b"]
+        );
+
+        let filters = MemoryFilters {
+            min_importance: Some(0.5),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&memories(&conn, "deploy", &filters, 10, 0).unwrap()),
+            ["a"]
+        );
+
+        let filters = MemoryFilters {
+            min_importance: Some(2.0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            memories(&conn, "deploy", &filters, 10, 0),
+            Err(SearchError::InvalidFilter(_))
+        ));
+    }
+
+    #[test]
+    fn memory_pages_know_when_there_is_more() {
+        let conn = memory_db(&[
+            ("a", "deploy one", "episodic", 0.5),
+            ("b", "deploy two", "episodic", 0.5),
+            ("c", "deploy three", "episodic", 0.5),
+        ]);
+        let filters = MemoryFilters::default();
+
+        let first = memories(&conn, "deploy", &filters, 2, 0).unwrap();
+        assert_eq!(first.hits.len(), 2);
+        assert!(first.has_more);
+        let last = memories(&conn, "deploy", &filters, 2, 2).unwrap();
+        assert_eq!((last.hits.len(), last.offset, last.has_more), (1, 2, false));
+        let exact = memories(&conn, "deploy", &filters, 3, 0).unwrap();
+        assert_eq!((exact.hits.len(), exact.has_more), (3, false));
+    }
+
+    #[test]
+    fn message_pages_know_when_there_is_more() {
+        let conn = history_db(5);
+        let history = History::new();
+        let db = Path::new(":memory:");
+        let range = DateRange::default();
+
+        // Once through the trigram index, once through the scan
+        for query in ["deploy", "de"] {
+            let first = messages(&history, &conn, db, query, &range, 2, 0).unwrap();
+            assert_eq!((first.hits.len(), first.has_more), (2, true), "{query}");
+            let middle = messages(&history, &conn, db, query, &range, 2, 2).unwrap();
+            assert_eq!((middle.hits.len(), middle.has_more), (2, true), "{query}");
+            let last = messages(&history, &conn, db, query, &range, 2, 4).unwrap();
+            assert_eq!((last.hits.len(), last.has_more), (1, false), "{query}");
+            let exact = messages(&history, &conn, db, query, &range, 5, 0).unwrap();
+            assert_eq!((exact.hits.len(), exact.has_more), (5, false), "{query}");
+        }
+    }
+
+    #[test]
+    fn empty_queries_find_nothing() {
+        let conn = memory_db(&[("a", "anything", "episodic", 0.5)]);
+        let page = memories(&conn, "   ", &MemoryFilters::default(), 10, 4).unwrap();
+        assert!(page.hits.is_empty() && !page.has_more);
+        assert_eq!(page.offset, 4);
+
+        let page = messages(
+            &History::new(),
+            &history_db(1),
+            Path::new(":memory:"),
+            "",
+            &DateRange::default(),
+            10,
+            0,
+        )
+        .unwrap();
+        assert!(page.hits.is_empty() && !page.has_more);
+    }
+}
@@ -0,0 +1,214 @@
+//! Provider API keys and the database key, kept out of the webview and off
+//! disk in the clear.
+//!
+//! Keys live in the OS keyring (Secret Service on Linux) or, when no
+//! keyring daemon answers, in an encrypted vault file. The shell hands them
+//! to the backend only as environment variables of the child process.
+
+mod vault;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use vault::Vault;
+
+/// Service name the keys are filed under in the keyring.
+pub const KEYRING_SERVICE: &str = "com.coolaw.deskflow";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretProvider {
+    Anthropic,
+    Openai,
+    Dashscope,
+}
+
+impl SecretProvider {
+    pub const ALL: [SecretProvider; 3] = [
+        SecretProvider::Anthropic,
+        SecretProvider::Openai,
+        SecretProvider::Dashscope,
+    ];
+
+    /// Keyring account / vault entry name.
+    pub fn account(self) -> &'static str {
+        match self {
+            SecretProvider::Anthropic => "anthropic_api_key",
+            SecretProvider::Openai => "openai_api_key",
+            SecretProvider::Dashscope => "dashscope_api_key",
+        }
+    }
+
+    /// Variable `LLMConfig` reads the key from.
+    pub fn env_var(self) -> &'static str {
+        match self {
+            SecretProvider::Anthropic => "DESKFLOW_ANTHROPIC_API_KEY",
+            SecretProvider::Openai => "DESKFLOW_OPENAI_API_KEY",
+            SecretProvider::Dashscope => "DESKFLOW_DASHSCOPE_API_KEY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    Keyring,
+    Vault,
+}
+
+/// What the frontend may know about stored secrets: where they live and
+/// which providers have one, never the values.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretsStatus {
+    pub backend: SecretBackend,
+    pub stored: BTreeMap<SecretProvider, bool>,
+}
+
+#[derive(Debug)]
+pub enum SecretError {
+    Keyring(String),
+    Vault(String),
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::Keyring(e) => write!(f, "Keyring error: {e}"),
+            SecretError::Vault(e) => write!(f, "Secret vault error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+impl From<keyring::Error> for SecretError {
+    fn from(err: keyring::Error) -> Self {
+        SecretError::Keyring(err.to_string())
+    }
+}
+
+enum Backend {
+    Keyring,
+    Vault(Vault),
+}
+
+/// Handle to wherever secrets are stored. Cheap to clone.
+///
+/// Calls may block on D-Bus or disk; keep them off the main thread.
+#[derive(Clone)]
+pub struct SecretStore {
+    backend: Arc<Backend>,
+}
+
+impl SecretStore {
+    /// Use the keyring when one is reachable, otherwise the vault in
+    /// `vault_dir`.
+    pub fn open(vault_dir: &Path) -> Self {
+        let backend = if keyring_available() {
+            Backend::Keyring
+        } else {
+            Backend::Vault(Vault::new(vault_dir))
+        };
+        Self {
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Only the vault in `vault_dir`, so tests never touch the keyring.
+    #[cfg(test)]
+    pub(crate) fn vault(vault_dir: &Path) -> Self {
+        Self {
+            backend: Arc::new(Backend::Vault(Vault::new(vault_dir))),
+        }
+    }
+
+    pub fn backend(&self) -> SecretBackend {
+        match *self.backend {
+            Backend::Keyring => SecretBackend::Keyring,
+            Backend::Vault(_) => SecretBackend::Vault,
+        }
+    }
+
+    pub fn get(&self, provider: SecretProvider) -> Result<Option<String>, SecretError> {
+        self.get_account(provider.account())
+    }
+
+    pub fn set(&self, provider: SecretProvider, value: &str) -> Result<(), SecretError> {
+        self.set_account(provider.account(), value)
+    }
+
+    pub fn delete(&self, provider: SecretProvider) -> Result<(), SecretError> {
+        self.delete_account(provider.account())
+    }
+
+    /// Read any entry by its keyring account name, for secrets other than
+    /// provider keys.
+    pub(crate) fn get_account(&self, account: &str) -> Result<Option<String>, SecretError> {
+        match &*self.backend {
+            Backend::Keyring => match entry(account)?.get_password() {
+                Ok(value) => Ok(Some(value)),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+            Backend::Vault(vault) => vault.get(account),
+        }
+    }
+
+    pub(crate) fn set_account(&self, account: &str, value: &str) -> Result<(), SecretError> {
+        match &*self.backend {
+            Backend::Keyring => Ok(entry(account)?.set_password(value)?),
+            Backend::Vault(vault) => vault.set(account, value),
+        }
+    }
+
+    pub(crate) fn delete_account(&self, account: &str) -> Result<(), SecretError> {
+        match &*self.backend {
+            Backend::Keyring => match entry(account)?.delete_credential() {
+                Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(e.into()),
+            },
+            Backend::Vault(vault) => vault.delete(account),
+        }
+    }
+
+    pub fn status(&self) -> Result<SecretsStatus, SecretError> {
+        let mut stored = BTreeMap::new();
+        for provider in SecretProvider::ALL {
+            stored.insert(provider, self.get(provider)?.is_some());
+        }
+        Ok(SecretsStatus {
+            backend: self.backend(),
+            stored,
+        })
+    }
+
+    /// Environment variables that hand the stored keys to the backend.
+    /// Unreadable entries are skipped so a locked keyring does not keep the
+    /// backend from starting.
+    pub fn env(&self) -> Vec<(String, String)> {
+        SecretProvider::ALL
+            .into_iter()
+            .filter_map(|provider| {
+                let value = self.get(provider).ok().flatten()?;
+                Some((provider.env_var().to_string(), value))
+            })
+            .collect()
+    }
+}
+
+fn entry(account: &str) -> Result<keyring::Entry, SecretError> {
+    Ok(keyring::Entry::new(KEYRING_SERVICE, account)?)
+}
+
+/// Whether a keyring daemon answers. A missing entry is a healthy answer.
+fn keyring_available() -> bool {
+    let probe = keyring::Entry::new(KEYRING_SERVICE, "availability-probe");
+    matches!(
+        probe.and_then(|entry| entry.get_password()),
+        Ok(_) | Err(keyring::Error::NoEntry)
+    )
+}
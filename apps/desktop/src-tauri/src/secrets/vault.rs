@@ -0,0 +1,143 @@
+//! Encrypted file fallback for machines without a keyring daemon.
+//!
+//! Entries are stored as one ChaCha20-Poly1305 sealed JSON map in
+//! `secrets.vault`; the key sits next to it in `vault.key`, readable by the
+//! owner only. That keeps the vault useless on its own (backups, synced
+//! folders, bug reports) but is not a substitute for a real keyring.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use super::SecretError;
+
+const VAULT_FILE: &str = "secrets.vault";
+const KEY_FILE: &str = "vault.key";
+const MAGIC: &[u8; 4] = b"DFV1";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+pub(super) struct Vault {
+    dir: PathBuf,
+    // Serializes read-modify-write cycles
+    lock: Mutex<()>,
+}
+
+impl Vault {
+    pub(super) fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub(super) fn get(&self, name: &str) -> Result<Option<String>, SecretError> {
+        let _guard = self.lock.lock().unwrap();
+        Ok(self.load()?.remove(name))
+    }
+
+    pub(super) fn set(&self, name: &str, value: &str) -> Result<(), SecretError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+        entries.insert(name.to_string(), value.to_string());
+        self.save(&entries)
+    }
+
+    pub(super) fn delete(&self, name: &str) -> Result<(), SecretError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+        if entries.remove(name).is_some() {
+            self.save(&entries)?;
+        }
+        Ok(())
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, String>, SecretError> {
+        let raw = match fs::read(self.dir.join(VAULT_FILE)) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(vault_error(e)),
+        };
+
+        let body = raw
+            .strip_prefix(MAGIC.as_slice())
+            .filter(|body| body.len() > NONCE_LEN)
+            .ok_or_else(|| SecretError::Vault("unrecognized vault format".into()))?;
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+        let plaintext = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| SecretError::Vault("vault cannot be decrypted with this key".into()))?;
+        serde_json::from_slice(&plaintext).map_err(vault_error)
+    }
+
+    fn save(&self, entries: &BTreeMap<String, String>) -> Result<(), SecretError> {
+        let plaintext = serde_json::to_vec(entries).map_err(vault_error)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| SecretError::Vault("encryption failed".into()))?;
+
+        let mut raw = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        raw.extend_from_slice(MAGIC);
+        raw.extend_from_slice(&nonce);
+        raw.extend_from_slice(&ciphertext);
+
+        // Write then rename so a crash never leaves a half-written vault
+        let tmp = self.dir.join(format!("{VAULT_FILE}.tmp"));
+        write_private(&tmp, &raw, false).map_err(vault_error)?;
+        fs::rename(&tmp, self.dir.join(VAULT_FILE)).map_err(vault_error)
+    }
+
+    /// Load the vault key, creating it on first use.
+    fn cipher(&self) -> Result<ChaCha20Poly1305, SecretError> {
+        let path = self.dir.join(KEY_FILE);
+        match fs::read(&path) {
+            Ok(key) if key.len() == KEY_LEN => {
+                return Ok(ChaCha20Poly1305::new(Key::from_slice(&key)));
+            }
+            Ok(_) => return Err(SecretError::Vault("vault key is corrupt".into())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(vault_error(e)),
+        }
+
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_private(&path, &key, true).map_err(vault_error)?;
+        Ok(ChaCha20Poly1305::new(&key))
+    }
+}
+
+/// Write `contents` to a file only the current user can read.
+fn write_private(path: &Path, contents: &[u8], create_new: bool) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if create_new {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+fn vault_error(err: impl std::fmt::Display) -> SecretError {
+    SecretError::Vault(err.to_string())
+}
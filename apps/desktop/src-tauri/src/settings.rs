@@ -0,0 +1,141 @@
+//! Preferences that belong to the desktop shell rather than the backend,
+//! stored as `shell.json` in the app config directory.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::notifications::NotificationCategory;
+
+pub const SETTINGS_FILE: &str = "shell.json";
+
+pub const DEFAULT_QUICK_ASK_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+pub const DEFAULT_BACKUP_RETENTION: u32 = 7;
+pub const DEFAULT_WATCHDOG_LATENCY_MS: u32 = 5000;
+pub const DEFAULT_WATCHDOG_MISSES: u32 = 3;
+pub const DEFAULT_UPDATE_CHECK_HOURS: u32 = 24;
+
+/// Which releases the updater offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// Release candidates ahead of the stable release.
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellSettings {
+    /// Hide the main window on close instead of quitting, so the agent and
+    /// IM channels keep running in the background.
+    pub minimize_to_tray: bool,
+    /// Global accelerator that toggles the quick-ask window, e.g.
+    /// `CommandOrControl+Shift+Space`. Empty disables it.
+    pub quick_ask_shortcut: String,
+    /// Hours between automatic backups of the active profile; 0 turns them
+    /// off.
+    pub auto_backup_hours: u32,
+    /// Automatic backups kept per profile.
+    pub backup_retention: u32,
+    /// How long `/api/health` may take before the backend counts as slow;
+    /// 0 turns the hang watchdog off.
+    pub watchdog_latency_ms: u32,
+    /// Consecutive slow health checks after which the backend is treated as
+    /// hung, dumped and restarted.
+    pub watchdog_misses: u32,
+    /// Show native notifications for agent events while no DeskFlow window
+    /// has focus.
+    pub notifications_enabled: bool,
+    /// Notification categories never shown.
+    pub muted_notifications: Vec<NotificationCategory>,
+    /// Start of the daily do-not-disturb window in local time, `HH:MM`.
+    /// Empty disables it.
+    pub quiet_hours_start: String,
+    /// End of the do-not-disturb window; before the start when it runs past
+    /// midnight.
+    pub quiet_hours_end: String,
+    pub update_channel: UpdateChannel,
+    /// Hours between update checks; 0 leaves checking to the user.
+    pub update_check_hours: u32,
+    /// Download updates in the background as soon as they are found.
+    /// Installing always waits for the user.
+    pub auto_download_updates: bool,
+}
+
+impl Default for ShellSettings {
+    fn default() -> Self {
+        Self {
+            minimize_to_tray: false,
+            quick_ask_shortcut: DEFAULT_QUICK_ASK_SHORTCUT.to_string(),
+            auto_backup_hours: 0,
+            backup_retention: DEFAULT_BACKUP_RETENTION,
+            watchdog_latency_ms: DEFAULT_WATCHDOG_LATENCY_MS,
+            watchdog_misses: DEFAULT_WATCHDOG_MISSES,
+            notifications_enabled: true,
+            muted_notifications: Vec::new(),
+            quiet_hours_start: String::new(),
+            quiet_hours_end: String::new(),
+            update_channel: UpdateChannel::default(),
+            update_check_hours: DEFAULT_UPDATE_CHECK_HOURS,
+            auto_download_updates: true,
+        }
+    }
+}
+
+/// Shell settings backed by a JSON file.
+pub struct SettingsStore {
+    path: PathBuf,
+    current: Mutex<ShellSettings>,
+}
+
+impl SettingsStore {
+    /// Load settings from `dir`, falling back to defaults when the file is
+    /// missing or unreadable.
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(SETTINGS_FILE);
+        let current = fs::read(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            current: Mutex::new(current),
+        }
+    }
+
+    pub fn get(&self) -> ShellSettings {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Replace the settings and write them to disk.
+    pub fn set(&self, settings: ShellSettings) -> io::Result<()> {
+        let raw = serde_json::to_vec_pretty(&settings)?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, raw)?;
+        *self.current.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut ShellSettings)) -> io::Result<ShellSettings> {
+        let mut settings = self.get();
+        f(&mut settings);
+        self.set(settings.clone())?;
+        Ok(settings)
+    }
+}
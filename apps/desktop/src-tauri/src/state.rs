@@ -0,0 +1,78 @@
+//! [`AppState`], what the shell itself keeps for the lifetime of the app.
+//! Everything with a life of its own (supervisor, stores, bridges) is
+//! managed separately and looked up by type.
+
+use std::io;
+use std::sync::Mutex;
+
+use crate::backend::{port, BackendEndpoint};
+use crate::instance::LaunchRequest;
+
+/// Set to skip spawning the backend, e.g. when running `deskflow serve` by
+/// hand.
+pub const EXTERNAL_BACKEND_ENV: &str = "DESKFLOW_EXTERNAL_BACKEND";
+
+pub struct AppState {
+    endpoint: BackendEndpoint,
+    external_backend: bool,
+    /// Launch requests (this process's own, then ones forwarded by later
+    /// launches) the frontend has not picked up yet.
+    launches: Mutex<Vec<LaunchRequest>>,
+    /// Links the app was started with, held until the frontend can follow
+    /// them.
+    startup_links: Mutex<Option<Vec<String>>>,
+}
+
+impl AppState {
+    pub fn new(endpoint: BackendEndpoint, external_backend: bool) -> Self {
+        Self {
+            endpoint,
+            external_backend,
+            launches: Mutex::default(),
+            startup_links: Mutex::default(),
+        }
+    }
+
+    /// An external backend is expected on the pinned (or default) port; a
+    /// supervised one gets a free port so several instances can coexist.
+    pub fn from_env() -> io::Result<Self> {
+        let external = std::env::var_os(EXTERNAL_BACKEND_ENV).is_some();
+        let port = if external {
+            port::configured_port().unwrap_or(port::DEFAULT_PORT)
+        } else {
+            port::allocate_port()?
+        };
+        Ok(Self::new(BackendEndpoint::new(port), external))
+    }
+
+    pub fn endpoint(&self) -> BackendEndpoint {
+        self.endpoint
+    }
+
+    /// Whether someone else runs the backend, so the shell must not spawn
+    /// one.
+    pub fn external_backend(&self) -> bool {
+        self.external_backend
+    }
+
+    pub fn queue_launch(&self, request: LaunchRequest) {
+        self.launches.lock().unwrap().push(request);
+    }
+
+    pub fn take_launches(&self) -> Vec<LaunchRequest> {
+        std::mem::take(&mut *self.launches.lock().unwrap())
+    }
+
+    pub fn hold_startup_links(&self, links: Option<Vec<String>>) {
+        *self.startup_links.lock().unwrap() = links;
+    }
+
+    /// The links the app was started with, once.
+    pub fn take_startup_links(&self) -> Vec<String> {
+        self.startup_links
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_default()
+    }
+}
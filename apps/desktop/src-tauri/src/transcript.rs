@@ -0,0 +1,615 @@
+//! Conversation transcripts for pasting into tickets and docs, rendered as
+//! Markdown, a self-contained HTML page or JSON Lines.
+//!
+//! Transcripts come from the agent's copy of a conversation in the profile
+//! database (see [`from_profile_db`]), which records the tool calls each
+//! assistant message made and the tool results. Conversations the agent has
+//! no record of fall back to the chat history (see
+//! [`History::conversation`](crate::history::History::conversation) or
+//! `/api/chat/{id}`), which only keeps each message's role and text. Tool
+//! calls and tool results are folded into collapsible `<details>` sections
+//! in Markdown and HTML.
+
+use std::collections::HashMap;
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::deskflow_client::models::Conversation;
+
+const MAX_FILE_STEM_CHARS: usize = 60;
+const UNTITLED: &str = "Untitled conversation";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    Markdown,
+    Html,
+    Jsonl,
+}
+
+impl TranscriptFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TranscriptFormat::Markdown => "md",
+            TranscriptFormat::Html => "html",
+            TranscriptFormat::Jsonl => "jsonl",
+        }
+    }
+
+    /// Name for the file type in save dialogs.
+    pub fn label(self) -> &'static str {
+        match self {
+            TranscriptFormat::Markdown => "Markdown",
+            TranscriptFormat::Html => "HTML",
+            TranscriptFormat::Jsonl => "JSON Lines",
+        }
+    }
+}
+
+/// A tool call as the agent stores it with the message that made it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptMessage {
+    pub id: String,
+    pub role: String,
+    pub content: String,
+    pub created_at: String,
+    /// Calls made by an assistant message.
+    pub tool_calls: Vec<ToolCall>,
+    /// The call a `tool` message answers.
+    pub tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+impl From<Conversation> for Transcript {
+    fn from(conversation: Conversation) -> Self {
+        Self {
+            id: conversation.id,
+            title: conversation.title,
+            created_at: conversation.created_at,
+            updated_at: conversation.updated_at,
+            messages: conversation
+                .messages
+                .into_iter()
+                .map(|message| TranscriptMessage {
+                    id: message.id,
+                    role: message.role,
+                    content: message.content,
+                    created_at: message.created_at,
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The agent's copy of conversation `id` from the profile database `conn`,
+/// with the tool calls and tool results the chat history lacks. `None` if
+/// the agent has no record of it.
+pub fn from_profile_db(conn: &Connection, id: &str) -> rusqlite::Result<Option<Transcript>> {
+    // Times are Unix seconds there, ISO 8601 local time in the history
+    let conversation = conn
+        .query_row(
+            "SELECT id, COALESCE(NULLIF(TRIM(title), ''), ?2),
+                    strftime('%Y-%m-%dT%H:%M:%S', created_at, 'unixepoch', 'localtime'),
+                    strftime('%Y-%m-%dT%H:%M:%S', updated_at, 'unixepoch', 'localtime')
+             FROM conversations
+             WHERE id = ?1",
+            (id, UNTITLED),
+            |row| {
+                Ok(Transcript {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                    messages: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+    let Some(mut transcript) = conversation else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT id, role, content,
+                strftime('%Y-%m-%dT%H:%M:%S', timestamp, 'unixepoch', 'localtime'),
+                tool_calls, tool_call_id
+         FROM messages
+         WHERE conversation_id = ?1
+         ORDER BY timestamp ASC",
+    )?;
+    transcript.messages = stmt
+        .query_map([id], |row| {
+            let calls: Option<String> = row.get(4)?;
+            Ok(TranscriptMessage {
+                id: row.get(0)?,
+                role: row.get(1)?,
+                content: row.get(2)?,
+                created_at: row.get(3)?,
+                tool_calls: calls
+                    .and_then(|raw| serde_json::from_str(&raw).ok())
+                    .unwrap_or_default(),
+                tool_call_id: row.get(5)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(Some(transcript))
+}
+
+pub fn render(transcript: &Transcript, format: TranscriptFormat) -> String {
+    match format {
+        TranscriptFormat::Markdown => markdown(transcript),
+        TranscriptFormat::Html => html(transcript),
+        TranscriptFormat::Jsonl => jsonl(transcript),
+    }
+}
+
+/// File name for a transcript, e.g. `2024-03-01-deploy-checklist.md`. With
+/// `unique` the start of the conversation id is added, for exporting many
+/// into one directory.
+pub fn file_name(transcript: &Transcript, format: TranscriptFormat, unique: bool) -> String {
+    let mut stem = String::new();
+    let mut len = 0;
+    let mut dash = false;
+    for c in transcript.title.chars().flat_map(char::to_lowercase) {
+        if !c.is_alphanumeric() {
+            dash = true;
+            continue;
+        }
+        let dash = std::mem::take(&mut dash) && !stem.is_empty();
+        len += 1 + usize::from(dash);
+        if len > MAX_FILE_STEM_CHARS {
+            break;
+        }
+        if dash {
+            stem.push('-');
+        }
+        stem.push(c);
+    }
+    if stem.is_empty() {
+        stem.push_str("conversation");
+    }
+
+    let mut name = String::new();
+    if let Some(date) = transcript.created_at.get(..10).filter(|d| is_iso_date(d)) {
+        name.push_str(date);
+        name.push('-');
+    }
+    name.push_str(&stem);
+    if unique {
+        let id: String = transcript
+            .id
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .take(8)
+            .collect();
+        name.push('-');
+        name.push_str(&id);
+    }
+    format!("{name}.{}", format.extension())
+}
+
+fn markdown(transcript: &Transcript) -> String {
+    let mut out = format!("# {}\n\n", transcript.title.trim());
+    out.push_str(&format!(
+        "_{} – {} · {} messages_\n",
+        display_time(&transcript.created_at),
+        display_time(&transcript.updated_at),
+        transcript.messages.len()
+    ));
+
+    let names = tool_names(transcript);
+    for message in &transcript.messages {
+        out.push('\n');
+        if message.role == "tool" {
+            out.push_str(&format!(
+                "<details>\n<summary>Tool result: {}</summary>\n\n{}\n\n</details>\n",
+                tool_name(&names, message),
+                fenced(&message.content, "")
+            ));
+            continue;
+        }
+
+        out.push_str(&format!(
+            "**{}** · {}\n",
+            role_label(&message.role),
+            display_time(&message.created_at)
+        ));
+        if !message.content.trim().is_empty() {
+            out.push('\n');
+            out.push_str(message.content.trim_end());
+            out.push('\n');
+        }
+        for call in &message.tool_calls {
+            out.push_str(&format!(
+                "\n<details>\n<summary>Tool call: {}</summary>\n\n{}\n\n</details>\n",
+                call.name,
+                fenced(&pretty(&call.arguments), "json")
+            ));
+        }
+    }
+    out
+}
+
+/// Wrap `text` in a code fence longer than any backtick run inside it.
+fn fenced(text: &str, language: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{language}\n{}\n{fence}", text.trim_end())
+}
+
+const HTML_STYLE: &str = "
+body { font: 15px/1.55 -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; max-width: 820px; margin: 2rem auto; padding: 0 1rem; color: #1f2328; background: #fff; }
+h1 { font-size: 1.5rem; margin-bottom: 0.25rem; }
+.meta, time { color: #656d76; font-size: 0.85rem; }
+section { border-top: 1px solid #d0d7de; padding: 0.75rem 0; }
+section > header { margin-bottom: 0.35rem; }
+.user > header strong { color: #0969da; }
+.assistant > header strong { color: #1a7f37; }
+.content { white-space: pre-wrap; overflow-wrap: anywhere; }
+details { margin: 0.5rem 0; border: 1px solid #d0d7de; border-radius: 6px; padding: 0.35rem 0.6rem; }
+summary { cursor: pointer; color: #656d76; font-size: 0.9rem; }
+pre { white-space: pre-wrap; overflow-wrap: anywhere; font: 13px/1.45 ui-monospace, SFMono-Regular, Menlo, monospace; margin: 0.5rem 0 0; }
+@media (prefers-color-scheme: dark) {
+  body { color: #e6edf3; background: #0d1117; }
+  section, details { border-color: #30363d; }
+  .meta, time, summary { color: #8d96a0; }
+}
+";
+
+fn html(transcript: &Transcript) -> String {
+    let title = escape_html(transcript.title.trim());
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"meta\">{} – {} · {} messages</p>\n",
+        escape_html(&display_time(&transcript.created_at)),
+        escape_html(&display_time(&transcript.updated_at)),
+        transcript.messages.len()
+    );
+
+    let names = tool_names(transcript);
+    for message in &transcript.messages {
+        if message.role == "tool" {
+            out.push_str(&format!(
+                "<details>\n<summary>Tool result: {}</summary>\n<pre>{}</pre>\n</details>\n",
+                escape_html(tool_name(&names, message)),
+                escape_html(message.content.trim_end())
+            ));
+            continue;
+        }
+
+        out.push_str(&format!(
+            "<section class=\"{}\">\n<header><strong>{}</strong> <time>{}</time></header>\n",
+            escape_html(&message.role),
+            escape_html(&role_label(&message.role)),
+            escape_html(&display_time(&message.created_at))
+        ));
+        if !message.content.trim().is_empty() {
+            out.push_str(&format!(
+                "<div class=\"content\">{}</div>\n",
+                escape_html(message.content.trim_end())
+            ));
+        }
+        for call in &message.tool_calls {
+            out.push_str(&format!(
+                "<details>\n<summary>Tool call: {}</summary>\n<pre>{}</pre>\n</details>\n",
+                escape_html(&call.name),
+                escape_html(&pretty(&call.arguments))
+            ));
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// A header line for the conversation, then one line per message.
+fn jsonl(transcript: &Transcript) -> String {
+    let header = json!({
+        "type": "conversation",
+        "id": transcript.id,
+        "title": transcript.title,
+        "created_at": transcript.created_at,
+        "updated_at": transcript.updated_at,
+        "message_count": transcript.messages.len(),
+    });
+    let mut out = format!("{header}\n");
+    for message in &transcript.messages {
+        let mut line = json!({ "type": "message", "conversation_id": transcript.id });
+        if let (Value::Object(line), Ok(Value::Object(fields))) =
+            (&mut line, serde_json::to_value(message))
+        {
+            line.extend(fields);
+        }
+        out.push_str(&format!("{line}\n"));
+    }
+    out
+}
+
+/// Tool names by call id, so results can be labelled with the tool that
+/// produced them.
+fn tool_names(transcript: &Transcript) -> HashMap<&str, &str> {
+    transcript
+        .messages
+        .iter()
+        .flat_map(|message| &message.tool_calls)
+        .map(|call| (call.id.as_str(), call.name.as_str()))
+        .collect()
+}
+
+fn tool_name<'a>(names: &HashMap<&str, &'a str>, message: &TranscriptMessage) -> &'a str {
+    message
+        .tool_call_id
+        .as_deref()
+        .and_then(|id| names.get(id).copied())
+        .unwrap_or("tool")
+}
+
+fn role_label(role: &str) -> String {
+    let mut chars = role.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// `2024-03-01T10:20:30.123456` → `2024-03-01 10:20`; anything else as is.
+fn display_time(timestamp: &str) -> String {
+    match (timestamp.get(..10), timestamp.get(11..16)) {
+        (Some(date), Some(time)) if is_iso_date(date) && timestamp.as_bytes()[10] == b'T' => {
+            format!("{date} {time}")
+        }
+        _ => timestamp.to_string(),
+    }
+}
+
+fn is_iso_date(value: &str) -> bool {
+    value.len() == 10
+        && value.bytes().enumerate().all(|(i, b)| match i {
+            4 | 7 => b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> TranscriptMessage {
+        TranscriptMessage {
+            id: format!("{role}-{}", content.len()),
+            role: role.to_string(),
+            content: content.to_string(),
+            created_at: "2024-03-01T10:20:30.123456".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    fn transcript(title: &str, messages: Vec<TranscriptMessage>) -> Transcript {
+        Transcript {
+            id: "3f2a9c1e-77b0-4d5e".to_string(),
+            title: title.to_string(),
+            created_at: "2024-03-01T10:20:30.123456".to_string(),
+            updated_at: "2024-03-01T11:00:00".to_string(),
+            messages,
+        }
+    }
+
+    /// An assistant message calling `search`, and the tool's result.
+    fn with_tool_call() -> Transcript {
+        let mut call = message("assistant", "Let me look.");
+        call.tool_calls.push(ToolCall {
+            id: "call-1".to_string(),
+            name: "search".to_string(),
+            arguments: json!({ "query": "<rust>" }),
+        });
+        let mut result = message("tool", "found </pre> & more");
+        result.tool_call_id = Some("call-1".to_string());
+        transcript("Tools", vec![message("user", "find it"), call, result])
+    }
+
+    #[test]
+    fn fences_outgrow_backtick_runs() {
+        assert_eq!(fenced("plain", ""), "```\nplain\n```");
+        assert_eq!(fenced("a `b` c", "json"), "```json\na `b` c\n```");
+        assert_eq!(fenced("```rust\nx\n```", ""), "````\n```rust\nx\n```\n````");
+        assert_eq!(fenced("`````", ""), "``````\n`````\n``````");
+        assert_eq!(fenced("trailing\n\n", ""), "```\ntrailing\n```");
+    }
+
+    #[test]
+    fn html_escapes_everything_from_the_conversation() {
+        let transcript = transcript(
+            "<script>alert('x')</script>",
+            vec![message("user\" onclick=\"x", "a < b && c > \"d\"")],
+        );
+        let html = render(&transcript, TranscriptFormat::Html);
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<title>&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;</title>"));
+        assert!(html.contains("a &lt; b &amp;&amp; c &gt; &quot;d&quot;"));
+        assert!(html.contains("<section class=\"user&quot; onclick=&quot;x\">"));
+    }
+
+    #[test]
+    fn tool_sections_are_folded() {
+        let transcript = with_tool_call();
+
+        let markdown = render(&transcript, TranscriptFormat::Markdown);
+        assert!(markdown.contains(
+            "<details>\n<summary>Tool call: search</summary>\n\n```json\n{\n  \"query\": \"<rust>\"\n}\n```\n\n</details>"
+        ));
+        assert!(markdown.contains(
+            "<details>\n<summary>Tool result: search</summary>\n\n```\nfound </pre> & more\n```\n\n</details>"
+        ));
+        assert!(!markdown.contains("**Tool**"));
+
+        let html = render(&transcript, TranscriptFormat::Html);
+        assert!(html.contains("<summary>Tool call: search</summary>"));
+        assert!(html.contains(
+            "<summary>Tool result: search</summary>\n<pre>found &lt;/pre&gt; &amp; more</pre>"
+        ));
+        assert_eq!(html.matches("<details>").count(), 2);
+    }
+
+    #[test]
+    fn results_without_a_known_call_are_labelled_generically() {
+        let mut result = message("tool", "42");
+        result.tool_call_id = Some("elsewhere".to_string());
+        let markdown = render(&transcript("t", vec![result]), TranscriptFormat::Markdown);
+        assert!(markdown.contains("<summary>Tool result: tool</summary>"));
+    }
+
+    #[test]
+    fn jsonl_has_a_header_and_a_line_per_message() {
+        let jsonl = render(&with_tool_call(), TranscriptFormat::Jsonl);
+        let lines: Vec<Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["type"], "conversation");
+        assert_eq!(lines[0]["message_count"], 3);
+        assert_eq!(lines[2]["tool_calls"][0]["name"], "search");
+        assert_eq!(lines[3]["tool_call_id"], "call-1");
+        assert_eq!(lines[3]["conversation_id"], "3f2a9c1e-77b0-4d5e");
+    }
+
+    #[test]
+    fn file_names_are_dated_slugs() {
+        let md = TranscriptFormat::Markdown;
+        assert_eq!(
+            file_name(&transcript("Deploy checklist: v2!", vec![]), md, false),
+            "2024-03-01-deploy-checklist-v2.md"
+        );
+        assert_eq!(
+            file_name(
+                &transcript("Deploy checklist", vec![]),
+                TranscriptFormat::Html,
+                true
+            ),
+            "2024-03-01-deploy-checklist-3f2a9c1e.html"
+        );
+        assert_eq!(
+            file_name(&transcript("../../etc/passwd", vec![]), md, false),
+            "2024-03-01-etc-passwd.md"
+        );
+        assert_eq!(
+            file_name(&transcript("???", vec![]), md, false),
+            "2024-03-01-conversation.md"
+        );
+        assert_eq!(
+            file_name(&transcript("Café 周报", vec![]), md, false),
+            "2024-03-01-café-周报.md"
+        );
+
+        let stem = |title: &str| {
+            let name = file_name(&transcript(title, vec![]), md, false);
+            name["2024-03-01-".len()..name.len() - ".md".len()].to_string()
+        };
+        assert_eq!(stem(&"x".repeat(100)).chars().count(), MAX_FILE_STEM_CHARS);
+        let words = stem(&"word ".repeat(40));
+        assert!(words.chars().count() <= MAX_FILE_STEM_CHARS);
+        assert!(words.ends_with("word"));
+
+        let mut undated = transcript("Notes", vec![]);
+        undated.created_at = "1709288430.5".to_string();
+        assert_eq!(file_name(&undated, md, false), "notes.md");
+    }
+
+    #[test]
+    fn the_agents_copy_has_tool_calls() {
+        // The tables `memory/storage.py` creates
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE conversations (
+                id TEXT PRIMARY KEY,
+                title TEXT,
+                created_at REAL NOT NULL,
+                updated_at REAL NOT NULL,
+                metadata TEXT NOT NULL DEFAULT '{}'
+            );
+            CREATE TABLE messages (
+                id TEXT PRIMARY KEY,
+                conversation_id TEXT NOT NULL,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                timestamp REAL NOT NULL,
+                tool_calls TEXT NOT NULL DEFAULT '[]',
+                tool_call_id TEXT,
+                metadata TEXT NOT NULL DEFAULT '{}'
+            );
+            INSERT INTO conversations VALUES ('c1', NULL, 1709288430.5, 1709288500, '{}');
+            INSERT INTO messages VALUES
+                ('m2', 'c1', 'assistant', '', 1709288431,
+                 '[{\"id\": \"call-1\", \"name\": \"search\", \"arguments\": {\"q\": 1}, \"status\": \"completed\"}]',
+                 NULL, '{}'),
+                ('m1', 'c1', 'user', 'find it', 1709288430.5, '[]', NULL, '{}'),
+                ('m3', 'c1', 'tool', 'found', 1709288432, '[]', 'call-1', '{}'),
+                ('x1', 'other', 'user', 'not this one', 1709288430, '[]', NULL, '{}');",
+        )
+        .unwrap();
+
+        assert!(from_profile_db(&conn, "missing").unwrap().is_none());
+        let transcript = from_profile_db(&conn, "c1").unwrap().unwrap();
+
+        assert_eq!(transcript.title, UNTITLED);
+        assert!(is_iso_date(&transcript.created_at[..10]));
+        assert_eq!(&transcript.created_at[10..11], "T");
+        let ids: Vec<&str> = transcript.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["m1", "m2", "m3"]);
+        assert_eq!(transcript.messages[1].tool_calls[0].name, "search");
+        assert_eq!(
+            transcript.messages[1].tool_calls[0].arguments,
+            json!({ "q": 1 })
+        );
+        assert_eq!(
+            transcript.messages[2].tool_call_id.as_deref(),
+            Some("call-1")
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! The tray icon: backend status and controls, quick actions, recent
+//! conversations and the minimize-to-tray switch.
+
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
+use tokio::sync::watch;
+use tracing::error;
+
+use crate::backend::{BackendHealth, BackendState, BackendSupervisor, HealthStatus};
+use crate::chat::ChatBridge;
+use crate::deskflow_client::models::{ConversationSummary, StreamChunkType};
+use crate::deskflow_client::DeskflowClient;
+use crate::events::{navigate, settings_changed, Navigate};
+use crate::settings::{SettingsStore, ShellSettings};
+use crate::windows::{show_main_window, show_search, toggle_quick_ask};
+
+const RECENT_CONVERSATIONS: u32 = 5;
+const CONVERSATION_ITEM_PREFIX: &str = "conversation:";
+
+/// Tray menu items that change at runtime.
+pub struct Tray {
+    status: MenuItem<Wry>,
+    start: MenuItem<Wry>,
+    stop: MenuItem<Wry>,
+    recent: Submenu<Wry>,
+    minimize_to_tray: CheckMenuItem<Wry>,
+}
+
+impl Tray {
+    pub fn new(app: &tauri::App, settings: &ShellSettings) -> tauri::Result<Self> {
+        let status = MenuItem::with_id(app, "status", "Backend: stopped", false, None::<&str>)?;
+        let start = MenuItem::with_id(app, "backend_start", "Start service", true, None::<&str>)?;
+        let stop = MenuItem::with_id(app, "backend_stop", "Stop service", false, None::<&str>)?;
+        let new_chat = MenuItem::with_id(app, "new_chat", "New chat", true, None::<&str>)?;
+        let quick_ask = MenuItem::with_id(app, "quick_ask", "Quick ask", true, None::<&str>)?;
+        let search = MenuItem::with_id(app, "search", "Search…", true, None::<&str>)?;
+        let recent = Submenu::with_id(app, "recent", "Recent conversations", true)?;
+        let show = MenuItem::with_id(app, "show", "Show DeskFlow", true, None::<&str>)?;
+        let minimize_to_tray = CheckMenuItem::with_id(
+            app,
+            "minimize_to_tray",
+            "Keep running in tray when closed",
+            true,
+            settings.minimize_to_tray,
+            None::<&str>,
+        )?;
+        let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+        let menu = Menu::with_items(
+            app,
+            &[
+                &status,
+                &PredefinedMenuItem::separator(app)?,
+                &start,
+                &stop,
+                &PredefinedMenuItem::separator(app)?,
+                &new_chat,
+                &quick_ask,
+                &search,
+                &recent,
+                &PredefinedMenuItem::separator(app)?,
+                &show,
+                &minimize_to_tray,
+                &quit,
+            ],
+        )?;
+
+        let mut builder = TrayIconBuilder::with_id("main")
+            .menu(&menu)
+            .tooltip("Coolaw DeskFlow")
+            .show_menu_on_left_click(false)
+            .on_menu_event(on_menu)
+            .on_tray_icon_event(|tray, event| {
+                if let TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    ..
+                } = event
+                {
+                    show_main_window(tray.app_handle());
+                }
+            });
+        if let Some(icon) = app.default_window_icon() {
+            builder = builder.icon(icon.clone());
+        }
+        builder.build(app)?;
+
+        let tray = Tray {
+            status,
+            start,
+            stop,
+            recent,
+            minimize_to_tray,
+        };
+        tray.show_conversations(app.handle(), &[])?;
+        Ok(tray)
+    }
+
+    fn show_status(&self, state: &BackendState, health: &BackendHealth) {
+        let text = match state {
+            BackendState::Running { .. } if health.is_reachable() => {
+                format!("Backend: running ({})", health_label(health.status))
+            }
+            BackendState::Running { .. } => "Backend: running, not responding".to_string(),
+            BackendState::Starting { .. } => "Backend: starting…".to_string(),
+            BackendState::Restarting { attempt, .. } => {
+                format!("Backend: restarting (attempt {attempt})")
+            }
+            BackendState::Stopping => "Backend: stopping…".to_string(),
+            // Not ours, but someone is serving on our port
+            BackendState::Stopped if health.is_reachable() => {
+                format!("Backend: external ({})", health_label(health.status))
+            }
+            BackendState::Stopped => "Backend: stopped".to_string(),
+            BackendState::Failed { .. } => "Backend: failed".to_string(),
+        };
+        let active = matches!(
+            state,
+            BackendState::Running { .. }
+                | BackendState::Starting { .. }
+                | BackendState::Restarting { .. }
+        );
+
+        let _ = self.status.set_text(text);
+        let _ = self
+            .start
+            .set_enabled(!active && *state != BackendState::Stopping);
+        let _ = self.stop.set_enabled(active);
+    }
+
+    fn show_conversations(
+        &self,
+        app: &AppHandle,
+        conversations: &[ConversationSummary],
+    ) -> tauri::Result<()> {
+        for item in self.recent.items()? {
+            self.recent.remove(&item)?;
+        }
+
+        if conversations.is_empty() {
+            let empty = MenuItem::with_id(
+                app,
+                "no_conversations",
+                "No conversations yet",
+                false,
+                None::<&str>,
+            )?;
+            return self.recent.append(&empty);
+        }
+
+        for conversation in conversations {
+            let title = conversation.title.trim();
+            let title = if title.is_empty() { "Untitled" } else { title };
+            let item = MenuItem::with_id(
+                app,
+                format!("{CONVERSATION_ITEM_PREFIX}{}", conversation.id),
+                truncate(title, 40),
+                true,
+                None::<&str>,
+            )?;
+            self.recent.append(&item)?;
+        }
+        Ok(())
+    }
+
+    pub fn show_settings(&self, settings: &ShellSettings) {
+        let _ = self.minimize_to_tray.set_checked(settings.minimize_to_tray);
+    }
+}
+
+fn health_label(status: HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Ok => "healthy",
+        HealthStatus::Degraded => "degraded",
+        HealthStatus::Error => "error",
+        HealthStatus::Unreachable => "unreachable",
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(max_chars - 1).collect();
+    short.push('…');
+    short
+}
+
+fn on_menu(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "backend_start" => app.state::<BackendSupervisor>().start(),
+        "backend_stop" => {
+            let supervisor = app.state::<BackendSupervisor>().inner().clone();
+            tauri::async_runtime::spawn(async move { supervisor.stop().await });
+        }
+        "new_chat" => navigate(app, Navigate::chat(None)),
+        "quick_ask" => toggle_quick_ask(app),
+        "search" => show_search(app),
+        "show" => show_main_window(app),
+        "minimize_to_tray" => {
+            let settings = app.state::<SettingsStore>();
+            match settings.update(|s| s.minimize_to_tray = !s.minimize_to_tray) {
+                Ok(updated) => settings_changed(app, &updated),
+                Err(e) => error!("Failed to save shell settings: {e}"),
+            }
+        }
+        "quit" => app.exit(0),
+        id => {
+            if let Some(conversation_id) = id.strip_prefix(CONVERSATION_ITEM_PREFIX) {
+                navigate(app, Navigate::chat(Some(conversation_id.to_string())));
+            }
+        }
+    }
+}
+
+/// Keep the tray in sync with the backend: status on every lifecycle or
+/// health change, recent conversations whenever they may have changed.
+pub fn spawn_updates(app: &tauri::App, mut health: watch::Receiver<BackendHealth>) {
+    let handle = app.handle().clone();
+    let mut states = app.state::<BackendSupervisor>().subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let state = states.borrow_and_update().clone();
+            let report = health.borrow_and_update().clone();
+            handle.state::<Tray>().show_status(&state, &report);
+            if report.is_reachable() {
+                refresh_recent_conversations(&handle).await;
+            }
+
+            tokio::select! {
+                changed = states.changed() => if changed.is_err() { break },
+                changed = health.changed() => if changed.is_err() { break },
+            }
+        }
+    });
+
+    let handle = app.handle().clone();
+    let mut events = app.state::<ChatBridge>().subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) if event.chunk.kind == StreamChunkType::Done => {
+                    refresh_recent_conversations(&handle).await;
+                }
+                Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+async fn refresh_recent_conversations(app: &AppHandle) {
+    let client = app.state::<DeskflowClient>().inner().clone();
+    if let Ok(list) = client.conversations(RECENT_CONVERSATIONS).await {
+        let _ = app
+            .state::<Tray>()
+            .show_conversations(app, &list.conversations);
+    }
+}
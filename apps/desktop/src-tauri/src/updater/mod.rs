@@ -0,0 +1,608 @@
+//! Updates of the app itself, through `tauri-plugin-updater`.
+//!
+//! Releases are signed with the project's key and the plugin checks every
+//! download against the public key in `plugins.updater.pubkey`. Release
+//! builds fail without one (see `build.rs`); a debug build without one never
+//! offers updates. Each [`UpdateChannel`] has its own release manifest.
+//! [`UPDATE_URL_ENV`] replaces them, e.g. with a local server while testing
+//! (plain `http` is only accepted by debug builds).
+//!
+//! Checks run on the schedule in the shell settings and a release found
+//! downloads in the background; installing waits for the user. Before the
+//! binaries are swapped the backend is stopped and the active profile
+//! snapshotted (see [`rollback`]). The new version's backend migrates the
+//! data on its first start; if it does not come up healthy the snapshot is
+//! put back, along with the previous app where the update replaced it in
+//! place. Progress goes to the webview as `updater://status`.
+
+pub mod rollback;
+
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tracing::{error, info, warn};
+
+use self::rollback::PendingUpdate;
+use crate::backend::{BackendState, BackendSupervisor, HealthProbe, HealthStatus};
+use crate::error::{DeskflowError, DeskflowResult};
+use crate::profile::ProfileStore;
+use crate::runtime::now_ms;
+use crate::settings::{SettingsStore, UpdateChannel};
+use crate::state::AppState;
+use crate::windows::show_error;
+
+/// Replaces the release manifest of every channel; `{{channel}}` in it
+/// becomes `stable` or `beta`, e.g.
+/// `DESKFLOW_UPDATE_URL=http://127.0.0.1:8000/{{channel}}.json`.
+pub const UPDATE_URL_ENV: &str = "DESKFLOW_UPDATE_URL";
+
+const STABLE_MANIFEST: &str =
+    "https://github.com/coolaw/coolaw-deskflow/releases/latest/download/latest.json";
+const BETA_MANIFEST: &str =
+    "https://github.com/coolaw/coolaw-deskflow/releases/download/beta/latest.json";
+
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+const SCHEDULE_TICK: Duration = Duration::from_secs(15 * 60);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long the backend of a freshly installed version gets to migrate the
+/// data and answer healthy.
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(120);
+const VERIFY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Where the updater is, emitted as `updater://status`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum UpdateStatus {
+    Idle,
+    /// This build has no key to verify updates with.
+    Disabled,
+    Checking,
+    UpToDate {
+        checked_at_ms: u64,
+    },
+    Available {
+        version: String,
+        notes: Option<String>,
+    },
+    Downloading {
+        version: String,
+        received: u64,
+        total: Option<u64>,
+    },
+    /// Downloaded and verified, waiting to be installed.
+    Ready {
+        version: String,
+        notes: Option<String>,
+    },
+    Installing {
+        version: String,
+    },
+    /// This run finished an update: the new backend came up healthy.
+    Updated {
+        from: String,
+        to: String,
+    },
+    /// The new backend did not come up, so the data from before the update
+    /// was put back.
+    RolledBack {
+        from: String,
+        to: String,
+        message: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+/// A release the last check found, and its package once downloaded.
+struct Found {
+    update: Update,
+    bytes: Option<Vec<u8>>,
+}
+
+/// The updater's state, managed by the app.
+pub struct Updates {
+    status: Mutex<UpdateStatus>,
+    found: Mutex<Option<Found>>,
+    last_check_ms: Mutex<u64>,
+    /// Held while checking, downloading or installing.
+    busy: tokio::sync::Mutex<()>,
+}
+
+impl Updates {
+    fn new(enabled: bool) -> Self {
+        Self {
+            status: Mutex::new(if enabled {
+                UpdateStatus::Idle
+            } else {
+                UpdateStatus::Disabled
+            }),
+            found: Mutex::new(None),
+            last_check_ms: Mutex::new(0),
+            busy: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn status(&self) -> UpdateStatus {
+        self.status
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// The downloaded release, if there is one.
+    fn ready(&self) -> Option<UpdateStatus> {
+        let found = self.found.lock().unwrap_or_else(PoisonError::into_inner);
+        let found = found.as_ref().filter(|found| found.bytes.is_some())?;
+        Some(UpdateStatus::Ready {
+            version: found.update.version.clone(),
+            notes: found.update.body.clone(),
+        })
+    }
+}
+
+/// Manage the updater state, finish an update installed by the previous
+/// run and start the scheduled checks.
+pub fn init(app: &tauri::App, probe: HealthProbe) {
+    app.manage(Updates::new(has_signing_key(app.handle())));
+    spawn_verification(app, probe);
+    spawn_schedule(app);
+}
+
+/// Look for a release on the configured channel and, with automatic
+/// downloads on, fetch it in the background.
+pub async fn check(app: &AppHandle) -> DeskflowResult<UpdateStatus> {
+    let updates = app.state::<Updates>();
+    if updates.status() == UpdateStatus::Disabled {
+        return Ok(UpdateStatus::Disabled);
+    }
+    let Ok(busy) = updates.busy.try_lock() else {
+        return Ok(updates.status());
+    };
+    *updates
+        .last_check_ms
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = now_ms();
+    set_status(app, UpdateStatus::Checking);
+
+    let settings = app.state::<SettingsStore>().get();
+    let status = match find(app, settings.update_channel).await {
+        Ok(None) => {
+            *updates.found.lock().unwrap_or_else(PoisonError::into_inner) = None;
+            UpdateStatus::UpToDate {
+                checked_at_ms: now_ms(),
+            }
+        }
+        Ok(Some(update)) => {
+            let available = UpdateStatus::Available {
+                version: update.version.clone(),
+                notes: update.body.clone(),
+            };
+            let mut found = updates.found.lock().unwrap_or_else(PoisonError::into_inner);
+            // Keep a download of the same release
+            if found
+                .as_ref()
+                .is_none_or(|found| found.update.version != update.version)
+            {
+                *found = Some(Found {
+                    update,
+                    bytes: None,
+                });
+            }
+            drop(found);
+            updates.ready().unwrap_or(available)
+        }
+        Err(e) => {
+            let e = e.context("Update check failed");
+            set_status(
+                app,
+                updates.ready().unwrap_or(UpdateStatus::Failed {
+                    message: e.message().to_string(),
+                }),
+            );
+            return Err(e);
+        }
+    };
+    set_status(app, status.clone());
+    drop(busy);
+
+    if matches!(status, UpdateStatus::Available { .. }) && settings.auto_download_updates {
+        let handle = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = download(&handle).await {
+                warn!("{e}");
+            }
+        });
+    }
+    Ok(status)
+}
+
+/// Download the release the last check found. The plugin verifies its
+/// signature before handing it over.
+pub async fn download(app: &AppHandle) -> DeskflowResult<UpdateStatus> {
+    let updates = app.state::<Updates>();
+    let Ok(_busy) = updates.busy.try_lock() else {
+        return Err(busy());
+    };
+    if let Some(ready) = updates.ready() {
+        return Ok(ready);
+    }
+    let Some(update) = updates
+        .found
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .map(|found| found.update.clone())
+    else {
+        return Err(DeskflowError::NotFound {
+            message: "No update to download; check for updates first".to_string(),
+        });
+    };
+
+    let version = update.version.clone();
+    info!(version, "Downloading update");
+    set_status(
+        app,
+        UpdateStatus::Downloading {
+            version: version.clone(),
+            received: 0,
+            total: None,
+        },
+    );
+    let (mut received, mut reported) = (0u64, 0u64);
+    let result = update
+        .download(
+            |chunk, total| {
+                received += chunk as u64;
+                // Report every percent, or every MiB when the size is unknown
+                let step = total.map_or(1 << 20, |total| (total / 100).max(1));
+                if received - reported >= step {
+                    reported = received;
+                    set_status(
+                        app,
+                        UpdateStatus::Downloading {
+                            version: version.clone(),
+                            received,
+                            total,
+                        },
+                    );
+                }
+            },
+            || {},
+        )
+        .await;
+
+    match result {
+        Ok(bytes) => {
+            if let Some(found) = updates
+                .found
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_mut()
+            {
+                found.bytes = Some(bytes);
+            }
+            let status = updates.ready().unwrap_or(UpdateStatus::Idle);
+            info!(version, "Update downloaded");
+            set_status(app, status.clone());
+            Ok(status)
+        }
+        Err(e) => {
+            let e = DeskflowError::from(e).context("Downloading the update failed");
+            set_status(
+                app,
+                UpdateStatus::Failed {
+                    message: e.message().to_string(),
+                },
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Install the downloaded release and restart into it, after stopping the
+/// backend and snapshotting the active profile. Only returns on failure,
+/// with the backend running again if it was.
+pub async fn install(app: &AppHandle) -> DeskflowResult<()> {
+    let updates = app.state::<Updates>();
+    let Ok(_busy) = updates.busy.try_lock() else {
+        return Err(busy());
+    };
+    let Some((update, bytes)) = updates
+        .found
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .and_then(|found| {
+            let bytes = found.bytes.clone()?;
+            Some((found.update.clone(), bytes))
+        })
+    else {
+        return Err(DeskflowError::NotFound {
+            message: "No update has been downloaded".to_string(),
+        });
+    };
+
+    let version = update.version.clone();
+    set_status(
+        app,
+        UpdateStatus::Installing {
+            version: version.clone(),
+        },
+    );
+    let supervisor = app.state::<BackendSupervisor>().inner().clone();
+    let was_running = supervisor.stop().await;
+
+    let dirs = app.state::<ProfileStore>().current();
+    let app_path = replaced_in_place(app);
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let prepared = rollback::prepare(
+            &dirs,
+            &update.current_version,
+            &update.version,
+            app_path.as_deref(),
+        )
+        .map_err(|e| DeskflowError::from(e).context("Cannot snapshot the profile"))
+        .and_then(|_| Ok(update.install(bytes)?));
+        if prepared.is_err() {
+            let _ = rollback::discard(&dirs);
+        }
+        prepared
+    })
+    .await?;
+
+    if let Err(e) = result {
+        let e = e.context("Installing the update failed");
+        error!("{e}");
+        if was_running {
+            supervisor.start();
+        }
+        set_status(
+            app,
+            UpdateStatus::Failed {
+                message: e.message().to_string(),
+            },
+        );
+        return Err(e);
+    }
+    info!(version, "Update installed, restarting");
+    app.restart()
+}
+
+/// Check as often as the settings ask, starting shortly after launch.
+fn spawn_schedule(app: &tauri::App) {
+    let handle = app.handle().clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FIRST_CHECK_DELAY).await;
+        let mut ticks = tokio::time::interval(SCHEDULE_TICK);
+        loop {
+            ticks.tick().await;
+            let hours = handle.state::<SettingsStore>().get().update_check_hours;
+            let last = *handle
+                .state::<Updates>()
+                .last_check_ms
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if hours == 0 || now_ms() < last + u64::from(hours) * 3_600_000 {
+                continue;
+            }
+            if let Err(e) = check(&handle).await {
+                warn!("{e}");
+            }
+        }
+    });
+}
+
+/// Judge the update the previous run installed by its backend: drop the
+/// snapshot once it answers healthy, roll back if it does not in time.
+fn spawn_verification(app: &tauri::App, probe: HealthProbe) {
+    let profiles = app.state::<ProfileStore>().inner().clone();
+    let current = profiles.current();
+    let Some(pending) = rollback::pending(&current) else {
+        return;
+    };
+    let version = app.package_info().version.to_string();
+    if version != pending.to_version || app.state::<AppState>().external_backend() {
+        // The installer did not run through, or the backend is not ours to
+        // judge the update by
+        info!(
+            from = pending.from_version,
+            to = pending.to_version,
+            version,
+            "Dropping the update snapshot"
+        );
+        if let Err(e) = rollback::discard(&current) {
+            warn!("Failed to remove the update snapshot: {e}");
+        }
+        return;
+    }
+
+    let handle = app.handle().clone();
+    let supervisor = app.state::<BackendSupervisor>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        match wait_until_healthy(&supervisor, &probe).await {
+            Ok(()) => {
+                info!(
+                    from = pending.from_version,
+                    to = pending.to_version,
+                    "Update verified"
+                );
+                if let Err(e) = rollback::discard(&current) {
+                    warn!("Failed to remove the update snapshot: {e}");
+                }
+                set_status(
+                    &handle,
+                    UpdateStatus::Updated {
+                        from: pending.from_version,
+                        to: pending.to_version,
+                    },
+                );
+            }
+            Err(reason) => roll_back(&handle, &supervisor, &profiles, pending, reason).await,
+        }
+    });
+}
+
+async fn wait_until_healthy(
+    supervisor: &BackendSupervisor,
+    probe: &HealthProbe,
+) -> Result<(), String> {
+    let deadline = Instant::now() + VERIFY_TIMEOUT;
+    let mut last = "the backend did not start".to_string();
+    while Instant::now() < deadline {
+        // Out of restarts
+        if let BackendState::Failed { message, .. } = supervisor.state() {
+            return Err(message);
+        }
+        let health = probe.check(false).await;
+        match health.status {
+            HealthStatus::Ok | HealthStatus::Degraded => return Ok(()),
+            status => {
+                last = health
+                    .error
+                    .unwrap_or_else(|| format!("the backend reports {status:?}"))
+            }
+        }
+        tokio::time::sleep(VERIFY_INTERVAL).await;
+    }
+    Err(last)
+}
+
+/// Put the data from before `pending` back, and the previous app if the
+/// update replaced it in place, then tell the user.
+async fn roll_back(
+    app: &AppHandle,
+    supervisor: &BackendSupervisor,
+    profiles: &ProfileStore,
+    pending: PendingUpdate,
+    reason: String,
+) {
+    error!(
+        from = pending.from_version,
+        to = pending.to_version,
+        reason,
+        "Backend unhealthy after updating, rolling back"
+    );
+    supervisor.stop().await;
+    let restored = match profiles.dirs(&pending.profile) {
+        Ok(dirs) => {
+            let pending = pending.clone();
+            tauri::async_runtime::spawn_blocking(move || rollback::restore(&dirs, &pending))
+                .await
+                .map_err(DeskflowError::from)
+                .and_then(|result| Ok(result?))
+        }
+        Err(e) => Err(e.into()),
+    };
+    let (from, to) = (pending.from_version, pending.to_version);
+
+    match restored {
+        Ok(true) => {
+            info!(version = from, "Previous version restored, restarting");
+            let handle = app.clone();
+            app.dialog()
+                .message(format!(
+                    "DeskFlow {to} could not start its backend ({reason}). DeskFlow {from} \
+                     and your data from before the update have been restored and will \
+                     start now."
+                ))
+                .title("Update rolled back")
+                .kind(MessageDialogKind::Warning)
+                .show(move |_| handle.restart());
+        }
+        Ok(false) => {
+            let message = format!(
+                "DeskFlow {to} could not start its backend ({reason}). Your data from before \
+                 the update has been restored; reinstall DeskFlow {from} to keep using it."
+            );
+            show_error(app, "Update rolled back", &message);
+            set_status(app, UpdateStatus::RolledBack { from, to, message });
+        }
+        Err(e) => {
+            error!("Rolling back the update failed: {e}");
+            let message = format!(
+                "DeskFlow {to} could not start its backend ({reason}), and restoring the \
+                 data from before the update failed: {e}"
+            );
+            show_error(app, "Update failed", &message);
+            set_status(app, UpdateStatus::Failed { message });
+        }
+    }
+}
+
+async fn find(app: &AppHandle, channel: UpdateChannel) -> DeskflowResult<Option<Update>> {
+    let updater = app
+        .updater_builder()
+        .endpoints(endpoints(channel)?)?
+        .build()?;
+    match tokio::time::timeout(CHECK_TIMEOUT, updater.check()).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(DeskflowError::Timeout {
+            message: "The update server did not answer".to_string(),
+        }),
+    }
+}
+
+fn endpoints(channel: UpdateChannel) -> DeskflowResult<Vec<Url>> {
+    let url = match std::env::var(UPDATE_URL_ENV) {
+        Ok(url) => url.replace("{{channel}}", channel.as_str()),
+        Err(_) => match channel {
+            UpdateChannel::Stable => STABLE_MANIFEST,
+            UpdateChannel::Beta => BETA_MANIFEST,
+        }
+        .to_string(),
+    };
+    let parsed = Url::parse(&url)
+        .map_err(|e| DeskflowError::invalid_input(format!("Invalid update URL {url}: {e}")))?;
+    Ok(vec![parsed])
+}
+
+/// Whether the build carries the public key updates are verified with.
+fn has_signing_key(app: &AppHandle) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|config| config.get("pubkey"))
+        .and_then(Value::as_str)
+        .is_some_and(|key| !key.trim().is_empty())
+}
+
+/// The app an update overwrites in place, which can be snapshotted and put
+/// back: the AppImage on Linux, the bundle on macOS. Packages and Windows
+/// installers are left to the system.
+fn replaced_in_place(app: &AppHandle) -> Option<PathBuf> {
+    #[cfg(target_os = "linux")]
+    let path = app.env().appimage.map(PathBuf::from);
+    #[cfg(target_os = "macos")]
+    let path = std::env::current_exe()
+        .ok()
+        .and_then(|exe| tauri_plugin_updater::extract_path_from_executable(&exe).ok())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "app"));
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let path = {
+        let _ = app;
+        None
+    };
+    path
+}
+
+fn set_status(app: &AppHandle, status: UpdateStatus) {
+    *app.state::<Updates>()
+        .status
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = status.clone();
+    let _ = app.emit("updater://status", status);
+}
+
+fn busy() -> DeskflowError {
+    DeskflowError::Busy {
+        message: "The updater is busy; try again in a moment".to_string(),
+    }
+}
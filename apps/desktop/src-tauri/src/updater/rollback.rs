@@ -0,0 +1,163 @@
+//! A snapshot of the active profile (and, where an update replaces it in
+//! place, of the app) taken right before an update is installed, so the
+//! update can be undone when the new backend does not come up.
+//!
+//! The snapshot lives in `.update-rollback` next to the profiles, which no
+//! profile can be called. `update.json` in it is written last and names
+//! the versions involved; the new version finds it on its first start and
+//! either [`discard`]s the snapshot once the backend is healthy or
+//! [`restore`]s it. Everything is copied as is, encrypted databases and
+//! their journals included, since the backend is stopped by then.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::profile::ProfileDirs;
+use crate::runtime::now_ms;
+
+pub const ROLLBACK_DIR: &str = ".update-rollback";
+
+const MARKER: &str = "update.json";
+const DATA_DIR: &str = "data";
+const CONFIG_DIR: &str = "config";
+const APP_DIR: &str = "app";
+
+/// An installed update that has not proven itself yet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingUpdate {
+    pub from_version: String,
+    pub to_version: String,
+    /// The profile that was active, and snapshotted.
+    pub profile: String,
+    /// The app the update replaced in place (an AppImage or a macOS
+    /// bundle), kept in the snapshot so it can be put back. `None` for
+    /// installers, which cannot be undone from here.
+    pub app_path: Option<PathBuf>,
+    /// Unix milliseconds.
+    pub created_at_ms: u64,
+}
+
+/// Where the snapshot for the profiles next to `dirs` goes.
+pub fn rollback_dir(dirs: &ProfileDirs) -> PathBuf {
+    dirs.data.with_file_name(ROLLBACK_DIR)
+}
+
+/// Snapshot the profile `dirs` and `app_path` before updating from
+/// `from_version` to `to_version`, replacing any older snapshot.
+pub fn prepare(
+    dirs: &ProfileDirs,
+    from_version: &str,
+    to_version: &str,
+    app_path: Option<&Path>,
+) -> io::Result<PendingUpdate> {
+    let root = rollback_dir(dirs);
+    discard(dirs)?;
+    copy_tree(&dirs.data, &root.join(DATA_DIR))?;
+    copy_tree(&dirs.config, &root.join(CONFIG_DIR))?;
+    if let Some(app) = app_path {
+        copy_tree(app, &root.join(APP_DIR))?;
+    }
+
+    let pending = PendingUpdate {
+        from_version: from_version.to_string(),
+        to_version: to_version.to_string(),
+        profile: dirs.name.clone(),
+        app_path: app_path.map(Path::to_path_buf),
+        created_at_ms: now_ms(),
+    };
+    // Only a complete snapshot gets a marker
+    let tmp = root.join(format!("{MARKER}.tmp"));
+    fs::write(&tmp, serde_json::to_vec_pretty(&pending)?)?;
+    fs::rename(&tmp, root.join(MARKER))?;
+    Ok(pending)
+}
+
+/// The update waiting to be confirmed by the profile `dirs`' backend, if
+/// any.
+pub fn pending(dirs: &ProfileDirs) -> Option<PendingUpdate> {
+    let raw = fs::read(rollback_dir(dirs).join(MARKER)).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+/// Drop the snapshot, keeping the update.
+pub fn discard(dirs: &ProfileDirs) -> io::Result<()> {
+    remove_if_exists(&rollback_dir(dirs))
+}
+
+/// Put the profile `dirs` back the way it was before `pending`, and the app
+/// too if the update replaced it in place. Returns whether the app was put
+/// back, i.e. a restart runs the previous version. The snapshot is only
+/// dropped once everything is back, so an interrupted restore can be
+/// tried again.
+pub fn restore(dirs: &ProfileDirs, pending: &PendingUpdate) -> io::Result<bool> {
+    let root = rollback_dir(dirs);
+    replace(&root.join(DATA_DIR), &dirs.data)?;
+    replace(&root.join(CONFIG_DIR), &dirs.config)?;
+    let snapshot = root.join(APP_DIR);
+    let app_restored = match &pending.app_path {
+        Some(app) if snapshot.exists() => {
+            replace(&snapshot, app)?;
+            true
+        }
+        _ => false,
+    };
+    discard(dirs)?;
+    Ok(app_restored)
+}
+
+/// Make `dest` a copy of `snapshot`.
+fn replace(snapshot: &Path, dest: &Path) -> io::Result<()> {
+    remove_if_exists(dest)?;
+    copy_tree(snapshot, dest)
+}
+
+/// Copy a file or a directory tree, keeping symlinks (macOS bundles are
+/// full of them) and, through `fs::copy`, permissions.
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    let kind = match fs::symlink_metadata(from) {
+        Ok(meta) => meta.file_type(),
+        // Nothing to keep, e.g. a profile without config yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if kind.is_symlink() {
+        return copy_link(from, to);
+    }
+    if kind.is_file() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_link(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_link(from: &Path, to: &Path) -> io::Result<()> {
+    fs::copy(from, to).map(|_| ())
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,167 @@
+//! The shell's windows: the main one, the quick-ask and search popups the
+//! frontend renders by window label, the quick-ask shortcut and native
+//! dialogs. Popups, the shortcut and dialogs only exist on desktop; on
+//! mobile the app is its one window and errors go to the log.
+
+use tauri::AppHandle;
+#[cfg(desktop)]
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder, Window, WindowEvent};
+#[cfg(desktop)]
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+#[cfg(desktop)]
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+use tracing::error;
+
+#[cfg(desktop)]
+use crate::settings::SettingsStore;
+
+pub const MAIN_LABEL: &str = "main";
+#[cfg(desktop)]
+pub const QUICK_ASK_LABEL: &str = "quick-ask";
+#[cfg(desktop)]
+pub const SEARCH_LABEL: &str = "search";
+
+#[cfg(desktop)]
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// The app's only window is always the one showing.
+#[cfg(mobile)]
+pub fn show_main_window(_app: &AppHandle) {}
+
+/// Show or hide the quick-ask popup, creating it on first use.
+#[cfg(desktop)]
+pub fn toggle_quick_ask(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(QUICK_ASK_LABEL) {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            let _ = window.center();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        return;
+    }
+
+    // The frontend picks the quick-ask view from the window label
+    let created = WebviewWindowBuilder::new(app, QUICK_ASK_LABEL, WebviewUrl::default())
+        .title("Quick Ask")
+        .inner_size(640.0, 360.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build();
+    if let Err(e) = created {
+        error!("Failed to open the quick-ask window: {e}");
+    }
+}
+
+/// Bring up the search palette, creating it on first use.
+#[cfg(desktop)]
+pub fn show_search(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(SEARCH_LABEL) {
+        let _ = window.center();
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+
+    // Like quick-ask, the frontend picks the view from the window label
+    let created = WebviewWindowBuilder::new(app, SEARCH_LABEL, WebviewUrl::default())
+        .title("Search")
+        .inner_size(720.0, 480.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build();
+    if let Err(e) = created {
+        error!("Failed to open the search window: {e}");
+    }
+}
+
+/// Bind the quick-ask toggle to `accelerator`, replacing any previous
+/// binding. An empty accelerator just clears it.
+#[cfg(desktop)]
+pub fn register_quick_ask_shortcut(app: &AppHandle, accelerator: &str) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts.unregister_all().map_err(|e| e.to_string())?;
+    if accelerator.trim().is_empty() {
+        return Ok(());
+    }
+    shortcuts
+        .register(accelerator.trim())
+        .map_err(|e| format!("Cannot register shortcut {accelerator}: {e}"))
+}
+
+#[cfg(desktop)]
+pub fn show_error(app: &AppHandle, title: &str, message: impl Into<String>) {
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+#[cfg(mobile)]
+pub fn show_error(_app: &AppHandle, title: &str, message: impl Into<String>) {
+    error!("{title}: {}", message.into());
+}
+
+/// Tell the user why the app cannot go on, then quit once they close the
+/// message. The main window stays hidden meanwhile.
+#[cfg(desktop)]
+pub fn show_fatal_error(app: &AppHandle, title: &str, message: impl Into<String>) {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        let _ = window.hide();
+    }
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Error)
+        .show(move |_| handle.exit(1));
+}
+
+#[cfg(mobile)]
+pub fn show_fatal_error(app: &AppHandle, title: &str, message: impl Into<String>) {
+    error!("{title}: {}", message.into());
+    app.exit(1);
+}
+
+#[cfg(desktop)]
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    // With minimize-to-tray the main window only hides; the backend and IM
+    // channels keep running until "Quit" in the tray
+    match event {
+        WindowEvent::CloseRequested { api, .. }
+            if window.label() == MAIN_LABEL
+                && window.state::<SettingsStore>().get().minimize_to_tray =>
+        {
+            api.prevent_close();
+            let _ = window.hide();
+        }
+        // Without the main window there is nothing left to show, even if a
+        // popup is still around
+        WindowEvent::Destroyed if window.label() == MAIN_LABEL => {
+            window.app_handle().exit(0);
+        }
+        // The popups get out of the way once they lose focus
+        WindowEvent::Focused(false)
+            if [QUICK_ASK_LABEL, SEARCH_LABEL].contains(&window.label()) =>
+        {
+            let _ = window.hide();
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,229 @@
+//! The chat bridge streaming from the mock backend, including what happens
+//! when the backend drops mid-reply or is down when a message is sent.
+
+mod common;
+
+use coolaw_deskflow_lib::chat::{ChatBridge, ChatEvent};
+use coolaw_deskflow_lib::deskflow_client::models::StreamChunkType;
+use serde_json::json;
+use tokio::sync::broadcast;
+
+use common::{text_reply, wait_for, MockBackend, StreamReply, WAIT};
+
+/// Collect the events of `request_id` up to and including its `done`.
+async fn reply(events: &mut broadcast::Receiver<ChatEvent>, request_id: &str) -> Vec<ChatEvent> {
+    let mut reply = Vec::new();
+    tokio::time::timeout(WAIT, async {
+        loop {
+            let event = events.recv().await.expect("chat bridge went away");
+            if event.request_id != request_id {
+                continue;
+            }
+            let done = event.chunk.kind == StreamChunkType::Done;
+            reply.push(event);
+            if done {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("timed out waiting for the reply");
+    reply
+}
+
+fn kinds(reply: &[ChatEvent]) -> Vec<StreamChunkType> {
+    reply.iter().map(|event| event.chunk.kind).collect()
+}
+
+#[tokio::test]
+async fn streams_a_reply() {
+    let backend = MockBackend::start().await;
+    let chat = ChatBridge::spawn(backend.chat_stream_url());
+    let mut events = chat.subscribe();
+
+    let request_id = chat
+        .send("hello".into(), None, Some("main".into()))
+        .unwrap();
+    let reply = reply(&mut events, &request_id).await;
+
+    assert_eq!(
+        kinds(&reply),
+        [
+            StreamChunkType::ConversationId,
+            StreamChunkType::Text,
+            StreamChunkType::Done
+        ]
+    );
+    assert_eq!(reply[1].chunk.content, "echo: hello");
+    assert_eq!(reply[2].conversation_id.as_deref(), Some("conv-mock"));
+    assert!(reply.iter().all(|e| e.origin.as_deref() == Some("main")));
+    assert_eq!(
+        backend.received(),
+        [json!({ "message": "hello", "conversation_id": null })]
+    );
+}
+
+#[tokio::test]
+async fn message_sent_while_down_is_delivered_after_restart() {
+    let mut backend = MockBackend::start().await;
+    let chat = ChatBridge::spawn(backend.chat_stream_url());
+    let mut connected = chat.subscribe_connection();
+    let mut events = chat.subscribe();
+    wait_for(&mut connected, |up| *up).await;
+
+    backend.stop().await;
+    wait_for(&mut connected, |up| !*up).await;
+    let request_id = chat
+        .send("still there?".into(), Some("conv-1".into()), None)
+        .unwrap();
+
+    backend.restart().await;
+    wait_for(&mut connected, |up| *up).await;
+    let reply = reply(&mut events, &request_id).await;
+
+    assert_eq!(reply[1].chunk.content, "echo: still there?");
+    assert_eq!(backend.received().len(), 1);
+}
+
+#[tokio::test]
+async fn hang_up_mid_reply_ends_with_an_error() {
+    let backend = MockBackend::start().await;
+    let mut partial = text_reply("conv-2", "half an ans");
+    partial.pop();
+    backend.script(|s| s.stream.push_back(StreamReply::HangUp(partial)));
+    let chat = ChatBridge::spawn(backend.chat_stream_url());
+    let mut events = chat.subscribe();
+
+    let request_id = chat.send("question".into(), None, None).unwrap();
+    let reply = reply(&mut events, &request_id).await;
+
+    assert_eq!(
+        kinds(&reply),
+        [
+            StreamChunkType::ConversationId,
+            StreamChunkType::Text,
+            StreamChunkType::Error,
+            StreamChunkType::Done
+        ]
+    );
+    // Not resent: the user already saw part of the answer
+    assert_eq!(backend.received().len(), 1);
+}
+
+#[tokio::test]
+async fn a_refused_message_is_finished_and_the_next_one_goes_through() {
+    let backend = MockBackend::start().await;
+    // What the backend sends for a message it will not answer: an error
+    // and no `done`
+    backend.script(|s| {
+        s.stream.push_back(StreamReply::Chunks(vec![
+            json!({ "type": "error", "content": "Empty message" }),
+        ]))
+    });
+    let chat = ChatBridge::spawn(backend.chat_stream_url());
+    let mut events = chat.subscribe();
+
+    let refused = chat.send("first".into(), None, None).unwrap();
+    let reply_to_refused = reply(&mut events, &refused).await;
+    assert_eq!(
+        kinds(&reply_to_refused),
+        [StreamChunkType::Error, StreamChunkType::Done]
+    );
+    assert_eq!(reply_to_refused[0].chunk.content, "Empty message");
+
+    let next = chat.send("hello".into(), None, None).unwrap();
+    assert_eq!(
+        reply(&mut events, &next).await[1].chunk.content,
+        "echo: hello"
+    );
+}
+
+#[tokio::test]
+async fn blank_messages_are_not_sent() {
+    let backend = MockBackend::start().await;
+    let chat = ChatBridge::spawn(backend.chat_stream_url());
+    let mut events = chat.subscribe();
+
+    for blank in ["", "  ", "\n\t"] {
+        let error = chat.send(blank.into(), None, None).unwrap_err();
+        assert_eq!(error.kind(), "invalid_input");
+    }
+    let request_id = chat.send("hi".into(), None, None).unwrap();
+    reply(&mut events, &request_id).await;
+    assert_eq!(backend.received().len(), 1);
+}
+
+#[tokio::test]
+async fn follow_ups_wait_for_the_conversation_they_continue() {
+    let mut backend = MockBackend::start().await;
+    let chat = ChatBridge::spawn(backend.chat_stream_url());
+    let mut connected = chat.subscribe_connection();
+    let mut events = chat.subscribe();
+    wait_for(&mut connected, |up| *up).await;
+    backend.stop().await;
+    wait_for(&mut connected, |up| !*up).await;
+
+    // The follow-up names the new conversation by its first message, and
+    // must not be overtaken by one sent to it by id later
+    let first = chat.send("first".into(), None, None).unwrap();
+    let follow_up = chat
+        .send("second".into(), Some(first.clone()), None)
+        .unwrap();
+    let by_id = chat
+        .send("third".into(), Some("conv-mock".into()), None)
+        .unwrap();
+
+    backend.restart().await;
+    for request_id in [&first, &follow_up, &by_id] {
+        let reply = reply(&mut events, request_id).await;
+        assert_eq!(reply[2].conversation_id.as_deref(), Some("conv-mock"));
+    }
+    assert_eq!(
+        backend.received(),
+        [
+            json!({ "message": "first", "conversation_id": null }),
+            json!({ "message": "second", "conversation_id": "conv-mock" }),
+            json!({ "message": "third", "conversation_id": "conv-mock" }),
+        ]
+    );
+
+    // Once started, the conversation is still found by its first message
+    let late = chat.send("fourth".into(), Some(first), None).unwrap();
+    reply(&mut events, &late).await;
+    assert_eq!(
+        backend.received()[3],
+        json!({ "message": "fourth", "conversation_id": "conv-mock" })
+    );
+}
+
+#[tokio::test]
+async fn follow_ups_of_a_refused_message_are_not_sent() {
+    let mut backend = MockBackend::start().await;
+    backend.script(|s| {
+        s.stream.push_back(StreamReply::Chunks(vec![
+            json!({ "type": "error", "content": "Empty message" }),
+        ]))
+    });
+    let chat = ChatBridge::spawn(backend.chat_stream_url());
+    let mut connected = chat.subscribe_connection();
+    let mut events = chat.subscribe();
+    wait_for(&mut connected, |up| *up).await;
+    backend.stop().await;
+    wait_for(&mut connected, |up| !*up).await;
+
+    let refused = chat.send("first".into(), None, None).unwrap();
+    let follow_up = chat
+        .send("second".into(), Some(refused.clone()), None)
+        .unwrap();
+
+    backend.restart().await;
+    assert_eq!(
+        kinds(&reply(&mut events, &refused).await),
+        [StreamChunkType::Error, StreamChunkType::Done]
+    );
+    assert_eq!(
+        kinds(&reply(&mut events, &follow_up).await),
+        [StreamChunkType::Error, StreamChunkType::Done]
+    );
+    assert_eq!(backend.received().len(), 1);
+}
@@ -0,0 +1,211 @@
+//! IPC commands invoked through Tauri's mock runtime against the mock
+//! backend, the way the webview calls them. Needs the `test-harness`
+//! feature.
+
+mod common;
+
+use coolaw_deskflow_lib::backend::{commands as backend_api, BackendEndpoint, HealthProbe};
+use coolaw_deskflow_lib::commands::shell;
+use coolaw_deskflow_lib::deskflow_client::{commands as api, DeskflowClient};
+use coolaw_deskflow_lib::instance::LaunchRequest;
+use coolaw_deskflow_lib::state::AppState;
+use serde_json::{json, Value};
+use tauri::ipc::{CallbackFn, InvokeBody};
+use tauri::test::{get_ipc_response, mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri::webview::InvokeRequest;
+use tauri::{App, Manager, WebviewWindow, WebviewWindowBuilder};
+
+use common::{llm_not_configured_reply, MockBackend, Reply};
+
+struct Shell {
+    app: App<MockRuntime>,
+    window: WebviewWindow<MockRuntime>,
+}
+
+impl Shell {
+    /// The shell's managed state and commands, pointed at `endpoint`.
+    fn new(endpoint: BackendEndpoint) -> Self {
+        let app = mock_builder()
+            .manage(HealthProbe::new(endpoint.url()))
+            .manage(DeskflowClient::new(endpoint.url()))
+            .manage(endpoint)
+            .manage(AppState::new(endpoint, true))
+            .invoke_handler(tauri::generate_handler![
+                backend_api::check_backend_health,
+                backend_api::get_backend_url,
+                shell::take_launch_requests,
+                api::api_chat,
+                api::api_config,
+                api::api_update_config,
+            ])
+            .build(mock_context(noop_assets()))
+            .expect("failed to build the mock app");
+        let window = WebviewWindowBuilder::new(&app, "main", Default::default())
+            .build()
+            .expect("failed to build the mock window");
+        Self { app, window }
+    }
+
+    fn invoke(&self, cmd: &str, args: Value) -> Result<Value, Value> {
+        let request = InvokeRequest {
+            cmd: cmd.into(),
+            callback: CallbackFn(0),
+            error: CallbackFn(1),
+            url: "tauri://localhost".parse().unwrap(),
+            body: InvokeBody::Json(args),
+            headers: Default::default(),
+            invoke_key: tauri::test::INVOKE_KEY.to_string(),
+        };
+        get_ipc_response(&self.window, request).map(|body| body.deserialize().unwrap())
+    }
+}
+
+fn start_backend() -> MockBackend {
+    tauri::async_runtime::block_on(MockBackend::start())
+}
+
+#[test]
+fn backend_url_is_the_endpoint() {
+    let shell = Shell::new(BackendEndpoint::new(8420));
+
+    let url = shell.invoke("get_backend_url", json!({})).unwrap();
+
+    assert_eq!(url, "http://127.0.0.1:8420");
+}
+
+#[test]
+fn launch_requests_are_handed_over_once() {
+    let shell = Shell::new(BackendEndpoint::new(8420));
+    let state = shell.app.state::<AppState>();
+    state.queue_launch(LaunchRequest {
+        prompt: Some("summarize this".into()),
+        files: vec!["/tmp/notes.md".into()],
+    });
+    state.queue_launch(LaunchRequest {
+        prompt: None,
+        files: vec!["/tmp/todo.txt".into()],
+    });
+
+    let launches = shell.invoke("take_launch_requests", json!({})).unwrap();
+    assert_eq!(
+        launches,
+        json!([
+            { "prompt": "summarize this", "files": ["/tmp/notes.md"] },
+            { "prompt": null, "files": ["/tmp/todo.txt"] },
+        ])
+    );
+
+    let launches = shell.invoke("take_launch_requests", json!({})).unwrap();
+    assert_eq!(launches, json!([]));
+}
+
+#[test]
+fn health_follows_the_backend() {
+    let mut backend = start_backend();
+    let shell = Shell::new(backend.endpoint());
+    let status = |shell: &Shell| shell.invoke("check_backend_health", json!({})).unwrap();
+
+    let health = status(&shell);
+    assert_eq!(health["status"], "ok");
+    assert_eq!(health["version"], "0.1.0-mock");
+    assert!(health["latency_ms"].is_u64());
+
+    backend.script(|s| s.health = Reply::Json(json!({ "status": "degraded" })));
+    assert_eq!(status(&shell)["status"], "degraded");
+
+    backend.script(|s| s.health = Reply::Json(json!({ "status": "error" })));
+    assert_eq!(status(&shell)["status"], "error");
+
+    backend.script(|s| s.health = Reply::Status(500, json!({ "detail": "boom" })));
+    assert_eq!(status(&shell)["status"], "unreachable");
+
+    tauri::async_runtime::block_on(backend.stop());
+    let health = status(&shell);
+    assert_eq!(health["status"], "unreachable");
+    assert!(health["error"].is_string());
+}
+
+#[test]
+fn detailed_health_failure_is_reported_not_fatal() {
+    let backend = start_backend();
+    let shell = Shell::new(backend.endpoint());
+    backend.script(|s| s.detailed_health = Reply::Status(503, json!({})));
+
+    let health = shell.invoke("check_backend_health", json!({})).unwrap();
+
+    assert_eq!(health["status"], "ok");
+    assert!(health["error"].is_string());
+}
+
+#[test]
+fn config_round_trip() {
+    let backend = start_backend();
+    let shell = Shell::new(backend.endpoint());
+
+    let config = shell.invoke("api_config", json!({})).unwrap();
+    assert_eq!(config["llm_model"], "gpt-4o-mini");
+
+    let updated = shell
+        .invoke(
+            "api_update_config",
+            json!({ "request": { "llm_model": "gpt-4o", "llm_temperature": 0.2 } }),
+        )
+        .unwrap();
+    assert_eq!(updated["llm_model"], "gpt-4o");
+    assert_eq!(updated["llm_temperature"], 0.2);
+
+    let config = shell.invoke("api_config", json!({})).unwrap();
+    assert_eq!(config["llm_model"], "gpt-4o");
+    assert_eq!(config["llm_provider"], "openai");
+}
+
+#[test]
+fn errors_reach_the_webview_by_kind() {
+    let mut backend = start_backend();
+    let shell = Shell::new(backend.endpoint());
+
+    backend.script(|s| s.config = Reply::Status(400, json!({ "detail": "bad provider" })));
+    let error = shell.invoke("api_config", json!({})).unwrap_err();
+    assert_eq!(
+        error,
+        json!({ "kind": "status", "message": "bad provider", "retryable": false, "status": 400 })
+    );
+
+    backend.script(|s| s.config = Reply::Status(503, json!({ "detail": "starting" })));
+    let error = shell.invoke("api_config", json!({})).unwrap_err();
+    assert_eq!(error["retryable"], true);
+
+    backend.script(|s| s.config = Reply::Garbage);
+    let error = shell.invoke("api_config", json!({})).unwrap_err();
+    assert_eq!(error["kind"], "decode");
+    assert_eq!(error["status"], Value::Null);
+
+    tauri::async_runtime::block_on(backend.stop());
+    let error = shell.invoke("api_config", json!({})).unwrap_err();
+    assert_eq!(error["kind"], "connect");
+    assert_eq!(error["retryable"], true);
+}
+
+#[test]
+fn chat_without_an_llm_is_its_own_error() {
+    let backend = start_backend();
+    let shell = Shell::new(backend.endpoint());
+    let chat = |shell: &Shell| shell.invoke("api_chat", json!({ "request": { "message": "hi" } }));
+
+    assert_eq!(chat(&shell).unwrap()["message"], "echo: hi");
+
+    backend.script(|s| s.chat = Some(llm_not_configured_reply()));
+    let error = chat(&shell).unwrap_err();
+    assert_eq!(error["kind"], "llm_not_configured");
+    assert_eq!(error["retryable"], false);
+
+    backend.script(|s| {
+        s.chat = Some(Reply::Status(
+            500,
+            json!({ "detail": "DESKFLOW_OPENAI_API_KEY is required for OpenAI provider" }),
+        ))
+    });
+    let error = chat(&shell).unwrap_err();
+    assert_eq!(error["kind"], "llm_not_configured");
+    assert_eq!(error["status"], 500);
+}
@@ -0,0 +1,314 @@
+//! An in-process stand-in for the FastAPI backend, so the shell can be
+//! tested without Python. It serves `/api/health`, `/api/health/detailed`,
+//! `/api/config`, `/api/chat` and the `/api/chat/stream` WebSocket from a
+//! [`Script`] the test edits as it goes, and can be stopped and restarted on
+//! the same port to exercise reconnection.
+
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use coolaw_deskflow_lib::backend::port::HOST;
+use coolaw_deskflow_lib::backend::BackendEndpoint;
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
+
+/// How long helpers wait for something that should happen.
+pub const WAIT: Duration = Duration::from_secs(10);
+
+/// A scripted HTTP answer.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    Json(Value),
+    Status(u16, Value),
+    /// A 200 whose body is not JSON.
+    Garbage,
+}
+
+impl IntoResponse for Reply {
+    fn into_response(self) -> Response {
+        match self {
+            Reply::Json(body) => Json(body).into_response(),
+            Reply::Status(status, body) => (
+                StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(body),
+            )
+                .into_response(),
+            Reply::Garbage => Response::new(Body::from("<html>not json</html>")),
+        }
+    }
+}
+
+/// How `/api/chat/stream` answers one message.
+#[derive(Debug, Clone)]
+pub enum StreamReply {
+    /// Send these frames.
+    Chunks(Vec<Value>),
+    /// Send these frames, then drop the connection without a `done`.
+    HangUp(Vec<Value>),
+}
+
+/// What the mock backend answers. Edit it with [`MockBackend::script`].
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub health: Reply,
+    pub detailed_health: Reply,
+    /// Served by `GET /api/config`; a JSON object is also updated by
+    /// `POST /api/config`.
+    pub config: Reply,
+    /// Served by `POST /api/chat`; `None` echoes the message.
+    pub chat: Option<Reply>,
+    /// Answers for the next streamed chat messages, in order. Once empty, messages
+    /// are echoed.
+    pub stream: VecDeque<StreamReply>,
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Self {
+            health: Reply::Json(json!({ "status": "ok", "version": "0.1.0-mock" })),
+            detailed_health: Reply::Json(json!({ "components": {}, "recommendations": [] })),
+            config: Reply::Json(json!({
+                "llm_provider": "openai",
+                "llm_model": "gpt-4o-mini",
+                "llm_temperature": 0.7,
+                "llm_max_tokens": 4096,
+                "has_api_key": true,
+                "openai_base_url": "",
+                "server_host": "127.0.0.1",
+                "server_port": 8420,
+                "memory_cache_size": 1000,
+                "tool_timeout": 30.0,
+                "log_level": "INFO",
+            })),
+            chat: None,
+            stream: VecDeque::new(),
+        }
+    }
+}
+
+/// What `POST /api/chat` answers when the backend has no LLM.
+pub fn llm_not_configured_reply() -> Reply {
+    Reply::Json(json!({
+        "message": "[LLM 未配置] 请在 .env 文件中设置 DESKFLOW_ANTHROPIC_API_KEY",
+        "conversation_id": "",
+        "tool_calls": [],
+        "metadata": { "warning": "LLM not configured" },
+    }))
+}
+
+/// The frames the real backend sends for a plain text answer.
+pub fn text_reply(conversation_id: &str, text: &str) -> Vec<Value> {
+    vec![
+        json!({ "type": "conversation_id", "content": conversation_id }),
+        json!({ "type": "text", "content": text }),
+        json!({ "type": "done" }),
+    ]
+}
+
+#[derive(Clone)]
+struct Shared {
+    script: Arc<Mutex<Script>>,
+    /// Chat messages as received.
+    received: Arc<Mutex<Vec<Value>>>,
+    /// Flipped to `true` to drop every open WebSocket.
+    hang_up: watch::Receiver<bool>,
+}
+
+struct Running {
+    shutdown: oneshot::Sender<()>,
+    hang_up: watch::Sender<bool>,
+    server: JoinHandle<()>,
+}
+
+pub struct MockBackend {
+    addr: SocketAddr,
+    script: Arc<Mutex<Script>>,
+    received: Arc<Mutex<Vec<Value>>>,
+    running: Option<Running>,
+}
+
+impl MockBackend {
+    /// Start on a free port with the default script.
+    pub async fn start() -> Self {
+        let mut backend = Self {
+            addr: SocketAddr::from((HOST, 0)),
+            script: Arc::default(),
+            received: Arc::default(),
+            running: None,
+        };
+        backend.listen().await;
+        backend
+    }
+
+    pub fn endpoint(&self) -> BackendEndpoint {
+        BackendEndpoint::new(self.addr.port())
+    }
+
+    pub fn url(&self) -> String {
+        self.endpoint().url()
+    }
+
+    pub fn chat_stream_url(&self) -> String {
+        format!("{}/api/chat/stream", self.endpoint().ws_url())
+    }
+
+    pub fn script(&self, edit: impl FnOnce(&mut Script)) {
+        edit(&mut self.script.lock().unwrap());
+    }
+
+    /// Chat messages received so far, as sent by the shell.
+    pub fn received(&self) -> Vec<Value> {
+        self.received.lock().unwrap().clone()
+    }
+
+    /// Stop listening and drop every open connection, like a crashed
+    /// backend.
+    pub async fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            let _ = running.hang_up.send(true);
+            let _ = running.shutdown.send(());
+            let _ = running.server.await;
+        }
+    }
+
+    /// Come back on the same port with the same script.
+    pub async fn restart(&mut self) {
+        self.stop().await;
+        self.listen().await;
+    }
+
+    async fn listen(&mut self) {
+        let listener = TcpListener::bind(self.addr)
+            .await
+            .expect("mock backend cannot bind");
+        self.addr = listener.local_addr().unwrap();
+
+        let (hang_up, hang_up_rx) = watch::channel(false);
+        let shared = Shared {
+            script: self.script.clone(),
+            received: self.received.clone(),
+            hang_up: hang_up_rx,
+        };
+        let app = Router::new()
+            .route("/api/health", get(health))
+            .route("/api/health/detailed", get(detailed_health))
+            .route("/api/config", get(config).post(update_config))
+            .route("/api/chat", post(chat))
+            .route("/api/chat/stream", get(chat_stream))
+            .with_state(shared);
+
+        let (shutdown, stopped) = oneshot::channel();
+        let server = tokio::spawn(async move {
+            let _ = axum::serve(listener, app)
+                .with_graceful_shutdown(async {
+                    let _ = stopped.await;
+                })
+                .await;
+        });
+        self.running = Some(Running {
+            shutdown,
+            hang_up,
+            server,
+        });
+    }
+}
+
+async fn health(State(shared): State<Shared>) -> Reply {
+    shared.script.lock().unwrap().health.clone()
+}
+
+async fn detailed_health(State(shared): State<Shared>) -> Reply {
+    shared.script.lock().unwrap().detailed_health.clone()
+}
+
+async fn config(State(shared): State<Shared>) -> Reply {
+    shared.script.lock().unwrap().config.clone()
+}
+
+async fn update_config(State(shared): State<Shared>, Json(update): Json<Value>) -> Reply {
+    let mut script = shared.script.lock().unwrap();
+    if let (Reply::Json(Value::Object(config)), Value::Object(update)) =
+        (&mut script.config, update)
+    {
+        for (key, value) in update {
+            config.insert(key, value);
+        }
+    }
+    script.config.clone()
+}
+
+async fn chat(State(shared): State<Shared>, Json(request): Json<Value>) -> Reply {
+    let scripted = shared.script.lock().unwrap().chat.clone();
+    scripted.unwrap_or_else(|| {
+        Reply::Json(json!({
+            "message": format!("echo: {}", request["message"].as_str().unwrap_or_default()),
+            "conversation_id": request["conversation_id"].as_str().unwrap_or("conv-mock"),
+        }))
+    })
+}
+
+async fn chat_stream(State(shared): State<Shared>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| serve_chat(socket, shared))
+}
+
+async fn serve_chat(mut socket: WebSocket, mut shared: Shared) {
+    loop {
+        let frame = tokio::select! {
+            frame = socket.recv() => frame,
+            _ = shared.hang_up.changed() => return,
+        };
+        let Some(Ok(Message::Text(text))) = frame else {
+            match frame {
+                Some(Ok(_)) => continue,
+                _ => return,
+            }
+        };
+
+        let message: Value = serde_json::from_str(text.as_str()).unwrap_or_default();
+        shared.received.lock().unwrap().push(message.clone());
+
+        let reply = shared.script.lock().unwrap().stream.pop_front();
+        let (frames, hang_up) = match reply {
+            Some(StreamReply::Chunks(frames)) => (frames, false),
+            Some(StreamReply::HangUp(frames)) => (frames, true),
+            None => {
+                let conversation_id = message["conversation_id"].as_str().unwrap_or("conv-mock");
+                let echo = format!("echo: {}", message["message"].as_str().unwrap_or_default());
+                (text_reply(conversation_id, &echo), false)
+            }
+        };
+        for frame in frames {
+            if socket
+                .send(Message::Text(frame.to_string().into()))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+        if hang_up {
+            return;
+        }
+    }
+}
+
+/// Wait until `ready` holds for the value in `rx`.
+pub async fn wait_for<T>(rx: &mut watch::Receiver<T>, mut ready: impl FnMut(&T) -> bool) {
+    tokio::time::timeout(WAIT, rx.wait_for(|value| ready(value)))
+        .await
+        .expect("timed out waiting for a state change")
+        .expect("sender dropped");
+}
@@ -0,0 +1,71 @@
+//! The background health prober against a backend that changes state, goes
+//! away and comes back.
+
+mod common;
+
+use std::time::Duration;
+
+use coolaw_deskflow_lib::backend::health::spawn_prober;
+use coolaw_deskflow_lib::backend::{HealthProbe, HealthStatus};
+use serde_json::json;
+
+use common::{wait_for, MockBackend, Reply};
+
+const INTERVAL: Duration = Duration::from_millis(50);
+
+#[tokio::test]
+async fn prober_reports_each_transition() {
+    let mut backend = MockBackend::start().await;
+    let mut health = spawn_prober(HealthProbe::new(backend.url()), INTERVAL);
+
+    wait_for(&mut health, |h| h.status == HealthStatus::Ok).await;
+    assert_eq!(health.borrow().version.as_deref(), Some("0.1.0-mock"));
+
+    backend.script(|s| s.health = Reply::Json(json!({ "status": "degraded" })));
+    wait_for(&mut health, |h| h.status == HealthStatus::Degraded).await;
+
+    backend.stop().await;
+    wait_for(&mut health, |h| h.status == HealthStatus::Unreachable).await;
+    assert!(health.borrow().error.is_some());
+
+    backend.script(|s| s.health = Reply::Json(json!({ "status": "ok" })));
+    backend.restart().await;
+    wait_for(&mut health, |h| h.status == HealthStatus::Ok).await;
+}
+
+#[tokio::test]
+async fn prober_only_wakes_on_change() {
+    let backend = MockBackend::start().await;
+    let mut health = spawn_prober(HealthProbe::new(backend.url()), INTERVAL);
+    wait_for(&mut health, |h| h.status == HealthStatus::Ok).await;
+    health.mark_unchanged();
+
+    tokio::time::sleep(INTERVAL * 5).await;
+
+    assert!(!health.has_changed().unwrap());
+}
+
+#[tokio::test]
+async fn transitions_are_reported_from_the_detailed_check() {
+    let backend = MockBackend::start().await;
+    backend.script(|s| {
+        s.detailed_health = Reply::Json(json!({
+            "components": {
+                "llm": { "status": "ok", "details": { "api_key_configured": true } },
+            },
+            "recommendations": ["Add a second LLM provider as a fallback"],
+        }))
+    });
+    let mut health = spawn_prober(HealthProbe::new(backend.url()), INTERVAL);
+
+    wait_for(&mut health, |h| h.status == HealthStatus::Ok).await;
+    let report = health.borrow().clone();
+    assert!(report.llm_configured);
+    assert_eq!(report.recommendations.len(), 1);
+
+    // Later quick checks keep the detailed report without waking anyone
+    health.mark_unchanged();
+    tokio::time::sleep(INTERVAL * 5).await;
+    assert!(!health.has_changed().unwrap());
+    assert!(health.borrow().llm_configured);
+}
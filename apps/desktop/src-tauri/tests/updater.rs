@@ -0,0 +1,131 @@
+//! The snapshot taken before an update: what it keeps, putting it back
+//! when the new backend does not come up, and dropping it when it does.
+
+#![cfg(feature = "desktop")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use coolaw_deskflow_lib::profile::ProfileDirs;
+use coolaw_deskflow_lib::updater::rollback;
+
+/// A profile with a database, a memory index and some config in a fresh
+/// directory.
+fn profile() -> ProfileDirs {
+    static NEXT: AtomicU32 = AtomicU32::new(0);
+    let root = std::env::temp_dir().join(format!(
+        "deskflow-updater-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&root);
+    let dirs = ProfileDirs {
+        name: "default".to_string(),
+        data: root.join("data").join("default"),
+        config: root.join("config").join("default"),
+        cache: root.join("cache").join("default"),
+    };
+    fs::create_dir_all(dirs.data.join("memory")).unwrap();
+    fs::create_dir_all(dirs.db_path().parent().unwrap()).unwrap();
+    fs::create_dir_all(&dirs.config).unwrap();
+    fs::write(dirs.db_path(), "v1 database").unwrap();
+    fs::write(dirs.data.join("memory").join("index.bin"), "v1 index").unwrap();
+    fs::write(dirs.config.join("settings.json"), "{\"v\":1}").unwrap();
+    dirs
+}
+
+fn read(path: impl AsRef<Path>) -> String {
+    fs::read_to_string(path).unwrap()
+}
+
+/// What the new version's migration does to the profile.
+fn migrate(dirs: &ProfileDirs) {
+    fs::write(dirs.db_path(), "v2 database").unwrap();
+    fs::remove_file(dirs.data.join("memory").join("index.bin")).unwrap();
+    fs::write(dirs.data.join("migrated"), "").unwrap();
+    fs::write(dirs.config.join("settings.json"), "{\"v\":2}").unwrap();
+}
+
+#[test]
+fn restore_puts_the_profile_back() {
+    let dirs = profile();
+    let pending = rollback::prepare(&dirs, "1.0.0", "1.1.0", None).unwrap();
+    assert_eq!(pending.profile, "default");
+    assert_eq!(rollback::pending(&dirs), Some(pending.clone()));
+
+    migrate(&dirs);
+    let app_restored = rollback::restore(&dirs, &pending).unwrap();
+
+    assert!(!app_restored);
+    assert_eq!(read(dirs.db_path()), "v1 database");
+    assert_eq!(read(dirs.data.join("memory").join("index.bin")), "v1 index");
+    assert!(!dirs.data.join("migrated").exists());
+    assert_eq!(read(dirs.config.join("settings.json")), "{\"v\":1}");
+    assert_eq!(rollback::pending(&dirs), None);
+    assert!(!rollback::rollback_dir(&dirs).exists());
+}
+
+#[test]
+fn restore_puts_an_app_replaced_in_place_back() {
+    let dirs = profile();
+    let app: PathBuf = dirs.cache.join("DeskFlow.AppImage");
+    fs::create_dir_all(&dirs.cache).unwrap();
+    fs::write(&app, "v1 app").unwrap();
+
+    let pending = rollback::prepare(&dirs, "1.0.0", "1.1.0", Some(&app)).unwrap();
+    fs::write(&app, "v2 app").unwrap();
+
+    assert!(rollback::restore(&dirs, &pending).unwrap());
+    assert_eq!(read(&app), "v1 app");
+}
+
+#[test]
+fn discard_keeps_the_update() {
+    let dirs = profile();
+    rollback::prepare(&dirs, "1.0.0", "1.1.0", None).unwrap();
+    migrate(&dirs);
+
+    rollback::discard(&dirs).unwrap();
+
+    assert_eq!(rollback::pending(&dirs), None);
+    assert_eq!(read(dirs.db_path()), "v2 database");
+    // Nothing left to discard
+    rollback::discard(&dirs).unwrap();
+}
+
+#[test]
+fn a_new_snapshot_replaces_the_old_one() {
+    let dirs = profile();
+    rollback::prepare(&dirs, "1.0.0", "1.1.0", None).unwrap();
+    migrate(&dirs);
+
+    let pending = rollback::prepare(&dirs, "1.1.0", "1.2.0", None).unwrap();
+    fs::write(dirs.db_path(), "v3 database").unwrap();
+    rollback::restore(&dirs, &pending).unwrap();
+
+    assert_eq!(read(dirs.db_path()), "v2 database");
+    assert!(dirs.data.join("migrated").exists());
+}
+
+#[test]
+fn a_profile_without_config_can_be_snapshotted() {
+    let dirs = profile();
+    fs::remove_dir_all(&dirs.config).unwrap();
+
+    let pending = rollback::prepare(&dirs, "1.0.0", "1.1.0", None).unwrap();
+    fs::create_dir_all(&dirs.config).unwrap();
+    fs::write(dirs.config.join("settings.json"), "{}").unwrap();
+    rollback::restore(&dirs, &pending).unwrap();
+
+    assert_eq!(read(dirs.db_path()), "v1 database");
+    assert!(!dirs.config.exists());
+}
+
+#[test]
+fn an_unfinished_snapshot_is_not_pending() {
+    let dirs = profile();
+    fs::create_dir_all(rollback::rollback_dir(&dirs).join("data")).unwrap();
+
+    assert_eq!(rollback::pending(&dirs), None);
+}
@@ -1,16 +1,33 @@
 import { useEffect } from "react";
 import { useTranslation } from "react-i18next";
+import { invoke, isTauri } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { TitleBar } from "./components/layout/TitleBar";
 import { Sidebar } from "./components/layout/Sidebar";
 import { StatusBar } from "./components/layout/StatusBar";
 import { SetupWizard } from "./components/setup/SetupWizard";
 import { useAppStore } from "./stores/appStore";
+import { useChatStore, openConversation } from "./stores/chatStore";
 import { useLocaleStore } from "./stores/localeStore";
 import ChatView from "./views/ChatView";
 import SkillsView from "./views/SkillsView";
 import { MonitorView } from "./views/MonitorView";
 import SettingsView from "./views/SettingsView";
 import { IMChannelsView } from "./views/IMChannelsView";
+import type { BackendHealth, ViewName } from "./types";
+
+/** Payload of `shell://navigate`, sent by the tray and other shell entry points. */
+interface ShellNavigate {
+  view: ViewName;
+  new_chat: boolean;
+  conversation_id: string | null;
+}
+
+/** Arguments of a launch, ours or one forwarded by a second launch. */
+interface LaunchRequest {
+  prompt: string | null;
+  files: string[];
+}
 
 function App() {
   const currentView = useAppStore((s) => s.currentView);
@@ -18,6 +35,9 @@ function App() {
   const setupCompleted = useAppStore((s) => s.setupCompleted);
   const setConnected = useAppStore((s) => s.setConnected);
   const setSetupCompleted = useAppStore((s) => s.setSetupCompleted);
+  const setCurrentView = useAppStore((s) => s.setCurrentView);
+  const clearMessages = useChatStore((s) => s.clearMessages);
+  const setDraft = useChatStore((s) => s.setDraft);
   const { i18n } = useTranslation();
   const locale = useLocaleStore((s) => s.locale);
 
@@ -26,8 +46,99 @@ function App() {
     i18n.changeLanguage(locale);
   }, [locale, i18n]);
 
-  // Health check polling
+  // Health is probed by the Tauri shell, which only emits on changes
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    const apply = (health: BackendHealth) => setConnected(health.status !== "unreachable");
+    invoke<BackendHealth>("check_backend_health").then(apply).catch(() => setConnected(false));
+
+    const unlisten = listen<BackendHealth>("backend://health", (event) => apply(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [setConnected]);
+
+  // Navigation requested by the shell (tray menu, etc.)
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    const unlisten = listen<ShellNavigate>("shell://navigate", async (event) => {
+      const { view, new_chat, conversation_id } = event.payload;
+      setCurrentView(view);
+      if (new_chat) {
+        clearMessages();
+      } else if (conversation_id) {
+        try {
+          await openConversation(conversation_id);
+        } catch (error) {
+          console.error("Failed to open conversation:", error);
+        }
+      }
+    });
+    // Links the app was started with are held back until we can follow them
+    unlisten.then(() => invoke("shell_ready"));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [setCurrentView, clearMessages]);
+
+  // Cmd/Ctrl+K opens the search palette
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    const onKeyDown = (e: KeyboardEvent) => {
+      if ((e.metaKey || e.ctrlKey) && e.key.toLowerCase() === "k") {
+        e.preventDefault();
+        invoke("open_search_window");
+      }
+    };
+    window.addEventListener("keydown", onKeyDown);
+    return () => window.removeEventListener("keydown", onKeyDown);
+  }, []);
+
+  // Everything the views hold belongs to the previous profile
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    const unlisten = listen("profile://changed", () => {
+      clearMessages();
+      window.location.reload();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [clearMessages]);
+
+  // Prompts and files passed on the command line end up in the chat input
   useEffect(() => {
+    if (!isTauri()) return;
+
+    const take = async () => {
+      const requests = await invoke<LaunchRequest[]>("take_launch_requests");
+      if (requests.length === 0) return;
+      const draft = requests
+        .map(({ prompt, files }) =>
+          [prompt, ...files.map((file) => `Attached file: ${file}`)].filter(Boolean).join("\n")
+        )
+        .join("\n\n");
+      setCurrentView("chat");
+      setDraft(draft);
+    };
+
+    take().catch((error) => console.error("Failed to read launch arguments:", error));
+    const unlisten = listen("instance://launch", () => {
+      take().catch((error) => console.error("Failed to read launch arguments:", error));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [setCurrentView, setDraft]);
+
+  // Health check polling (web-only development)
+  useEffect(() => {
+    if (isTauri()) return;
+
     const checkHealth = async () => {
       try {
         const response = await fetch(`${serverUrl}/api/health`, {
@@ -1,28 +1,47 @@
-import { useState, useRef, useCallback, KeyboardEvent } from "react";
+import { useState, useRef, useCallback, useEffect, KeyboardEvent } from "react";
 import { ArrowUp, Square, Paperclip, Wrench } from "lucide-react";
 
 interface ChatInputProps {
   onSend: (message: string) => void;
   onStop: () => void;
   isStreaming: boolean;
+  /** Replaces the current text when set; cleared through `onDraftConsumed`. */
+  draft?: string;
+  onDraftConsumed?: () => void;
+  /** Read-only mode, e.g. while the backend is unreachable. */
+  disabled?: boolean;
 }
 
 /**
  * Chat input with auto-resize, send button, and stop button.
  */
-export function ChatInput({ onSend, onStop, isStreaming }: ChatInputProps) {
+export function ChatInput({ onSend, onStop, isStreaming, draft, onDraftConsumed, disabled }: ChatInputProps) {
   const [value, setValue] = useState("");
   const textareaRef = useRef<HTMLTextAreaElement>(null);
 
+  useEffect(() => {
+    if (!draft) return;
+    setValue(draft);
+    onDraftConsumed?.();
+    const el = textareaRef.current;
+    if (el) {
+      el.focus();
+      requestAnimationFrame(() => {
+        el.style.height = "44px";
+        el.style.height = Math.min(el.scrollHeight, 160) + "px";
+      });
+    }
+  }, [draft, onDraftConsumed]);
+
   const handleSend = useCallback(() => {
     const trimmed = value.trim();
-    if (!trimmed || isStreaming) return;
+    if (!trimmed || isStreaming || disabled) return;
     onSend(trimmed);
     setValue("");
     if (textareaRef.current) {
       textareaRef.current.style.height = "44px";
     }
-  }, [value, isStreaming, onSend]);
+  }, [value, isStreaming, disabled, onSend]);
 
   const handleKeyDown = useCallback(
     (e: KeyboardEvent<HTMLTextAreaElement>) => {
@@ -58,7 +77,7 @@ export function ChatInput({ onSend, onStop, isStreaming }: ChatInputProps) {
             rows={1}
             placeholder="Type a message... (Shift+Enter for new line)"
             style={{ minHeight: 44, maxHeight: 160 }}
-            disabled={isStreaming}
+            disabled={isStreaming || disabled}
           />
           <div className="absolute bottom-3 left-3 flex items-center gap-1">
             <button
@@ -87,7 +106,7 @@ export function ChatInput({ onSend, onStop, isStreaming }: ChatInputProps) {
         ) : (
           <button
             onClick={handleSend}
-            disabled={!value.trim()}
+            disabled={!value.trim() || disabled}
             className="w-10 h-10 rounded-xl bg-accent flex items-center justify-center cursor-pointer shrink-0 hover:bg-accent-hover transition-colors duration-200 disabled:opacity-40 disabled:cursor-not-allowed"
             title="Send message"
           >
@@ -0,0 +1,212 @@
+import { useCallback, useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import { Search, MessageSquare, Download } from "lucide-react";
+import { errorMessage } from "../../errors";
+
+interface ConversationSummary {
+  id: string;
+  title: string;
+  updated_at: string;
+  message_count: number;
+}
+
+interface SearchHit {
+  conversation_id: string;
+  conversation_title: string;
+  message_id: string;
+  role: string;
+  snippet: string;
+  created_at: string;
+}
+
+type TranscriptFormat = "markdown" | "html" | "jsonl";
+
+// Match markers used by the shell's history search
+const MATCH_START = "\u0002";
+const MATCH_END = "\u0003";
+
+interface HistoryPanelProps {
+  activeId: string | null;
+  onOpen: (conversationId: string) => void;
+}
+
+/**
+ * Conversation list and search, read by the shell straight from the
+ * history database so it also works while the backend is down.
+ */
+export function HistoryPanel({ activeId, onOpen }: HistoryPanelProps) {
+  const { t } = useTranslation();
+  const [query, setQuery] = useState("");
+  const [conversations, setConversations] = useState<ConversationSummary[]>([]);
+  const [hits, setHits] = useState<SearchHit[] | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [format, setFormat] = useState<TranscriptFormat>("markdown");
+  const [since, setSince] = useState("");
+  const [until, setUntil] = useState("");
+  const [exportStatus, setExportStatus] = useState<string | null>(null);
+
+  const exportOne = (id: string) => {
+    setExportStatus(null);
+    invoke<string | null>("export_conversation", { id, format })
+      .then((path) => path && setExportStatus(t("chat.exportedTo", { path })))
+      .catch((e) => setExportStatus(errorMessage(e)));
+  };
+
+  const exportAll = () => {
+    setExportStatus(null);
+    invoke<{ dir: string; count: number } | null>("export_conversations", {
+      range: { since: since || null, until: until || null },
+      format,
+    })
+      .then((result) => result && setExportStatus(t("chat.exportedMany", result)))
+      .catch((e) => setExportStatus(errorMessage(e)));
+  };
+
+  const loadConversations = useCallback(() => {
+    invoke<{ conversations: ConversationSummary[] }>("list_conversations", { limit: 100 })
+      .then((list) => {
+        setConversations(list.conversations);
+        setError(null);
+      })
+      .catch((e) => setError(errorMessage(e)));
+  }, []);
+
+  useEffect(loadConversations, [loadConversations, activeId]);
+
+  // Debounced search; an empty query goes back to the plain list
+  useEffect(() => {
+    const q = query.trim();
+    if (!q) {
+      setHits(null);
+      return;
+    }
+    const timer = setTimeout(() => {
+      invoke<SearchHit[]>("search_conversations", { query: q, limit: 50 })
+        .then((result) => {
+          setHits(result);
+          setError(null);
+        })
+        .catch((e) => setError(errorMessage(e)));
+    }, 200);
+    return () => clearTimeout(timer);
+  }, [query]);
+
+  return (
+    <div className="w-64 border-r border-surface flex flex-col shrink-0 min-h-0">
+      <div className="p-3 border-b border-surface">
+        <div className="relative">
+          <Search className="w-4 h-4 absolute left-2.5 top-1/2 -translate-y-1/2 text-text-m" />
+          <input
+            value={query}
+            onChange={(e) => setQuery(e.target.value)}
+            placeholder={t("chat.searchHistory")}
+            className="w-full bg-surface border border-surface-el rounded-lg pl-8 pr-2 py-1.5 text-sm text-text-p placeholder:text-text-m focus:border-accent focus:outline-none"
+          />
+        </div>
+      </div>
+
+      <div className="flex-1 overflow-y-auto py-1">
+        {error && <p className="px-3 py-2 text-xs text-text-m">{error}</p>}
+
+        {hits === null &&
+          conversations.map((c) => (
+            <div
+              key={c.id}
+              className={`group flex items-center transition-colors duration-200 ${
+                c.id === activeId ? "bg-accent/10 text-accent" : "text-text-s hover:bg-surface"
+              }`}
+            >
+              <button
+                onClick={() => onOpen(c.id)}
+                className="flex-1 min-w-0 text-left pl-3 py-2 text-sm flex items-center gap-2"
+              >
+                <MessageSquare className="w-3.5 h-3.5 shrink-0" />
+                <span className="truncate">{c.title}</span>
+              </button>
+              <button
+                onClick={() => exportOne(c.id)}
+                title={t("chat.exportConversation")}
+                className="px-2 py-2 opacity-0 group-hover:opacity-100 text-text-m hover:text-text-p transition-opacity"
+              >
+                <Download className="w-3.5 h-3.5" />
+              </button>
+            </div>
+          ))}
+
+        {hits?.map((hit) => (
+          <button
+            key={hit.message_id}
+            onClick={() => onOpen(hit.conversation_id)}
+            className="w-full text-left px-3 py-2 hover:bg-surface transition-colors duration-200"
+          >
+            <div className="text-xs text-text-m truncate">{hit.conversation_title}</div>
+            <div className="text-sm text-text-s line-clamp-3">
+              <Snippet text={hit.snippet} />
+            </div>
+          </button>
+        ))}
+
+        {!error && (hits ?? conversations).length === 0 && (
+          <p className="px-3 py-2 text-xs text-text-m">{t("chat.noHistory")}</p>
+        )}
+      </div>
+
+      <div className="p-3 border-t border-surface space-y-2 text-xs">
+        <div className="flex items-center gap-1.5">
+          <input
+            type="date"
+            value={since}
+            onChange={(e) => setSince(e.target.value)}
+            title={t("search.since")}
+            className="flex-1 min-w-0 bg-surface border border-surface-el rounded-md px-1.5 py-1 text-text-s"
+          />
+          <input
+            type="date"
+            value={until}
+            onChange={(e) => setUntil(e.target.value)}
+            title={t("search.until")}
+            className="flex-1 min-w-0 bg-surface border border-surface-el rounded-md px-1.5 py-1 text-text-s"
+          />
+        </div>
+        <div className="flex items-center gap-1.5">
+          <select
+            value={format}
+            onChange={(e) => setFormat(e.target.value as TranscriptFormat)}
+            className="flex-1 bg-surface border border-surface-el rounded-md px-1.5 py-1 text-text-s"
+          >
+            <option value="markdown">Markdown</option>
+            <option value="html">HTML</option>
+            <option value="jsonl">JSONL</option>
+          </select>
+          <button
+            onClick={exportAll}
+            className="px-2 py-1 rounded-md border border-surface-el text-text-s hover:bg-surface transition-colors"
+          >
+            {since || until ? t("chat.exportRange") : t("chat.exportAll")}
+          </button>
+        </div>
+        {exportStatus && <p className="text-text-m break-all">{exportStatus}</p>}
+      </div>
+    </div>
+  );
+}
+
+/** Render a search snippet with its matches highlighted, without HTML. */
+export function Snippet({ text }: { text: string }) {
+  const parts = text.split(MATCH_START);
+  return (
+    <>
+      {parts[0]}
+      {parts.slice(1).map((part, i) => {
+        const [match, rest = ""] = part.split(MATCH_END);
+        return (
+          <span key={i}>
+            <mark className="bg-accent/20 text-text-p rounded-sm">{match}</mark>
+            {rest}
+          </span>
+        );
+      })}
+    </>
+  );
+}
@@ -1,7 +1,9 @@
 import { useState } from "react";
 import { useTranslation } from "react-i18next";
 import { CheckCircle, Loader, Play, XCircle } from "lucide-react";
+import { invoke, isTauri } from "@tauri-apps/api/core";
 import { useSetupConfigStore } from "../../stores/setupConfigStore";
+import { useAppStore } from "../../stores/appStore";
 
 interface AutoConfigStepProps {
   onComplete: (success: boolean) => void;
@@ -10,6 +12,7 @@ interface AutoConfigStepProps {
 export function AutoConfigStep({ onComplete }: AutoConfigStepProps) {
   const { t } = useTranslation();
   const { llm, im, workspace, installDeps } = useSetupConfigStore();
+  const serverUrl = useAppStore((s) => s.serverUrl);
 
   const [status, setStatus] = useState<"idle" | "running" | "success" | "error">("idle");
   const [currentTask, setCurrentTask] = useState("");
@@ -55,12 +58,19 @@ export function AutoConfigStep({ onComplete }: AutoConfigStepProps) {
       setCurrentTask(t("setup.savingConfig", "保存配置..."));
       addLog(t("setup.savingConfig", "保存配置..."));
 
+      // 桌面端把 API 密钥交给系统钥匙串，由 Rust 端在启动后端时通过环境变量注入，
+      // 不写入配置文件
+      const keepKeyInShell = isTauri();
+      if (keepKeyInShell) {
+        await invoke("secrets_set", { provider: llm.provider, value: llm.apiKey });
+      }
+
       // 调用后端 API 保存配置
       const configData = {
         llm: {
           provider: llm.provider,
           base_url: llm.baseUrl,
-          api_key: llm.apiKey,
+          api_key: keepKeyInShell ? "" : llm.apiKey,
           model: llm.model,
           max_tokens: llm.maxTokens,
           temperature: llm.temperature / 100,
@@ -77,7 +87,7 @@ export function AutoConfigStep({ onComplete }: AutoConfigStepProps) {
         },
       };
 
-      const response = await fetch("http://127.0.0.1:8420/api/setup/config", {
+      const response = await fetch(`${serverUrl}/api/setup/config`, {
         method: "POST",
         headers: { "Content-Type": "application/json" },
         body: JSON.stringify(configData),
@@ -91,7 +101,7 @@ export function AutoConfigStep({ onComplete }: AutoConfigStepProps) {
         setCurrentTask(t("setup.startingService", "启动服务..."));
         addLog(t("setup.startingService", "启动服务..."));
 
-        const startResponse = await fetch("http://127.0.0.1:8420/api/setup/start", {
+        const startResponse = await fetch(`${serverUrl}/api/setup/start`, {
           method: "POST",
           headers: { "Content-Type": "application/json" },
         });
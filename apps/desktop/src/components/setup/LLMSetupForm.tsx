@@ -2,6 +2,7 @@ import { useState, useEffect } from "react";
 import { useTranslation } from "react-i18next";
 import { Eye, EyeOff, CheckCircle, XCircle, Loader } from "lucide-react";
 import { useSetupConfigStore } from "../../stores/setupConfigStore";
+import { useAppStore } from "../../stores/appStore";
 
 // Provider 配置
 const PROVIDERS = [
@@ -37,6 +38,7 @@ interface LLMSetupFormProps {
 export function LLMSetupForm({ onComplete }: LLMSetupFormProps) {
   const { t } = useTranslation();
   const { llm, setLLMConfig } = useSetupConfigStore();
+  const serverUrl = useAppStore((s) => s.serverUrl);
 
   const [showKey, setShowKey] = useState(false);
   const [models, setModels] = useState<string[]>([]);
@@ -72,7 +74,7 @@ export function LLMSetupForm({ onComplete }: LLMSetupFormProps) {
         }
 
         // 使用后端 API
-        const response = await fetch(`http://127.0.0.1:8420/api/llm/models?${params}`);
+        const response = await fetch(`${serverUrl}/api/llm/models?${params}`);
         if (response.ok) {
           const data = await response.json();
           setModels(data.models || []);
@@ -96,13 +98,13 @@ export function LLMSetupForm({ onComplete }: LLMSetupFormProps) {
 
     const timeoutId = setTimeout(fetchModels, 500);
     return () => clearTimeout(timeoutId);
-  }, [llm.provider, llm.baseUrl, llm.apiKey]);
+  }, [llm.provider, llm.baseUrl, llm.apiKey, serverUrl]);
 
   // Test connection
   const handleTestConnection = async () => {
     setTestStatus("testing");
     try {
-      const response = await fetch("http://127.0.0.1:8420/api/llm/test", {
+      const response = await fetch(`${serverUrl}/api/llm/test`, {
         method: "POST",
         headers: { "Content-Type": "application/json" },
         body: JSON.stringify({
@@ -0,0 +1,17 @@
+import type { DeskflowError } from "./types";
+
+export function isDeskflowError(error: unknown): error is DeskflowError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    typeof (error as DeskflowError).kind === "string" &&
+    typeof (error as DeskflowError).message === "string"
+  );
+}
+
+/** Text for an error thrown by `invoke` or anything else. */
+export function errorMessage(error: unknown): string {
+  if (isDeskflowError(error)) return error.message;
+  if (error instanceof Error) return error.message;
+  return String(error);
+}
@@ -1,10 +1,41 @@
 import { useCallback, useRef } from "react";
+import { invoke, isTauri } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useChatStore } from "../stores/chatStore";
 import { useAppStore } from "../stores/appStore";
 import type { StreamChunk } from "../types";
 
+/** Payload of the `chat://chunk` event emitted by the Rust chat bridge. */
+interface ChatEvent {
+  request_id: string;
+  conversation_id: string | null;
+  chunk: StreamChunk;
+}
+
+/** A message the bridge has not finished streaming (see `chat_snapshot`). */
+interface PendingMessage {
+  request_id: string;
+  conversation_id: string | null;
+  message: string;
+  state: "queued" | "streaming";
+  chunks: StreamChunk[];
+}
+
+// Kept outside the hook so a stream keeps landing in the chat store when the
+// chat view is unmounted and mounted again.
+const active: {
+  /** Request currently streamed into the last assistant message. */
+  requestId: string | null;
+  /** Chunks that arrived before `chat_send` resolved with their request id. */
+  earlyEvents: ChatEvent[] | null;
+} = { requestId: null, earlyEvents: null };
+
 /**
- * Hook for managing WebSocket chat connection and message sending.
+ * Hook for sending chat messages and applying the streamed reply.
+ *
+ * In the desktop app the Rust shell owns the `/api/chat/stream` connection
+ * and forwards chunks as `chat://chunk` events; in a plain browser (web-only
+ * development) the hook falls back to its own WebSocket.
  */
 export function useChat() {
   const wsRef = useRef<WebSocket | null>(null);
@@ -26,7 +57,6 @@ export function useChat() {
 
   const handleChunk = useCallback(
     (chunk: StreamChunk) => {
-      console.log('[useChat] Received chunk:', chunk.type, chunk);
       switch (chunk.type) {
         case "conversation_id":
           // 保存后端返回的 conversation_id
@@ -57,9 +87,12 @@ export function useChat() {
           break;
         case "error":
           appendToLastMessage(`\n\n**Error:** ${chunk.content || "Unknown error"}`);
-          finishStreaming();
+          if (!isTauri()) {
+            finishStreaming();
+          }
           break;
         case "done":
+          active.requestId = null;
           finishStreaming();
           break;
       }
@@ -70,7 +103,6 @@ export function useChat() {
   const sendPendingMessage = useCallback(() => {
     const ws = wsRef.current;
     const pending = pendingMessageRef.current;
-    console.log('[useChat] sendPendingMessage:', pending);
     if (ws && ws.readyState === WebSocket.OPEN && pending) {
       ws.send(JSON.stringify({
         message: pending.message,
@@ -80,26 +112,56 @@ export function useChat() {
     }
   }, []);
 
-  const connect = useCallback(() => {
+  /** Start receiving chunks. Returns a cleanup function. */
+  const connect = useCallback((): (() => void) => {
+    if (isTauri()) {
+      let disposed = false;
+      const unlisten = listen<ChatEvent>("chat://chunk", (event) => {
+        if (event.payload.request_id === active.requestId) {
+          handleChunk(event.payload.chunk);
+        } else if (active.earlyEvents) {
+          active.earlyEvents.push(event.payload);
+        }
+      });
+
+      // Pick up a reply that kept streaming while the webview reloaded
+      if (!active.requestId) {
+        invoke<PendingMessage[]>("chat_snapshot")
+          .then((pending) => {
+            const current = pending.find((p) => p.state === "streaming") ?? pending[0];
+            if (disposed || !current || active.requestId) return;
+            active.requestId = current.request_id;
+            if (current.conversation_id) {
+              setConversationId(current.conversation_id);
+            }
+            addUserMessage(current.message);
+            startAssistantMessage();
+            current.chunks.forEach(handleChunk);
+          })
+          .catch((e) => console.error("[useChat] Failed to restore chat:", e));
+      }
+
+      return () => {
+        disposed = true;
+        unlisten.then((fn) => fn());
+      };
+    }
+
     const wsUrl = serverUrl.replace("http", "ws") + "/api/chat/stream";
-    console.log('[useChat] Connecting to:', wsUrl);
     const ws = new WebSocket(wsUrl);
 
     ws.onopen = () => {
-      console.log('[useChat] WebSocket connected');
       setConnected(true);
       // Send pending message if exists
       sendPendingMessage();
     };
 
     ws.onclose = () => {
-      console.log('[useChat] WebSocket closed');
       setConnected(false);
       wsRef.current = null;
     };
 
     ws.onerror = () => {
-      console.log('[useChat] WebSocket error');
       setConnected(false);
     };
 
@@ -113,44 +175,63 @@ export function useChat() {
     };
 
     wsRef.current = ws;
-    return ws;
-  }, [serverUrl, setConnected, handleChunk, sendPendingMessage]);
+    return () => ws.close();
+  }, [serverUrl, setConnected, handleChunk, sendPendingMessage, addUserMessage, startAssistantMessage, setConversationId]);
 
   const sendMessage = useCallback(
     (content: string) => {
-      console.log('[useChat] sendMessage called, isStreaming:', isStreaming, 'content:', content);
       if (!content.trim() || isStreaming) {
-        console.log('[useChat] Aborting send: content empty or streaming');
         return;
       }
 
       addUserMessage(content);
       startAssistantMessage();
 
-      let ws = wsRef.current;
-      console.log('[useChat] WebSocket state:', ws?.readyState);
+      if (isTauri()) {
+        active.earlyEvents = [];
+        invoke<string>("chat_send", { message: content, conversationId })
+          .then((requestId) => {
+            const early = active.earlyEvents ?? [];
+            active.earlyEvents = null;
+            active.requestId = requestId;
+            early
+              .filter((event) => event.request_id === requestId)
+              .forEach((event) => handleChunk(event.chunk));
+          })
+          .catch((e) => {
+            active.earlyEvents = null;
+            appendToLastMessage(`\n\n**Error:** ${e}`);
+            finishStreaming();
+          });
+        return;
+      }
+
+      const ws = wsRef.current;
       if (!ws || ws.readyState !== WebSocket.OPEN) {
         // Store pending message and connect
-        console.log('[useChat] WebSocket not ready, storing pending message');
         pendingMessageRef.current = {
           message: content,
           conversationId: conversationId,
         };
         connect();
       } else {
-        console.log('[useChat] Sending message via WebSocket');
         ws.send(JSON.stringify({
           message: content,
           conversation_id: conversationId,
         }));
       }
     },
-    [isStreaming, conversationId, addUserMessage, startAssistantMessage, connect]
+    [isStreaming, conversationId, addUserMessage, startAssistantMessage, appendToLastMessage, finishStreaming, handleChunk, connect]
   );
 
   const stopGeneration = useCallback(() => {
-    // Close and reconnect to stop streaming
-    if (wsRef.current) {
+    if (isTauri()) {
+      if (active.requestId) {
+        invoke("chat_cancel", { requestId: active.requestId });
+        active.requestId = null;
+      }
+    } else if (wsRef.current) {
+      // Close and reconnect to stop streaming
       wsRef.current.close();
       wsRef.current = null;
     }
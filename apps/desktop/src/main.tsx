@@ -1,14 +1,31 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
+import { isTauri } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 import App from "./App";
+import QuickAskView from "./views/QuickAskView";
+import SearchView from "./views/SearchView";
 import { ThemeProvider } from "./components/ThemeProvider";
+import { resolveBackendUrl } from "./stores/appStore";
 import "./i18n/config";
 import "./styles/globals.css";
 
-ReactDOM.createRoot(document.getElementById("root")!).render(
-  <React.StrictMode>
-    <ThemeProvider>
-      <App />
-    </ThemeProvider>
-  </React.StrictMode>
-);
+// The shell opens extra windows on the same page and tells them apart by label
+const windowLabel = isTauri() ? getCurrentWindow().label : "main";
+const popups: Record<string, () => JSX.Element> = {
+  "quick-ask": QuickAskView,
+  search: SearchView,
+};
+const Root = popups[windowLabel] ?? App;
+
+// Views fetch from the backend as soon as they mount, so its real port
+// has to be known first
+resolveBackendUrl().then(() => {
+  ReactDOM.createRoot(document.getElementById("root")!).render(
+    <React.StrictMode>
+      <ThemeProvider>
+        <Root />
+      </ThemeProvider>
+    </React.StrictMode>
+  );
+});
@@ -1,4 +1,5 @@
 import { create } from "zustand";
+import { invoke, isTauri } from "@tauri-apps/api/core";
 import type { ViewName } from "../types";
 
 interface AppState {
@@ -11,9 +12,11 @@ interface AppState {
   toggleSidebar: () => void;
   setConnected: (connected: boolean) => void;
   setSetupCompleted: (completed: boolean) => void;
+  setServerUrl: (url: string) => void;
 }
 
-// 从环境变量读取后端 URL，默认本地地址
+// 纯 Web 开发时从环境变量读取后端 URL，默认本地地址；
+// 在 Tauri 中由 Rust 端分配端口，渲染前通过 resolveBackendUrl 获取
 const getBackendUrl = () => {
   if (import.meta.env.VITE_BACKEND_URL) {
     return import.meta.env.VITE_BACKEND_URL;
@@ -31,4 +34,19 @@ export const useAppStore = create<AppState>((set) => ({
   toggleSidebar: () => set((s) => ({ sidebarExpanded: !s.sidebarExpanded })),
   setConnected: (connected) => set({ isConnected: connected }),
   setSetupCompleted: (completed) => set({ setupCompleted: completed }),
+  setServerUrl: (url) => set({ serverUrl: url }),
 }));
+
+// 后端端口可能不是 8420（被占用时由系统分配），必须在首次请求前拿到，
+// 否则请求会被 CSP 拦截或打到其他配置的后端上
+export const resolveBackendUrl = async (): Promise<void> => {
+  if (!isTauri()) {
+    return;
+  }
+  try {
+    const url = await invoke<string>("get_backend_url");
+    useAppStore.getState().setServerUrl(url);
+  } catch (error) {
+    console.error("Failed to resolve backend URL:", error);
+  }
+};
@@ -1,10 +1,13 @@
 import { create } from "zustand";
+import { invoke } from "@tauri-apps/api/core";
 import type { ChatMessage, ToolCallInfo, ToolResultInfo } from "../types";
 
 interface ChatState {
   messages: ChatMessage[];
   isStreaming: boolean;
   conversationId: string | null;
+  /** Text to prefill the chat input with, e.g. from a forwarded launch. */
+  draft: string;
   addUserMessage: (content: string) => string;
   startAssistantMessage: () => string;
   appendToLastMessage: (text: string) => void;
@@ -14,6 +17,8 @@ interface ChatState {
   setStreaming: (streaming: boolean) => void;
   clearMessages: () => void;
   setConversationId: (id: string) => void;
+  loadConversation: (id: string, messages: ChatMessage[]) => void;
+  setDraft: (draft: string) => void;
 }
 
 // Use timestamp + counter for unique IDs (more robust against HMR resets)
@@ -24,6 +29,7 @@ export const useChatStore = create<ChatState>((set) => ({
   messages: [],
   isStreaming: false,
   conversationId: null,
+  draft: "",
 
   addUserMessage: (content: string) => {
     const id = makeId();
@@ -128,4 +134,29 @@ export const useChatStore = create<ChatState>((set) => ({
   clearMessages: () => set({ messages: [], conversationId: null }),
 
   setConversationId: (id: string) => set({ conversationId: id }),
+
+  loadConversation: (id: string, messages: ChatMessage[]) =>
+    set({ conversationId: id, messages, isStreaming: false }),
+
+  setDraft: (draft: string) => set({ draft }),
 }));
+
+interface StoredConversation {
+  id: string;
+  messages: { id: string; role: string; content: string; created_at: string }[];
+}
+
+/**
+ * Load a saved conversation into the chat view. Read by the shell from the
+ * history database, so it works whether or not the backend is running.
+ */
+export async function openConversation(id: string): Promise<void> {
+  const conversation = await invoke<StoredConversation>("get_conversation", { id });
+  const messages: ChatMessage[] = conversation.messages.map((m) => ({
+    id: m.id,
+    role: m.role === "user" ? "user" : "assistant",
+    content: m.content,
+    timestamp: Date.parse(m.created_at) || Date.now(),
+  }));
+  useChatStore.getState().loadConversation(conversation.id, messages);
+}
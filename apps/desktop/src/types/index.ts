@@ -56,6 +56,39 @@ export interface HealthResponse {
   components: Record<string, { status: string; details: Record<string, unknown> }>;
 }
 
+/** Backend health as reported by the Tauri shell (`check_backend_health`, `backend://health`). */
+export interface BackendHealth {
+  status: "ok" | "degraded" | "error" | "unreachable";
+  version: string | null;
+  llm_configured: boolean;
+  memory_reachable: boolean;
+  components: Record<string, { status: string; details: Record<string, unknown> }>;
+  recommendations: string[];
+  latency_ms: number | null;
+  error: string | null;
+  checked_at_ms: number;
+}
+
+/** What every failing Tauri command rejects with. `status` is the backend's HTTP status, if it answered. */
+export interface DeskflowError {
+  kind:
+    | "connect"
+    | "timeout"
+    | "status"
+    | "llm_not_configured"
+    | "decode"
+    | "not_found"
+    | "invalid_input"
+    | "busy"
+    | "database"
+    | "io"
+    | "keyring"
+    | "internal";
+  message: string;
+  retryable: boolean;
+  status: number | null;
+}
+
 export interface SkillInfo {
   name: string;
   description: string;
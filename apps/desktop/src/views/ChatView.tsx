@@ -1,43 +1,69 @@
-import { useEffect, useRef } from "react";
+import { useCallback, useEffect, useRef } from "react";
 import { useTranslation } from "react-i18next";
-import { Brain } from "lucide-react";
-import { useChatStore } from "../stores/chatStore";
+import { isTauri } from "@tauri-apps/api/core";
+import { Brain, CloudOff } from "lucide-react";
+import { useAppStore } from "../stores/appStore";
+import { useChatStore, openConversation } from "../stores/chatStore";
 import { useChat } from "../hooks/useChat";
 import { MessageBubble } from "../components/chat/MessageBubble";
 import { ChatInput } from "../components/chat/ChatInput";
+import { HistoryPanel } from "../components/chat/HistoryPanel";
 
 export default function ChatView() {
   const { t } = useTranslation();
   const messages = useChatStore((s) => s.messages);
+  const conversationId = useChatStore((s) => s.conversationId);
+  const isConnected = useAppStore((s) => s.isConnected);
+  const draft = useChatStore((s) => s.draft);
+  const setDraft = useChatStore((s) => s.setDraft);
+  const clearDraft = useCallback(() => setDraft(""), [setDraft]);
   const { sendMessage, stopGeneration, isStreaming, connect } = useChat();
   const messagesEndRef = useRef<HTMLDivElement>(null);
 
   // Auto-connect to backend on mount
-  useEffect(() => {
-    const ws = connect();
-    return () => {
-      ws.close();
-    };
-  }, [connect]);
+  useEffect(() => connect(), [connect]);
 
   // Auto-scroll to bottom on new messages
   useEffect(() => {
     messagesEndRef.current?.scrollIntoView({ behavior: "smooth" });
   }, [messages]);
 
+  const openHistory = useCallback((id: string) => {
+    openConversation(id).catch((error) => console.error("Failed to open conversation:", error));
+  }, []);
+
   return (
-    <div className="flex-1 flex flex-col overflow-hidden">
-      {/* Messages */}
-      <div className="flex-1 overflow-y-auto px-6 py-6 space-y-4">
-        {messages.length === 0 && <EmptyState t={t} />}
-        {messages.map((msg) => (
-          <MessageBubble key={msg.id} message={msg} />
-        ))}
-        <div ref={messagesEndRef} />
-      </div>
+    <div className="flex-1 flex overflow-hidden">
+      {isTauri() && <HistoryPanel activeId={conversationId} onOpen={openHistory} />}
+
+      <div className="flex-1 flex flex-col overflow-hidden">
+        {/* Read-only while the backend is away; history still comes from disk */}
+        {!isConnected && (
+          <div className="flex items-center gap-2 px-6 py-2 text-xs text-text-m bg-surface border-b border-surface-el shrink-0">
+            <CloudOff className="w-3.5 h-3.5" />
+            {t("chat.offlineReadOnly")}
+          </div>
+        )}
 
-      {/* Input */}
-      <ChatInput onSend={sendMessage} onStop={stopGeneration} isStreaming={isStreaming} />
+        {/* Messages */}
+        <div className="flex-1 overflow-y-auto px-6 py-6 space-y-4">
+          {messages.length === 0 && <EmptyState t={t} />}
+          {messages.map((msg) => (
+            <MessageBubble key={msg.id} message={msg} />
+          ))}
+          <div ref={messagesEndRef} />
+        </div>
+
+        {/* Input */}
+        <ChatInput
+          onSend={sendMessage}
+          onStop={stopGeneration}
+          isStreaming={isStreaming}
+          draft={draft}
+          onDraftConsumed={clearDraft}
+          disabled={!isConnected}
+        />
+      </div>
     </div>
   );
 }
@@ -1,5 +1,7 @@
 import { useEffect, useState, useCallback, useRef } from "react";
 import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useAppStore } from "../stores/appStore";
 import {
   RefreshCw,
@@ -16,6 +18,7 @@ import {
   Terminal,
   X,
 } from "lucide-react";
+import { errorMessage } from "../errors";
 
 interface SystemStatus {
   cpu: { percent: number; cores: number };
@@ -59,6 +62,36 @@ interface ServiceStatus {
   logFile?: string;
 }
 
+// Lifecycle state of the backend supervised by the Tauri shell
+type BackendState =
+  | { state: "stopped" | "stopping" }
+  | { state: "starting"; attempt: number }
+  | { state: "running"; pid: number; started_at_ms: number }
+  | { state: "restarting"; attempt: number; delay_ms: number; exit_code: number | null }
+  | { state: "failed"; exit_code: number | null; message: string };
+
+// Steps of the shell's hang watchdog, see backend/watchdog.rs
+type WatchdogEvent =
+  | { step: "slow"; latency_ms: number | null; slo_ms: number; misses: number }
+  | { step: "hang_detected"; pid: number; misses: number }
+  | { step: "dumped"; path: string; method: "py_spy" | "signal" }
+  | { step: "dump_failed"; error: string }
+  | { step: "restarting" }
+  | { step: "recovered"; latency_ms: number };
+
+const toServiceStatus = (backend: BackendState): ServiceStatus => {
+  if (backend.state === "running") {
+    return {
+      running: true,
+      pid: backend.pid,
+      status: backend.state,
+      start_time: new Date(backend.started_at_ms).toISOString(),
+      uptime_seconds: (Date.now() - backend.started_at_ms) / 1000,
+    };
+  }
+  return { running: false, status: backend.state };
+};
+
 interface MonitorViewProps {
   // Add props if needed
 }
@@ -66,6 +99,7 @@ interface MonitorViewProps {
 export function MonitorView({}: MonitorViewProps) {
   const { t } = useTranslation();
   const isConnected = useAppStore((s) => s.isConnected);
+  const serverUrl = useAppStore((s) => s.serverUrl);
   const [status, setStatus] = useState<SystemStatus | null>(null);
   const [llmStats, setLlmStats] = useState<LLMStats | null>(null);
   const [activities, setActivities] = useState<Activity[]>([]);
@@ -73,15 +107,17 @@ export function MonitorView({}: MonitorViewProps) {
   const [serviceLoading, setServiceLoading] = useState(false);
   const [filter, setFilter] = useState<"all" | "llm" | "tools" | "memory">("all");
   const [showLogStream, setShowLogStream] = useState(false);
+  const [diagnosticsBusy, setDiagnosticsBusy] = useState(false);
+  const [diagnosticsNote, setDiagnosticsNote] = useState<string | null>(null);
+  const [watchdogEvent, setWatchdogEvent] = useState<WatchdogEvent | null>(null);
 
   // Fetch system status
   const fetchStatus = useCallback(async () => {
     try {
-      const [statusRes, llmRes, activityRes, serviceRes] = await Promise.all([
-        fetch("http://127.0.0.1:8420/api/monitor/status"),
-        fetch("http://127.0.0.1:8420/api/monitor/llm-stats"),
-        fetch("http://127.0.0.1:8420/api/monitor/activity?limit=10"),
-        fetch("http://127.0.0.1:8420/api/monitor/service/status"),
+      const [statusRes, llmRes, activityRes] = await Promise.all([
+        fetch(`${serverUrl}/api/monitor/status`),
+        fetch(`${serverUrl}/api/monitor/llm-stats`),
+        fetch(`${serverUrl}/api/monitor/activity?limit=10`),
       ]);
 
       if (statusRes.ok) {
@@ -98,15 +134,10 @@ export function MonitorView({}: MonitorViewProps) {
         const data = await activityRes.json();
         setActivities(data.activities || []);
       }
-
-      if (serviceRes.ok) {
-        const data = await serviceRes.json();
-        setServiceStatus(data);
-      }
     } catch (error) {
       console.error("Failed to fetch status:", error);
     }
-  }, []);
+  }, [serverUrl]);
 
   useEffect(() => {
     fetchStatus();
@@ -114,16 +145,55 @@ export function MonitorView({}: MonitorViewProps) {
     return () => clearInterval(interval);
   }, [fetchStatus]);
 
+  // Backend process state is pushed by the Tauri shell
+  useEffect(() => {
+    invoke<BackendState>("backend_status")
+      .then((state) => setServiceStatus(toServiceStatus(state)))
+      .catch((error) => console.error("Failed to get backend status:", error));
+
+    const unlisten = listen<BackendState>("backend://state", (event) => {
+      setServiceStatus(toServiceStatus(event.payload));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<WatchdogEvent>("backend://watchdog", (event) => {
+      setWatchdogEvent(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const describeWatchdog = (event: WatchdogEvent) => {
+    switch (event.step) {
+      case "slow":
+        return t("monitor.watchdogSlow", {
+          latency: event.latency_ms === null ? t("monitor.watchdogNoAnswer") : `${event.latency_ms} ms`,
+          slo: event.slo_ms,
+          misses: event.misses,
+        });
+      case "hang_detected":
+        return t("monitor.watchdogHang", { pid: event.pid });
+      case "dumped":
+        return t("monitor.watchdogDumped", { path: event.path });
+      case "dump_failed":
+        return t("monitor.watchdogDumpFailed", { error: event.error });
+      case "restarting":
+        return t("monitor.watchdogRestarting");
+      case "recovered":
+        return t("monitor.watchdogRecovered", { latency: event.latency_ms });
+    }
+  };
+
   // Service control
   const handleStartService = async () => {
     setServiceLoading(true);
     try {
-      const res = await fetch("http://127.0.0.1:8420/api/monitor/service/start", {
-        method: "POST",
-      });
-      if (res.ok) {
-        await fetchStatus();
-      }
+      await invoke("backend_start");
     } catch (error) {
       console.error("Failed to start service:", error);
     }
@@ -133,18 +203,26 @@ export function MonitorView({}: MonitorViewProps) {
   const handleStopService = async () => {
     setServiceLoading(true);
     try {
-      const res = await fetch("http://127.0.0.1:8420/api/monitor/service/stop", {
-        method: "POST",
-      });
-      if (res.ok) {
-        await fetchStatus();
-      }
+      await invoke("backend_stop");
     } catch (error) {
       console.error("Failed to stop service:", error);
     }
     setServiceLoading(false);
   };
 
+  // Logs, backend state, health and redacted config in one zip for bug reports
+  const handleCollectDiagnostics = async () => {
+    setDiagnosticsBusy(true);
+    setDiagnosticsNote(null);
+    try {
+      const path = await invoke<string | null>("collect_diagnostics");
+      if (path) setDiagnosticsNote(t("monitor.diagnosticsSaved", { path }));
+    } catch (error) {
+      setDiagnosticsNote(t("monitor.diagnosticsFailed", { error: errorMessage(error) }));
+    }
+    setDiagnosticsBusy(false);
+  };
+
   // Filter activities - fixed type comparison
   const filteredActivities = activities.filter((activity) => {
     if (filter === "all") return true;
@@ -183,10 +261,14 @@ export function MonitorView({}: MonitorViewProps) {
               {t("monitor.refresh", "刷新")}
             </button>
             <button
-              className="px-3 py-1.5 rounded-lg border border-surface-el text-text-s hover:bg-surface cursor-pointer transition-colors duration-200 flex items-center gap-1.5"
+              onClick={handleCollectDiagnostics}
+              disabled={diagnosticsBusy}
+              className="px-3 py-1.5 rounded-lg border border-surface-el text-text-s hover:bg-surface cursor-pointer transition-colors duration-200 flex items-center gap-1.5 disabled:opacity-50 disabled:cursor-not-allowed"
             >
               <Download className="w-4 h-4" />
-              {t("monitor.export", "导出")}
+              {diagnosticsBusy
+                ? t("monitor.diagnosticsBusy", "正在收集…")
+                : t("monitor.diagnostics", "导出诊断包")}
             </button>
             <button
               onClick={() => setShowLogStream(true)}
@@ -199,6 +281,28 @@ export function MonitorView({}: MonitorViewProps) {
           </div>
         </div>
 
+        {watchdogEvent && (
+          <div
+            className={`mb-4 px-3 py-2 rounded-lg text-xs flex items-center gap-2 break-all ${
+              watchdogEvent.step === "recovered" ? "bg-accent/10 text-accent" : "bg-warning/10 text-warning"
+            }`}
+          >
+            {watchdogEvent.step === "recovered" ? (
+              <CheckCircle className="w-4 h-4 shrink-0" />
+            ) : (
+              <AlertTriangle className="w-4 h-4 shrink-0" />
+            )}
+            <span className="flex-1">{describeWatchdog(watchdogEvent)}</span>
+            <button onClick={() => setWatchdogEvent(null)} className="shrink-0 hover:opacity-70">
+              <X className="w-3.5 h-3.5" />
+            </button>
+          </div>
+        )}
+
+        {diagnosticsNote && (
+          <p className="mb-4 text-xs text-text-s font-code break-all">{diagnosticsNote}</p>
+        )}
+
         {/* Service Control Card */}
         <div className="mb-6">
           <ServiceControlCard
@@ -516,15 +620,31 @@ function ResourceBar({ label, value, percentage, color }: {
   );
 }
 
-// 日志条目类型
+// 日志条目类型，与 shell 的 logs::LogEntry 一致
 interface LogEntry {
   timestamp: string;
-  level: "DEBUG" | "INFO" | "WARNING" | "ERROR";
+  timestamp_ms: number;
+  level: "TRACE" | "DEBUG" | "INFO" | "WARN" | "ERROR";
+  source: "shell" | "backend";
+  target: string;
   message: string;
-  [key: string]: unknown;
+  fields: Record<string, unknown>;
+}
+
+interface LogFilter {
+  level?: LogEntry["level"];
+  source?: LogEntry["source"];
+  module?: string;
+  since_ms?: number;
 }
 
-// 实时日志流组件
+const LOG_POLL_INTERVAL = 2000;
+const LOG_LIMIT = 500;
+
+const sameEntry = (a: LogEntry, b: LogEntry) =>
+  a.timestamp === b.timestamp && a.source === b.source && a.message === b.message;
+
+// 日志查看器：读取 shell 保存的 shell 与后端日志，后端停止时同样可用
 function LogStreamPanel({
   onClose,
 }: {
@@ -532,8 +652,10 @@ function LogStreamPanel({
 }) {
   const [logs, setLogs] = useState<LogEntry[]>([]);
   const [filterLevel, setFilterLevel] = useState<string>("all");
-  const [connected, setConnected] = useState(false);
-  const eventSourceRef = useRef<EventSource | null>(null);
+  const [filterSource, setFilterSource] = useState<string>("all");
+  const [filterModule, setFilterModule] = useState("");
+  const [error, setError] = useState<string | null>(null);
+  const [exported, setExported] = useState<string | null>(null);
   const logsEndRef = useRef<HTMLDivElement>(null);
 
   // 自动滚动到底部
@@ -541,50 +663,64 @@ function LogStreamPanel({
     logsEndRef.current?.scrollIntoView({ behavior: "smooth" });
   }, [logs]);
 
-  useEffect(() => {
-    // 连接 SSE 流
-    const eventSource = new EventSource("http://127.0.0.1:8420/api/logs/stream");
+  const filter = useCallback((): LogFilter => ({
+    level: filterLevel === "all" ? undefined : (filterLevel as LogEntry["level"]),
+    source: filterSource === "all" ? undefined : (filterSource as LogEntry["source"]),
+    module: filterModule.trim() || undefined,
+  }), [filterLevel, filterSource, filterModule]);
 
-    eventSource.onopen = () => {
-      setConnected(true);
-      console.log("Log stream connected");
-    };
+  useEffect(() => {
+    let cancelled = false;
+    let latest: LogEntry[] = [];
+    setLogs([]);
 
-    eventSource.addEventListener("log", (event) => {
+    // 先取最近的日志，之后只取上次最后一条之后的
+    const poll = async () => {
+      const last = latest[latest.length - 1];
       try {
-        const logEntry: LogEntry = JSON.parse(event.data);
-        setLogs((prev) => [...prev.slice(-99), logEntry]); // 保留最新 100 条
+        const entries = await invoke<LogEntry[]>("tail_logs", {
+          filter: { ...filter(), since_ms: last?.timestamp_ms },
+          limit: LOG_LIMIT,
+        });
+        if (cancelled) return;
+        const fresh = last
+          ? entries.filter(
+              (e) => e.timestamp_ms > last.timestamp_ms || !latest.some((l) => sameEntry(l, e)),
+            )
+          : entries;
+        if (fresh.length > 0) {
+          latest = [...latest, ...fresh].slice(-LOG_LIMIT);
+          setLogs(latest);
+        }
+        setError(null);
       } catch (e) {
-        console.error("Failed to parse log entry:", e);
+        if (!cancelled) setError(errorMessage(e));
       }
-    });
-
-    eventSource.addEventListener("connected", (event) => {
-      console.log("Stream status:", event.data);
-    });
-
-    eventSource.onerror = (error) => {
-      console.error("Log stream error:", error);
-      setConnected(false);
-      eventSource.close();
     };
 
-    eventSourceRef.current = eventSource;
-
+    poll();
+    const timer = setInterval(poll, LOG_POLL_INTERVAL);
     return () => {
-      eventSource.close();
+      cancelled = true;
+      clearInterval(timer);
     };
-  }, []);
+  }, [filter]);
 
-  const filteredLogs = filterLevel === "all"
-    ? logs
-    : logs.filter((log) => log.level === filterLevel);
+  const handleExport = async () => {
+    try {
+      const path = await invoke<string | null>("export_logs", { filter: filter() });
+      if (path) setExported(path);
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  };
 
   const getLevelColor = (level: string) => {
     switch (level) {
+      case "TRACE":
       case "DEBUG": return "text-text-s";
       case "INFO": return "text-info";
-      case "WARNING": return "text-warning";
+      case "WARN": return "text-warning";
       case "ERROR": return "text-rose-500";
       default: return "text-text-p";
     }
@@ -592,9 +728,10 @@ function LogStreamPanel({
 
   const getLevelBg = (level: string) => {
     switch (level) {
+      case "TRACE":
       case "DEBUG": return "bg-bg-base";
       case "INFO": return "bg-info/10";
-      case "WARNING": return "bg-warning/10";
+      case "WARN": return "bg-warning/10";
       case "ERROR": return "bg-rose-500/10";
       default: return "bg-surface";
     }
@@ -607,30 +744,49 @@ function LogStreamPanel({
         <div className="flex items-center justify-between p-4 border-b border-surface">
           <div className="flex items-center gap-3">
             <Terminal className="w-5 h-5 text-accent" />
-            <h2 className="text-lg font-semibold text-text-p">实时日志流</h2>
-            <span className={`text-xs px-2 py-0.5 rounded-full ${connected ? "bg-accent/20 text-accent" : "bg-text-m/20 text-text-m"}`}>
-              {connected ? "已连接" : "未连接"}
-            </span>
+            <h2 className="text-lg font-semibold text-text-p">日志</h2>
+            {error && (
+              <span className="text-xs px-2 py-0.5 rounded-full bg-rose-500/20 text-rose-500" title={error}>
+                读取失败
+              </span>
+            )}
           </div>
           <div className="flex items-center gap-2">
-            {/* Level Filter */}
+            {/* Source Filter */}
+            <select
+              value={filterSource}
+              onChange={(e) => setFilterSource(e.target.value)}
+              className="px-2 py-1 bg-bg-base border border-surface rounded text-xs text-text-s focus:outline-none focus:border-accent"
+            >
+              <option value="all">全部来源</option>
+              <option value="shell">Shell</option>
+              <option value="backend">后端</option>
+            </select>
+            {/* Level Filter: 该级别及以上 */}
             <select
               value={filterLevel}
               onChange={(e) => setFilterLevel(e.target.value)}
               className="px-2 py-1 bg-bg-base border border-surface rounded text-xs text-text-s focus:outline-none focus:border-accent"
             >
-              <option value="all">全部</option>
-              <option value="DEBUG">DEBUG</option>
-              <option value="INFO">INFO</option>
-              <option value="WARNING">WARNING</option>
+              <option value="all">全部级别</option>
+              <option value="DEBUG">DEBUG+</option>
+              <option value="INFO">INFO+</option>
+              <option value="WARN">WARN+</option>
               <option value="ERROR">ERROR</option>
             </select>
+            {/* Module Filter */}
+            <input
+              value={filterModule}
+              onChange={(e) => setFilterModule(e.target.value)}
+              placeholder="模块"
+              className="w-28 px-2 py-1 bg-bg-base border border-surface rounded text-xs text-text-s focus:outline-none focus:border-accent"
+            />
             <button
-              onClick={() => setLogs([])}
+              onClick={handleExport}
               className="p-1.5 text-text-s hover:bg-bg-base rounded transition-colors"
-              title="清空日志"
+              title="导出日志"
             >
-              <RefreshCw className="w-4 h-4" />
+              <Download className="w-4 h-4" />
             </button>
             <button
               onClick={onClose}
@@ -643,14 +799,14 @@ function LogStreamPanel({
 
         {/* Log Content */}
         <div className="flex-1 overflow-auto p-4 font-mono text-xs bg-bg-base">
-          {filteredLogs.length === 0 ? (
+          {logs.length === 0 ? (
             <div className="text-center text-text-s py-8">
               <Terminal className="w-12 h-12 mx-auto mb-2 opacity-20" />
-              <p>等待日志...</p>
+              <p>暂无日志</p>
             </div>
           ) : (
             <div className="space-y-0.5">
-              {filteredLogs.map((log, index) => (
+              {logs.map((log, index) => (
                 <div
                   key={index}
                   className={`px-2 py-1 rounded ${getLevelBg(log.level)} hover:bg-surface-el/50`}
@@ -661,6 +817,10 @@ function LogStreamPanel({
                   <span className={`font-semibold mr-2 ${getLevelColor(log.level)}`}>
                     {log.level}
                   </span>
+                  <span className="text-text-m mr-2">
+                    {log.source === "shell" ? "shell" : "backend"}
+                    {log.target && ` ${log.target}`}
+                  </span>
                   <span className="text-text-p">{log.message}</span>
                 </div>
               ))}
@@ -671,8 +831,8 @@ function LogStreamPanel({
 
         {/* Footer Stats */}
         <div className="px-4 py-2 border-t border-surface flex items-center justify-between text-xs text-text-s">
-          <span>显示 {filteredLogs.length} / {logs.length} 条日志</span>
-          <span className="font-code">SSE 实时流</span>
+          <span>显示最近 {logs.length} 条日志</span>
+          <span className="font-code truncate ml-4">{exported ? `已导出到 ${exported}` : "shell 日志文件"}</span>
         </div>
       </div>
     </div>
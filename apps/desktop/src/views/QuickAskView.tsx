@@ -0,0 +1,151 @@
+import { useCallback, useEffect, useRef, useState, KeyboardEvent } from "react";
+import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import ReactMarkdown from "react-markdown";
+import { ArrowUpRight, Loader2, Square } from "lucide-react";
+import type { StreamChunk } from "../types";
+
+interface ChatEvent {
+  request_id: string;
+  conversation_id: string | null;
+  chunk: StreamChunk;
+}
+
+/**
+ * Frameless popup opened by the global quick-ask shortcut. Sends one prompt
+ * through the shell's chat bridge and shows the streamed answer.
+ */
+export default function QuickAskView() {
+  const { t } = useTranslation();
+  const [prompt, setPrompt] = useState("");
+  const [answer, setAnswer] = useState("");
+  const [streaming, setStreaming] = useState(false);
+  const [conversationId, setConversationId] = useState<string | null>(null);
+  const requestIdRef = useRef<string | null>(null);
+  const inputRef = useRef<HTMLInputElement>(null);
+
+  // Chunks are routed to this window only
+  useEffect(() => {
+    const unlisten = listen<ChatEvent>("chat://chunk", (event) => {
+      const { request_id, conversation_id, chunk } = event.payload;
+      if (requestIdRef.current && request_id !== requestIdRef.current) return;
+      requestIdRef.current = request_id;
+      if (conversation_id) setConversationId(conversation_id);
+
+      switch (chunk.type) {
+        case "text":
+          setAnswer((a) => a + (chunk.content || ""));
+          break;
+        case "error":
+          setAnswer((a) => `${a}\n\n**Error:** ${chunk.content || "Unknown error"}`);
+          break;
+        case "done":
+          requestIdRef.current = null;
+          setStreaming(false);
+          break;
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Focus the prompt every time the popup is shown again
+  useEffect(() => {
+    const unlisten = getCurrentWindow().onFocusChanged(({ payload: focused }) => {
+      if (focused) inputRef.current?.focus();
+    });
+    inputRef.current?.focus();
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const send = useCallback(() => {
+    const message = prompt.trim();
+    if (!message || streaming) return;
+
+    setAnswer("");
+    setConversationId(null);
+    setStreaming(true);
+    invoke<string>("chat_send", { message, conversationId: null })
+      .then((requestId) => {
+        requestIdRef.current = requestId;
+      })
+      .catch((e) => {
+        setAnswer(`**Error:** ${e}`);
+        setStreaming(false);
+      });
+  }, [prompt, streaming]);
+
+  const stop = useCallback(() => {
+    if (requestIdRef.current) {
+      invoke("chat_cancel", { requestId: requestIdRef.current });
+    }
+  }, []);
+
+  const handleKeyDown = useCallback(
+    (e: KeyboardEvent<HTMLInputElement>) => {
+      if (e.key === "Enter") {
+        e.preventDefault();
+        send();
+      } else if (e.key === "Escape") {
+        getCurrentWindow().hide();
+      }
+    },
+    [send]
+  );
+
+  const openInApp = useCallback(() => {
+    invoke("open_in_main_window", { conversationId });
+    getCurrentWindow().hide();
+  }, [conversationId]);
+
+  return (
+    <div className="w-full h-screen flex flex-col bg-bg-deep border border-surface-el rounded-xl overflow-hidden">
+      <div className="flex items-center gap-3 px-4 py-3 border-b border-surface" data-tauri-drag-region>
+        <input
+          ref={inputRef}
+          value={prompt}
+          onChange={(e) => setPrompt(e.target.value)}
+          onKeyDown={handleKeyDown}
+          placeholder={t("quickAsk.placeholder")}
+          className="flex-1 bg-transparent text-sm text-text-p placeholder:text-text-m focus:outline-none"
+        />
+        {streaming && (
+          <button
+            onClick={stop}
+            className="p-1.5 rounded-lg text-text-m hover:text-text-p hover:bg-surface transition-colors"
+            title={t("quickAsk.stop")}
+          >
+            <Square size={14} />
+          </button>
+        )}
+      </div>
+
+      <div className="flex-1 overflow-y-auto px-4 py-3 text-sm text-text-p">
+        {streaming && !answer && (
+          <div className="flex items-center gap-2 text-text-m">
+            <Loader2 size={14} className="animate-spin" />
+            {t("quickAsk.thinking")}
+          </div>
+        )}
+        {answer && <ReactMarkdown>{answer}</ReactMarkdown>}
+      </div>
+
+      {conversationId && !streaming && (
+        <div className="flex justify-end px-4 py-2 border-t border-surface">
+          <button
+            onClick={openInApp}
+            className="flex items-center gap-1 text-xs text-accent hover:underline"
+          >
+            {t("quickAsk.openInApp")}
+            <ArrowUpRight size={12} />
+          </button>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,321 @@
+import { useCallback, useEffect, useRef, useState, KeyboardEvent, ReactNode } from "react";
+import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { Brain, MessageSquare, Search } from "lucide-react";
+import { Snippet } from "../components/chat/HistoryPanel";
+import { errorMessage } from "../errors";
+
+interface Page<T> {
+  hits: T[];
+  offset: number;
+  has_more: boolean;
+}
+
+interface MemoryHit {
+  id: string;
+  memory_type: string;
+  importance: number;
+  tags: string[];
+  source_conversation_id: string | null;
+  snippet: string;
+  created_at: number;
+}
+
+interface MessageHit {
+  conversation_id: string;
+  conversation_title: string;
+  message_id: string;
+  role: string;
+  snippet: string;
+  created_at: string;
+}
+
+type Scope = "all" | "memories" | "messages";
+
+const MEMORY_TYPES = ["episodic", "semantic", "procedural", "insight"];
+const PAGE_SIZE = 20;
+
+interface Filters {
+  memoryType: string;
+  minImportance: string;
+  since: string;
+  until: string;
+}
+
+const NO_FILTERS: Filters = { memoryType: "", minImportance: "", since: "", until: "" };
+
+/**
+ * Command-palette search over memories and chat history, opened by the
+ * shell as a frameless popup. Results come straight from the profile's
+ * databases, so it also works while the backend is down.
+ */
+export default function SearchView() {
+  const { t } = useTranslation();
+  const [query, setQuery] = useState("");
+  const [scope, setScope] = useState<Scope>("all");
+  const [filters, setFilters] = useState<Filters>(NO_FILTERS);
+  const [memories, setMemories] = useState<Page<MemoryHit> | null>(null);
+  const [messages, setMessages] = useState<Page<MessageHit> | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const inputRef = useRef<HTMLInputElement>(null);
+
+  const range = {
+    since: filters.since || null,
+    until: filters.until || null,
+  };
+  const memoryFilters = {
+    memory_types: filters.memoryType ? [filters.memoryType] : [],
+    min_importance: filters.minImportance ? Number(filters.minImportance) : null,
+    ...range,
+  };
+
+  const searchMemories = (q: string, offset: number) =>
+    invoke<Page<MemoryHit>>("search_memories", {
+      query: q,
+      filters: memoryFilters,
+      limit: PAGE_SIZE,
+      offset,
+    });
+  const searchMessages = (q: string, offset: number) =>
+    invoke<Page<MessageHit>>("search_messages", { query: q, range, limit: PAGE_SIZE, offset });
+
+  // Debounced search from the first page whenever the query or filters change
+  useEffect(() => {
+    const q = query.trim();
+    if (!q) {
+      setMemories(null);
+      setMessages(null);
+      return;
+    }
+    // Either database may be missing (e.g. no chats yet); show what the other has
+    const settle = <T,>(page: Promise<T>) =>
+      page.catch((e) => {
+        setError(errorMessage(e));
+        return null;
+      });
+    const timer = setTimeout(() => {
+      setError(null);
+      Promise.all([
+        scope !== "messages" ? settle(searchMemories(q, 0)) : null,
+        scope !== "memories" ? settle(searchMessages(q, 0)) : null,
+      ]).then(([memoryPage, messagePage]) => {
+        setMemories(memoryPage);
+        setMessages(messagePage);
+      });
+    }, 200);
+    return () => clearTimeout(timer);
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [query, scope, filters]);
+
+  const moreMemories = useCallback(() => {
+    if (!memories) return;
+    searchMemories(query.trim(), memories.offset + memories.hits.length)
+      .then((page) => setMemories({ ...page, hits: [...memories.hits, ...page.hits] }))
+      .catch((e) => setError(errorMessage(e)));
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [memories, query, filters]);
+
+  const moreMessages = useCallback(() => {
+    if (!messages) return;
+    searchMessages(query.trim(), messages.offset + messages.hits.length)
+      .then((page) => setMessages({ ...page, hits: [...messages.hits, ...page.hits] }))
+      .catch((e) => setError(errorMessage(e)));
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [messages, query, filters]);
+
+  // Focus the query every time the palette is shown again
+  useEffect(() => {
+    const unlisten = getCurrentWindow().onFocusChanged(({ payload: focused }) => {
+      if (focused) inputRef.current?.select();
+    });
+    inputRef.current?.focus();
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const openConversation = useCallback((conversationId: string) => {
+    invoke("open_in_main_window", { conversationId });
+    getCurrentWindow().hide();
+  }, []);
+
+  const handleKeyDown = useCallback((e: KeyboardEvent<HTMLDivElement>) => {
+    if (e.key === "Escape") {
+      getCurrentWindow().hide();
+    }
+  }, []);
+
+  const setFilter = (key: keyof Filters) => (value: string) =>
+    setFilters((f) => ({ ...f, [key]: value }));
+
+  const empty =
+    query.trim() && !error && !memories?.hits.length && !messages?.hits.length;
+
+  return (
+    <div
+      onKeyDown={handleKeyDown}
+      className="w-full h-screen flex flex-col bg-bg-deep border border-surface-el rounded-xl overflow-hidden"
+    >
+      <div className="flex items-center gap-3 px-4 py-3 border-b border-surface" data-tauri-drag-region>
+        <Search size={16} className="text-text-m shrink-0" />
+        <input
+          ref={inputRef}
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          placeholder={t("search.placeholder")}
+          className="flex-1 bg-transparent text-sm text-text-p placeholder:text-text-m focus:outline-none"
+        />
+      </div>
+
+      <div className="flex flex-wrap items-center gap-2 px-4 py-2 border-b border-surface text-xs">
+        {(["all", "memories", "messages"] as Scope[]).map((s) => (
+          <button
+            key={s}
+            onClick={() => setScope(s)}
+            className={`px-2 py-1 rounded-md transition-colors ${
+              scope === s ? "bg-accent/10 text-accent" : "text-text-s hover:bg-surface"
+            }`}
+          >
+            {t(`search.scope.${s}`)}
+          </button>
+        ))}
+        <span className="flex-1" />
+        {scope !== "messages" && (
+          <>
+            <FilterSelect
+              value={filters.memoryType}
+              onChange={setFilter("memoryType")}
+              options={[["", t("search.anyType")], ...MEMORY_TYPES.map((m) => [m, m])]}
+            />
+            <FilterSelect
+              value={filters.minImportance}
+              onChange={setFilter("minImportance")}
+              options={[
+                ["", t("search.anyImportance")],
+                ["0.5", "≥ 0.5"],
+                ["0.7", "≥ 0.7"],
+                ["0.9", "≥ 0.9"],
+              ]}
+            />
+          </>
+        )}
+        <input
+          type="date"
+          value={filters.since}
+          onChange={(e) => setFilter("since")(e.target.value)}
+          title={t("search.since")}
+          className="bg-surface border border-surface-el rounded-md px-1.5 py-0.5 text-text-s"
+        />
+        <input
+          type="date"
+          value={filters.until}
+          onChange={(e) => setFilter("until")(e.target.value)}
+          title={t("search.until")}
+          className="bg-surface border border-surface-el rounded-md px-1.5 py-0.5 text-text-s"
+        />
+      </div>
+
+      <div className="flex-1 overflow-y-auto py-1">
+        {error && <p className="px-4 py-2 text-xs text-text-m">{error}</p>}
+        {empty && <p className="px-4 py-2 text-xs text-text-m">{t("search.noResults")}</p>}
+
+        {memories && memories.hits.length > 0 && (
+          <Section title={t("search.scope.memories")} hasMore={memories.has_more} onMore={moreMemories}>
+            {memories.hits.map((hit) => (
+              <button
+                key={hit.id}
+                disabled={!hit.source_conversation_id}
+                onClick={() => hit.source_conversation_id && openConversation(hit.source_conversation_id)}
+                className="w-full text-left px-4 py-2 flex gap-3 hover:bg-surface transition-colors disabled:hover:bg-transparent"
+              >
+                <Brain size={14} className="text-accent shrink-0 mt-0.5" />
+                <div className="min-w-0">
+                  <div className="text-sm text-text-s line-clamp-2">
+                    <Snippet text={hit.snippet} />
+                  </div>
+                  <div className="text-xs text-text-m mt-0.5">
+                    {hit.memory_type} · {hit.importance.toFixed(1)} ·{" "}
+                    {new Date(hit.created_at * 1000).toLocaleDateString()}
+                    {hit.tags.length > 0 && ` · ${hit.tags.join(", ")}`}
+                  </div>
+                </div>
+              </button>
+            ))}
+          </Section>
+        )}
+
+        {messages && messages.hits.length > 0 && (
+          <Section title={t("search.scope.messages")} hasMore={messages.has_more} onMore={moreMessages}>
+            {messages.hits.map((hit) => (
+              <button
+                key={hit.message_id}
+                onClick={() => openConversation(hit.conversation_id)}
+                className="w-full text-left px-4 py-2 flex gap-3 hover:bg-surface transition-colors"
+              >
+                <MessageSquare size={14} className="text-text-m shrink-0 mt-0.5" />
+                <div className="min-w-0">
+                  <div className="text-sm text-text-s line-clamp-2">
+                    <Snippet text={hit.snippet} />
+                  </div>
+                  <div className="text-xs text-text-m mt-0.5 truncate">
+                    {hit.conversation_title} · {new Date(hit.created_at).toLocaleDateString()}
+                  </div>
+                </div>
+              </button>
+            ))}
+          </Section>
+        )}
+      </div>
+    </div>
+  );
+}
+
+function Section({
+  title,
+  hasMore,
+  onMore,
+  children,
+}: {
+  title: string;
+  hasMore: boolean;
+  onMore: () => void;
+  children: ReactNode;
+}) {
+  const { t } = useTranslation();
+  return (
+    <div className="py-1">
+      <div className="px-4 py-1 text-xs font-semibold text-text-m uppercase tracking-wide">{title}</div>
+      {children}
+      {hasMore && (
+        <button onClick={onMore} className="px-4 py-1.5 text-xs text-accent hover:underline">
+          {t("search.more")}
+        </button>
+      )}
+    </div>
+  );
+}
+
+function FilterSelect({
+  value,
+  onChange,
+  options,
+}: {
+  value: string;
+  onChange: (value: string) => void;
+  options: string[][];
+}) {
+  return (
+    <select
+      value={value}
+      onChange={(e) => onChange(e.target.value)}
+      className="bg-surface border border-surface-el rounded-md px-1.5 py-0.5 text-text-s"
+    >
+      {options.map(([v, label]) => (
+        <option key={v} value={v}>
+          {label}
+        </option>
+      ))}
+    </select>
+  );
+}
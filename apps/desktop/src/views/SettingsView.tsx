@@ -1,9 +1,12 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useCallback } from "react";
 import { useTranslation } from "react-i18next";
 import { Play, Save, Plus, Eye, EyeOff, CheckCircle, XCircle, Loader, Sun, Moon } from "lucide-react";
+import { invoke, isTauri } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useAppStore } from "../stores/appStore";
 import { useThemeStore } from "../stores/themeStore";
 import { useLocaleStore } from "../stores/localeStore";
+import { errorMessage } from "../errors";
 
 type SettingsSection = "llm" | "channels" | "identity" | "system";
 type SaveStatus = "idle" | "saving" | "success" | "error";
@@ -303,6 +306,13 @@ export default function SettingsView() {
     setSaveMessage("");
 
     try {
+      // In the desktop app the key goes to the OS keyring; the shell restarts
+      // the backend with it, so it is never sent over HTTP
+      const keepKeyInShell = isTauri();
+      if (keepKeyInShell && apiKey) {
+        await invoke("secrets_set", { provider, value: apiKey });
+      }
+
       const response = await fetch(`${serverUrl}/api/config`, {
         method: "POST",
         headers: { "Content-Type": "application/json" },
@@ -311,7 +321,7 @@ export default function SettingsView() {
           llm_model: model,
           llm_temperature: temperature / 100,
           llm_max_tokens: maxTokens,
-          ...(apiKey && { api_key: apiKey }),
+          ...(apiKey && !keepKeyInShell && { api_key: apiKey }),
           ...(provider !== "anthropic" && { base_url: baseUrl }),
           // System settings
           server_port: serverPort,
@@ -366,8 +376,9 @@ export default function SettingsView() {
   };
 
   // Store API key in session storage to persist across page reloads
+  // (web-only development; the desktop app keeps keys in the keyring)
   useEffect(() => {
-    if (apiKey) {
+    if (apiKey && !isTauri()) {
       sessionStorage.setItem(`llm_api_key_${provider}`, apiKey);
     }
   }, [apiKey, provider]);
@@ -830,6 +841,13 @@ export default function SettingsView() {
                   </FormField>
                 </div>
 
+                {isTauri() && <ProfileSection />}
+                {isTauri() && <EncryptionSection />}
+                {isTauri() && <BackupSection />}
+                {isTauri() && <WatchdogSection />}
+                {isTauri() && <NotificationsSection />}
+                {isTauri() && <UpdatesSection />}
+
                 <FormField label={t("settings.serverPort")} hint={t("settings.requiresRestart")}>
                   <input
                     type="number"
@@ -919,6 +937,637 @@ function FormField({
   );
 }
 
+interface ProfileEntry {
+  name: string;
+  created_at: number;
+  active: boolean;
+}
+
+// Profiles are managed by the desktop shell; switching restarts the backend
+function ProfileSection() {
+  const { t } = useTranslation();
+  const [profiles, setProfiles] = useState<ProfileEntry[]>([]);
+  const [newName, setNewName] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const load = useCallback(() => {
+    invoke<ProfileEntry[]>("list_profiles").then(setProfiles).catch((e) => setError(errorMessage(e)));
+  }, []);
+
+  useEffect(load, [load]);
+
+  // Imports from the backup section add profiles behind our back
+  useEffect(() => {
+    const unlisten = listen("profile://imported", load);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [load]);
+
+  const switchTo = async (name: string) => {
+    setBusy(true);
+    setError(null);
+    try {
+      await invoke("switch_profile", { name });
+      load();
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const create = async () => {
+    const name = newName.trim();
+    if (!name) return;
+    setError(null);
+    try {
+      await invoke("create_profile", { name });
+      setNewName("");
+      load();
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  };
+
+  return (
+    <div className="space-y-4 py-4 border-b border-surface-el">
+      <FormField label={t("settings.profile")} hint={busy ? t("settings.switchingProfile") : t("settings.profileHint")}>
+        <div className="flex items-center gap-2">
+          <select
+            value={profiles.find((p) => p.active)?.name ?? ""}
+            onChange={(e) => switchTo(e.target.value)}
+            disabled={busy}
+            className="setting-input flex-1"
+          >
+            {profiles.map((p) => (
+              <option key={p.name} value={p.name}>
+                {p.name}
+              </option>
+            ))}
+          </select>
+          <input
+            value={newName}
+            onChange={(e) => setNewName(e.target.value)}
+            onKeyDown={(e) => e.key === "Enter" && create()}
+            placeholder={t("settings.newProfile")}
+            className="setting-input flex-1"
+          />
+          <button
+            onClick={create}
+            disabled={!newName.trim() || busy}
+            className="px-3 py-2 rounded-lg border border-surface-el text-sm text-text-s hover:bg-surface transition-colors disabled:opacity-40"
+          >
+            {t("settings.createProfile")}
+          </button>
+        </div>
+      </FormField>
+      {error && <p className="text-xs text-red-400">{error}</p>}
+    </div>
+  );
+}
+
+interface EncryptionStatus {
+  state: "missing" | "plaintext" | "encrypted";
+  key_stored: boolean;
+}
+
+interface IntegrityReport {
+  ok: boolean;
+  problems: string[];
+}
+
+// The database key lives in the OS keyring, managed by the desktop shell
+function EncryptionSection() {
+  const { t } = useTranslation();
+  const [status, setStatus] = useState<EncryptionStatus | null>(null);
+  const [busy, setBusy] = useState(false);
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<EncryptionStatus>("database_encryption_status")
+      .then(setStatus)
+      .catch((e) => setMessage(errorMessage(e)));
+  }, []);
+
+  const run = async (action: () => Promise<void>) => {
+    setBusy(true);
+    setMessage(null);
+    try {
+      await action();
+    } catch (e) {
+      setMessage(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const encrypt = () =>
+    run(async () => {
+      setStatus(await invoke<EncryptionStatus>("encrypt_database"));
+    });
+  const rotate = () =>
+    run(async () => {
+      await invoke("rotate_database_key");
+      setMessage(t("settings.keyRotated"));
+    });
+  const verify = () =>
+    run(async () => {
+      const report = await invoke<IntegrityReport>("verify_database");
+      setMessage(report.ok ? t("settings.databaseIntact") : report.problems.join("\n"));
+    });
+
+  const encrypted = status?.state === "encrypted" || (status?.state === "missing" && status.key_stored);
+  const buttonClass =
+    "px-3 py-2 rounded-lg border border-surface-el text-sm text-text-s hover:bg-surface transition-colors disabled:opacity-40";
+
+  return (
+    <div className="space-y-4 py-4 border-b border-surface-el">
+      <FormField
+        label={t("settings.databaseEncryption")}
+        hint={busy ? t("settings.encryptionBusy") : t("settings.databaseEncryptionHint")}
+      >
+        <div className="flex items-center gap-2">
+          <span className="flex-1 text-sm text-text-s">
+            {status && (encrypted ? t("settings.databaseEncrypted") : t("settings.databasePlaintext"))}
+          </span>
+          {status && !encrypted && (
+            <button onClick={encrypt} disabled={busy} className={buttonClass}>
+              {t("settings.encryptDatabase")}
+            </button>
+          )}
+          {status?.state === "encrypted" && (
+            <button onClick={rotate} disabled={busy} className={buttonClass}>
+              {t("settings.rotateKey")}
+            </button>
+          )}
+          <button onClick={verify} disabled={busy} className={buttonClass}>
+            {t("settings.verifyDatabase")}
+          </button>
+        </div>
+      </FormField>
+      {message && <p className="text-xs text-text-m whitespace-pre-line">{message}</p>}
+    </div>
+  );
+}
+
+interface ShellSettings {
+  auto_backup_hours: number;
+  backup_retention: number;
+  watchdog_latency_ms: number;
+  watchdog_misses: number;
+  notifications_enabled: boolean;
+  muted_notifications: NotificationCategory[];
+  quiet_hours_start: string;
+  quiet_hours_end: string;
+  update_channel: UpdateChannel;
+  update_check_hours: number;
+  auto_download_updates: boolean;
+  [key: string]: unknown;
+}
+
+type NotificationCategory = "task_completed" | "error" | "channel_message" | "suggestion";
+
+const AUTO_BACKUP_HOURS = [0, 24, 168];
+
+// Archives are written and read by the desktop shell, which owns the
+// profile directories and database key
+function BackupSection() {
+  const { t } = useTranslation();
+  const [settings, setSettings] = useState<ShellSettings | null>(null);
+  const [decrypt, setDecrypt] = useState(false);
+  const [importName, setImportName] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<ShellSettings>("get_shell_settings")
+      .then(setSettings)
+      .catch((e) => setMessage(errorMessage(e)));
+  }, []);
+
+  const run = async (action: () => Promise<void>) => {
+    setBusy(true);
+    setMessage(null);
+    try {
+      await action();
+    } catch (e) {
+      setMessage(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const exportProfile = () =>
+    run(async () => {
+      const path = await invoke<string | null>("export_profile", { decrypt });
+      if (path) setMessage(t("settings.exportedTo", { path }));
+    });
+  const importProfile = () =>
+    run(async () => {
+      const info = await invoke<ProfileEntry | null>("import_profile", {
+        name: importName.trim() || null,
+      });
+      if (info) {
+        setImportName("");
+        setMessage(t("settings.profileImported", { name: info.name }));
+      }
+    });
+
+  const update = (change: Partial<ShellSettings>) => {
+    if (!settings) return;
+    invoke<ShellSettings>("set_shell_settings", { value: { ...settings, ...change } })
+      .then(setSettings)
+      .catch((e) => setMessage(errorMessage(e)));
+  };
+
+  const hourOptions = settings && !AUTO_BACKUP_HOURS.includes(settings.auto_backup_hours)
+    ? [...AUTO_BACKUP_HOURS, settings.auto_backup_hours]
+    : AUTO_BACKUP_HOURS;
+  const hoursLabel = (hours: number) =>
+    hours === 0
+      ? t("settings.autoBackupOff")
+      : hours === 24
+        ? t("settings.autoBackupDaily")
+        : hours === 168
+          ? t("settings.autoBackupWeekly")
+          : t("settings.autoBackupEvery", { hours });
+  const buttonClass =
+    "px-3 py-2 rounded-lg border border-surface-el text-sm text-text-s hover:bg-surface transition-colors disabled:opacity-40";
+
+  return (
+    <div className="space-y-4 py-4 border-b border-surface-el">
+      <FormField label={t("settings.backup")} hint={busy ? t("settings.backupBusy") : t("settings.backupHint")}>
+        <div className="space-y-2">
+          <div className="flex items-center gap-2">
+            <label className="flex-1 flex items-center gap-2 text-sm text-text-s">
+              <input type="checkbox" checked={decrypt} onChange={(e) => setDecrypt(e.target.checked)} />
+              {t("settings.decryptArchive")}
+            </label>
+            <button onClick={exportProfile} disabled={busy} className={buttonClass}>
+              {t("settings.exportProfile")}
+            </button>
+          </div>
+          <div className="flex items-center gap-2">
+            <input
+              value={importName}
+              onChange={(e) => setImportName(e.target.value)}
+              placeholder={t("settings.importName")}
+              className="setting-input flex-1"
+            />
+            <button onClick={importProfile} disabled={busy} className={buttonClass}>
+              {t("settings.importProfile")}
+            </button>
+          </div>
+        </div>
+      </FormField>
+      {settings && (
+        <FormField label={t("settings.autoBackup")} hint={t("settings.autoBackupHint")}>
+          <div className="flex items-center gap-2">
+            <select
+              value={settings.auto_backup_hours}
+              onChange={(e) => update({ auto_backup_hours: Number(e.target.value) })}
+              className="setting-input flex-1"
+            >
+              {hourOptions.map((hours) => (
+                <option key={hours} value={hours}>
+                  {hoursLabel(hours)}
+                </option>
+              ))}
+            </select>
+            <span className="text-sm text-text-s">{t("settings.backupsKept")}</span>
+            <input
+              type="number"
+              min={1}
+              value={settings.backup_retention}
+              onChange={(e) => update({ backup_retention: Math.max(1, parseInt(e.target.value) || 1) })}
+              disabled={settings.auto_backup_hours === 0}
+              className="setting-input w-20"
+            />
+          </div>
+        </FormField>
+      )}
+      {message && <p className="text-xs text-text-m whitespace-pre-line break-all">{message}</p>}
+    </div>
+  );
+}
+
+const WATCHDOG_LATENCY_MS = [0, 2000, 5000, 10000, 30000];
+
+// The shell probes the backend it supervises and restarts it when hung
+function WatchdogSection() {
+  const { t } = useTranslation();
+  const [settings, setSettings] = useState<ShellSettings | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<ShellSettings>("get_shell_settings")
+      .then(setSettings)
+      .catch((e) => setMessage(errorMessage(e)));
+  }, []);
+
+  const update = (change: Partial<ShellSettings>) => {
+    if (!settings) return;
+    invoke<ShellSettings>("set_shell_settings", { value: { ...settings, ...change } })
+      .then(setSettings)
+      .catch((e) => setMessage(errorMessage(e)));
+  };
+
+  if (!settings) {
+    return message ? <p className="text-xs text-text-m">{message}</p> : null;
+  }
+
+  const latencyOptions = WATCHDOG_LATENCY_MS.includes(settings.watchdog_latency_ms)
+    ? WATCHDOG_LATENCY_MS
+    : [...WATCHDOG_LATENCY_MS, settings.watchdog_latency_ms];
+
+  return (
+    <div className="space-y-4 py-4 border-b border-surface-el">
+      <FormField label={t("settings.watchdog")} hint={t("settings.watchdogHint")}>
+        <div className="flex items-center gap-2">
+          <select
+            value={settings.watchdog_latency_ms}
+            onChange={(e) => update({ watchdog_latency_ms: Number(e.target.value) })}
+            className="setting-input flex-1"
+          >
+            {latencyOptions.map((ms) => (
+              <option key={ms} value={ms}>
+                {ms === 0 ? t("settings.watchdogOff") : t("settings.watchdogSeconds", { seconds: ms / 1000 })}
+              </option>
+            ))}
+          </select>
+          <span className="text-sm text-text-s">{t("settings.watchdogMisses")}</span>
+          <input
+            type="number"
+            min={1}
+            value={settings.watchdog_misses}
+            onChange={(e) => update({ watchdog_misses: Math.max(1, parseInt(e.target.value) || 1) })}
+            disabled={settings.watchdog_latency_ms === 0}
+            className="setting-input w-20"
+          />
+        </div>
+      </FormField>
+      {message && <p className="text-xs text-text-m">{message}</p>}
+    </div>
+  );
+}
+
+const NOTIFICATION_CATEGORIES: { category: NotificationCategory; label: string }[] = [
+  { category: "task_completed", label: "settings.notifyTaskCompleted" },
+  { category: "error", label: "settings.notifyError" },
+  { category: "channel_message", label: "settings.notifyChannelMessage" },
+  { category: "suggestion", label: "settings.notifySuggestion" },
+];
+
+// Notifications are raised by the shell from the backend's activity feed
+function NotificationsSection() {
+  const { t } = useTranslation();
+  const [settings, setSettings] = useState<ShellSettings | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<ShellSettings>("get_shell_settings")
+      .then(setSettings)
+      .catch((e) => setMessage(errorMessage(e)));
+    // Muting from a notification changes the settings behind our back
+    const unlisten = listen<ShellSettings>("shell://settings", (event) => setSettings(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const update = (change: Partial<ShellSettings>) => {
+    if (!settings) return;
+    invoke<ShellSettings>("set_shell_settings", { value: { ...settings, ...change } })
+      .then(setSettings)
+      .catch((e) => setMessage(errorMessage(e)));
+  };
+
+  if (!settings) {
+    return message ? <p className="text-xs text-text-m">{message}</p> : null;
+  }
+
+  const toggleCategory = (category: NotificationCategory, shown: boolean) =>
+    update({
+      muted_notifications: shown
+        ? settings.muted_notifications.filter((c) => c !== category)
+        : [...settings.muted_notifications, category],
+    });
+
+  return (
+    <div className="space-y-4 py-4 border-b border-surface-el">
+      <FormField label={t("settings.notifications")} hint={t("settings.notificationsHint")}>
+        <div className="space-y-2">
+          <label className="flex items-center gap-2 text-sm text-text-s">
+            <input
+              type="checkbox"
+              checked={settings.notifications_enabled}
+              onChange={(e) => update({ notifications_enabled: e.target.checked })}
+            />
+            {t("settings.notificationsEnabled")}
+          </label>
+          <div className="grid grid-cols-2 gap-2 pl-6">
+            {NOTIFICATION_CATEGORIES.map(({ category, label }) => (
+              <label key={category} className="flex items-center gap-2 text-sm text-text-s">
+                <input
+                  type="checkbox"
+                  checked={!settings.muted_notifications.includes(category)}
+                  onChange={(e) => toggleCategory(category, e.target.checked)}
+                  disabled={!settings.notifications_enabled}
+                />
+                {t(label)}
+              </label>
+            ))}
+          </div>
+        </div>
+      </FormField>
+      <FormField label={t("settings.quietHours")} hint={t("settings.quietHoursHint")}>
+        <div className="flex items-center gap-2">
+          <input
+            type="time"
+            value={settings.quiet_hours_start}
+            onChange={(e) => update({ quiet_hours_start: e.target.value })}
+            disabled={!settings.notifications_enabled}
+            className="setting-input flex-1"
+          />
+          <span className="text-sm text-text-s">{t("settings.quietHoursTo")}</span>
+          <input
+            type="time"
+            value={settings.quiet_hours_end}
+            onChange={(e) => update({ quiet_hours_end: e.target.value })}
+            disabled={!settings.notifications_enabled}
+            className="setting-input flex-1"
+          />
+        </div>
+      </FormField>
+      {message && <p className="text-xs text-text-m">{message}</p>}
+    </div>
+  );
+}
+
+type UpdateChannel = "stable" | "beta";
+
+type UpdateStatus =
+  | { state: "idle" | "disabled" | "checking" }
+  | { state: "up_to_date"; checked_at_ms: number }
+  | { state: "available" | "ready"; version: string; notes: string | null }
+  | { state: "downloading"; version: string; received: number; total: number | null }
+  | { state: "installing"; version: string }
+  | { state: "updated"; from: string; to: string }
+  | { state: "rolled_back"; from: string; to: string; message: string }
+  | { state: "failed"; message: string };
+
+const UPDATE_CHECK_HOURS = [0, 24, 168];
+
+// Updates are checked, downloaded and installed by the shell, which stops
+// the backend and can roll the update back
+function UpdatesSection() {
+  const { t } = useTranslation();
+  const [settings, setSettings] = useState<ShellSettings | null>(null);
+  const [status, setStatus] = useState<UpdateStatus>({ state: "idle" });
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<ShellSettings>("get_shell_settings")
+      .then(setSettings)
+      .catch((e) => setMessage(errorMessage(e)));
+    invoke<UpdateStatus>("update_status")
+      .then(setStatus)
+      .catch(() => {});
+    const unlisten = listen<UpdateStatus>("updater://status", (event) => setStatus(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const update = (change: Partial<ShellSettings>) => {
+    if (!settings) return;
+    invoke<ShellSettings>("set_shell_settings", { value: { ...settings, ...change } })
+      .then(setSettings)
+      .catch((e) => setMessage(errorMessage(e)));
+  };
+
+  const run = (command: string) => {
+    setMessage(null);
+    invoke<UpdateStatus | null>(command)
+      .then((next) => next && setStatus(next))
+      .catch((e) => setMessage(errorMessage(e)));
+  };
+
+  if (!settings) {
+    return message ? <p className="text-xs text-text-m">{message}</p> : null;
+  }
+
+  const hourOptions = UPDATE_CHECK_HOURS.includes(settings.update_check_hours)
+    ? UPDATE_CHECK_HOURS
+    : [...UPDATE_CHECK_HOURS, settings.update_check_hours];
+  const hoursLabel = (hours: number) =>
+    hours === 0
+      ? t("settings.updateCheckManual")
+      : hours === 24
+        ? t("settings.updateCheckDaily")
+        : hours === 168
+          ? t("settings.updateCheckWeekly")
+          : t("settings.updateCheckEvery", { hours });
+
+  const statusText = (() => {
+    switch (status.state) {
+      case "idle":
+        return null;
+      case "disabled":
+        return t("settings.updatesDisabled");
+      case "checking":
+        return t("settings.updateChecking");
+      case "up_to_date":
+        return t("settings.updateUpToDate");
+      case "available":
+        return t("settings.updateAvailable", { version: status.version });
+      case "downloading":
+        return status.total
+          ? t("settings.updateDownloading", {
+              version: status.version,
+              percent: Math.floor((status.received / status.total) * 100),
+            })
+          : t("settings.updateDownloadingUnknown", {
+              version: status.version,
+              mb: (status.received / 1048576).toFixed(1),
+            });
+      case "ready":
+        return t("settings.updateReady", { version: status.version });
+      case "installing":
+        return t("settings.updateInstalling", { version: status.version });
+      case "updated":
+        return t("settings.updateUpdated", { from: status.from, to: status.to });
+      case "rolled_back":
+      case "failed":
+        return status.message;
+    }
+  })();
+  const disabled = status.state === "disabled";
+  const working = ["checking", "downloading", "installing"].includes(status.state);
+  const buttonClass =
+    "px-3 py-2 rounded-lg border border-surface-el text-sm text-text-s hover:bg-surface transition-colors disabled:opacity-40";
+
+  return (
+    <div className="space-y-4 py-4 border-b border-surface-el">
+      <FormField label={t("settings.updates")} hint={t("settings.updatesHint")}>
+        <div className="space-y-2">
+          <div className="flex items-center gap-2">
+            <select
+              value={settings.update_channel}
+              onChange={(e) => update({ update_channel: e.target.value as UpdateChannel })}
+              disabled={disabled}
+              className="setting-input flex-1"
+            >
+              <option value="stable">{t("settings.updateChannelStable")}</option>
+              <option value="beta">{t("settings.updateChannelBeta")}</option>
+            </select>
+            <select
+              value={settings.update_check_hours}
+              onChange={(e) => update({ update_check_hours: Number(e.target.value) })}
+              disabled={disabled}
+              className="setting-input flex-1"
+            >
+              {hourOptions.map((hours) => (
+                <option key={hours} value={hours}>
+                  {hoursLabel(hours)}
+                </option>
+              ))}
+            </select>
+          </div>
+          <label className="flex items-center gap-2 text-sm text-text-s">
+            <input
+              type="checkbox"
+              checked={settings.auto_download_updates}
+              onChange={(e) => update({ auto_download_updates: e.target.checked })}
+              disabled={disabled}
+            />
+            {t("settings.autoDownloadUpdates")}
+          </label>
+          <div className="flex items-center gap-2">
+            <span className="flex-1 text-sm text-text-m">{statusText}</span>
+            {status.state === "available" && (
+              <button onClick={() => run("download_update")} className={buttonClass}>
+                {t("settings.downloadUpdate")}
+              </button>
+            )}
+            {status.state === "ready" && (
+              <button onClick={() => run("install_update")} className={buttonClass}>
+                {t("settings.installUpdate")}
+              </button>
+            )}
+            <button onClick={() => run("check_for_updates")} disabled={disabled || working} className={buttonClass}>
+              {t("settings.checkForUpdates")}
+            </button>
+          </div>
+        </div>
+      </FormField>
+      {message && <p className="text-xs text-text-m">{message}</p>}
+    </div>
+  );
+}
+
 // Identity Section Component
 function IdentitySection() {
   const { t } = useTranslation();
@@ -1,5 +1,7 @@
 import { useEffect, useState, useCallback, useMemo } from "react";
 import { useTranslation } from "react-i18next";
+import { isTauri } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import {
   Globe,
   FolderOpen,
@@ -99,6 +101,15 @@ export default function SkillsView() {
     fetchSkills();
   }, [fetchSkills]);
 
+  // Skills installed from a deskflow:// link
+  useEffect(() => {
+    if (!isTauri()) return;
+    const unlisten = listen("skills://changed", () => fetchSkills());
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [fetchSkills]);
+
   const handleToggle = async (skillName: string, currentStatus: boolean) => {
     setToggling(skillName);
     try {
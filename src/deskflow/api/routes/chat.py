@@ -6,7 +6,6 @@ import contextlib
 import json
 import sqlite3
 from datetime import datetime
-from pathlib import Path
 from typing import Any
 from uuid import uuid4
 
@@ -14,17 +13,13 @@ from fastapi import APIRouter, WebSocket, WebSocketDisconnect, HTTPException
 
 from deskflow.api.schemas.models import ChatRequest, ChatResponse, ToolCallInfo
 from deskflow.config import AppConfig
+from deskflow.memory import conversations
 from deskflow.observability.logging import get_logger
 
 logger = get_logger(__name__)
 
 router = APIRouter(prefix="/api", tags=["chat"])
 
-# Database path for conversation history
-DB_PATH = Path.cwd() / "data" / "conversations.db"
-DB_PATH.parent.mkdir(parents=True, exist_ok=True)
-
-
 def _get_agent() -> Any:
     """Get the agent instance from app state.
 
@@ -44,7 +39,7 @@ def _get_config() -> AppConfig:
 
 def init_conversation_db() -> None:
     """Initialize conversation database."""
-    conn = sqlite3.connect(DB_PATH)
+    conn = conversations.connect()
     cursor = conn.cursor()
     cursor.execute("""
         CREATE TABLE IF NOT EXISTS conversations (
@@ -71,9 +66,7 @@ def init_conversation_db() -> None:
 
 def _get_db_connection() -> sqlite3.Connection:
     """Get database connection."""
-    conn = sqlite3.connect(DB_PATH)
-    conn.row_factory = sqlite3.Row
-    return conn
+    return conversations.connect()
 
 
 @router.post("/chat", response_model=ChatResponse)
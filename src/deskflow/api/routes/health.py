@@ -64,6 +64,21 @@ async def _check_memory_health(state: Any) -> ComponentHealth:
         return ComponentHealth(status="error", details={"error": str(e)})
 
 
+def _check_encryption_health() -> ComponentHealth:
+    """Report whether encrypted databases can be opened.
+
+    The desktop shell only encrypts a profile when this says ``ok``.
+    "unavailable" is not a fault, so it does not count against the summary.
+    """
+    from deskflow.memory.storage import sqlcipher_available
+
+    available = sqlcipher_available()
+    return ComponentHealth(
+        status="ok" if available else "unavailable",
+        details={"sqlcipher": available},
+    )
+
+
 async def _check_tools_health(state: Any) -> ComponentHealth:
     """Check tools system health."""
     try:
@@ -315,6 +330,7 @@ async def detailed_health_check() -> dict[str, Any]:
         "memory": memory_health.model_dump(),
         "tools": tools_health.model_dump(),
         "llm": llm_health.model_dump(),
+        "encryption": _check_encryption_health().model_dump(),
     }
 
     # System health
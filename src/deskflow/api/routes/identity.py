@@ -26,7 +26,7 @@ def _get_identity_dir() -> Path:
     state = _get_state()
     from deskflow.config import AppConfig
     config: AppConfig = state.config
-    return config.get_project_root() / "identity"
+    return config.get_identity_dir()
 
 
 # Pre-built personas
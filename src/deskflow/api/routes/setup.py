@@ -3,6 +3,7 @@
 from __future__ import annotations
 
 import json
+import os
 from pathlib import Path
 from typing import Any
 
@@ -16,7 +17,8 @@ logger = get_logger(__name__)
 router = APIRouter(prefix="/api/setup", tags=["setup"])
 
 # Config file path
-CONFIG_DIR = Path.home() / ".deskflow"
+# The desktop shell points this at the active profile's config dir
+CONFIG_DIR = Path(os.environ.get("DESKFLOW_CONFIG_DIR") or Path.home() / ".deskflow")
 CONFIG_FILE = CONFIG_DIR / "config.json"
 
 
@@ -43,6 +45,18 @@ def _save_config(config: dict[str, Any]) -> None:
         json.dump(config, f, indent=2, ensure_ascii=False)
 
 
+def _has_api_key(llm_config: dict[str, Any]) -> bool:
+    """Check for an API key in the config file or in the environment.
+
+    The desktop shell keeps keys in the OS keyring and passes them to this
+    process as ``DESKFLOW_<PROVIDER>_API_KEY`` instead of writing them here.
+    """
+    if llm_config.get("api_key"):
+        return True
+    provider = llm_config.get("provider", "dashscope")
+    return bool(os.environ.get(f"DESKFLOW_{provider.upper()}_API_KEY"))
+
+
 class LLMConfig(BaseModel):
     """LLM configuration."""
 
@@ -175,7 +189,7 @@ async def start_service() -> SetupResponse:
     try:
         # Verify configuration exists
         config = _load_config()
-        if not config.get("llm") or not config["llm"].get("api_key"):
+        if not config.get("llm") or not _has_api_key(config["llm"]):
             raise HTTPException(status_code=400, detail="LLM configuration is required")
 
         # In a real implementation, this would:
@@ -71,7 +71,7 @@ def _get_skills_dir() -> Path:
     state = _get_state()
     from deskflow.config import AppConfig
     config: AppConfig = state.config
-    return config.get_project_root() / "skills"
+    return config.get_skills_dir()
 
 
 def _get_icon_and_color(skill_name: str) -> tuple[str, str]:
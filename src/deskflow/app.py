@@ -93,7 +93,7 @@ async def _initialize_components(app_config: AppConfig) -> AppState:
         logger.warning("llm_init_skipped", reason=str(e))
 
     # 4. Identity
-    identity_dir = app_config.get_project_root() / "identity"
+    identity_dir = app_config.get_identity_dir()
     identity = DefaultIdentity(identity_dir=identity_dir)
 
     # 5. Skills - Load skills from skills/ directory
@@ -17,6 +17,7 @@ from datetime import datetime
 from enum import Enum
 from typing import Any, Protocol, runtime_checkable
 
+from deskflow.observability.agent_events import AgentEvent, publish_agent_event
 from deskflow.observability.logging import get_logger
 
 logger = get_logger(__name__)
@@ -293,12 +294,14 @@ class MessageQueue:
         """Process a single message through registered handlers."""
         logger.debug("message_processing", message_id=message.message_id)
 
+        handled = False
         for handler in self._handlers:
             try:
                 if asyncio.iscoroutinefunction(handler):
                     await handler(message)
                 else:
                     handler(message)
+                handled = True
             except Exception as e:
                 logger.warning(
                     "message_handler_failed",
@@ -307,6 +310,16 @@ class MessageQueue:
                     error=str(e),
                 )
 
+        # Nobody answered, so the user has to
+        if not handled and message.message_type != MessageType.SYSTEM:
+            publish_agent_event(
+                AgentEvent.CHANNEL_MESSAGE,
+                message.content or f"[{message.message_type.value}]",
+                channel_id=message.channel_id,
+                sender_id=message.sender_id,
+                message_id=message.message_id,
+            )
+
         logger.debug("message_processed", message_id=message.message_id)
 
     @property
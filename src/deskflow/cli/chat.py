@@ -66,7 +66,7 @@ async def _async_chat(conversation_id: str | None) -> None:
 
     llm_client = LLMClient(primary=primary)
 
-    identity = DefaultIdentity(identity_dir=config.get_project_root() / "identity")
+    identity = DefaultIdentity(identity_dir=config.get_identity_dir())
     monitor = TaskMonitor()
 
     agent = Agent(
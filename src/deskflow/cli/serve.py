@@ -9,14 +9,18 @@ console = Console()
 
 
 def serve_command(
-    host: str = typer.Option("127.0.0.1", "--host", "-h", help="Bind host"),
-    port: int = typer.Option(8420, "--port", "-p", help="Bind port"),
+    host: str = typer.Option("127.0.0.1", "--host", "-h", envvar="DESKFLOW_HOST", help="Bind host"),
+    port: int = typer.Option(8420, "--port", "-p", envvar="DESKFLOW_PORT", help="Bind port"),
     reload: bool = typer.Option(False, "--reload", help="Enable auto-reload (dev)"),
     log_level: str = typer.Option("info", "--log-level", "-l", help="Log level"),
 ) -> None:
     """Start the DeskFlow API server."""
     import uvicorn
 
+    from deskflow.observability.stack_dump import install_stack_dump_handler
+
+    install_stack_dump_handler()
+
     console.print(
         f"[bold green]Starting DeskFlow API server[/bold green]\n"
         f"  Host: [cyan]{host}[/cyan]\n"
@@ -3,6 +3,7 @@
 from __future__ import annotations
 
 import os
+import shutil
 from enum import StrEnum
 from pathlib import Path
 
@@ -114,16 +115,53 @@ class AppConfig(BaseSettings):
     memory: MemoryConfig = Field(default_factory=MemoryConfig)
     tools: ToolConfig = Field(default_factory=ToolConfig)
 
+    # Per-profile directories, set by the desktop shell. Unset in a source
+    # checkout, where everything stays under the project root.
+    data_dir: str | None = None
+    config_dir: str | None = None
+    cache_dir: str | None = None
+
     def get_project_root(self) -> Path:
         """Return the project root directory."""
         return Path(__file__).parent.parent.parent
 
     def get_data_dir(self) -> Path:
         """Return the data directory, creating it if needed."""
-        data_dir = self.get_project_root() / "data"
+        data_dir = Path(self.data_dir) if self.data_dir else self.get_project_root() / "data"
         data_dir.mkdir(parents=True, exist_ok=True)
         return data_dir
 
+    def get_config_dir(self) -> Path:
+        """Return the directory for user-editable configuration."""
+        config_dir = Path(self.config_dir) if self.config_dir else Path.home() / ".deskflow"
+        config_dir.mkdir(parents=True, exist_ok=True)
+        return config_dir
+
+    def get_cache_dir(self) -> Path:
+        """Return the directory for disposable cached data."""
+        cache_dir = Path(self.cache_dir) if self.cache_dir else self.get_data_dir() / "cache"
+        cache_dir.mkdir(parents=True, exist_ok=True)
+        return cache_dir
+
+    def get_identity_dir(self) -> Path:
+        """Return the directory holding the agent's identity files.
+
+        A new profile starts from a copy of the bundled identity templates.
+        """
+        bundled = self.get_project_root() / "identity"
+        if not self.data_dir:
+            return bundled
+        identity_dir = Path(self.data_dir) / "identity"
+        if not identity_dir.exists() and bundled.is_dir():
+            shutil.copytree(bundled, identity_dir)
+        return identity_dir
+
+    def get_skills_dir(self) -> Path:
+        """Return the directory user-installed skills are kept in."""
+        if self.data_dir:
+            return Path(self.data_dir) / "skills"
+        return self.get_project_root() / "skills"
+
     def get_db_path(self) -> Path:
         """Return the database file path, creating parent dir if needed."""
         db_path = self.get_project_root() / self.memory.db_path
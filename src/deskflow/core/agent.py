@@ -18,6 +18,8 @@ from deskflow.core.models import (
 from deskflow.core.prompt_assembler import PromptAssembler
 from deskflow.core.ralph import RalphLoop
 from deskflow.core.task_monitor import TaskMonitor
+from deskflow.memory import conversations
+from deskflow.observability.agent_events import AgentEvent, publish_agent_event
 from deskflow.observability.logging import get_logger
 
 if TYPE_CHECKING:
@@ -81,19 +83,15 @@ class Agent:
         if not conversation_id:
             return []
         try:
-            import sqlite3
-            from pathlib import Path
-            db_path = Path.cwd() / "data" / "conversations.db"
-            if not db_path.exists():
+            if not conversations.conversations_db_path().exists():
                 return []
-            conn = sqlite3.connect(db_path)
-            conn.row_factory = sqlite3.Row
+            conn = conversations.connect()
             cursor = conn.cursor()
             cursor.execute("SELECT id, role, content, created_at FROM messages WHERE conversation_id = ? ORDER BY created_at ASC", (conversation_id,))
             rows = cursor.fetchall()
             messages = []
             for row in rows:
-                msg = Message(id=row["id"], role=Role(row["role"]), content=row["content"], created_at=float(row["created_at"]))
+                msg = Message(id=row["id"], role=Role(row["role"]), content=row["content"], timestamp=conversations.from_db_time(row["created_at"]))
                 messages.append(msg)
             conn.close()
             return messages
@@ -103,21 +101,17 @@ class Agent:
 
     async def _save_conversation(self, conversation_id: str, conversation: Conversation) -> None:
         try:
-            import sqlite3
-            from pathlib import Path
-            db_path = Path.cwd() / "data" / "conversations.db"
-            db_path.parent.mkdir(parents=True, exist_ok=True)
-            conn = sqlite3.connect(db_path)
+            conn = conversations.connect()
             cursor = conn.cursor()
-            now = time.time()
+            now = conversations.to_db_time(time.time())
             cursor.execute("SELECT id FROM conversations WHERE id = ?", (conversation_id,))
             if cursor.fetchone():
                 cursor.execute("UPDATE conversations SET updated_at = ?, message_count = ? WHERE id = ?", (now, len(conversation.messages), conversation_id))
                 cursor.execute("DELETE FROM messages WHERE conversation_id = ?", (conversation_id,))
             else:
-                cursor.execute("INSERT INTO conversations (id, title, created_at, updated_at, message_count) VALUES (?, ?, ?, ?, ?)", (conversation_id, conversation.title or "Untitled", conversation.created_at, now, len(conversation.messages)))
+                cursor.execute("INSERT INTO conversations (id, title, created_at, updated_at, message_count) VALUES (?, ?, ?, ?, ?)", (conversation_id, conversation.title or "Untitled", conversations.to_db_time(conversation.created_at), now, len(conversation.messages)))
             for msg in conversation.messages:
-                cursor.execute("INSERT OR REPLACE INTO messages (id, conversation_id, role, content, created_at) VALUES (?, ?, ?, ?, ?)", (msg.id, conversation_id, msg.role.value, msg.content, msg.timestamp))
+                cursor.execute("INSERT OR REPLACE INTO messages (id, conversation_id, role, content, created_at) VALUES (?, ?, ?, ?, ?)", (msg.id, conversation_id, msg.role.value, msg.content, conversations.to_db_time(msg.timestamp)))
             conn.commit()
             conn.close()
         except Exception as e:
@@ -181,6 +175,7 @@ class Agent:
     async def chat(self, user_message: str, conversation_id: str | None = None) -> Message:
         self._cancel_requested = False
         self._monitor.set_busy("chatting")
+        started = time.time()
         try:
             await self._load_conversation_history(conversation_id)
             conversation = self._get_or_create_conversation(conversation_id)
@@ -214,7 +209,11 @@ class Agent:
             if conversation_id:
                 await self._save_conversation(conversation_id, conversation)
             await self._store_interaction_memory(user_message, full_response_text, conversation.id)
+            publish_agent_event(AgentEvent.TASK_COMPLETED, full_response_text or user_message, conversation_id=conversation.id, duration_ms=(time.time() - started) * 1000)
             return response_msg
+        except Exception as e:
+            publish_agent_event(AgentEvent.TASK_FAILED, str(e), conversation_id=conversation_id, duration_ms=(time.time() - started) * 1000)
+            raise
         finally:
             self._monitor.set_idle()
 
@@ -222,6 +221,7 @@ class Agent:
         """Stream chat response with tool execution support."""
         self._cancel_requested = False
         self._monitor.set_busy("streaming chat")
+        started = time.time()
         try:
             await self._load_conversation_history(conversation_id)
             conversation = self._get_or_create_conversation(conversation_id)
@@ -242,6 +242,7 @@ class Agent:
             full_response_text = ""
             all_tool_calls: list[ToolCall] = []
             tool_round = 0
+            error: str | None = None
 
             # Track messages for conversation storage
             conversation_messages: list[Message] = []
@@ -296,6 +297,7 @@ class Agent:
 
                 except Exception as e:
                     logger.error("stream_chat_error", error=str(e))
+                    error = str(e)
                     yield StreamChunk(type="error", content=str(e))
                     break
 
@@ -313,6 +315,12 @@ class Agent:
                     await self._save_conversation(conversation_id, conversation)
                 await self._store_interaction_memory(user_message, full_response_text, conversation.id)
 
+            duration_ms = (time.time() - started) * 1000
+            if error is not None:
+                publish_agent_event(AgentEvent.TASK_FAILED, error, conversation_id=conversation.id, duration_ms=duration_ms)
+            else:
+                publish_agent_event(AgentEvent.TASK_COMPLETED, full_response_text or user_message, conversation_id=conversation.id, duration_ms=duration_ms)
+
             yield StreamChunk(type="done")
 
         finally:
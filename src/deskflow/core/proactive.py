@@ -15,6 +15,7 @@ from dataclasses import dataclass
 from enum import Enum
 from typing import Any
 
+from deskflow.observability.agent_events import AgentEvent, publish_agent_event
 from deskflow.observability.logging import get_logger
 
 logger = get_logger(__name__)
@@ -371,6 +372,11 @@ class GreetingManager:
             type=greeting.greeting_type.value,
             count=self._greeting_count,
         )
+        publish_agent_event(
+            AgentEvent.PROACTIVE_SUGGESTION,
+            greeting.message,
+            greeting_type=greeting.greeting_type.value,
+        )
 
         return greeting.message
 
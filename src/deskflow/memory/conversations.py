@@ -0,0 +1,60 @@
+"""The chat history database, ``conversations.db`` in the data directory.
+
+The chat routes and the agent both write it, and the desktop shell reads
+it for the offline history, search and export, so everyone must resolve it
+the same way: through ``AppConfig.get_data_dir()``, i.e. the profile's
+``DESKFLOW_DATA_DIR`` under the shell.
+
+When the shell has encrypted the profile it passes the key in
+``DESKFLOW_DB_KEY``, and this database is opened through SQLCipher just
+like ``deskflow.db``.
+"""
+
+from __future__ import annotations
+
+import os
+import sqlite3
+from datetime import datetime
+from pathlib import Path
+
+from deskflow.config import AppConfig
+from deskflow.memory.storage import DB_KEY_ENV, key_pragma, sqlcipher_module
+
+CONVERSATIONS_DB = "conversations.db"
+
+
+def conversations_db_path(config: AppConfig | None = None) -> Path:
+    """Return the path of ``conversations.db``, creating its directory."""
+    return (config or AppConfig()).get_data_dir() / CONVERSATIONS_DB
+
+
+def connect(config: AppConfig | None = None) -> sqlite3.Connection:
+    """Open ``conversations.db`` with rows addressable by column name."""
+    key = os.environ.get(DB_KEY_ENV)
+    if not key:
+        conn = sqlite3.connect(conversations_db_path(config))
+        conn.row_factory = sqlite3.Row
+        return conn
+
+    pragma = key_pragma(key)
+    sqlcipher = sqlcipher_module()
+    conn = sqlcipher.connect(str(conversations_db_path(config)))
+    conn.row_factory = sqlcipher.Row
+    conn.execute(pragma)
+    return conn  # type: ignore[no-any-return]
+
+
+def to_db_time(timestamp: float) -> str:
+    """Format a Unix timestamp the way ``created_at`` is stored: local ISO 8601.
+
+    The shell sorts and filters on the text, so every writer must use it.
+    """
+    return datetime.fromtimestamp(timestamp).isoformat()
+
+
+def from_db_time(value: str | float) -> float:
+    """Parse a stored ``created_at``, including older rows that hold a float."""
+    try:
+        return float(value)
+    except ValueError:
+        return datetime.fromisoformat(str(value)).timestamp()
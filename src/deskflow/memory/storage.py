@@ -1,12 +1,17 @@
 """SQLite storage backend for DeskFlow memory system.
 
 Uses aiosqlite for async operations and FTS5 for full-text search.
+When the desktop shell has encrypted the database, it passes the key in
+``DESKFLOW_DB_KEY`` and the database is opened through SQLCipher.
 """
 
 from __future__ import annotations
 
 import json
+import os
+import re
 import time
+from functools import partial
 from pathlib import Path
 from typing import Any
 
@@ -18,8 +23,43 @@ from deskflow.observability.logging import get_logger
 
 logger = get_logger(__name__)
 
+# The desktop shell refuses to restore backups newer than its own copy of
+# this (SCHEMA_VERSION in apps/desktop/src-tauri/src/backup.rs)
 SCHEMA_VERSION = 1
 
+# Raw 256-bit SQLCipher key, hex encoded, as generated by the desktop shell
+DB_KEY_ENV = "DESKFLOW_DB_KEY"
+_RAW_KEY_RE = re.compile(r"^[0-9a-fA-F]{64}$")
+
+
+def sqlcipher_available() -> bool:
+    """Whether encrypted databases can be opened, i.e. sqlcipher3 is installed."""
+    try:
+        sqlcipher_module()
+    except MemoryStorageError:
+        return False
+    return True
+
+
+def sqlcipher_module() -> Any:
+    """Return sqlcipher3's DB-API module, a drop-in for ``sqlite3``."""
+    try:
+        from sqlcipher3 import dbapi2 as sqlcipher  # type: ignore[import-not-found]
+    except ImportError as e:
+        raise MemoryStorageError(
+            "Database is encrypted but sqlcipher3 is not installed "
+            "(pip install 'coolaw-deskflow[encryption]')"
+        ) from e
+    return sqlcipher
+
+
+def key_pragma(key: str) -> str:
+    """Return the ``PRAGMA key`` statement for a raw key from the shell."""
+    if not _RAW_KEY_RE.match(key):
+        raise MemoryStorageError("Database key must be 64 hex characters")
+    return f"PRAGMA key = \"x'{key}'\""
+
+
 CREATE_TABLES_SQL = """
 CREATE TABLE IF NOT EXISTS schema_version (
     version INTEGER PRIMARY KEY
@@ -95,16 +135,16 @@ CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages(conversation_id
 class MemoryStorage:
     """Async SQLite storage for memories with FTS5 full-text search."""
 
-    def __init__(self, db_path: str | Path) -> None:
+    def __init__(self, db_path: str | Path, encryption_key: str | None = None) -> None:
         self._db_path = str(db_path)
+        self._key = encryption_key or os.environ.get(DB_KEY_ENV) or None
         self._db: aiosqlite.Connection | None = None
 
     async def initialize(self) -> None:
         """Open the database connection and create tables."""
         try:
             Path(self._db_path).parent.mkdir(parents=True, exist_ok=True)
-            self._db = await aiosqlite.connect(self._db_path)
-            self._db.row_factory = aiosqlite.Row
+            self._db = await self._connect()
             await self._db.executescript(CREATE_TABLES_SQL)
             await self._db.execute(
                 "INSERT OR IGNORE INTO schema_version (version) VALUES (?)",
@@ -115,6 +155,22 @@ class MemoryStorage:
         except Exception as e:
             raise MemoryStorageError(f"Failed to initialize database: {e}") from e
 
+    async def _connect(self) -> aiosqlite.Connection:
+        """Open the database, through SQLCipher if a key is set."""
+        if not self._key:
+            db = await aiosqlite.connect(self._db_path)
+            db.row_factory = aiosqlite.Row
+            return db
+
+        pragma = key_pragma(self._key)
+        sqlcipher = sqlcipher_module()
+        db = await aiosqlite.Connection(partial(sqlcipher.connect, self._db_path), 64)
+        db.row_factory = sqlcipher.Row
+        await db.execute(pragma)
+        # A wrong key only shows on the first read
+        await db.execute("SELECT count(*) FROM sqlite_master")
+        return db
+
     async def close(self) -> None:
         """Close the database connection."""
         if self._db:
@@ -0,0 +1,69 @@
+"""Agent events the user may want to hear about while looking elsewhere.
+
+Events are logged as system activities with ``details["event"]`` set, so
+they reach clients of ``/api/monitor/ws/activity`` like any other activity.
+The desktop shell turns them into native notifications.
+"""
+
+from __future__ import annotations
+
+from enum import Enum
+from typing import Any
+
+from deskflow.observability.activity_logger import (
+    ActivityStatus,
+    ActivityType,
+    get_activity_logger,
+)
+from deskflow.observability.logging import get_logger
+
+logger = get_logger(__name__)
+
+# Summaries end up in notification bodies
+MAX_SUMMARY_CHARS = 200
+
+
+class AgentEvent(str, Enum):
+    """Kind of agent event."""
+
+    TASK_COMPLETED = "task_completed"
+    TASK_FAILED = "task_failed"
+    CHANNEL_MESSAGE = "channel_message"
+    PROACTIVE_SUGGESTION = "proactive_suggestion"
+
+
+def publish_agent_event(
+    event: AgentEvent,
+    summary: str,
+    *,
+    conversation_id: str | None = None,
+    duration_ms: float = 0.0,
+    **details: Any,
+) -> None:
+    """Log an agent event on the activity feed.
+
+    Never raises: a failure to publish must not fail the task it reports on.
+
+    Args:
+        event: What happened.
+        summary: One line for the user, cut to ``MAX_SUMMARY_CHARS``.
+        conversation_id: Conversation the event belongs to, if any.
+        duration_ms: How long the task took.
+        **details: Extra JSON-serialisable fields, e.g. ``channel_id``.
+    """
+    summary = " ".join(summary.split())
+    if len(summary) > MAX_SUMMARY_CHARS:
+        summary = summary[: MAX_SUMMARY_CHARS - 1] + "…"
+    status = ActivityStatus.FAILED if event == AgentEvent.TASK_FAILED else ActivityStatus.SUCCESS
+
+    try:
+        get_activity_logger().log(
+            activity_type=ActivityType.SYSTEM_EVENT,
+            status=status,
+            summary=summary,
+            duration_ms=duration_ms,
+            details={"event": event.value, **details},
+            conversation_id=conversation_id,
+        )
+    except Exception as e:
+        logger.warning("agent_event_publish_failed", event=event.value, error=str(e))
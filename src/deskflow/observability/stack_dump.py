@@ -0,0 +1,48 @@
+"""Thread stack dumps on request, for diagnosing a wedged backend.
+
+The desktop shell sets ``DESKFLOW_STACK_DUMP_FILE`` when it starts the
+backend. With it set, ``SIGUSR1`` makes the interpreter append the stacks of
+all threads to that file. The dump is written by :mod:`faulthandler` from the
+signal handler itself, so it works even while the event loop is blocked
+inside a tool call.
+"""
+
+from __future__ import annotations
+
+import faulthandler
+import os
+import signal
+from pathlib import Path
+from typing import IO
+
+STACK_DUMP_FILE_ENV = "DESKFLOW_STACK_DUMP_FILE"
+
+# faulthandler writes to the file descriptor, so the file must stay open
+_dump_file: IO[str] | None = None
+
+
+def install_stack_dump_handler(path: str | os.PathLike[str] | None = None) -> Path | None:
+    """Dump all thread stacks to ``path`` on ``SIGUSR1``.
+
+    Args:
+        path: File to append dumps to. Defaults to ``DESKFLOW_STACK_DUMP_FILE``.
+
+    Returns:
+        The dump file, or None if no file was configured or the platform has
+        no ``SIGUSR1`` (Windows).
+    """
+    global _dump_file
+
+    target = path if path is not None else os.environ.get(STACK_DUMP_FILE_ENV)
+    if not target or not hasattr(signal, "SIGUSR1"):
+        return None
+
+    dump_path = Path(target)
+    dump_path.parent.mkdir(parents=True, exist_ok=True)
+    dump_file = dump_path.open("a", encoding="utf-8")
+    faulthandler.register(signal.SIGUSR1, file=dump_file, all_threads=True)
+
+    if _dump_file is not None:
+        _dump_file.close()
+    _dump_file = dump_file
+    return dump_path
@@ -108,6 +108,25 @@ class TestAppConfig:
         data_dir = config.get_data_dir()
         assert data_dir.exists()
 
+    def test_profile_dirs_from_env(self, tmp_path: Path, monkeypatch: pytest.MonkeyPatch) -> None:
+        monkeypatch.setenv("DESKFLOW_DATA_DIR", str(tmp_path / "data"))
+        monkeypatch.setenv("DESKFLOW_CONFIG_DIR", str(tmp_path / "config"))
+        monkeypatch.setenv("DESKFLOW_DB_PATH", str(tmp_path / "data" / "db" / "deskflow.db"))
+        config = AppConfig()
+        assert config.get_data_dir() == tmp_path / "data"
+        assert config.get_config_dir() == tmp_path / "config"
+        assert config.get_db_path() == tmp_path / "data" / "db" / "deskflow.db"
+        assert config.get_skills_dir() == tmp_path / "data" / "skills"
+
+    def test_identity_dir_seeded_from_bundle(self, tmp_path: Path, monkeypatch: pytest.MonkeyPatch) -> None:
+        monkeypatch.setenv("DESKFLOW_DATA_DIR", str(tmp_path))
+        config = AppConfig()
+        identity_dir = config.get_identity_dir()
+        assert identity_dir == tmp_path / "identity"
+        bundled = config.get_project_root() / "identity"
+        if bundled.is_dir():
+            assert (identity_dir / "SOUL.md").exists()
+
     def test_load_config(self) -> None:
         config = load_config()
         assert isinstance(config, AppConfig)
@@ -0,0 +1,115 @@
+"""Tests for the shared conversations.db location, encryption and timestamp format."""
+
+from __future__ import annotations
+
+import importlib.util
+import sqlite3
+from pathlib import Path
+from unittest.mock import AsyncMock
+
+import pytest
+
+from deskflow.api.routes import chat as chat_routes
+from deskflow.core.agent import Agent
+from deskflow.core.identity import DefaultIdentity
+from deskflow.core.models import Conversation, Message, Role
+from deskflow.errors import MemoryStorageError
+from deskflow.memory import conversations
+
+HAS_SQLCIPHER = importlib.util.find_spec("sqlcipher3") is not None
+
+
+@pytest.fixture
+def data_dir(tmp_path: Path, monkeypatch: pytest.MonkeyPatch) -> Path:
+    """A profile data dir that differs from the working directory."""
+    data_dir = tmp_path / "profile"
+    monkeypatch.setenv("DESKFLOW_DATA_DIR", str(data_dir))
+    cwd = tmp_path / "cwd"
+    cwd.mkdir()
+    monkeypatch.chdir(cwd)
+    chat_routes.init_conversation_db()
+    return data_dir
+
+
+def _agent() -> Agent:
+    return Agent(brain=AsyncMock(), memory=AsyncMock(), tools=AsyncMock(), identity=DefaultIdentity())
+
+
+class TestConversationsDb:
+    """The chat routes and the agent share one conversations.db."""
+
+    def test_path_follows_data_dir(self, data_dir: Path) -> None:
+        assert conversations.conversations_db_path() == data_dir / "conversations.db"
+
+    async def test_both_writers_hit_the_same_file(self, data_dir: Path) -> None:
+        await chat_routes.save_conversation({
+            "conversation_id": "from-ui",
+            "title": "Saved by the UI",
+            "messages": [{"role": "user", "content": "hi from the UI"}],
+        })
+        conversation = Conversation(title="Streamed")
+        conversation.add_message(Message(role=Role.USER, content="hi from the agent"))
+        await _agent()._save_conversation("from-agent", conversation)
+
+        db = data_dir / "conversations.db"
+        assert not (Path.cwd() / "data" / "conversations.db").exists()
+        with sqlite3.connect(db) as conn:
+            ids = {row[0] for row in conn.execute("SELECT id FROM conversations")}
+            times = [row[0] for row in conn.execute("SELECT created_at FROM messages")]
+        assert ids == {"from-ui", "from-agent"}
+        # The shell reads created_at as ISO text
+        assert all(isinstance(t, str) and "T" in t for t in times)
+
+        history = await chat_routes.get_conversation_history()
+        assert {c["id"] for c in history["conversations"]} == {"from-ui", "from-agent"}
+
+    async def test_agent_loads_conversations_saved_by_the_ui(self, data_dir: Path) -> None:
+        await chat_routes.save_conversation({
+            "conversation_id": "from-ui",
+            "messages": [
+                {"role": "user", "content": "question"},
+                {"role": "assistant", "content": "answer"},
+            ],
+        })
+
+        messages = await _agent()._load_conversation_history("from-ui")
+
+        assert [(m.role, m.content) for m in messages] == [
+            (Role.USER, "question"),
+            (Role.ASSISTANT, "answer"),
+        ]
+
+
+class TestEncryptedConversationsDb:
+    """With the shell's key set, conversations.db is encrypted too."""
+
+    @pytest.mark.skipif(not HAS_SQLCIPHER, reason="sqlcipher3 not installed")
+    def test_written_encrypted(self, tmp_path: Path, monkeypatch: pytest.MonkeyPatch) -> None:
+        monkeypatch.setenv("DESKFLOW_DATA_DIR", str(tmp_path))
+        monkeypatch.setenv("DESKFLOW_DB_KEY", "ab" * 32)
+        conn = conversations.connect()
+        conn.execute("CREATE TABLE t (x TEXT)")
+        conn.execute("INSERT INTO t VALUES ('secret')")
+        conn.commit()
+        conn.close()
+
+        raw = (tmp_path / "conversations.db").read_bytes()
+        assert not raw.startswith(b"SQLite format 3")
+        assert b"secret" not in raw
+
+    @pytest.mark.skipif(HAS_SQLCIPHER, reason="sqlcipher3 installed")
+    def test_key_without_sqlcipher_raises(
+        self, tmp_path: Path, monkeypatch: pytest.MonkeyPatch
+    ) -> None:
+        monkeypatch.setenv("DESKFLOW_DATA_DIR", str(tmp_path))
+        monkeypatch.setenv("DESKFLOW_DB_KEY", "ab" * 32)
+        with pytest.raises(MemoryStorageError, match="sqlcipher3 is not installed"):
+            conversations.connect()
+
+
+class TestDbTime:
+    def test_round_trip(self) -> None:
+        assert conversations.from_db_time(conversations.to_db_time(1700000000.5)) == 1700000000.5
+
+    def test_reads_legacy_float(self) -> None:
+        assert conversations.from_db_time("1700000000.5") == 1700000000.5
@@ -2,6 +2,7 @@
 
 from __future__ import annotations
 
+import importlib.util
 from pathlib import Path
 
 import pytest
@@ -116,6 +117,27 @@ class TestMemoryStorage:
         with pytest.raises(MemoryStorageError, match="not initialized"):
             storage._ensure_connected()
 
+    async def test_key_from_environment(
+        self, temp_db_path: Path, monkeypatch: pytest.MonkeyPatch
+    ) -> None:
+        monkeypatch.setenv("DESKFLOW_DB_KEY", "ab" * 32)
+        storage = MemoryStorage(temp_db_path)
+        assert storage._key == "ab" * 32
+
+    async def test_malformed_key_raises(self, temp_db_path: Path) -> None:
+        storage = MemoryStorage(temp_db_path, encryption_key="not-a-key")
+        with pytest.raises(MemoryStorageError, match="64 hex"):
+            await storage.initialize()
+        assert not temp_db_path.exists()
+
+    @pytest.mark.skipif(
+        importlib.util.find_spec("sqlcipher3") is not None, reason="sqlcipher3 installed"
+    )
+    async def test_key_without_sqlcipher_raises(self, temp_db_path: Path) -> None:
+        storage = MemoryStorage(temp_db_path, encryption_key="ab" * 32)
+        with pytest.raises(MemoryStorageError, match="sqlcipher3 is not installed"):
+            await storage.initialize()
+
     async def test_store_with_metadata(self, memory_storage: MemoryStorage) -> None:
         entry = MemoryEntry(
             content="test",
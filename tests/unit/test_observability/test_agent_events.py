@@ -0,0 +1,112 @@
+"""Tests for agent events published on the activity feed."""
+
+from __future__ import annotations
+
+from pathlib import Path
+from unittest.mock import MagicMock, patch
+
+import pytest
+
+from deskflow.channels.gateway import BaseMessage, MessageQueue
+from deskflow.observability.activity_logger import ActivityLogger, ActivityStatus, ActivityType
+from deskflow.observability.agent_events import (
+    MAX_SUMMARY_CHARS,
+    AgentEvent,
+    publish_agent_event,
+)
+
+
+@pytest.fixture
+def activity_logger(temp_dir: Path):
+    logger = ActivityLogger(working_dir=temp_dir)
+    with (
+        patch("deskflow.observability.agent_events.get_activity_logger", return_value=logger),
+        patch("deskflow.observability.activity_logger._notify_new_activity"),
+    ):
+        yield logger
+
+
+class TestPublishAgentEvent:
+    """Tests for publish_agent_event."""
+
+    def test_logs_system_event(self, activity_logger: ActivityLogger) -> None:
+        publish_agent_event(
+            AgentEvent.TASK_COMPLETED,
+            "Report written",
+            conversation_id="conv-1",
+            duration_ms=1500.0,
+        )
+
+        [record] = activity_logger.get_recent_activities()
+        assert record.type == ActivityType.SYSTEM_EVENT
+        assert record.status == ActivityStatus.SUCCESS
+        assert record.summary == "Report written"
+        assert record.details == {"event": "task_completed"}
+        assert record.conversation_id == "conv-1"
+        assert record.duration_ms == 1500.0
+
+    def test_failure_is_failed_status(self, activity_logger: ActivityLogger) -> None:
+        publish_agent_event(AgentEvent.TASK_FAILED, "Rate limited")
+
+        [record] = activity_logger.get_recent_activities()
+        assert record.status == ActivityStatus.FAILED
+        assert record.details["event"] == "task_failed"
+
+    def test_extra_details(self, activity_logger: ActivityLogger) -> None:
+        publish_agent_event(AgentEvent.CHANNEL_MESSAGE, "hi", channel_id="telegram")
+
+        [record] = activity_logger.get_recent_activities()
+        assert record.details == {"event": "channel_message", "channel_id": "telegram"}
+
+    def test_summary_is_one_short_line(self, activity_logger: ActivityLogger) -> None:
+        publish_agent_event(AgentEvent.TASK_COMPLETED, "line one\n\nline   two " + "x" * 500)
+
+        [record] = activity_logger.get_recent_activities()
+        assert record.summary.startswith("line one line two x")
+        assert len(record.summary) == MAX_SUMMARY_CHARS
+        assert record.summary.endswith("…")
+
+    def test_never_raises(self) -> None:
+        broken = MagicMock()
+        broken.log.side_effect = OSError("disk full")
+        with patch("deskflow.observability.agent_events.get_activity_logger", return_value=broken):
+            publish_agent_event(AgentEvent.PROACTIVE_SUGGESTION, "Good morning")
+
+
+class TestChannelMessageEvents:
+    """Inbound IM messages that no handler took."""
+
+    @pytest.mark.asyncio
+    async def test_unhandled_message_is_published(self, activity_logger: ActivityLogger) -> None:
+        queue = MessageQueue()
+        message = BaseMessage(_channel_id="telegram", _content="Are you there?", _sender_id="u1")
+
+        await queue._process_message(message)
+
+        [record] = activity_logger.get_recent_activities()
+        assert record.summary == "Are you there?"
+        assert record.details["event"] == "channel_message"
+        assert record.details["channel_id"] == "telegram"
+        assert record.details["sender_id"] == "u1"
+
+    @pytest.mark.asyncio
+    async def test_handled_message_is_not(self, activity_logger: ActivityLogger) -> None:
+        queue = MessageQueue()
+        queue.register_handler(lambda message: None)
+
+        await queue._process_message(BaseMessage(_channel_id="telegram", _content="hi", _sender_id="u1"))
+
+        assert activity_logger.get_recent_activities() == []
+
+    @pytest.mark.asyncio
+    async def test_failed_handler_counts_as_unhandled(self, activity_logger: ActivityLogger) -> None:
+        def broken(message):
+            raise RuntimeError("boom")
+
+        queue = MessageQueue()
+        queue.register_handler(broken)
+
+        await queue._process_message(BaseMessage(_channel_id="feishu", _content="hello", _sender_id="u2"))
+
+        [record] = activity_logger.get_recent_activities()
+        assert record.details["channel_id"] == "feishu"
@@ -0,0 +1,58 @@
+"""Tests for the SIGUSR1 stack dump handler."""
+
+from __future__ import annotations
+
+import os
+import signal
+import time
+from pathlib import Path
+
+import pytest
+
+from deskflow.observability import stack_dump
+from deskflow.observability.stack_dump import STACK_DUMP_FILE_ENV, install_stack_dump_handler
+
+needs_sigusr1 = pytest.mark.skipif(not hasattr(signal, "SIGUSR1"), reason="no SIGUSR1")
+
+
+@pytest.fixture(autouse=True)
+def _unregister():
+    yield
+    if hasattr(signal, "SIGUSR1"):
+        import faulthandler
+
+        faulthandler.unregister(signal.SIGUSR1)
+    if stack_dump._dump_file is not None:
+        stack_dump._dump_file.close()
+        stack_dump._dump_file = None
+
+
+class TestInstallStackDumpHandler:
+    """Tests for install_stack_dump_handler."""
+
+    def test_without_file_does_nothing(self, monkeypatch: pytest.MonkeyPatch) -> None:
+        monkeypatch.delenv(STACK_DUMP_FILE_ENV, raising=False)
+        assert install_stack_dump_handler() is None
+
+    @needs_sigusr1
+    def test_reads_path_from_env(self, temp_dir: Path, monkeypatch: pytest.MonkeyPatch) -> None:
+        target = temp_dir / "dumps" / "backend-stacks.txt"
+        monkeypatch.setenv(STACK_DUMP_FILE_ENV, str(target))
+
+        assert install_stack_dump_handler() == target
+        assert target.exists()
+
+    @needs_sigusr1
+    def test_sigusr1_appends_thread_stacks(self, temp_dir: Path) -> None:
+        target = temp_dir / "backend-stacks.txt"
+        install_stack_dump_handler(target)
+
+        os.kill(os.getpid(), signal.SIGUSR1)
+        for _ in range(50):
+            if target.stat().st_size > 0:
+                break
+            time.sleep(0.01)
+
+        dump = target.read_text()
+        assert "Current thread" in dump or "Thread 0x" in dump
+        assert "test_sigusr1_appends_thread_stacks" in dump